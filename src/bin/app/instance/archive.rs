@@ -0,0 +1,1633 @@
+use std::collections::{HashMap, HashSet};
+
+use log::{error, warn};
+use rusqlite::{params, Connection};
+
+use crate::yt_fetch::SearchType;
+
+use super::settings::{PlaybackSpeed, StreamQuality};
+
+use super::PomeloError;
+
+const ARCHIVE_FILE: &str = "archive.db";
+
+// Playlists made in Pomelo get ids starting with this, so they can't clash with Youtube playlist ids.
+const LOCAL_PLAYLIST_PREFIX: &str = "local:";
+
+// Saved positions closer to the start than this aren't worth resuming from, in seconds.
+pub (crate) const MIN_RESUME_POSITION: f64 = 5.0;
+
+// Tables are only created if they're missing, so existing archives are left alone.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS channel (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS video (
+        id TEXT PRIMARY KEY,
+        title TEXT NOT NULL,
+        author TEXT NOT NULL,
+        channel_id TEXT REFERENCES channel(id),
+        file_path TEXT NOT NULL,
+        thumbnail TEXT,
+        archived_at INTEGER NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS playlist (
+        id TEXT PRIMARY KEY,
+        title TEXT NOT NULL,
+        author TEXT NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS playlist_video (
+        playlist TEXT NOT NULL REFERENCES playlist(id),
+        position INTEGER NOT NULL,
+        video TEXT NOT NULL,
+        title TEXT NOT NULL,
+        local INTEGER NOT NULL,
+        PRIMARY KEY (playlist, position)
+    );
+
+    CREATE TABLE IF NOT EXISTS playlist_snapshot (
+        playlist TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        video TEXT NOT NULL,
+        title TEXT NOT NULL,
+        PRIMARY KEY (playlist, position)
+    );
+
+    CREATE TABLE IF NOT EXISTS history (
+        video TEXT PRIMARY KEY,
+        title TEXT NOT NULL,
+        local INTEGER NOT NULL,
+        watched_at INTEGER NOT NULL,
+        position REAL NOT NULL DEFAULT 0
+    );
+
+    CREATE TABLE IF NOT EXISTS watch_time (
+        day TEXT NOT NULL,
+        video TEXT NOT NULL,
+        channel_id TEXT,
+        channel TEXT,
+        seconds REAL NOT NULL,
+        PRIMARY KEY (day, video)
+    );
+
+    CREATE TABLE IF NOT EXISTS watched (
+        video TEXT PRIMARY KEY,
+        watched INTEGER NOT NULL,
+        marked_at INTEGER NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS subscription (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        thumbnail TEXT,
+        subscribed_at INTEGER NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS subscription_group (
+        name TEXT PRIMARY KEY
+    );
+
+    CREATE TABLE IF NOT EXISTS subscription_group_channel (
+        channel TEXT PRIMARY KEY,
+        name TEXT NOT NULL REFERENCES subscription_group(name)
+    );
+
+    CREATE TABLE IF NOT EXISTS channel_preference (
+        channel TEXT PRIMARY KEY,
+        speed REAL,
+        quality TEXT,
+        volume_offset REAL NOT NULL DEFAULT 0
+    );
+
+    CREATE TABLE IF NOT EXISTS favorite (
+        id TEXT PRIMARY KEY,
+        kind TEXT NOT NULL,
+        title TEXT NOT NULL,
+        thumbnail TEXT,
+        added_at INTEGER NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS allowed_channel (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS search_history (
+        query TEXT NOT NULL,
+        search_type TEXT NOT NULL,
+        searched_at INTEGER NOT NULL,
+        PRIMARY KEY (query, search_type)
+    );
+
+    CREATE TABLE IF NOT EXISTS pending_download (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        title TEXT NOT NULL,
+        path TEXT NOT NULL,
+        args TEXT NOT NULL,
+        queued_at INTEGER NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS player_session (
+        id INTEGER PRIMARY KEY CHECK (id = 0),
+        videos TEXT NOT NULL,
+        video_index INTEGER NOT NULL,
+        position REAL NOT NULL,
+        saved_at INTEGER NOT NULL
+    );
+";
+
+// Columns added to tables after they were first made, as (table, column, definition).
+// Existing archives get them when they're opened.
+const ADDED_COLUMNS: &[(&str, &str, &str)] = &[
+    ("history", "duration", "REAL NOT NULL DEFAULT 0")
+];
+
+// A downloaded file, as reported by yt-dlp once the file is in its final place.
+#[derive(serde::Deserialize, Debug, Clone)]
+pub (crate) struct ArchivedVideo {
+    pub (crate) id: String,
+    pub (crate) title: String,
+
+    #[serde(rename = "uploader", default)]
+    pub (crate) author: String,
+
+    #[serde(default)]
+    pub (crate) channel_id: Option<String>,
+
+    #[serde(rename = "filepath")]
+    pub (crate) file_path: String,
+
+    #[serde(default)]
+    pub (crate) thumbnail: Option<String>
+}
+
+// A video that was played, with where playback was when it was last stopped.
+// Youtube videos are stored by id, files from the computer by url.
+#[derive(Debug, Clone)]
+pub (crate) struct WatchEntry {
+    pub (crate) video: String,
+    pub (crate) title: String,
+    pub (crate) local: bool,
+    pub (crate) watched_at: i64,
+    pub (crate) position: f64
+}
+
+// A watch history entry as it's exported, with its channel and total time spent watching it, in seconds.
+// Videos watched before watch time was recorded have no channel, and a watch time of 0.
+#[derive(serde::Serialize, Debug, Clone)]
+pub (crate) struct ExportedWatch {
+    #[serde(rename = "video_id")]
+    pub (crate) video: String,
+    pub (crate) title: String,
+    pub (crate) channel: Option<String>,
+    pub (crate) watched_at: i64,
+    pub (crate) seconds_watched: f64
+}
+
+// A channel the user is subscribed to. Its latest uploads are shown on the Feed page.
+#[derive(Debug, Clone)]
+pub (crate) struct SubscribedChannel {
+    pub (crate) id: String,
+    pub (crate) name: String,
+    pub (crate) thumbnail: Option<String>
+}
+
+// Playback defaults for a channel, applied when one of its videos starts.
+// Missing speeds and qualities use the player's current ones. The volume offset is added to the player's volume.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub (crate) struct ChannelPreference {
+    pub (crate) speed: Option<PlaybackSpeed>,
+    pub (crate) quality: Option<StreamQuality>,
+    pub (crate) volume_offset: f64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub (crate) enum FavoriteKind {
+    Channel,
+    Playlist
+}
+
+impl FavoriteKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Channel => "channel",
+            Self::Playlist => "playlist"
+        }
+    }
+
+    fn from_str(kind: &str) -> Option<Self> {
+        match kind {
+            "channel" => Some(Self::Channel),
+            "playlist" => Some(Self::Playlist),
+            _ => None
+        }
+    }
+}
+
+// A Youtube channel or playlist bookmarked from its page, listed on the main menu so it can be opened without searching.
+#[derive(Debug, Clone)]
+pub (crate) struct Favorite {
+    pub (crate) id: String,
+    pub (crate) kind: FavoriteKind,
+    pub (crate) title: String,
+    pub (crate) thumbnail: Option<String>
+}
+
+// A playlist made in Pomelo, with its videos in the order they're played.
+#[derive(Debug, Clone)]
+pub (crate) struct LocalPlaylist {
+    pub (crate) id: String,
+    pub (crate) title: String,
+    pub (crate) videos: Vec<PlaylistEntry>
+}
+
+// Youtube videos are stored by id, files from the computer by url.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub (crate) struct PlaylistEntry {
+    pub (crate) video: String,
+    pub (crate) title: String,
+    pub (crate) local: bool
+}
+
+// A video that was in a Youtube playlist the last time it was opened.
+#[derive(Debug, Clone)]
+pub (crate) struct SnapshotVideo {
+    pub (crate) id: String,
+    pub (crate) title: String
+}
+
+// Differences between a Youtube playlist and its last snapshot.
+#[derive(Debug, Clone, Default)]
+pub (crate) struct PlaylistDiff {
+    // Ids of videos that weren't in the playlist last time.
+    pub (crate) added: HashSet<String>,
+
+    // Videos that were in the playlist last time, but aren't anymore.
+    pub (crate) removed: Vec<SnapshotVideo>
+}
+
+// A download that was queued or running when Pomelo was closed.
+// The arguments include the format, quality, and output folder it was started with.
+#[derive(Debug, Clone)]
+pub (crate) struct PendingDownload {
+    pub (crate) row: i64,
+    pub (crate) title: String,
+    pub (crate) path: String,
+    pub (crate) args: Vec<String>
+}
+
+// The player's list of videos when Pomelo was last closed, with the video and position playback was at.
+#[derive(Debug, Clone)]
+pub (crate) struct PlayerSession {
+    pub (crate) videos: Vec<PlaylistEntry>,
+    pub (crate) index: usize,
+    pub (crate) position: f64
+}
+
+impl PlayerSession {
+    pub (crate) fn current(&self) -> Option<&PlaylistEntry> {
+        self.videos.get(self.index)
+    }
+}
+
+// SQLite database of everything Pomelo has downloaded or played. Persistant between runs.
+// If the database can't be opened, downloads and playback still work, they just aren't recorded.
+// Subscriptions and their groups, favorites, channels allowed in restricted mode, channel preferences, local playlists,
+// downloaded files, watched videos, and saved positions are also kept in memory, since pages check them while drawing.
+pub (crate) struct Archive {
+    connection: Option<Connection>,
+    subscriptions: Vec<SubscribedChannel>,
+
+    // Names of the subscription groups, in the order they were made, and the group of each channel that's in one.
+    groups: Vec<String>,
+    channel_groups: HashMap<String, String>,
+    preferences: HashMap<String, ChannelPreference>,
+    favorites: Vec<Favorite>,
+    allowed_channels: Vec<(String, String)>,
+    playlists: Vec<LocalPlaylist>,
+    files: HashMap<String, String>,
+    watched: HashSet<String>,
+
+    // Saved (position, duration) of videos in the watch history.
+    progress: HashMap<String, (f64, f64)>
+}
+
+impl Archive {
+    // Open the archive.db file, creating it and its tables if needed.
+    pub (crate) fn open() -> Self {
+        Self::with_connection(Connection::open(ARCHIVE_FILE))
+    }
+
+    // An archive that isn't saved anywhere, so tests don't touch archive.db.
+    #[cfg(test)]
+    pub (crate) fn in_memory() -> Self {
+        Self::with_connection(Connection::open_in_memory())
+    }
+
+    fn with_connection(connection: rusqlite::Result<Connection>) -> Self {
+        let result = connection
+            .and_then(|connection| {
+                connection.execute_batch(SCHEMA)?;
+                add_missing_columns(&connection)?;
+                Ok(connection)
+            })
+            .map_err(PomeloError::new);
+
+        match result {
+            Ok(connection) => {
+                let subscriptions = load_subscriptions(&connection);
+                let (groups, channel_groups) = load_groups(&connection);
+                let preferences = load_preferences(&connection);
+                let favorites = load_favorites(&connection);
+                let allowed_channels = load_allowed_channels(&connection);
+                let playlists = load_playlists(&connection);
+                let files = load_files(&connection);
+                let watched = load_watched(&connection);
+                let progress = load_progress(&connection);
+                Self {
+                    connection: Some(connection),
+                    subscriptions,
+                    groups,
+                    channel_groups,
+                    preferences,
+                    favorites,
+                    allowed_channels,
+                    playlists,
+                    files,
+                    watched,
+                    progress
+                }
+            },
+            Err(e) => {
+                warn!("Failed to open the archive, downloads won't be recorded: {}", e.error);
+                Self {
+                    connection: None,
+                    subscriptions: Vec::new(),
+                    groups: Vec::new(),
+                    channel_groups: HashMap::new(),
+                    preferences: HashMap::new(),
+                    favorites: Vec::new(),
+                    allowed_channels: Vec::new(),
+                    playlists: Vec::new(),
+                    files: HashMap::new(),
+                    watched: HashSet::new(),
+                    progress: HashMap::new()
+                }
+            }
+        }
+    }
+
+    // Record a downloaded video, replacing the old row if it was downloaded before.
+    pub (crate) fn add_video(&mut self, video: &ArchivedVideo) {
+        self.files.insert(video.id.clone(), video.file_path.clone());
+
+        let Some(connection) = &self.connection else {
+            return;
+        };
+
+        let result = connection.execute_batch("BEGIN")
+            .and_then(|_| match &video.channel_id {
+                Some(channel_id) => connection.execute(
+                    "INSERT OR REPLACE INTO channel (id, name) VALUES (?1, ?2)",
+                    params![channel_id, video.author]
+                ).map(|_| ()),
+                None => Ok(())
+            })
+            .and_then(|_| connection.execute(
+                "INSERT OR REPLACE INTO video (id, title, author, channel_id, file_path, thumbnail, archived_at)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    video.id,
+                    video.title,
+                    video.author,
+                    video.channel_id,
+                    video.file_path,
+                    video.thumbnail,
+                    chrono::Local::now().timestamp()
+                ]
+            ))
+            .and_then(|_| connection.execute_batch("COMMIT"));
+
+        if let Err(e) = result {
+            error!("Failed to add \"{}\" to the archive: {}", video.title, e);
+            let _ = connection.execute_batch("ROLLBACK");
+        }
+    }
+
+    // Forget a downloaded video, e.g. after its file was deleted.
+    pub (crate) fn remove_video(&mut self, id: &str) {
+        self.files.remove(id);
+
+        let Some(connection) = &self.connection else {
+            return;
+        };
+
+        if let Err(e) = connection.execute("DELETE FROM video WHERE id = ?1", params![id]) {
+            error!("Failed to remove video {} from the archive: {}", id, e);
+        }
+    }
+
+    // Record that a video was played. Watching it again moves it back to the top, and keeps its saved position.
+    // Playing a video that was marked unwatched makes it watched again.
+    pub (crate) fn add_watch(&mut self, video: &str, title: &str, local: bool) {
+        self.watched.insert(String::from(video));
+
+        let Some(connection) = &self.connection else {
+            return;
+        };
+
+        let result = connection.execute("DELETE FROM watched WHERE video = ?1 AND watched = 0", params![video])
+            .and_then(|_| connection.execute(
+                "INSERT INTO history (video, title, local, watched_at) VALUES (?1, ?2, ?3, ?4)
+                    ON CONFLICT(video) DO UPDATE SET title = excluded.title, watched_at = excluded.watched_at",
+                params![video, title, local, chrono::Local::now().timestamp()]
+            ));
+
+        if let Err(e) = result {
+            error!("Failed to add \"{}\" to the watch history: {}", title, e);
+        }
+    }
+
+    // Videos count as watched if they're in the watch history or were marked watched, unless they were marked unwatched since.
+    pub (crate) fn is_watched(&self, video: &str) -> bool {
+        self.watched.contains(video)
+    }
+
+    // Mark a video as watched or unwatched without playing it. The watch history is left as it is.
+    pub (crate) fn set_watched(&mut self, video: &str, watched: bool) {
+        if watched {
+            self.watched.insert(String::from(video));
+        } else {
+            self.watched.remove(video);
+        }
+
+        let Some(connection) = &self.connection else {
+            return;
+        };
+
+        let result = connection.execute(
+            "INSERT OR REPLACE INTO watched (video, watched, marked_at) VALUES (?1, ?2, ?3)",
+            params![video, watched, chrono::Local::now().timestamp()]
+        );
+
+        if let Err(e) = result {
+            error!("Failed to mark {} as {}: {}", video, if watched { "watched" } else { "unwatched" }, e);
+        }
+    }
+
+    // Save where playback of a video stopped, and how long the video is, so it can be resumed later.
+    pub (crate) fn set_watch_position(&mut self, video: &str, position: f64, duration: f64) {
+        self.progress.insert(String::from(video), (position, duration));
+
+        let Some(connection) = &self.connection else {
+            return;
+        };
+
+        let result = connection.execute(
+            "UPDATE history SET position = ?2, duration = ?3 WHERE video = ?1",
+            params![video, position, duration]
+        );
+
+        if let Err(e) = result {
+            error!("Failed to save playback position: {}", e);
+        }
+    }
+
+    // Where playback of a video was last stopped, if it's far enough in to resume from.
+    pub (crate) fn watch_position(&self, video: &str) -> Option<f64> {
+        self.watch_progress(video).map(|(position, _)| position)
+    }
+
+    // Where playback of a video was last stopped and how long it is, as (position, duration), if it can be resumed.
+    // Videos stopped before durations were saved have a duration of 0.
+    pub (crate) fn watch_progress(&self, video: &str) -> Option<(f64, f64)> {
+        self.progress.get(video)
+            .copied()
+            .filter(|(position, _)| *position >= MIN_RESUME_POSITION)
+    }
+
+    // Watched videos with titles containing the search text, most recent first.
+    pub (crate) fn watch_history(&self, search: &str) -> Vec<WatchEntry> {
+        let Some(connection) = &self.connection else {
+            return Vec::new();
+        };
+
+        let pattern = format!(
+            "%{}%",
+            search.trim().replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+        );
+
+        let result = connection
+            .prepare(
+                "SELECT video, title, local, watched_at, position FROM history
+                    WHERE title LIKE ?1 ESCAPE '\\' ORDER BY watched_at DESC"
+            )
+            .and_then(|mut statement| {
+                let entries = statement
+                    .query_map(params![pattern], |row| Ok(WatchEntry {
+                        video: row.get(0)?,
+                        title: row.get(1)?,
+                        local: row.get(2)?,
+                        watched_at: row.get(3)?,
+                        position: row.get(4)?
+                    }))?
+                    .collect::<Result<Vec<_>, _>>();
+
+                entries
+            });
+
+        result.unwrap_or_else(|e| {
+            error!("Failed to read the watch history: {}", e);
+            Vec::new()
+        })
+    }
+
+    // The whole watch history for exporting, most recent first.
+    pub (crate) fn export_watch_history(&self) -> Result<Vec<ExportedWatch>, PomeloError> {
+        let Some(connection) = &self.connection else {
+            return Err(PomeloError::from("The archive couldn't be opened."));
+        };
+
+        let mut statement = connection
+            .prepare(
+                "SELECT history.video, history.title, MAX(watch_time.channel), history.watched_at, COALESCE(SUM(watch_time.seconds), 0)
+                    FROM history LEFT JOIN watch_time ON watch_time.video = history.video
+                    GROUP BY history.video ORDER BY history.watched_at DESC"
+            )
+            .map_err(PomeloError::new)?;
+
+        let entries = statement
+            .query_map([], |row| Ok(ExportedWatch {
+                video: row.get(0)?,
+                title: row.get(1)?,
+                channel: row.get(2)?,
+                watched_at: row.get(3)?,
+                seconds_watched: row.get(4)?
+            }))
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(PomeloError::new);
+
+        entries
+    }
+
+    // Add time spent watching a video to today's total for it. Local files have no channel.
+    pub (crate) fn add_watch_time(&self, video: &str, channel: Option<(&str, &str)>, seconds: f64) {
+        let Some(connection) = &self.connection else {
+            return;
+        };
+
+        let (channel_id, channel) = channel.unzip();
+
+        let result = connection.execute(
+            "INSERT INTO watch_time (day, video, channel_id, channel, seconds) VALUES (?1, ?2, ?3, ?4, ?5)
+                ON CONFLICT(day, video) DO UPDATE SET seconds = seconds + excluded.seconds",
+            params![chrono::Local::now().format("%Y-%m-%d").to_string(), video, channel_id, channel, seconds]
+        );
+
+        if let Err(e) = result {
+            error!("Failed to record watch time: {}", e);
+        }
+    }
+
+    // Seconds watched on each of the last few days, oldest first, as (day, seconds). Days with nothing watched are left out.
+    pub (crate) fn daily_watch_time(&self, days: u32) -> Vec<(String, f64)> {
+        let Some(connection) = &self.connection else {
+            return Vec::new();
+        };
+
+        let since = (chrono::Local::now() - chrono::Duration::days(days as i64 - 1)).format("%Y-%m-%d").to_string();
+
+        let result = connection
+            .prepare("SELECT day, SUM(seconds) FROM watch_time WHERE day >= ?1 GROUP BY day ORDER BY day")
+            .and_then(|mut statement| {
+                let days = statement
+                    .query_map(params![since], |row| Ok((row.get(0)?, row.get(1)?)))?
+                    .collect::<Result<Vec<_>, _>>();
+
+                days
+            });
+
+        result.unwrap_or_else(|e| {
+            error!("Failed to read watch time: {}", e);
+            Vec::new()
+        })
+    }
+
+    // Channels watched the longest, as (name, seconds). Local files aren't counted.
+    pub (crate) fn top_channels(&self, limit: usize) -> Vec<(String, f64)> {
+        let Some(connection) = &self.connection else {
+            return Vec::new();
+        };
+
+        let result = connection
+            .prepare(
+                "SELECT MAX(channel), SUM(seconds) AS total FROM watch_time WHERE channel_id IS NOT NULL
+                    GROUP BY channel_id ORDER BY total DESC LIMIT ?1"
+            )
+            .and_then(|mut statement| {
+                let channels = statement
+                    .query_map(params![limit], |row| Ok((row.get(0)?, row.get(1)?)))?
+                    .collect::<Result<Vec<_>, _>>();
+
+                channels
+            });
+
+        result.unwrap_or_else(|e| {
+            error!("Failed to read watch time: {}", e);
+            Vec::new()
+        })
+    }
+
+    // Videos marked watched by hand stay watched. Watch time goes with the history.
+    pub (crate) fn clear_watch_history(&mut self) {
+        let Some(connection) = &self.connection else {
+            return;
+        };
+
+        let result = connection.execute("DELETE FROM history", [])
+            .and_then(|_| connection.execute("DELETE FROM watch_time", []));
+
+        if let Err(e) = result {
+            error!("Failed to clear the watch history: {}", e);
+        }
+
+        self.watched = load_watched(connection);
+        self.progress.clear();
+    }
+
+    // Record a search query. Searching for it again moves it back to the top.
+    pub (crate) fn add_search(&self, query: &str, search_type: SearchType) {
+        let Some(connection) = &self.connection else {
+            return;
+        };
+
+        let result = connection.execute(
+            "INSERT INTO search_history (query, search_type, searched_at) VALUES (?1, ?2, ?3)
+                ON CONFLICT(query, search_type) DO UPDATE SET searched_at = excluded.searched_at",
+            params![query, search_type.to_string(), chrono::Local::now().timestamp()]
+        );
+
+        if let Err(e) = result {
+            error!("Failed to add a query to the search history: {}", e);
+        }
+    }
+
+    // The latest queries of a search type, most recent first.
+    pub (crate) fn recent_searches(&self, search_type: SearchType, limit: usize) -> Vec<String> {
+        let Some(connection) = &self.connection else {
+            return Vec::new();
+        };
+
+        let result = connection
+            .prepare(
+                "SELECT query FROM search_history WHERE search_type = ?1
+                    ORDER BY searched_at DESC LIMIT ?2"
+            )
+            .and_then(|mut statement| {
+                let queries = statement
+                    .query_map(params![search_type.to_string(), limit], |row| row.get(0))?
+                    .collect::<Result<Vec<_>, _>>();
+
+                queries
+            });
+
+        result.unwrap_or_else(|e| {
+            error!("Failed to read the search history: {}", e);
+            Vec::new()
+        })
+    }
+
+    pub (crate) fn remove_search(&self, query: &str, search_type: SearchType) {
+        let Some(connection) = &self.connection else {
+            return;
+        };
+
+        let result = connection.execute(
+            "DELETE FROM search_history WHERE query = ?1 AND search_type = ?2",
+            params![query, search_type.to_string()]
+        );
+
+        if let Err(e) = result {
+            error!("Failed to remove a query from the search history: {}", e);
+        }
+    }
+
+    pub (crate) fn clear_search_history(&self) {
+        let Some(connection) = &self.connection else {
+            return;
+        };
+
+        if let Err(e) = connection.execute("DELETE FROM search_history", []) {
+            error!("Failed to clear the search history: {}", e);
+        }
+    }
+
+    // Remember a download until it's done, so it can be resumed if Pomelo is closed first. Returns its row id.
+    pub (crate) fn add_pending_download(&self, title: &str, path: &str, args: &[String]) -> Option<i64> {
+        let connection = self.connection.as_ref()?;
+
+        let result = serde_json::to_string(args)
+            .map_err(PomeloError::new)
+            .and_then(|args| connection.execute(
+                "INSERT INTO pending_download (title, path, args, queued_at) VALUES (?1, ?2, ?3, ?4)",
+                params![title, path, args, chrono::Local::now().timestamp()]
+            ).map_err(PomeloError::new));
+
+        match result {
+            Ok(_) => Some(connection.last_insert_rowid()),
+            Err(e) => {
+                error!("Failed to save pending download \"{}\": {}", title, e.error);
+                None
+            }
+        }
+    }
+
+    pub (crate) fn remove_pending_download(&self, row: i64) {
+        let Some(connection) = &self.connection else {
+            return;
+        };
+
+        if let Err(e) = connection.execute("DELETE FROM pending_download WHERE id = ?1", params![row]) {
+            error!("Failed to remove pending download: {}", e);
+        }
+    }
+
+    // Downloads left unfinished by the last session, oldest first.
+    pub (crate) fn pending_downloads(&self) -> Vec<PendingDownload> {
+        let Some(connection) = &self.connection else {
+            return Vec::new();
+        };
+
+        let result = connection
+            .prepare("SELECT id, title, path, args FROM pending_download ORDER BY queued_at")
+            .and_then(|mut statement| {
+                let rows = statement
+                    .query_map([], |row| Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?
+                    )))?
+                    .collect::<Result<Vec<_>, _>>();
+
+                rows
+            });
+
+        let rows = result.unwrap_or_else(|e| {
+            error!("Failed to read pending downloads: {}", e);
+            Vec::new()
+        });
+
+        // Rows with unreadable arguments can't be resumed, so they're dropped.
+        rows.into_iter()
+            .filter_map(|(row, title, path, args)| match serde_json::from_str(&args) {
+                Ok(args) => Some(PendingDownload { row, title, path, args }),
+                Err(e) => {
+                    warn!("Skipping pending download \"{}\": {}", title, e);
+                    self.remove_pending_download(row);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    // Only one session is kept, so saving replaces the last one.
+    pub (crate) fn save_player_session(&self, session: &PlayerSession) {
+        let Some(connection) = &self.connection else {
+            return;
+        };
+
+        let result = serde_json::to_string(&session.videos)
+            .map_err(PomeloError::new)
+            .and_then(|videos| connection.execute(
+                "INSERT OR REPLACE INTO player_session (id, videos, video_index, position, saved_at) VALUES (0, ?1, ?2, ?3, ?4)",
+                params![videos, session.index as i64, session.position, chrono::Local::now().timestamp()]
+            ).map_err(PomeloError::new));
+
+        if let Err(e) = result {
+            error!("Failed to save the player session: {}", e.error);
+        }
+    }
+
+    pub (crate) fn clear_player_session(&self) {
+        let Some(connection) = &self.connection else {
+            return;
+        };
+
+        if let Err(e) = connection.execute("DELETE FROM player_session", []) {
+            error!("Failed to clear the player session: {}", e);
+        }
+    }
+
+    // The player session saved by the last run, if there is one and it can still be played.
+    pub (crate) fn player_session(&self) -> Option<PlayerSession> {
+        let connection = self.connection.as_ref()?;
+
+        let result = connection.query_row(
+            "SELECT videos, video_index, position FROM player_session WHERE id = 0",
+            [],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, f64>(2)?))
+        );
+
+        let (videos, index, position) = match result {
+            Ok(row) => row,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return None,
+            Err(e) => {
+                error!("Failed to read the player session: {}", e);
+                return None;
+            }
+        };
+
+        let session = match serde_json::from_str(&videos) {
+            Ok(videos) => PlayerSession { videos, index: index.max(0) as usize, position },
+            Err(e) => {
+                warn!("Skipping unreadable player session: {}", e);
+                self.clear_player_session();
+                return None;
+            }
+        };
+
+        session.current().is_some().then_some(session)
+    }
+
+    // Downloaded videos whose files are still there, most recently downloaded first.
+    pub (crate) fn archived_videos(&self) -> Vec<ArchivedVideo> {
+        let Some(connection) = &self.connection else {
+            return Vec::new();
+        };
+
+        let result = connection
+            .prepare("SELECT id, title, author, channel_id, file_path, thumbnail FROM video ORDER BY archived_at DESC")
+            .and_then(|mut statement| {
+                let videos = statement
+                    .query_map([], |row| Ok(ArchivedVideo {
+                        id: row.get(0)?,
+                        title: row.get(1)?,
+                        author: row.get(2)?,
+                        channel_id: row.get(3)?,
+                        file_path: row.get(4)?,
+                        thumbnail: row.get(5)?
+                    }))?
+                    .collect::<Result<Vec<_>, _>>();
+
+                videos
+            });
+
+        let videos = result.unwrap_or_else(|e| {
+            error!("Failed to read downloaded videos: {}", e);
+            Vec::new()
+        });
+
+        videos.into_iter()
+            .filter(|video| self.files.get(&video.id) == Some(&video.file_path))
+            .collect()
+    }
+
+    // Where the downloaded copy of a video is, if it's been downloaded.
+    pub (crate) fn local_file(&self, video: &str) -> Option<&str> {
+        self.files.get(video).map(String::as_str)
+    }
+
+    // Subscribed channels, oldest subscription first.
+    pub (crate) fn subscriptions(&self) -> &[SubscribedChannel] {
+        &self.subscriptions
+    }
+
+    pub (crate) fn is_subscribed(&self, channel_id: &str) -> bool {
+        self.subscriptions.iter().any(|channel| channel.id == channel_id)
+    }
+
+    pub (crate) fn subscribe(&mut self, channel: SubscribedChannel) {
+        if self.is_subscribed(&channel.id) {
+            return;
+        }
+
+        if let Some(connection) = &self.connection {
+            let result = connection.execute(
+                "INSERT OR REPLACE INTO subscription (id, name, thumbnail, subscribed_at) VALUES (?1, ?2, ?3, ?4)",
+                params![channel.id, channel.name, channel.thumbnail, chrono::Local::now().timestamp()]
+            );
+
+            if let Err(e) = result {
+                error!("Failed to subscribe to \"{}\": {}", channel.name, e);
+            }
+        }
+
+        self.subscriptions.push(channel);
+    }
+
+    pub (crate) fn unsubscribe(&mut self, channel_id: &str) {
+        self.set_channel_group(channel_id, None);
+
+        if let Some(connection) = &self.connection {
+            if let Err(e) = connection.execute("DELETE FROM subscription WHERE id = ?1", params![channel_id]) {
+                error!("Failed to unsubscribe from {}: {}", channel_id, e);
+            }
+        }
+
+        self.subscriptions.retain(|channel| channel.id != channel_id);
+    }
+
+    // Subscription groups, in the order they were made.
+    pub (crate) fn subscription_groups(&self) -> &[String] {
+        &self.groups
+    }
+
+    pub (crate) fn create_group(&mut self, name: &str) {
+        if name.is_empty() || self.groups.iter().any(|group| group == name) {
+            return;
+        }
+
+        if let Some(connection) = &self.connection {
+            if let Err(e) = connection.execute("INSERT OR IGNORE INTO subscription_group (name) VALUES (?1)", params![name]) {
+                error!("Failed to create subscription group \"{}\": {}", name, e);
+            }
+        }
+
+        self.groups.push(String::from(name));
+    }
+
+    // Channels in the group are kept, they just aren't in a group anymore.
+    pub (crate) fn delete_group(&mut self, name: &str) {
+        if let Some(connection) = &self.connection {
+            let result = connection.execute("DELETE FROM subscription_group_channel WHERE name = ?1", params![name])
+                .and_then(|_| connection.execute("DELETE FROM subscription_group WHERE name = ?1", params![name]));
+
+            if let Err(e) = result {
+                error!("Failed to delete subscription group \"{}\": {}", name, e);
+            }
+        }
+
+        self.groups.retain(|group| group != name);
+        self.channel_groups.retain(|_, group| group != name);
+    }
+
+    pub (crate) fn channel_group(&self, channel_id: &str) -> Option<&str> {
+        self.channel_groups.get(channel_id).map(String::as_str)
+    }
+
+    // Move a channel into a group, or out of its group with None. A channel is in at most one group.
+    pub (crate) fn set_channel_group(&mut self, channel_id: &str, group: Option<&str>) {
+        let group = group.filter(|group| self.groups.iter().any(|name| name == group));
+
+        if let Some(connection) = &self.connection {
+            let result = match group {
+                Some(group) => connection.execute(
+                    "INSERT OR REPLACE INTO subscription_group_channel (channel, name) VALUES (?1, ?2)",
+                    params![channel_id, group]
+                ),
+                None => connection.execute("DELETE FROM subscription_group_channel WHERE channel = ?1", params![channel_id])
+            };
+
+            if let Err(e) = result {
+                error!("Failed to change the group of {}: {}", channel_id, e);
+            }
+        }
+
+        match group {
+            Some(group) => self.channel_groups.insert(String::from(channel_id), String::from(group)),
+            None => self.channel_groups.remove(channel_id)
+        };
+    }
+
+    pub (crate) fn channel_preference(&self, channel_id: &str) -> Option<&ChannelPreference> {
+        self.preferences.get(channel_id)
+    }
+
+    // Preferences with nothing set are removed, so the channel plays like any other.
+    pub (crate) fn set_channel_preference(&mut self, channel_id: &str, preference: ChannelPreference) {
+        let is_default = preference == ChannelPreference::default();
+
+        if let Some(connection) = &self.connection {
+            let result = if is_default {
+                connection.execute("DELETE FROM channel_preference WHERE channel = ?1", params![channel_id])
+                    .map_err(PomeloError::new)
+            }
+            else {
+                preference.quality
+                    .map(|quality| serde_json::to_string(&quality))
+                    .transpose()
+                    .map_err(PomeloError::new)
+                    .and_then(|quality| connection.execute(
+                        "INSERT OR REPLACE INTO channel_preference (channel, speed, quality, volume_offset) VALUES (?1, ?2, ?3, ?4)",
+                        params![channel_id, preference.speed.map(|speed| speed.0), quality, preference.volume_offset]
+                    ).map_err(PomeloError::new))
+            };
+
+            if let Err(e) = result {
+                error!("Failed to save playback preferences for {}: {}", channel_id, e.error);
+            }
+        }
+
+        if is_default {
+            self.preferences.remove(channel_id);
+        } else {
+            self.preferences.insert(String::from(channel_id), preference);
+        }
+    }
+
+    // Favorite channels and playlists, in the order they were added.
+    pub (crate) fn favorites(&self) -> &[Favorite] {
+        &self.favorites
+    }
+
+    pub (crate) fn is_favorite(&self, id: &str) -> bool {
+        self.favorites.iter().any(|favorite| favorite.id == id)
+    }
+
+    pub (crate) fn toggle_favorite(&mut self, favorite: Favorite) {
+        if self.is_favorite(&favorite.id) {
+            self.remove_favorite(&favorite.id);
+            return;
+        }
+
+        if let Some(connection) = &self.connection {
+            let result = connection.execute(
+                "INSERT OR REPLACE INTO favorite (id, kind, title, thumbnail, added_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    favorite.id,
+                    favorite.kind.as_str(),
+                    favorite.title,
+                    favorite.thumbnail,
+                    chrono::Local::now().timestamp()
+                ]
+            );
+
+            if let Err(e) = result {
+                error!("Failed to add \"{}\" to favorites: {}", favorite.title, e);
+            }
+        }
+
+        self.favorites.push(favorite);
+    }
+
+    pub (crate) fn remove_favorite(&mut self, id: &str) {
+        if let Some(connection) = &self.connection {
+            if let Err(e) = connection.execute("DELETE FROM favorite WHERE id = ?1", params![id]) {
+                error!("Failed to remove {} from favorites: {}", id, e);
+            }
+        }
+
+        self.favorites.retain(|favorite| favorite.id != id);
+    }
+
+    // Channels that can be played in restricted mode, as (id, name) pairs, in the order they were allowed.
+    pub (crate) fn allowed_channels(&self) -> &[(String, String)] {
+        &self.allowed_channels
+    }
+
+    pub (crate) fn is_allowed(&self, channel_id: &str) -> bool {
+        self.allowed_channels.iter().any(|(id, _)| id == channel_id)
+    }
+
+    pub (crate) fn allow_channel(&mut self, channel_id: &str, name: &str) {
+        if self.is_allowed(channel_id) {
+            return;
+        }
+
+        if let Some(connection) = &self.connection {
+            let result = connection.execute(
+                "INSERT OR REPLACE INTO allowed_channel (id, name) VALUES (?1, ?2)",
+                params![channel_id, name]
+            );
+
+            if let Err(e) = result {
+                error!("Failed to allow \"{}\" in restricted mode: {}", name, e);
+            }
+        }
+
+        self.allowed_channels.push((String::from(channel_id), String::from(name)));
+    }
+
+    pub (crate) fn disallow_channel(&mut self, channel_id: &str) {
+        if let Some(connection) = &self.connection {
+            if let Err(e) = connection.execute("DELETE FROM allowed_channel WHERE id = ?1", params![channel_id]) {
+                error!("Failed to remove {} from the allowed channels: {}", channel_id, e);
+            }
+        }
+
+        self.allowed_channels.retain(|(id, _)| id != channel_id);
+    }
+
+    // Playlists made in Pomelo, in the order they were created.
+    pub (crate) fn playlists(&self) -> &[LocalPlaylist] {
+        &self.playlists
+    }
+
+    pub (crate) fn playlist(&self, id: &str) -> Option<&LocalPlaylist> {
+        self.playlists.iter().find(|playlist| playlist.id == id)
+    }
+
+    pub (crate) fn create_playlist(&mut self, title: &str) {
+        let id = format!("{}{}", LOCAL_PLAYLIST_PREFIX, chrono::Local::now().timestamp_millis());
+
+        if let Some(connection) = &self.connection {
+            let result = connection.execute(
+                "INSERT INTO playlist (id, title, author) VALUES (?1, ?2, '')",
+                params![id, title]
+            );
+
+            if let Err(e) = result {
+                error!("Failed to create playlist \"{}\": {}", title, e);
+            }
+        }
+
+        self.playlists.push(LocalPlaylist { id, title: String::from(title), videos: Vec::new() });
+    }
+
+    pub (crate) fn delete_playlist(&mut self, id: &str) {
+        if let Some(connection) = &self.connection {
+            let result = connection.execute("DELETE FROM playlist_video WHERE playlist = ?1", params![id])
+                .and_then(|_| connection.execute("DELETE FROM playlist WHERE id = ?1", params![id]));
+
+            if let Err(e) = result {
+                error!("Failed to delete playlist {}: {}", id, e);
+            }
+        }
+
+        self.playlists.retain(|playlist| playlist.id != id);
+    }
+
+    // Add a video to the end of a playlist. Videos already in the playlist aren't added again.
+    pub (crate) fn add_to_playlist(&mut self, id: &str, entry: PlaylistEntry) {
+        let Some(playlist) = self.playlists.iter_mut().find(|playlist| playlist.id == id) else {
+            return;
+        };
+
+        if playlist.videos.iter().any(|video| video.video == entry.video) {
+            return;
+        }
+
+        playlist.videos.push(entry);
+        self.save_playlist_videos(id);
+    }
+
+    pub (crate) fn remove_from_playlist(&mut self, id: &str, index: usize) {
+        let Some(playlist) = self.playlists.iter_mut().find(|playlist| playlist.id == id) else {
+            return;
+        };
+
+        if index < playlist.videos.len() {
+            playlist.videos.remove(index);
+            self.save_playlist_videos(id);
+        }
+    }
+
+    // Swap a video with the one before or after it.
+    pub (crate) fn move_in_playlist(&mut self, id: &str, index: usize, up: bool) {
+        let Some(playlist) = self.playlists.iter_mut().find(|playlist| playlist.id == id) else {
+            return;
+        };
+
+        let other = if up { index.checked_sub(1) } else { Some(index + 1) };
+
+        if let Some(other) = other.filter(|other| *other < playlist.videos.len() && index < playlist.videos.len()) {
+            playlist.videos.swap(index, other);
+            self.save_playlist_videos(id);
+        }
+    }
+
+    // Compare a Youtube playlist's videos with its last snapshot, then replace the snapshot.
+    // Returns None the first time a playlist is opened, since there's nothing to compare with.
+    pub (crate) fn update_playlist_snapshot(&self, playlist_id: &str, videos: &[SnapshotVideo]) -> Option<PlaylistDiff> {
+        let connection = self.connection.as_ref()?;
+
+        let result = connection.unchecked_transaction().and_then(|transaction| {
+            let previous = transaction
+                .prepare("SELECT video, title FROM playlist_snapshot WHERE playlist = ?1 ORDER BY position")
+                .and_then(|mut statement| {
+                    let videos = statement
+                        .query_map(params![playlist_id], |row| Ok(SnapshotVideo { id: row.get(0)?, title: row.get(1)? }))?
+                        .collect::<Result<Vec<_>, _>>();
+
+                    videos
+                })?;
+
+            transaction.execute("DELETE FROM playlist_snapshot WHERE playlist = ?1", params![playlist_id])?;
+
+            for (position, video) in videos.iter().enumerate() {
+                transaction.execute(
+                    "INSERT INTO playlist_snapshot (playlist, position, video, title) VALUES (?1, ?2, ?3, ?4)",
+                    params![playlist_id, position, video.id, video.title]
+                )?;
+            }
+
+            transaction.commit().map(|_| previous)
+        });
+
+        let previous = match result {
+            Ok(previous) if previous.is_empty() => return None,
+            Ok(previous) => previous,
+            Err(e) => {
+                error!("Failed to update the snapshot of playlist {}: {}", playlist_id, e);
+                return None;
+            }
+        };
+
+        let previous_ids: HashSet<&str> = previous.iter().map(|video| video.id.as_str()).collect();
+        let current_ids: HashSet<&str> = videos.iter().map(|video| video.id.as_str()).collect();
+
+        Some(PlaylistDiff {
+            added: videos.iter()
+                .filter(|video| !previous_ids.contains(video.id.as_str()))
+                .map(|video| video.id.clone())
+                .collect(),
+
+            removed: previous.iter()
+                .filter(|video| !current_ids.contains(video.id.as_str()))
+                .cloned()
+                .collect()
+        })
+    }
+
+    // Write a playlist's videos over the stored ones, numbered in their current order.
+    fn save_playlist_videos(&self, id: &str) {
+        let (Some(connection), Some(playlist)) = (&self.connection, self.playlist(id)) else {
+            return;
+        };
+
+        let result = connection.unchecked_transaction().and_then(|transaction| {
+            transaction.execute("DELETE FROM playlist_video WHERE playlist = ?1", params![id])?;
+
+            for (position, entry) in playlist.videos.iter().enumerate() {
+                transaction.execute(
+                    "INSERT INTO playlist_video (playlist, position, video, title, local) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![id, position, entry.video, entry.title, entry.local]
+                )?;
+            }
+
+            transaction.commit()
+        });
+
+        if let Err(e) = result {
+            error!("Failed to save playlist \"{}\": {}", playlist.title, e);
+        }
+    }
+}
+
+fn add_missing_columns(connection: &Connection) -> rusqlite::Result<()> {
+    for (table, column, definition) in ADDED_COLUMNS {
+        let exists: bool = connection.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2",
+            params![table, column],
+            |row| row.get(0)
+        )?;
+
+        if !exists {
+            connection.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))?;
+        }
+    }
+
+    Ok(())
+}
+
+// Downloaded files by video id. Files that were moved or deleted since they were downloaded are left out.
+fn load_files(connection: &Connection) -> HashMap<String, String> {
+    let result = connection
+        .prepare("SELECT id, file_path FROM video")
+        .and_then(|mut statement| {
+            let files = statement
+                .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+                .collect::<Result<Vec<_>, _>>();
+
+            files
+        });
+
+    match result {
+        Ok(files) => files.into_iter()
+            .filter(|(_, path)| std::path::Path::new(path).exists())
+            .collect(),
+        Err(e) => {
+            error!("Failed to read downloaded files: {}", e);
+            HashMap::new()
+        }
+    }
+}
+
+fn load_progress(connection: &Connection) -> HashMap<String, (f64, f64)> {
+    let result = connection
+        .prepare("SELECT video, position, duration FROM history")
+        .and_then(|mut statement| {
+            let progress = statement
+                .query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))?
+                .collect::<Result<HashMap<_, _>, _>>();
+
+            progress
+        });
+
+    result.unwrap_or_else(|e| {
+        error!("Failed to read playback positions: {}", e);
+        HashMap::new()
+    })
+}
+
+// Played videos and videos marked watched, minus the ones marked unwatched.
+fn load_watched(connection: &Connection) -> HashSet<String> {
+    let result = connection
+        .prepare(
+            "SELECT video FROM history UNION SELECT video FROM watched WHERE watched = 1
+                EXCEPT SELECT video FROM watched WHERE watched = 0"
+        )
+        .and_then(|mut statement| {
+            let videos = statement
+                .query_map([], |row| row.get(0))?
+                .collect::<Result<HashSet<_>, _>>();
+
+            videos
+        });
+
+    result.unwrap_or_else(|e| {
+        error!("Failed to read watched videos: {}", e);
+        HashSet::new()
+    })
+}
+
+fn load_subscriptions(connection: &Connection) -> Vec<SubscribedChannel> {
+    let result = connection
+        .prepare("SELECT id, name, thumbnail FROM subscription ORDER BY subscribed_at")
+        .and_then(|mut statement| {
+            let channels = statement
+                .query_map([], |row| Ok(SubscribedChannel {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    thumbnail: row.get(2)?
+                }))?
+                .collect::<Result<Vec<_>, _>>();
+
+            channels
+        });
+
+    result.unwrap_or_else(|e| {
+        error!("Failed to read subscriptions: {}", e);
+        Vec::new()
+    })
+}
+
+// Group names in the order they were made, and the group of each channel.
+fn load_groups(connection: &Connection) -> (Vec<String>, HashMap<String, String>) {
+    let groups = connection
+        .prepare("SELECT name FROM subscription_group ORDER BY rowid")
+        .and_then(|mut statement| {
+            let groups = statement
+                .query_map([], |row| row.get(0))?
+                .collect::<Result<Vec<_>, _>>();
+
+            groups
+        });
+
+    let channels = connection
+        .prepare("SELECT channel, name FROM subscription_group_channel")
+        .and_then(|mut statement| {
+            let channels = statement
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<HashMap<_, _>, _>>();
+
+            channels
+        });
+
+    match (groups, channels) {
+        (Ok(groups), Ok(channels)) => (groups, channels),
+        (Err(e), _) | (_, Err(e)) => {
+            error!("Failed to read subscription groups: {}", e);
+            (Vec::new(), HashMap::new())
+        }
+    }
+}
+
+// Preferences with an unreadable quality still keep their speed and volume.
+fn load_preferences(connection: &Connection) -> HashMap<String, ChannelPreference> {
+    let result = connection
+        .prepare("SELECT channel, speed, quality, volume_offset FROM channel_preference")
+        .and_then(|mut statement| {
+            let preferences = statement
+                .query_map([], |row| Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<f64>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, f64>(3)?
+                )))?
+                .collect::<Result<Vec<_>, _>>();
+
+            preferences
+        });
+
+    let rows = result.unwrap_or_else(|e| {
+        error!("Failed to read channel preferences: {}", e);
+        Vec::new()
+    });
+
+    rows.into_iter()
+        .map(|(channel, speed, quality, volume_offset)| {
+            let quality = quality.and_then(|quality| match serde_json::from_str(&quality) {
+                Ok(quality) => Some(quality),
+                Err(e) => {
+                    warn!("Ignoring unreadable quality for {}: {}", channel, e);
+                    None
+                }
+            });
+
+            (channel, ChannelPreference { speed: speed.map(PlaybackSpeed), quality, volume_offset })
+        })
+        .collect()
+}
+
+fn load_favorites(connection: &Connection) -> Vec<Favorite> {
+    let result = connection
+        .prepare("SELECT id, kind, title, thumbnail FROM favorite ORDER BY added_at")
+        .and_then(|mut statement| {
+            let favorites = statement
+                .query_map([], |row| Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, Option<String>>(3)?
+                )))?
+                .collect::<Result<Vec<_>, _>>();
+
+            favorites
+        });
+
+    let rows = result.unwrap_or_else(|e| {
+        error!("Failed to read favorites: {}", e);
+        Vec::new()
+    });
+
+    // Rows of an unknown kind can't be opened, so they're skipped.
+    rows.into_iter()
+        .filter_map(|(id, kind, title, thumbnail)| match FavoriteKind::from_str(&kind) {
+            Some(kind) => Some(Favorite { id, kind, title, thumbnail }),
+            None => {
+                warn!("Skipping favorite \"{}\" of unknown kind \"{}\"", title, kind);
+                None
+            }
+        })
+        .collect()
+}
+
+fn load_playlists(connection: &Connection) -> Vec<LocalPlaylist> {
+    let result = connection
+        .prepare("SELECT id, title FROM playlist WHERE id LIKE ?1 ORDER BY rowid")
+        .and_then(|mut statement| {
+            let playlists = statement
+                .query_map(params![format!("{}%", LOCAL_PLAYLIST_PREFIX)], |row| Ok(LocalPlaylist {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    videos: Vec::new()
+                }))?
+                .collect::<Result<Vec<_>, _>>();
+
+            playlists
+        })
+        .and_then(|mut playlists| {
+            let mut statement = connection.prepare(
+                "SELECT video, title, local FROM playlist_video WHERE playlist = ?1 ORDER BY position"
+            )?;
+
+            for playlist in playlists.iter_mut() {
+                playlist.videos = statement
+                    .query_map(params![playlist.id], |row| Ok(PlaylistEntry {
+                        video: row.get(0)?,
+                        title: row.get(1)?,
+                        local: row.get(2)?
+                    }))?
+                    .collect::<Result<Vec<_>, _>>()?;
+            }
+
+            Ok(playlists)
+        });
+
+    result.unwrap_or_else(|e| {
+        error!("Failed to read playlists: {}", e);
+        Vec::new()
+    })
+}
+
+fn load_allowed_channels(connection: &Connection) -> Vec<(String, String)> {
+    let result = connection
+        .prepare("SELECT id, name FROM allowed_channel ORDER BY rowid")
+        .and_then(|mut statement| {
+            let channels = statement
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<Vec<_>, _>>();
+
+            channels
+        });
+
+    result.unwrap_or_else(|e| {
+        error!("Failed to read allowed channels: {}", e);
+        Vec::new()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Archive;
+
+    #[test]
+    fn test_watched() {
+        let mut archive = Archive::in_memory();
+
+        archive.add_watch("dQw4w9WgXcQ", "Growing pomelos at home", false);
+        archive.set_watched("pomeloDemo02", true);
+        assert!(archive.is_watched("dQw4w9WgXcQ"));
+        assert!(archive.is_watched("pomeloDemo02"));
+
+        // Marking a played video unwatched sticks, even though it's still in the history.
+        archive.set_watched("dQw4w9WgXcQ", false);
+        let watched = super::load_watched(archive.connection.as_ref().unwrap());
+        assert!(!watched.contains("dQw4w9WgXcQ"));
+        assert!(watched.contains("pomeloDemo02"));
+
+        // Playing it again makes it watched, until the history is cleared. Videos marked by hand stay watched.
+        archive.add_watch("dQw4w9WgXcQ", "Growing pomelos at home", false);
+        assert!(archive.is_watched("dQw4w9WgXcQ"));
+
+        archive.clear_watch_history();
+        assert!(!archive.is_watched("dQw4w9WgXcQ"));
+        assert!(archive.is_watched("pomeloDemo02"));
+    }
+
+    #[test]
+    fn test_watch_time() {
+        let mut archive = Archive::in_memory();
+        let gardening = Some(("UCpomeloDemoChannel0000001", "Pomelo Gardening"));
+
+        archive.add_watch_time("dQw4w9WgXcQ", gardening, 60.0);
+        archive.add_watch_time("dQw4w9WgXcQ", gardening, 30.0);
+        archive.add_watch_time("pomeloDemo02", Some(("UCpomeloDemoChannel0000002", "Citrus Kitchen")), 120.0);
+        archive.add_watch_time("file:///home/pomelo/video.mp4", None, 600.0);
+
+        assert_eq!(archive.daily_watch_time(30).iter().map(|(_, seconds)| seconds).sum::<f64>(), 810.0);
+        assert_eq!(
+            archive.top_channels(10),
+            vec![(String::from("Citrus Kitchen"), 120.0), (String::from("Pomelo Gardening"), 90.0)]
+        );
+
+        archive.clear_watch_history();
+        assert!(archive.daily_watch_time(30).is_empty());
+    }
+
+    #[test]
+    fn test_watch_progress_in_old_archive() {
+        // History table from before durations were saved.
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        connection.execute_batch(
+            "CREATE TABLE history (
+                video TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                local INTEGER NOT NULL,
+                watched_at INTEGER NOT NULL,
+                position REAL NOT NULL DEFAULT 0
+            );
+            INSERT INTO history VALUES ('dQw4w9WgXcQ', 'Growing pomelos at home', 0, 0, 42);"
+        ).unwrap();
+
+        let mut archive = Archive::with_connection(Ok(connection));
+        assert_eq!(archive.watch_progress("dQw4w9WgXcQ"), Some((42.0, 0.0)));
+
+        archive.set_watch_position("dQw4w9WgXcQ", 60.0, 212.0);
+        let progress = super::load_progress(archive.connection.as_ref().unwrap());
+        assert_eq!(progress.get("dQw4w9WgXcQ"), Some(&(60.0, 212.0)));
+
+        // Too close to the start to resume from.
+        archive.set_watch_position("dQw4w9WgXcQ", 2.0, 212.0);
+        assert_eq!(archive.watch_progress("dQw4w9WgXcQ"), None);
+    }
+
+    #[test]
+    fn test_subscription_groups() {
+        let mut archive = Archive::in_memory();
+
+        archive.create_group("Gardening");
+        archive.set_channel_group("UCpomeloDemoChannel0000001", Some("Gardening"));
+        archive.set_channel_group("UCpomeloDemoChannel0000002", Some("Cooking"));
+        assert_eq!(archive.channel_group("UCpomeloDemoChannel0000001"), Some("Gardening"));
+
+        // Channels can only be put in groups that exist.
+        assert_eq!(archive.channel_group("UCpomeloDemoChannel0000002"), None);
+
+        archive.delete_group("Gardening");
+        assert!(archive.subscription_groups().is_empty());
+        assert_eq!(archive.channel_group("UCpomeloDemoChannel0000001"), None);
+        assert!(super::load_groups(archive.connection.as_ref().unwrap()).1.is_empty());
+    }
+
+    #[test]
+    fn test_playlist_snapshot() {
+        use super::SnapshotVideo;
+
+        let archive = Archive::in_memory();
+        let video = |id: &str| SnapshotVideo { id: String::from(id), title: format!("Pomelo video {}", id) };
+
+        assert!(archive.update_playlist_snapshot("PLpomelo", &[video("a"), video("b")]).is_none());
+
+        let diff = archive.update_playlist_snapshot("PLpomelo", &[video("b"), video("c")]).unwrap();
+        assert_eq!(diff.added.into_iter().collect::<Vec<_>>(), vec![String::from("c")]);
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].id, "a");
+    }
+}
@@ -1,33 +1,193 @@
-use std::collections::HashMap;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
 use iced::widget::image::Handle;
+use log::{info, warn};
+
+// Folder thumbnails are saved to, so they don't need to be downloaded again next run.
+const THUMBNAIL_DIR: &str = "./cache/thumbnails";
+
+// A thumbnail saved in the thumbnail folder.
+struct DiskThumbnail {
+    size: u64,
+    saved: SystemTime,
+
+    // Last time the thumbnail was shown, for deciding what to delete when the folder is full.
+    // Thumbnails are shown while drawing, which can't change the cache, so this is a Cell.
+    // Usage isn't kept between runs, so thumbnails from earlier runs start out at the time they were saved.
+    last_used: Cell<SystemTime>
+}
 
 // Stores items loaded from youtube so that they won't need to be loaded again.
+// Thumbnails are also saved to disk, up to a size limit. The least recently shown ones are deleted first.
 pub (crate) struct PomeloCache {
-    // Maps a video, channel, or playlist id to a thumbnail image.
+    // Maps a video, channel, or playlist id to a thumbnail image, and when it was downloaded.
     // The length of each type of id is different, so there shouldn't be any conflicts.
-    thumbnails: HashMap<String, Handle>
+    thumbnails: HashMap<String, (Handle, Instant)>,
+
+    // Ids whose thumbnails couldn't be downloaded, these get a placeholder instead.
+    failed_thumbnails: HashSet<String>,
+
+    // Thumbnails on disk, by file name without the extension, and the most space they can take up in bytes.
+    disk: HashMap<String, DiskThumbnail>,
+    max_disk_size: u64
 }
 
 impl PomeloCache {
-    pub (crate) fn new() -> Self {
-        Self {
-            thumbnails: HashMap::new()
-        }
-    }
+    pub (crate) fn new(max_disk_size: u64) -> Self {
+        let mut cache = Self {
+            thumbnails: HashMap::new(),
+            failed_thumbnails: HashSet::new(),
+            disk: load_disk_thumbnails(),
+            max_disk_size
+        };
 
-    pub (crate) fn thumbnails(&self) -> &HashMap<String, Handle> {
-        &self.thumbnails
+        cache.evict();
+        cache
     }
 
     pub (crate) fn has_thumbnail(&self, id: &str) -> bool {
-        self.thumbnails.contains_key(id)
+        self.thumbnails.contains_key(id) || self.disk.contains_key(&file_stem(id))
+    }
+
+    // Thumbnails older than the max age are still shown, but should be downloaded again.
+    pub (crate) fn thumbnail_is_stale(&self, id: &str, max_age: Duration) -> bool {
+        match self.thumbnails.get(id) {
+            Some((_, fetched)) => fetched.elapsed() > max_age,
+            None => self.disk.get(&file_stem(id))
+                .is_some_and(|thumbnail| thumbnail.saved.elapsed().unwrap_or_default() > max_age)
+        }
     }
 
     pub (crate) fn get_thumbnail(&self, id: &str) -> Option<Handle> {
-        self.thumbnails.get(id).cloned()
+        if let Some((handle, _)) = self.thumbnails.get(id) {
+            return Some(handle.clone());
+        }
+
+        let stem = file_stem(id);
+        let thumbnail = self.disk.get(&stem)?;
+
+        thumbnail.last_used.set(SystemTime::now());
+        Some(Handle::from_path(thumbnail_path(&stem)))
     }
 
     pub (crate) fn add_thumbnail(&mut self, id: String, handle: Handle) {
-        self.thumbnails.insert(id, handle);
+        if let Handle::Bytes(_, bytes) = &handle {
+            self.save_thumbnail(&id, bytes);
+        }
+
+        self.failed_thumbnails.remove(&id);
+        self.thumbnails.insert(id, (handle, Instant::now()));
+    }
+
+    pub (crate) fn thumbnail_failed(&self, id: &str) -> bool {
+        self.failed_thumbnails.contains(id)
+    }
+
+    // A failed refresh keeps the old thumbnail.
+    pub (crate) fn add_failed_thumbnail(&mut self, id: String) {
+        if !self.has_thumbnail(&id) {
+            self.failed_thumbnails.insert(id);
+        }
+    }
+
+    // Space taken up by thumbnails on disk, in bytes.
+    pub (crate) fn disk_size(&self) -> u64 {
+        self.disk.values().map(|thumbnail| thumbnail.size).sum()
+    }
+
+    pub (crate) fn set_max_disk_size(&mut self, bytes: u64) {
+        self.max_disk_size = bytes;
+        self.evict();
+    }
+
+    // Delete every saved thumbnail, and forget the ones in memory so they're downloaded again.
+    pub (crate) fn clear(&mut self) {
+        info!("Clearing thumbnail cache.");
+
+        for stem in self.disk.keys() {
+            if let Err(e) = std::fs::remove_file(thumbnail_path(stem)) {
+                warn!("Failed to delete cached thumbnail {}: {}", stem, e);
+            }
+        }
+
+        self.disk.clear();
+        self.thumbnails.clear();
+        self.failed_thumbnails.clear();
+    }
+
+    fn save_thumbnail(&mut self, id: &str, bytes: &[u8]) {
+        let stem = file_stem(id);
+
+        let result = std::fs::create_dir_all(THUMBNAIL_DIR)
+            .and_then(|_| std::fs::write(thumbnail_path(&stem), bytes));
+
+        if let Err(e) = result {
+            warn!("Failed to save thumbnail for {}: {}", id, e);
+            return;
+        }
+
+        let now = SystemTime::now();
+
+        self.disk.insert(stem, DiskThumbnail { size: bytes.len() as u64, saved: now, last_used: Cell::new(now) });
+        self.evict();
+    }
+
+    // Delete the least recently shown thumbnails until the rest fit in the size limit.
+    fn evict(&mut self) {
+        let mut size = self.disk_size();
+
+        if size <= self.max_disk_size {
+            return;
+        }
+
+        let mut stems: Vec<(String, SystemTime, u64)> = self.disk.iter()
+            .map(|(stem, thumbnail)| (stem.clone(), thumbnail.last_used.get(), thumbnail.size))
+            .collect();
+
+        stems.sort_by_key(|(_, last_used, _)| *last_used);
+
+        for (stem, _, file_size) in stems {
+            if size <= self.max_disk_size {
+                break;
+            }
+
+            if let Err(e) = std::fs::remove_file(thumbnail_path(&stem)) {
+                warn!("Failed to delete cached thumbnail {}: {}", stem, e);
+            }
+
+            self.disk.remove(&stem);
+            size -= file_size;
+        }
     }
-}
\ No newline at end of file
+}
+
+// Ids are used as file names, with anything that isn't safe in a file name replaced.
+fn file_stem(id: &str) -> String {
+    id.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+fn thumbnail_path(stem: &str) -> PathBuf {
+    PathBuf::from(format!("{}/{}.jpg", THUMBNAIL_DIR, stem))
+}
+
+// Find the thumbnails saved by earlier runs.
+fn load_disk_thumbnails() -> HashMap<String, DiskThumbnail> {
+    std::fs::read_dir(THUMBNAIL_DIR)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            let stem = path.file_stem()?.to_string_lossy().into_owned();
+            let metadata = entry.metadata().ok().filter(|metadata| metadata.is_file())?;
+            let saved = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+
+            Some((stem, DiskThumbnail { size: metadata.len(), saved, last_used: Cell::new(saved) }))
+        })
+        .collect()
+}
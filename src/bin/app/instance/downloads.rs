@@ -0,0 +1,859 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+use log::{error, info, warn};
+
+use super::archive::ArchivedVideo;
+use super::history::HistoryEntry;
+use super::process::RunningProcess;
+use super::PomeloError;
+
+pub (crate) type DownloadId = usize;
+
+// How often the combined download speed is recorded, and how many recordings are kept (3 minutes' worth).
+const SPEED_SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+const SPEED_SAMPLES: usize = 90;
+
+// Options added to every download, so the manager can follow its progress.
+// Progress lines start with the id of the video being downloaded, since a download can have several videos.
+// Finished files are printed as JSON, so they can be added to the archive.
+pub (crate) const PROGRESS_ARGS: [&str; 8] = [
+    "-q",
+    "--no-warnings",
+    "--progress",
+    "--newline",
+    "--progress-template",
+    "download:%(info.id)s|%(progress.downloaded_bytes)s|%(progress.total_bytes)s|%(progress.fragment_index)s|%(progress.fragment_count)s|%(progress.speed)s|%(progress.eta)s",
+    "--print",
+    "after_move:archive:%(.{id,title,uploader,channel_id,thumbnail,filepath})j"
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub (crate) enum DownloadStatus {
+    Queued,
+    Running,
+    Paused,
+    Finished,
+    Failed(String)
+}
+
+// Order queued downloads are started in.
+// Shortest first starts downloads with the least video to get first, so quick ones don't wait behind long ones.
+// Downloads whose length isn't known go last. Manual uses the order set on the Downloads page.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub (crate) enum QueuePolicy {
+    #[default]
+    Fifo,
+    ShortestFirst,
+    Manual
+}
+
+impl QueuePolicy {
+    pub (crate) const ALL: [Self; 3] = [Self::Fifo, Self::ShortestFirst, Self::Manual];
+}
+
+impl std::fmt::Display for QueuePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Fifo => "First in, first out",
+            Self::ShortestFirst => "Shortest first",
+            Self::Manual => "Manual"
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+// How far along a single video of a download is, e.g. one entry of a playlist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub (crate) enum VideoState {
+    Pending,
+    Downloading,
+    Done,
+    Failed
+}
+
+// Output from a running yt-dlp process, sent by the threads reading its stdout and stderr.
+enum Output {
+    Line(String),
+    Error(String)
+}
+
+// A yt-dlp process and the output it has written so far.
+// Everything it writes is also copied to the download's log file.
+struct Process {
+    child: Box<dyn RunningProcess>,
+    output: Receiver<Output>,
+    error: Option<String>,
+    log: Option<File>
+}
+
+impl Process {
+    // Stop logging if the file can't be written to, rather than warning about every line.
+    fn log_line(&mut self, line: &str) {
+        let Some(file) = self.log.as_mut() else {
+            return;
+        };
+
+        if let Err(e) = writeln!(file, "{}", line) {
+            warn!("Failed to write to download log, no more output will be logged: {}", e);
+            self.log = None;
+        }
+    }
+}
+
+// A single yt-dlp job, e.g. a video, a playlist, or an imported list of videos.
+pub (crate) struct Download {
+    id: DownloadId,
+    title: String,
+
+    // Uploader of the video or playlist, if the download was started from its page.
+    channel: Option<String>,
+    path: String,
+    args: Vec<String>,
+
+    // Combined length of the videos in seconds, if it was known when the download was queued.
+    duration: Option<u64>,
+    status: DownloadStatus,
+    process: Option<Process>,
+
+    // Id of the video being downloaded, and the progress of its current file.
+    current_video: Option<String>,
+    progress: usize,
+    length: usize,
+
+    // Bytes per second and seconds left for the current file, if yt-dlp knows them.
+    speed: Option<f64>,
+    eta: Option<u64>,
+
+    downloaded_bytes: usize,
+    total_bytes: u64,
+    started: DateTime<Local>,
+    archived: Vec<ArchivedVideo>,
+
+    // Every video yt-dlp has started on, in order.
+    started_videos: Vec<String>,
+
+    // File with everything yt-dlp has written for this download, once it's been started.
+    // Retries and resumes are added to the same file.
+    log_path: Option<String>
+}
+
+impl Download {
+    pub (crate) fn id(&self) -> DownloadId {
+        self.id
+    }
+
+    pub (crate) fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub (crate) fn channel(&self) -> Option<&str> {
+        self.channel.as_deref()
+    }
+
+    // Id of the video or playlist to show a thumbnail for. While a playlist downloads, that's the current video.
+    // Otherwise it's the first argument that isn't an option, which is what yt-dlp was asked to download.
+    pub (crate) fn thumbnail_id(&self) -> Option<&str> {
+        self.current_video.as_deref()
+            .or_else(|| self.args.iter().find(|arg| !arg.starts_with('-')).map(String::as_str))
+    }
+
+    pub (crate) fn path(&self) -> &str {
+        &self.path
+    }
+
+    // Arguments yt-dlp is started with, not counting the progress options.
+    pub (crate) fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    pub (crate) fn duration(&self) -> Option<u64> {
+        self.duration
+    }
+
+    pub (crate) fn status(&self) -> &DownloadStatus {
+        &self.status
+    }
+
+    pub (crate) fn log_path(&self) -> Option<&str> {
+        self.log_path.as_deref()
+    }
+
+    // Queued, running, or paused.
+    pub (crate) fn is_active(&self) -> bool {
+        matches!(self.status, DownloadStatus::Queued | DownloadStatus::Running | DownloadStatus::Paused)
+    }
+
+    pub (crate) fn current_video(&self) -> Option<&str> {
+        self.current_video.as_deref()
+    }
+
+    pub (crate) fn speed(&self) -> Option<f64> {
+        self.speed
+    }
+
+    pub (crate) fn eta(&self) -> Option<u64> {
+        self.eta
+    }
+
+    // Bytes downloaded by this attempt, across all of its files.
+    pub (crate) fn total_bytes(&self) -> u64 {
+        self.total_bytes
+    }
+
+    // Files that have been finished so far.
+    pub (crate) fn finished_files(&self) -> &[ArchivedVideo] {
+        &self.archived
+    }
+
+    // Where a video of this download is at. A video that was started but never finished, and isn't
+    // being worked on anymore, failed. yt-dlp moves on to the next video when one can't be downloaded.
+    // The video a paused download stopped on is still pending, since resuming picks it up again.
+    pub (crate) fn video_state(&self, video_id: &str) -> VideoState {
+        if self.archived.iter().any(|video| video.id == video_id) {
+            VideoState::Done
+        }
+        else if self.current_video.as_deref() == Some(video_id) && self.is_active() {
+            match self.status {
+                DownloadStatus::Running => VideoState::Downloading,
+                _ => VideoState::Pending
+            }
+        }
+        else if self.started_videos.iter().any(|id| id == video_id) {
+            VideoState::Failed
+        }
+        else {
+            VideoState::Pending
+        }
+    }
+
+    // Progress of the current file, from 0.0 to 1.0.
+    pub (crate) fn fraction(&self) -> f32 {
+        if self.length == 0 {
+            0.0
+        }
+        else {
+            (self.progress as f32 / self.length as f32).min(1.0)
+        }
+    }
+
+    pub (crate) fn history_entry(&self) -> HistoryEntry {
+        let error = match &self.status {
+            DownloadStatus::Failed(e) => Some(e.clone()),
+            _ => None
+        };
+
+        HistoryEntry::new(self.title.clone(), self.path.clone(), self.started, self.total_bytes, error)
+    }
+
+    // Read everything yt-dlp has written since the last poll. Returns the number of new bytes downloaded,
+    // and whether the process has closed its output, which means it's done.
+    fn read_output(&mut self) -> (u64, bool) {
+        let mut bytes = 0;
+
+        loop {
+            let Some(process) = self.process.as_mut() else {
+                return (bytes, true);
+            };
+
+            let output = process.output.try_recv();
+
+            if let Ok(Output::Line(line) | Output::Error(line)) = &output {
+                process.log_line(line);
+            }
+
+            match output {
+                Ok(Output::Line(line)) => bytes += self.read_line(&line),
+                Ok(Output::Error(line)) => process.error = Some(line),
+                Err(TryRecvError::Empty) => return (bytes, false),
+                Err(TryRecvError::Disconnected) => return (bytes, true)
+            }
+        }
+    }
+
+    // Handle a line from stdout, either a finished file or a progress update.
+    fn read_line(&mut self, line: &str) -> u64 {
+        let line = line.trim();
+
+        if let Some(json) = line.strip_prefix("archive:") {
+            match serde_json::from_str(json) {
+                Ok(video) => self.archived.push(video),
+                Err(e) => warn!("Failed to read downloaded file info: {}", e)
+            }
+
+            return 0;
+        }
+
+        let mut parts = line.split('|');
+
+        if let Some(id) = parts.next().filter(|id| !id.is_empty()) {
+            if !self.started_videos.iter().any(|started| started == id) {
+                self.started_videos.push(String::from(id));
+            }
+
+            self.current_video = Some(String::from(id));
+        }
+
+        let parts: Vec<&str> = parts.collect();
+
+        let nums: Vec<usize> = parts.iter()
+            .map(|s| s.parse().unwrap_or_default())
+            .chain(std::iter::repeat(0))
+            .take(4)
+            .collect();
+
+        // yt-dlp writes "NA" for speed and ETA until it has an estimate.
+        self.speed = parts.get(4).and_then(|s| s.parse().ok());
+        self.eta = parts.get(5).and_then(|s| s.parse().ok());
+
+        // The count starts over for each file, so a smaller count means a new file was started.
+        let mut delta = 0;
+
+        if nums[0] != 0 {
+            delta = nums[0].checked_sub(self.downloaded_bytes).unwrap_or(nums[0]) as u64;
+            self.downloaded_bytes = nums[0];
+            self.total_bytes += delta;
+        }
+
+        // Fallback to fragments if total_bytes is 0.
+        if nums[1] != 0 {
+            self.progress = nums[0];
+            self.length = nums[1];
+        }
+        else {
+            self.progress = nums[2];
+            self.length = nums[3];
+        }
+
+        delta
+    }
+
+    // Wait for the finished process, and decide if the download worked.
+    // yt-dlp runs with warnings off, so anything written to stderr is an error.
+    fn finish(&mut self) {
+        let Some(mut process) = self.process.take() else {
+            return;
+        };
+
+        let status = process.child.wait();
+
+        self.status = match (process.error, status) {
+            (Some(e), _) => DownloadStatus::Failed(e),
+            (None, Ok(Some(0))) => DownloadStatus::Finished,
+            (None, Ok(Some(code))) => DownloadStatus::Failed(format!("yt-dlp exited with code {}", code)),
+            (None, Ok(None)) => DownloadStatus::Failed(String::from("yt-dlp was stopped before it finished")),
+            (None, Err(e)) => DownloadStatus::Failed(e.to_string())
+        };
+
+        match &self.status {
+            DownloadStatus::Failed(e) => error!("Download failed: {}: {}", self.title, e),
+            _ => info!("\"{}\" downloaded to: {}", self.title, self.path)
+        }
+    }
+}
+
+// Keeps track of every download started this session.
+// Downloads wait in a queue, and a few of them are run at once, each with its own yt-dlp process.
+#[derive(Default)]
+pub (crate) struct DownloadManager {
+    downloads: Vec<Download>,
+    next_id: DownloadId,
+
+    // Combined speed of the running downloads in bytes per second, oldest first.
+    speed_history: VecDeque<u64>,
+    last_sample: Option<Instant>,
+
+    // Folder each download's output is logged to. Nothing is logged without one.
+    log_folder: Option<String>
+}
+
+impl DownloadManager {
+    pub (crate) fn with_log_folder(mut self, folder: String) -> Self {
+        self.log_folder = Some(folder);
+        self
+    }
+
+    // Add a download to the end of the queue. It starts once there's a free slot.
+    pub (crate) fn enqueue(&mut self, title: String, channel: Option<String>, path: String, args: Vec<String>, duration: Option<u64>) -> DownloadId {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        info!("Queued download: {}", title);
+
+        self.downloads.push(Download {
+            id,
+            title,
+            channel,
+            path,
+            args,
+            duration,
+            status: DownloadStatus::Queued,
+            process: None,
+            current_video: None,
+            progress: 0,
+            length: 0,
+            speed: None,
+            eta: None,
+            downloaded_bytes: 0,
+            total_bytes: 0,
+            started: Local::now(),
+            archived: Vec::new(),
+            started_videos: Vec::new(),
+            log_path: None
+        });
+
+        id
+    }
+
+    pub (crate) fn get(&self, id: DownloadId) -> Option<&Download> {
+        self.downloads.iter().find(|download| download.id == id)
+    }
+
+    pub (crate) fn downloads(&self) -> &[Download] {
+        &self.downloads
+    }
+
+    // Number of downloads that are queued or running.
+    pub (crate) fn active(&self) -> usize {
+        self.downloads.iter().filter(|download| download.is_active()).count()
+    }
+
+    pub (crate) fn running(&self) -> usize {
+        self.downloads.iter().filter(|download| download.status == DownloadStatus::Running).count()
+    }
+
+    // Average progress of the running downloads.
+    pub (crate) fn progress(&self) -> Option<f32> {
+        let running: Vec<f32> = self.downloads.iter()
+            .filter(|download| download.status == DownloadStatus::Running)
+            .map(Download::fraction)
+            .collect();
+
+        (!running.is_empty()).then(|| running.iter().sum::<f32>() / running.len() as f32)
+    }
+
+    // Queued and paused downloads, in the order the policy would start them.
+    pub (crate) fn queue(&self, policy: QueuePolicy) -> Vec<&Download> {
+        let mut queue: Vec<&Download> = self.downloads.iter()
+            .filter(|download| matches!(download.status, DownloadStatus::Queued | DownloadStatus::Paused))
+            .collect();
+
+        match policy {
+            QueuePolicy::Fifo => queue.sort_by_key(|download| download.id),
+            QueuePolicy::ShortestFirst => queue.sort_by_key(|download| (download.duration.is_none(), download.duration, download.id)),
+            QueuePolicy::Manual => ()
+        }
+
+        queue
+    }
+
+    // The next download waiting for a slot, and the arguments to start it with.
+    pub (crate) fn next_queued(&self, policy: QueuePolicy) -> Option<(DownloadId, &[String])> {
+        self.queue(policy).into_iter()
+            .find(|download| download.status == DownloadStatus::Queued)
+            .map(|download| (download.id, download.args.as_slice()))
+    }
+
+    // Swap a waiting download with the one before or after it in the manual order.
+    pub (crate) fn move_queued(&mut self, id: DownloadId, later: bool) {
+        let waiting: Vec<usize> = self.downloads.iter()
+            .enumerate()
+            .filter(|(_, download)| matches!(download.status, DownloadStatus::Queued | DownloadStatus::Paused))
+            .map(|(i, _)| i)
+            .collect();
+
+        let Some(position) = waiting.iter().position(|i| self.downloads[*i].id == id) else {
+            return;
+        };
+
+        let other = match later {
+            true => waiting.get(position + 1),
+            false => position.checked_sub(1).and_then(|p| waiting.get(p))
+        };
+
+        if let Some(other) = other {
+            self.downloads.swap(waiting[position], *other);
+        }
+    }
+
+    // Attach the yt-dlp process for a queued download, or mark it failed if the process couldn't be started.
+    pub (crate) fn start(&mut self, id: DownloadId, result: Result<Box<dyn RunningProcess>, PomeloError>) {
+        let Some(download) = self.downloads.iter_mut().find(|download| download.id == id) else {
+            return;
+        };
+
+        download.started = Local::now();
+
+        match result {
+            Ok(mut child) => {
+                let (sender, receiver) = mpsc::channel();
+
+                if let Some(stdout) = child.take_stdout() {
+                    forward_lines(stdout, sender.clone(), Output::Line);
+                }
+
+                if let Some(stderr) = child.take_stderr() {
+                    forward_lines(stderr, sender, Output::Error);
+                }
+
+                info!("Started download: {}", download.title);
+
+                let log = self.log_folder.as_deref().and_then(|folder| {
+                    let path = download.log_path.get_or_insert_with(|| log_file_path(folder, id));
+                    open_log(folder, path, &download.args)
+                });
+
+                download.status = DownloadStatus::Running;
+                download.process = Some(Process { child, output: receiver, error: None, log });
+            },
+
+            Err(e) => {
+                error!("Failed to start download: {}: {}", download.title, e.error);
+                download.status = DownloadStatus::Failed(e.error);
+            }
+        }
+    }
+
+    // Read the output of every running download. Returns the number of new bytes downloaded,
+    // and the downloads that finished since the last poll.
+    pub (crate) fn poll(&mut self) -> (u64, Vec<DownloadId>) {
+        let mut bytes = 0;
+        let mut finished = Vec::new();
+
+        for download in self.downloads.iter_mut().filter(|download| download.status == DownloadStatus::Running) {
+            let (new_bytes, done) = download.read_output();
+            bytes += new_bytes;
+
+            if done {
+                download.finish();
+                finished.push(download.id);
+            }
+        }
+
+        self.sample_speed();
+
+        (bytes, finished)
+    }
+
+    // Record the combined speed of the running downloads, if it's time for a new sample.
+    // Nothing is recorded while no downloads are running, so the history covers the last few minutes of activity.
+    fn sample_speed(&mut self) {
+        if self.last_sample.is_some_and(|time| time.elapsed() < SPEED_SAMPLE_INTERVAL) || self.running() == 0 {
+            return;
+        }
+
+        let speed: f64 = self.downloads.iter()
+            .filter(|download| download.status == DownloadStatus::Running)
+            .filter_map(Download::speed)
+            .sum();
+
+        if self.speed_history.len() == SPEED_SAMPLES {
+            self.speed_history.pop_front();
+        }
+
+        self.speed_history.push_back(speed as u64);
+        self.last_sample = Some(Instant::now());
+    }
+
+    pub (crate) fn speed_history(&self) -> &VecDeque<u64> {
+        &self.speed_history
+    }
+
+    // Stop a download, killing its process if it's running. Returns true if the download was still active.
+    pub (crate) fn cancel(&mut self, id: DownloadId) -> bool {
+        let Some(download) = self.downloads.iter_mut().find(|download| download.id == id && download.is_active()) else {
+            return false;
+        };
+
+        if let Some(mut process) = download.process.take() {
+            match process.child.kill().and_then(|_| process.child.wait()) {
+                Ok(_) => info!("Download cancelled. Yt-dlp process successfully killed."),
+                Err(e) => error!("Failed to kill yt-dlp process: {}", e)
+            }
+        }
+
+        download.status = DownloadStatus::Failed(String::from("Cancelled by user."));
+        true
+    }
+
+    // Stop a running download, and keep it out of the queue until it's resumed.
+    // yt-dlp keeps the partly downloaded file, so resuming carries on from where it was stopped.
+    pub (crate) fn pause(&mut self, id: DownloadId) {
+        let Some(download) = self.downloads.iter_mut().find(|download| download.id == id && download.is_active()) else {
+            return;
+        };
+
+        if let Some(mut process) = download.process.take() {
+            if let Err(e) = process.child.kill().and_then(|_| process.child.wait()) {
+                error!("Failed to kill yt-dlp process: {}", e);
+            }
+        }
+
+        info!("Paused download: {}", download.title);
+
+        download.status = DownloadStatus::Paused;
+        download.speed = None;
+        download.eta = None;
+    }
+
+    // Put a paused download back in the queue.
+    pub (crate) fn resume(&mut self, id: DownloadId) {
+        if let Some(download) = self.downloads.iter_mut().find(|download| download.id == id) {
+            if download.status == DownloadStatus::Paused {
+                download.status = DownloadStatus::Queued;
+            }
+        }
+    }
+
+    // Queue a failed download again, with the same arguments. Its files from the last try were already recorded.
+    pub (crate) fn retry(&mut self, id: DownloadId) {
+        let Some(download) = self.downloads.iter_mut().find(|download| download.id == id) else {
+            return;
+        };
+
+        if !matches!(download.status, DownloadStatus::Failed(_)) {
+            return;
+        }
+
+        info!("Retrying download: {}", download.title);
+
+        download.status = DownloadStatus::Queued;
+        download.current_video = None;
+        download.progress = 0;
+        download.length = 0;
+        download.downloaded_bytes = 0;
+        download.total_bytes = 0;
+        download.archived.clear();
+        download.started_videos.clear();
+    }
+
+    // Forget downloads that have finished or failed.
+    pub (crate) fn clear_finished(&mut self) {
+        self.downloads.retain(|download| download.is_active());
+    }
+
+    // Stop everything, e.g. when Pomelo is closed.
+    pub (crate) fn cancel_all(&mut self) {
+        let ids: Vec<DownloadId> = self.downloads.iter()
+            .filter(|download| download.is_active())
+            .map(|download| download.id)
+            .collect();
+
+        for id in ids {
+            self.cancel(id);
+        }
+    }
+}
+
+// Logs are named after the time the download first started, with its id in case several start at once.
+fn log_file_path(folder: &str, id: DownloadId) -> String {
+    format!("{}/download-{}-{}.txt", folder, Local::now().format("%F-%H-%M-%S"), id)
+}
+
+// Open a download's log for adding to, starting with the arguments yt-dlp was run with.
+// Downloads still work if the log can't be opened, they just aren't logged.
+fn open_log(folder: &str, path: &str, args: &[String]) -> Option<File> {
+    let result = std::fs::create_dir_all(folder)
+        .and_then(|_| std::fs::OpenOptions::new().create(true).append(true).open(path))
+        .and_then(|mut file| {
+            writeln!(file, "[{}] yt-dlp {}", Local::now().format("%F %T"), args.join(" "))?;
+            Ok(file)
+        });
+
+    match result {
+        Ok(file) => Some(file),
+        Err(e) => {
+            warn!("Failed to open download log {}: {}", path, e);
+            None
+        }
+    }
+}
+
+// Send each line from the reader over the channel, on a separate thread. The thread ends once the reader is closed.
+fn forward_lines(reader: Box<dyn Read + Send>, sender: Sender<Output>, wrap: fn(String) -> Output) {
+    std::thread::spawn(move || {
+        for line in BufReader::new(reader).lines().map_while(Result::ok) {
+            if sender.send(wrap(line)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Cursor, Read};
+    use std::process::Command;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use crate::app::PomeloError;
+    use crate::app::instance::process::{ProcessRunner, RunningProcess};
+
+    use super::{DownloadId, DownloadManager, DownloadStatus, QueuePolicy};
+
+    // A yt-dlp process that writes the given output, then exits with the given code.
+    struct FakeProcess {
+        stdout: Option<String>,
+        stderr: Option<String>,
+        exit_code: Option<i32>,
+        killed: Arc<AtomicBool>
+    }
+
+    impl RunningProcess for FakeProcess {
+        fn take_stdout(&mut self) -> Option<Box<dyn Read + Send>> {
+            self.stdout.take().map(|out| Box::new(Cursor::new(out)) as Box<dyn Read + Send>)
+        }
+
+        fn take_stderr(&mut self) -> Option<Box<dyn Read + Send>> {
+            self.stderr.take().map(|err| Box::new(Cursor::new(err)) as Box<dyn Read + Send>)
+        }
+
+        fn kill(&mut self) -> io::Result<()> {
+            self.killed.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn wait(&mut self) -> io::Result<Option<i32>> {
+            Ok(if self.killed.load(Ordering::SeqCst) { None } else { self.exit_code })
+        }
+    }
+
+    struct FakeRunner {
+        stdout: &'static str,
+        stderr: &'static str,
+        exit_code: i32,
+        killed: Arc<AtomicBool>
+    }
+
+    impl FakeRunner {
+        fn new(stdout: &'static str, stderr: &'static str, exit_code: i32) -> Self {
+            Self { stdout, stderr, exit_code, killed: Arc::new(AtomicBool::new(false)) }
+        }
+    }
+
+    impl ProcessRunner for FakeRunner {
+        fn spawn(&self, _command: Command) -> Result<Box<dyn RunningProcess>, PomeloError> {
+            Ok(Box::new(FakeProcess {
+                stdout: Some(String::from(self.stdout)),
+                stderr: Some(String::from(self.stderr)),
+                exit_code: Some(self.exit_code),
+                killed: self.killed.clone()
+            }))
+        }
+
+        fn run(&self, _command: Command) -> Result<bool, PomeloError> {
+            Ok(self.exit_code == 0)
+        }
+    }
+
+    fn start_download(manager: &mut DownloadManager, runner: &FakeRunner) -> DownloadId {
+        let id = manager.enqueue(String::from("Test"), None, String::from("./out"), vec![String::from("abc")], None);
+        manager.start(id, runner.spawn(Command::new("yt-dlp")));
+        id
+    }
+
+    // The fake output is read on other threads, so poll until the download is done.
+    fn poll_until_finished(manager: &mut DownloadManager) -> u64 {
+        let mut bytes = 0;
+
+        for _ in 0..200 {
+            let (new_bytes, finished) = manager.poll();
+            bytes += new_bytes;
+
+            if !finished.is_empty() {
+                return bytes;
+            }
+
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+
+        panic!("Download never finished");
+    }
+
+    #[test]
+    fn test_download_progress() {
+        let runner = FakeRunner::new(
+            "abc|500|1000|0|0|2048|10\n\
+            abc|1000|1000|0|0|NA|NA\n\
+            archive:{\"id\": \"abc\", \"title\": \"Test\", \"filepath\": \"./out/Test.mp4\"}\n",
+            "",
+            0
+        );
+
+        let mut manager = DownloadManager::default();
+        let id = start_download(&mut manager, &runner);
+
+        assert_eq!(poll_until_finished(&mut manager), 1000);
+
+        let download = manager.get(id).unwrap();
+
+        assert_eq!(download.status(), &DownloadStatus::Finished);
+        assert_eq!(download.total_bytes(), 1000);
+        assert_eq!(download.finished_files().len(), 1);
+        assert_eq!(download.finished_files()[0].file_path, "./out/Test.mp4");
+    }
+
+    #[test]
+    fn test_download_errors() {
+        let mut manager = DownloadManager::default();
+
+        let id = start_download(&mut manager, &FakeRunner::new("", "ERROR: Video unavailable\n", 1));
+        poll_until_finished(&mut manager);
+        assert_eq!(manager.get(id).unwrap().status(), &DownloadStatus::Failed(String::from("ERROR: Video unavailable")));
+
+        let id = start_download(&mut manager, &FakeRunner::new("", "", 2));
+        poll_until_finished(&mut manager);
+        assert_eq!(manager.get(id).unwrap().status(), &DownloadStatus::Failed(String::from("yt-dlp exited with code 2")));
+
+        let id = manager.enqueue(String::from("Test"), None, String::from("./out"), Vec::new(), None);
+        manager.start(id, Err(PomeloError::from(String::from("yt-dlp not found"))));
+        assert_eq!(manager.get(id).unwrap().status(), &DownloadStatus::Failed(String::from("yt-dlp not found")));
+    }
+
+    #[test]
+    fn test_download_cancel() {
+        let runner = FakeRunner::new("abc|500|1000|0|0|2048|10\n", "", 0);
+
+        let mut manager = DownloadManager::default();
+        let id = start_download(&mut manager, &runner);
+
+        assert!(manager.cancel(id));
+        assert!(runner.killed.load(Ordering::SeqCst));
+        assert_eq!(manager.get(id).unwrap().status(), &DownloadStatus::Failed(String::from("Cancelled by user.")));
+
+        // Cancelled downloads aren't active anymore, so they can't be cancelled again.
+        assert!(!manager.cancel(id));
+    }
+
+    #[test]
+    fn test_queue_policy() {
+        let mut manager = DownloadManager::default();
+
+        let long = manager.enqueue(String::from("Long"), None, String::from("./out"), Vec::new(), Some(36000));
+        let unknown = manager.enqueue(String::from("Unknown"), None, String::from("./out"), Vec::new(), None);
+        let short = manager.enqueue(String::from("Short"), None, String::from("./out"), Vec::new(), Some(60));
+
+        let order = |manager: &DownloadManager, policy| -> Vec<DownloadId> {
+            manager.queue(policy).iter().map(|download| download.id()).collect()
+        };
+
+        assert_eq!(order(&manager, QueuePolicy::Fifo), vec![long, unknown, short]);
+        assert_eq!(order(&manager, QueuePolicy::ShortestFirst), vec![short, long, unknown]);
+        assert_eq!(manager.next_queued(QueuePolicy::ShortestFirst).map(|(id, _)| id), Some(short));
+
+        manager.move_queued(short, false);
+        manager.move_queued(short, false);
+        manager.move_queued(short, false);
+        assert_eq!(order(&manager, QueuePolicy::Manual), vec![short, long, unknown]);
+
+        // Paused downloads keep their place, but aren't started.
+        manager.pause(short);
+        assert_eq!(manager.next_queued(QueuePolicy::Manual).map(|(id, _)| id), Some(long));
+    }
+}
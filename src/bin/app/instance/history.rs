@@ -0,0 +1,186 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone};
+use log::{error, warn};
+
+use super::PomeloError;
+
+const HISTORY_FILE: &str = "history.json";
+
+// A single finished download, successful or not.
+// Times are stored as unix timestamps so the file doesn't depend on chrono's serde support.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub (crate) struct HistoryEntry {
+    title: String,
+    path: String,
+    started: i64,
+    duration: u64,
+    bytes: u64,
+    error: Option<String>
+}
+
+impl HistoryEntry {
+    pub (crate) fn new(title: String, path: String, started: DateTime<Local>, bytes: u64, error: Option<String>) -> Self {
+        let duration = (Local::now() - started).num_seconds().max(0) as u64;
+
+        Self {
+            title,
+            path,
+            started: started.timestamp(),
+            duration,
+            bytes,
+            error
+        }
+    }
+
+    pub (crate) fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub (crate) fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub (crate) fn started(&self) -> DateTime<Local> {
+        Local.timestamp_opt(self.started, 0)
+            .single()
+            .unwrap_or_default()
+    }
+
+    // How long the download took, in seconds.
+    pub (crate) fn duration(&self) -> u64 {
+        self.duration
+    }
+
+    pub (crate) fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    pub (crate) fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub (crate) fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+// Totals for a single week, starting on Monday.
+pub (crate) struct WeekTotals {
+    pub (crate) start: NaiveDate,
+    pub (crate) completed: usize,
+    pub (crate) failed: usize,
+    pub (crate) bytes: u64,
+    pub (crate) duration: u64
+}
+
+// Record of every download that has finished. Persistant between runs.
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+pub (crate) struct DownloadHistory {
+    entries: Vec<HistoryEntry>
+}
+
+impl DownloadHistory {
+    // Load history from the history.json file. Starts from scratch if it can't be loaded.
+    pub (crate) fn load() -> Self {
+        let result = std::fs::read_to_string(HISTORY_FILE)
+            .map_err(PomeloError::new)
+            .and_then(|buffer| serde_json::from_str(&buffer).map_err(PomeloError::new));
+
+        result.unwrap_or_else(|e| {
+            warn!("Failed to load download history: {}", e.error);
+            Self::default()
+        })
+    }
+
+    pub (crate) fn save(&self) {
+        match serde_json::to_string_pretty(self) {
+            Ok(json) => if let Err(e) = std::fs::write(HISTORY_FILE, json) {
+                error!("Failed to save download history: {}", e);
+            },
+            Err(e) => error!("Failed to save download history: {}", e)
+        }
+    }
+
+    // Add an entry and save right away, so the history survives a crash.
+    pub (crate) fn add(&mut self, entry: HistoryEntry) {
+        self.entries.push(entry);
+        self.save();
+    }
+
+    pub (crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.save();
+    }
+
+    // All entries, newest first.
+    pub (crate) fn entries(&self) -> impl Iterator<Item = &HistoryEntry> {
+        self.entries.iter().rev()
+    }
+
+    // Totals for each week with at least one download, newest first.
+    pub (crate) fn weekly_totals(&self) -> Vec<WeekTotals> {
+        let mut weeks: Vec<WeekTotals> = Vec::new();
+
+        for entry in self.entries() {
+            let date = entry.started().date_naive();
+            let start = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+
+            let index = match weeks.iter().position(|week| week.start == start) {
+                Some(index) => index,
+                None => {
+                    weeks.push(WeekTotals { start, completed: 0, failed: 0, bytes: 0, duration: 0 });
+                    weeks.len() - 1
+                }
+            };
+
+            let week = &mut weeks[index];
+
+            if entry.succeeded() {
+                week.completed += 1;
+            }
+            else {
+                week.failed += 1;
+            }
+
+            week.bytes += entry.bytes();
+            week.duration += entry.duration();
+        }
+
+        weeks.sort_by(|a, b| b.start.cmp(&a.start));
+        weeks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(day: u32, bytes: u64, failed: bool) -> HistoryEntry {
+        let started = Local.with_ymd_and_hms(2024, 1, day, 12, 0, 0).unwrap();
+
+        HistoryEntry {
+            title: String::from("Video"),
+            path: String::from("video.mp4"),
+            started: started.timestamp(),
+            duration: 10,
+            bytes,
+            error: failed.then(|| String::from("error"))
+        }
+    }
+
+    #[test]
+    fn test_weekly_totals() {
+        // Wednesday and Sunday of one week, then the following Monday.
+        let history = DownloadHistory {
+            entries: vec![entry(3, 100, false), entry(7, 50, true), entry(8, 25, false)]
+        };
+
+        let weeks = history.weekly_totals();
+        assert_eq!(weeks.len(), 2);
+
+        assert_eq!(weeks[0].start, NaiveDate::from_ymd_opt(2024, 1, 8).unwrap());
+        assert_eq!((weeks[0].completed, weeks[0].failed, weeks[0].bytes, weeks[0].duration), (1, 0, 25, 10));
+
+        assert_eq!(weeks[1].start, NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+        assert_eq!((weeks[1].completed, weeks[1].failed, weeks[1].bytes, weeks[1].duration), (1, 1, 150, 20));
+    }
+}
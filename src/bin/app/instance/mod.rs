@@ -1,5 +1,16 @@
 pub (crate) mod settings;
 pub (crate) mod cache;
+pub (crate) mod theme;
+pub (crate) mod usage;
+pub (crate) mod history;
+pub (crate) mod queue;
+pub (crate) mod sound;
+pub (crate) mod archive;
+pub (crate) mod downloads;
+pub (crate) mod process;
+
+use std::collections::HashMap;
+use std::ffi::OsStr;
 
 use log::{info, warn, error};
 
@@ -7,26 +18,105 @@ use super::PomeloError;
 
 use self::settings::PomeloSettings;
 use self::cache::PomeloCache;
+use self::theme::PomeloTheme;
+use self::usage::BandwidthUsage;
+use self::history::DownloadHistory;
+use self::queue::PlayQueue;
+use self::archive::{Archive, PendingDownload, PlayerSession};
+use self::downloads::{DownloadId, DownloadManager, PROGRESS_ARGS};
+use self::process::{ProcessRunner, RunningProcess, SystemRunner};
+
+use crate::yt_fetch::instances::{self, InvidiousInstance};
+
+// Folder where the yt-dlp executable is kept.
+const YT_DLP_DIR: &str = "./yt-dlp";
+
+// Yt-dlp's cache, and its working directory for fragments and other temporary files.
+// Keeps them out of whatever directory Pomelo was started from.
+const YT_DLP_CACHE_DIR: &str = "./yt-dlp/cache";
+const YT_DLP_WORK_DIR: &str = "./yt-dlp/work";
 
-// Readers for the yt-dlp process' stdout and stderr
-type DownloadReader = (
-    std::io::BufReader<std::process::ChildStdout>,
-    std::io::BufReader<std::process::ChildStderr>
-);
+// Folder inside the log folder where each download's yt-dlp output is written, one file per download.
+const DOWNLOAD_LOG_FOLDER: &str = "downloads";
+
+// Environment variables yt-dlp is allowed to see. It needs these to find programs, certificates, and proxies.
+const YT_DLP_ENV: &[&str] = &[
+    "PATH", "PATHEXT", "HOME", "USERPROFILE", "APPDATA", "LOCALAPPDATA", "SYSTEMROOT", "SYSTEMDRIVE", "WINDIR",
+    "LANG", "LC_ALL", "SSL_CERT_FILE", "SSL_CERT_DIR",
+    "HTTP_PROXY", "HTTPS_PROXY", "NO_PROXY", "http_proxy", "https_proxy", "no_proxy"
+];
 
 // Collection of items that'll be used during the program's runtime.
 pub (crate) struct PomeloInstance {
     settings: PomeloSettings,
     cache: PomeloCache,
-    download_process: Option<std::process::Child>
+    downloads: DownloadManager,
+    runner: Box<dyn ProcessRunner>,
+
+    // Whether yt-dlp was already checked for updates this session. The check is a network request that blocks.
+    yt_dlp_updated: bool,
+    now_playing: Option<String>,
+    fullscreen: bool,
+    mini_player: bool,
+    theme: Option<PomeloTheme>,
+    usage: BandwidthUsage,
+    history: DownloadHistory,
+    queue: PlayQueue,
+    archive: Archive,
+
+    // Archive rows of this session's unfinished downloads, and downloads left unfinished by the last session.
+    pending_rows: HashMap<DownloadId, i64>,
+    interrupted: Vec<PendingDownload>,
+
+    // What the player was playing when the last session was closed, until it's resumed or dismissed.
+    last_session: Option<PlayerSession>
 }
 
 impl PomeloInstance {
     pub (crate) fn new(settings: PomeloSettings) -> Self {
+        Self::with_archive(settings, Archive::open())
+    }
+
+    pub (crate) fn with_archive(settings: PomeloSettings, archive: Archive) -> Self {
+        let theme = settings.theme().and_then(|name| match PomeloTheme::load(name) {
+            Ok(theme) => Some(theme),
+            Err(e) => {
+                warn!("Failed to load theme \"{}\", using default: {}", name, e.error);
+                None
+            }
+        });
+
+        let interrupted = archive.pending_downloads();
+
+        if !interrupted.is_empty() {
+            info!("{} downloads were left unfinished by the last session.", interrupted.len());
+        }
+
+        let last_session = archive.player_session();
+        let cache = PomeloCache::new(settings.thumbnail_cache_size());
+
+        crate::yt_fetch::response_cache::configure(settings.response_cache_ttl(), settings.response_cache_disk());
+        crate::yt_fetch::fixtures::set_offline(settings.offline_demo().then_some(crate::yt_fetch::fixtures::DEMO_FOLDER));
+        crate::logger::configure(settings.log_limits());
+        instances::set_custom(settings.custom_instances());
+
         Self {
             settings,
-            cache: PomeloCache::new(),
-            download_process: None
+            cache,
+            downloads: DownloadManager::default().with_log_folder(format!("{}/{}", crate::LOG_DIR, DOWNLOAD_LOG_FOLDER)),
+            runner: Box::new(SystemRunner),
+            yt_dlp_updated: false,
+            now_playing: None,
+            fullscreen: false,
+            mini_player: false,
+            theme,
+            usage: BandwidthUsage::load(),
+            history: DownloadHistory::load(),
+            queue: PlayQueue::default(),
+            archive,
+            pending_rows: HashMap::new(),
+            interrupted,
+            last_session
         }
     }
 
@@ -39,6 +129,26 @@ impl PomeloInstance {
         &mut self.settings
     }
 
+    // Replace the current settings, e.g. with a backup, and save them right away.
+    pub (crate) fn restore_settings(&mut self, settings: PomeloSettings) {
+        let theme = settings.theme().map(String::from);
+
+        self.cache.set_max_disk_size(settings.thumbnail_cache_size());
+        crate::yt_fetch::response_cache::configure(settings.response_cache_ttl(), settings.response_cache_disk());
+        crate::yt_fetch::fixtures::set_offline(settings.offline_demo().then_some(crate::yt_fetch::fixtures::DEMO_FOLDER));
+        crate::logger::configure(settings.log_limits());
+        instances::set_custom(settings.custom_instances());
+
+        self.settings = settings;
+
+        // The backup's theme is kept even if it can't be loaded right now, in case the file comes back.
+        if self.set_theme(theme).is_err() {
+            self.theme = None;
+        }
+
+        self.settings.save();
+    }
+
     pub (crate) fn cache(&self) -> &PomeloCache {
         &self.cache
     }
@@ -47,75 +157,394 @@ impl PomeloInstance {
         &mut self.cache
     }
 
-    // Build and run a command for yt-dlp, returns a reader for stdout and stderr if successful.
-    pub (crate) fn create_download_process(&mut self, args: &[&str]) -> Result<DownloadReader, PomeloError> {
-        use std::process::{Command, Stdio};
+    pub (crate) fn usage(&self) -> &BandwidthUsage {
+        &self.usage
+    }
 
-        match self.yt_dlp_check() {
-            Ok(yt_dlp_path) => {
-                let mut command = &mut Command::new(yt_dlp_path);
-    
-                command = command
-                    .args(args)
-                    .stdout(Stdio::piped())
-                    .stderr(Stdio::piped());
-    
-                command.spawn()
-                .map(|mut child| {
-                    let stdout = child.stdout
-                        .take()
-                        .map(std::io::BufReader::new)
-                        .unwrap();
-    
-                    let stderr = child.stderr
-                        .take()
-                        .map(std::io::BufReader::new)
-                        .unwrap();
-    
-                    self.download_process = Some(child);
-    
-                    (stdout, stderr)
-                })
-                .map_err(PomeloError::new)
-            },
-    
-            Err(e) => Err(e)
+    pub (crate) fn usage_mut(&mut self) -> &mut BandwidthUsage {
+        &mut self.usage
+    }
+
+    pub (crate) fn archive(&self) -> &Archive {
+        &self.archive
+    }
+
+    pub (crate) fn archive_mut(&mut self) -> &mut Archive {
+        &mut self.archive
+    }
+
+    pub (crate) fn history(&self) -> &DownloadHistory {
+        &self.history
+    }
+
+    pub (crate) fn history_mut(&mut self) -> &mut DownloadHistory {
+        &mut self.history
+    }
+
+    pub (crate) fn queue(&self) -> &PlayQueue {
+        &self.queue
+    }
+
+    pub (crate) fn queue_mut(&mut self) -> &mut PlayQueue {
+        &mut self.queue
+    }
+
+    pub (crate) fn theme(&self) -> Option<&PomeloTheme> {
+        self.theme.as_ref()
+    }
+
+    // Switch to the theme with the given name, or back to the default theme if None.
+    // The setting is only changed if the theme loads, so a broken theme file doesn't get saved as the choice.
+    pub (crate) fn set_theme(&mut self, name: Option<String>) -> Result<(), PomeloError> {
+        let theme = match &name {
+            Some(name) => Some(PomeloTheme::load(name).inspect_err(|e| {
+                error!("Failed to load theme \"{}\": {}", name, e.error);
+            })?),
+            None => None
+        };
+
+        self.theme = theme;
+        self.settings.set_theme(name);
+
+        Ok(())
+    }
+
+    // Thumbnails over the new limit are deleted right away.
+    pub (crate) fn set_thumbnail_cache_size(&mut self, megabytes: u64) {
+        self.settings.set_thumbnail_cache_size(megabytes);
+        self.cache.set_max_disk_size(self.settings.thumbnail_cache_size());
+    }
+
+    // Responses older than the new TTL are thrown away right away. A TTL of zero turns the cache off.
+    pub (crate) fn set_response_cache(&mut self, minutes: u64, persist: bool) {
+        self.settings.set_response_cache_ttl(minutes);
+        self.settings.set_response_cache_disk(persist);
+
+        crate::yt_fetch::response_cache::configure(self.settings.response_cache_ttl(), persist);
+    }
+
+    // Switch to a newly fetched instance list, keeping the selected instance if it's still listed.
+    // Otherwise the first instance is used, which is the healthiest one.
+    pub (crate) fn set_invidious_instances(&mut self, list: Vec<InvidiousInstance>) {
+        let selected = instances::url(self.settings.invidious_index());
+
+        instances::set(list);
+
+        let index = instances::position(&selected).unwrap_or_else(|| {
+            info!("Instance {} is no longer listed, switching to {}.", selected, instances::url(0));
+            0
+        });
+
+        self.settings.set_invidious_index(index);
+    }
+
+    // Add an instance to the list and switch to it.
+    pub (crate) fn add_custom_instance(&mut self, url: String) {
+        info!("Adding custom Invidious instance: {}", url);
+
+        self.settings.add_custom_instance(url.clone());
+        instances::set_custom(self.settings.custom_instances());
+
+        if let Some(index) = instances::position(&url) {
+            self.settings.set_invidious_index(index);
+        }
+    }
+
+    // Remove an added instance. If it was selected, the first instance is used instead.
+    pub (crate) fn remove_custom_instance(&mut self, url: &str) {
+        let selected = instances::url(self.settings.invidious_index());
+
+        self.settings.remove_custom_instance(url);
+        instances::set_custom(self.settings.custom_instances());
+
+        self.settings.set_invidious_index(instances::position(&selected).unwrap_or(0));
+    }
+
+    // Logs over the new limits are deleted right away.
+    pub (crate) fn set_log_limits(&mut self, max_file_size: u64, max_total_size: u64, max_age: u64) {
+        self.settings.set_log_limits(max_file_size, max_total_size, max_age);
+        crate::logger::configure(self.settings.log_limits());
+    }
+
+    pub (crate) fn set_offline_demo(&mut self, offline: bool) {
+        self.settings.set_offline_demo(offline);
+        crate::yt_fetch::fixtures::set_offline(offline.then_some(crate::yt_fetch::fixtures::DEMO_FOLDER));
+    }
+
+    // Reload the current theme if its file was edited.
+    pub (crate) fn reload_theme(&mut self) {
+        let Some(name) = self.theme.as_ref()
+            .filter(|theme| theme.is_stale())
+            .map(|theme| String::from(theme.name()))
+        else {
+            return;
+        };
+
+        info!("Theme file changed, reloading: {}", name);
+
+        match PomeloTheme::load(&name) {
+            Ok(theme) => self.theme = Some(theme),
+            Err(e) => warn!("Failed to reload theme, keeping previous version: {}", e.error)
+        }
+    }
+
+    // Title of the video currently being played, if any.
+    pub (crate) fn now_playing(&self) -> Option<&str> {
+        self.now_playing.as_deref()
+    }
+
+    pub (crate) fn set_now_playing(&mut self, title: Option<String>) {
+        self.now_playing = title;
+    }
+
+    // Whether the window is in fullscreen mode. The window itself is changed by the app.
+    pub (crate) fn is_fullscreen(&self) -> bool {
+        self.fullscreen
+    }
+
+    pub (crate) fn set_fullscreen(&mut self, fullscreen: bool) {
+        self.fullscreen = fullscreen;
+    }
+
+    // Whether the mini player window is open. The video is shown there instead of in the main window while it is.
+    pub (crate) fn mini_player_open(&self) -> bool {
+        self.mini_player
+    }
+
+    pub (crate) fn set_mini_player_open(&mut self, open: bool) {
+        self.mini_player = open;
+    }
+
+    pub (crate) fn downloads(&self) -> &DownloadManager {
+        &self.downloads
+    }
+
+    // Number of downloads that are queued or running.
+    pub (crate) fn active_downloads(&self) -> usize {
+        self.downloads.active()
+    }
+
+    // Queue a download with yt-dlp. It starts right away if fewer downloads than the parallel limit are running.
+    // The download is saved to the archive until it's done, so it can be resumed after a restart.
+    // The length of its videos, if known, is used by the shortest first queue policy.
+    pub (crate) fn enqueue_download(&mut self, title: String, channel: Option<String>, path: String, args: Vec<String>, duration: Option<u64>) -> DownloadId {
+        let row = self.archive.add_pending_download(&title, &path, &args);
+        let id = self.downloads.enqueue(title, channel, path, args, duration);
+
+        if let Some(row) = row {
+            self.pending_rows.insert(id, row);
+        }
+
+        self.start_downloads();
+        id
+    }
+
+    pub (crate) fn interrupted_downloads(&self) -> &[PendingDownload] {
+        &self.interrupted
+    }
+
+    // Queue a download left unfinished by the last session.
+    // yt-dlp picks up partly downloaded files with --continue, instead of starting them over.
+    pub (crate) fn resume_interrupted(&mut self, index: usize) {
+        if index >= self.interrupted.len() {
+            return;
+        }
+
+        let pending = self.interrupted.remove(index);
+        self.archive.remove_pending_download(pending.row);
+
+        let mut args = pending.args;
+
+        if !args.iter().any(|arg| arg == "--continue") {
+            args.insert(0, String::from("--continue"));
         }
-           
+
+        if let Err(e) = std::fs::create_dir_all(&pending.path) {
+            warn!("Failed to create download folder {}: {}", pending.path, e);
+        }
+
+        self.enqueue_download(pending.title, None, pending.path, args, None);
+    }
+
+    // Forget a download left unfinished by the last session. Its partly downloaded files are left alone.
+    pub (crate) fn discard_interrupted(&mut self, index: usize) {
+        if index < self.interrupted.len() {
+            let pending = self.interrupted.remove(index);
+            self.archive.remove_pending_download(pending.row);
+        }
+    }
+
+    pub (crate) fn last_session(&self) -> Option<&PlayerSession> {
+        self.last_session.as_ref()
+    }
+
+    // Take the last session to resume it. It's saved again when Pomelo closes, if it's still playing.
+    pub (crate) fn take_last_session(&mut self) -> Option<PlayerSession> {
+        self.archive.clear_player_session();
+        self.last_session.take()
+    }
+
+    pub (crate) fn dismiss_last_session(&mut self) {
+        self.archive.clear_player_session();
+        self.last_session = None;
+    }
+
+    // Follow the progress of running downloads. Finished downloads are recorded, and queued ones take their place.
+    pub (crate) fn poll_downloads(&mut self) {
+        let (bytes, finished) = self.downloads.poll();
+
+        self.usage.add_bytes(bytes);
+
+        for id in finished {
+            self.record_download(id);
+        }
+
+        self.usage.save_if_due();
+
+        self.start_downloads();
+    }
+
+    // Cancel a queued or running download.
+    pub (crate) fn cancel_download(&mut self, id: DownloadId) {
+        if self.downloads.cancel(id) {
+            self.record_download(id);
+            self.start_downloads();
+        }
+    }
+
+    // Stop a download without cancelling it, so it can be resumed later. Its slot goes to the next queued download.
+    pub (crate) fn pause_download(&mut self, id: DownloadId) {
+        self.downloads.pause(id);
+        self.start_downloads();
+    }
+
+    pub (crate) fn resume_download(&mut self, id: DownloadId) {
+        self.downloads.resume(id);
+        self.start_downloads();
+    }
+
+    // Move a waiting download earlier or later in the manual queue order.
+    pub (crate) fn move_download(&mut self, id: DownloadId, later: bool) {
+        self.downloads.move_queued(id, later);
     }
 
-    // Kill the yt-dlp process.
-    pub (crate) fn cancel_download(&mut self) {
-        if let Some(mut child) = self.download_process.take() {
-            match child.kill() {
-                Ok(_) => info!("Download cancelled. Yt-dlp process successfully killed."),
-                Err(e) => error!("Failed to kill yt-dlp process: {}", e)
+    pub (crate) fn retry_download(&mut self, id: DownloadId) {
+        self.downloads.retry(id);
+
+        if let Some(download) = self.downloads.get(id).filter(|download| download.is_active()) {
+            if let Some(row) = self.archive.add_pending_download(download.title(), download.path(), download.args()) {
+                self.pending_rows.insert(id, row);
             }
         }
+
+        self.start_downloads();
     }
-    
-    // Checks if yt-dlp exists. If it does, try to update it. If not, download it.
-    fn yt_dlp_check(&self) -> Result<String, PomeloError> {
+
+    pub (crate) fn clear_finished_downloads(&mut self) {
+        self.downloads.clear_finished();
+    }
+
+    // Kill every yt-dlp process, e.g. when Pomelo is closed.
+    // Their archive rows are kept, so they can be resumed next time.
+    pub (crate) fn cancel_all_downloads(&mut self) {
+        self.downloads.cancel_all();
+    }
+
+    // The command a download with these arguments would run, as a single line for a terminal.
+    // The cleared environment and working directory aren't part of the line, so it runs with the caller's.
+    pub (crate) fn download_command_line<S: AsRef<OsStr>>(&self, args: &[S]) -> String {
+        use crate::utils::quote_arg;
+
+        let mut command = yt_dlp_command(&yt_dlp_path());
+        command.args(self.settings.yt_dlp_config_args()).args(PROGRESS_ARGS).args(args);
+
+        std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|arg| quote_arg(&arg.to_string_lossy()))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    // Start queued downloads until the parallel limit is reached.
+    fn start_downloads(&mut self) {
+        let policy = self.settings.queue_policy();
+
+        if self.downloads.running() >= self.settings.parallel_downloads() || self.downloads.next_queued(policy).is_none() {
+            return;
+        }
+
+        let yt_dlp_path = self.yt_dlp_check();
+
+        while self.downloads.running() < self.settings.parallel_downloads() {
+            let Some((id, args)) = self.downloads.next_queued(policy) else {
+                break;
+            };
+
+            let result = yt_dlp_path.clone().and_then(|path| self.spawn_yt_dlp(&path, args));
+            let failed = result.is_err();
+
+            self.downloads.start(id, result);
+
+            if failed {
+                self.record_download(id);
+            }
+        }
+    }
+
+    // Run yt-dlp with the given arguments, with its output piped so the download manager can read it.
+    fn spawn_yt_dlp<S: AsRef<OsStr>>(&self, yt_dlp_path: &str, args: &[S]) -> Result<Box<dyn RunningProcess>, PomeloError> {
+        let mut command = yt_dlp_command(yt_dlp_path);
+        command.args(self.settings.yt_dlp_config_args()).args(PROGRESS_ARGS).args(args);
+
+        self.runner.spawn(command)
+    }
+
+    // Add a finished, failed, or cancelled download to the download history, and its files to the archive.
+    fn record_download(&mut self, id: DownloadId) {
+        let Some(download) = self.downloads.get(id) else {
+            return;
+        };
+
+        self.history.add(download.history_entry());
+        self.usage.save();
+
+        if let Some(row) = self.pending_rows.remove(&id) {
+            self.archive.remove_pending_download(row);
+        }
+
+        for video in download.finished_files() {
+            self.archive.add_video(video);
+        }
+
+        self.play_download_sound(download.status() == &downloads::DownloadStatus::Finished);
+    }
+
+    // Play the download sound, if the user turned it on.
+    pub (crate) fn play_download_sound(&self, succeeded: bool) {
+        use self::sound::SoundCue;
+
+        if !self.settings.download_sound() {
+            return;
+        }
+
+        let cue = if succeeded { SoundCue::DownloadFinished } else { SoundCue::DownloadFailed };
+        sound::play(cue, self.settings.download_sound_file().map(String::from));
+    }
+
+    // Checks if yt-dlp exists. If it does, try to update it, once per session. If not, download it.
+    fn yt_dlp_check(&mut self) -> Result<String, PomeloError> {
         use std::path::Path;
 
-        let path_str = String::from("./yt-dlp");
-    
-        if !Path::exists(Path::new(&path_str)) {
-            let _ = std::fs::create_dir(&path_str);
+        if !Path::exists(Path::new(YT_DLP_DIR)) {
+            let _ = std::fs::create_dir(YT_DLP_DIR);
         }     
     
-        let filename = if cfg!(target_os = "windows") {
-            "/yt-dlp.exe"
-        } else {
-            "/yt-dlp"
-        };
-    
-        let yt_dlp_path = [&path_str, filename].concat();
+        let yt_dlp_path = yt_dlp_path();
     
         if !Path::exists(Path::new(&yt_dlp_path)) {
             // Download yt-dlp
             info!("Yt-dlp not found. Downloading...");
-            if let Err(e) = futures::executor::block_on(youtube_dl::download_yt_dlp(&path_str)) {
+            if let Err(e) = futures::executor::block_on(youtube_dl::download_yt_dlp(YT_DLP_DIR)) {
                 error!("Failed to download yt-dlp: {}", e);
                 Err(PomeloError::new(e))
             }
@@ -125,19 +554,21 @@ impl PomeloInstance {
             }
         }
         else {
-            self.update_yt_dlp(&yt_dlp_path);
+            if !self.yt_dlp_updated {
+                self.update_yt_dlp(&yt_dlp_path);
+                self.yt_dlp_updated = true;
+            }
+
             Ok(yt_dlp_path)
         }
     }
 
-    // Update yt-dlp to latest stable or nightly release.
-    fn update_yt_dlp(&self, yt_dlp_path: &str) {
-        use std::process::Command;
-
+    // Update yt-dlp to latest stable or nightly release. Returns whether it's up to date.
+    fn update_yt_dlp(&self, yt_dlp_path: &str) -> bool {
         info!("Checking for yt-dlp update...");
 
-        let mut cmd = &mut Command::new(yt_dlp_path);
-        cmd = cmd.args(
+        let mut cmd = yt_dlp_command(yt_dlp_path);
+        cmd.args(
             [
                 "--update-to",
                 if self.settings.use_nightly() {
@@ -148,11 +579,118 @@ impl PomeloInstance {
             ]
         );
 
-        if let Err(e) = cmd.output() {
-            warn!("Failed to update yt-dlp: {}", e);
+        match self.runner.run(cmd) {
+            Ok(true) => {
+                info!("Yt-dlp up to date.");
+                true
+            },
+            Ok(false) => {
+                warn!("Yt-dlp update check failed.");
+                false
+            },
+            Err(e) => {
+                warn!("Failed to update yt-dlp: {}", e.error);
+                false
+            }
         }
-        else {
-            info!("Yt-dlp up to date.");
+    }
+}
+
+// Location of the yt-dlp executable. It might not exist yet if nothing has been downloaded.
+pub (crate) fn yt_dlp_path() -> String {
+    let filename = if cfg!(target_os = "windows") {
+        "/yt-dlp.exe"
+    } else {
+        "/yt-dlp"
+    };
+
+    [YT_DLP_DIR, filename].concat()
+}
+
+// Build a command that runs yt-dlp in its own working directory, with only the environment variables it needs.
+// Paths passed to yt-dlp should be absolute, since relative paths would be resolved against its working directory.
+pub (crate) fn yt_dlp_command(yt_dlp_path: &str) -> std::process::Command {
+    let work_dir = absolute_path(YT_DLP_WORK_DIR);
+    let cache_dir = absolute_path(YT_DLP_CACHE_DIR);
+
+    for dir in [&work_dir, &cache_dir] {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            warn!("Failed to create yt-dlp folder {}: {}", dir, e);
         }
     }
-}
\ No newline at end of file
+
+    let mut command = std::process::Command::new(absolute_path(yt_dlp_path));
+
+    command
+        .current_dir(&work_dir)
+        .env_clear()
+        .envs(YT_DLP_ENV.iter().filter_map(|key| std::env::var_os(key).map(|value| (key, value))))
+        .envs(["TMP", "TEMP", "TMPDIR"].map(|key| (key, &work_dir)))
+        .args(["--cache-dir", &cache_dir, "--paths", &format!("temp:{}", work_dir)]);
+
+    command
+}
+
+// Resolve a path against the current directory, leaving it as is if that fails.
+pub (crate) fn absolute_path(path: &str) -> String {
+    std::path::absolute(path)
+        .map(|path| path.to_string_lossy().replace('\\', "/"))
+        .unwrap_or_else(|_| String::from(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::process::Command;
+    use std::rc::Rc;
+
+    use super::*;
+
+    // Runs nothing. Records the arguments of each command, and answers with the given result, or a spawn error if None.
+    struct FakeRunner {
+        result: Option<bool>,
+        args: Rc<RefCell<Vec<String>>>
+    }
+
+    impl ProcessRunner for FakeRunner {
+        fn spawn(&self, _command: Command) -> Result<Box<dyn RunningProcess>, PomeloError> {
+            Err(PomeloError::from("spawn isn't used by the update check"))
+        }
+
+        fn run(&self, command: Command) -> Result<bool, PomeloError> {
+            self.args.borrow_mut().extend(command.get_args().map(|arg| arg.to_string_lossy().to_string()));
+            self.result.ok_or_else(|| PomeloError::from("failed to start yt-dlp"))
+        }
+    }
+
+    fn instance_with_runner(result: Option<bool>, nightly: bool) -> (PomeloInstance, Rc<RefCell<Vec<String>>>) {
+        let mut settings = PomeloSettings::default();
+        settings.set_use_nightly(nightly);
+
+        let args = Rc::new(RefCell::new(Vec::new()));
+        let mut instance = PomeloInstance::with_archive(settings, Archive::in_memory());
+        instance.runner = Box::new(FakeRunner { result, args: args.clone() });
+
+        (instance, args)
+    }
+
+    #[test]
+    fn test_update_yt_dlp() {
+        let (instance, args) = instance_with_runner(Some(true), false);
+        assert!(instance.update_yt_dlp(&yt_dlp_path()));
+        assert!(args.borrow().windows(2).any(|pair| pair == ["--update-to", "stable@latest"]));
+
+        let (instance, args) = instance_with_runner(Some(true), true);
+        assert!(instance.update_yt_dlp(&yt_dlp_path()));
+        assert!(args.borrow().windows(2).any(|pair| pair == ["--update-to", "nightly@latest"]));
+
+        // yt-dlp ran, but exited with an error.
+        let (instance, args) = instance_with_runner(Some(false), false);
+        assert!(!instance.update_yt_dlp(&yt_dlp_path()));
+        assert!(!args.borrow().is_empty());
+
+        // yt-dlp couldn't be started at all.
+        let (instance, _) = instance_with_runner(None, false);
+        assert!(!instance.update_yt_dlp(&yt_dlp_path()));
+    }
+}
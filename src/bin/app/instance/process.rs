@@ -0,0 +1,62 @@
+use std::io::{self, Read};
+use std::process::{Child, Command, Stdio};
+
+use super::PomeloError;
+
+// A started process, as far as the download manager needs it.
+pub (crate) trait RunningProcess {
+    fn take_stdout(&mut self) -> Option<Box<dyn Read + Send>>;
+    fn take_stderr(&mut self) -> Option<Box<dyn Read + Send>>;
+    fn kill(&mut self) -> io::Result<()>;
+
+    // Wait for the process to end. Returns its exit code, or None if it was ended by a signal.
+    fn wait(&mut self) -> io::Result<Option<i32>>;
+}
+
+impl RunningProcess for Child {
+    fn take_stdout(&mut self) -> Option<Box<dyn Read + Send>> {
+        self.stdout.take().map(|stdout| Box::new(stdout) as Box<dyn Read + Send>)
+    }
+
+    fn take_stderr(&mut self) -> Option<Box<dyn Read + Send>> {
+        self.stderr.take().map(|stderr| Box::new(stderr) as Box<dyn Read + Send>)
+    }
+
+    fn kill(&mut self) -> io::Result<()> {
+        Child::kill(self)
+    }
+
+    fn wait(&mut self) -> io::Result<Option<i32>> {
+        Child::wait(self).map(|status| status.code())
+    }
+}
+
+// Starts external programs. yt-dlp is run through this, so the download pipeline can be tested with fake processes.
+pub (crate) trait ProcessRunner {
+    // Start the command with its stdout and stderr piped.
+    fn spawn(&self, command: Command) -> Result<Box<dyn RunningProcess>, PomeloError>;
+
+    // Run the command to the end, returning whether it succeeded.
+    fn run(&self, command: Command) -> Result<bool, PomeloError>;
+}
+
+// Runs commands as real processes.
+pub (crate) struct SystemRunner;
+
+impl ProcessRunner for SystemRunner {
+    fn spawn(&self, mut command: Command) -> Result<Box<dyn RunningProcess>, PomeloError> {
+        command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map(|child| Box::new(child) as Box<dyn RunningProcess>)
+            .map_err(PomeloError::new)
+    }
+
+    fn run(&self, mut command: Command) -> Result<bool, PomeloError> {
+        command
+            .output()
+            .map(|output| output.status.success())
+            .map_err(PomeloError::new)
+    }
+}
@@ -0,0 +1,42 @@
+use std::collections::VecDeque;
+
+// A video waiting to be played.
+#[derive(Debug, Clone)]
+pub (crate) struct QueuedVideo {
+    pub (crate) id: String,
+    pub (crate) title: String
+}
+
+// Videos added with "Add to queue". The video player takes videos from here as its own list runs out,
+// and the main menu can start playing the queue if nothing is playing.
+#[derive(Default)]
+pub (crate) struct PlayQueue {
+    videos: VecDeque<QueuedVideo>
+}
+
+impl PlayQueue {
+    pub (crate) fn push(&mut self, id: String, title: String) {
+        self.videos.push_back(QueuedVideo { id, title });
+    }
+
+    pub (crate) fn contains(&self, id: &str) -> bool {
+        self.videos.iter().any(|video| video.id == id)
+    }
+
+    pub (crate) fn len(&self) -> usize {
+        self.videos.len()
+    }
+
+    pub (crate) fn is_empty(&self) -> bool {
+        self.videos.is_empty()
+    }
+
+    pub (crate) fn iter(&self) -> impl Iterator<Item = &QueuedVideo> {
+        self.videos.iter()
+    }
+
+    // Remove and return every queued video, in order.
+    pub (crate) fn take_all(&mut self) -> Vec<QueuedVideo> {
+        self.videos.drain(..).collect()
+    }
+}
@@ -1,15 +1,332 @@
-use log::error;
+use std::collections::HashMap;
+
+use log::{error, warn};
+
+use crate::logger::LogLimits;
+use crate::utils::Locale;
+use crate::yt_fetch::SearchType;
 
 use super::PomeloError;
+use super::downloads::QueuePolicy;
+
+// Which stream to pick when playing a video from Youtube.
+// Auto picks the stream closest to the window's height, so small windows don't waste bandwidth.
+// Height is picked from the player's quality menu, and uses the tallest stream that isn't taller than it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub (crate) enum StreamQuality {
+    Auto,
+    Highest,
+    Lowest,
+    Height(u32)
+}
+
+impl StreamQuality {
+    pub (crate) const ALL: [Self; 3] = [Self::Auto, Self::Highest, Self::Lowest];
+}
+
+impl std::fmt::Display for StreamQuality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Auto => "Auto",
+            Self::Highest => "Highest",
+            Self::Lowest => "Lowest",
+            Self::Height(height) => return write!(f, "{}p", height)
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+// How fast videos are played, as a multiple of normal speed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub (crate) struct PlaybackSpeed(pub (crate) f64);
+
+impl PlaybackSpeed {
+    pub (crate) const ALL: [Self; 8] = [
+        Self(0.5), Self(0.75), Self(1.0), Self(1.25), Self(1.5), Self(1.75), Self(2.0), Self(3.0)
+    ];
+}
+
+impl Default for PlaybackSpeed {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+impl std::fmt::Display for PlaybackSpeed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}×", self.0)
+    }
+}
+
+// Kinds of SponsorBlock segments that can be skipped during playback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub (crate) enum SponsorCategory {
+    Sponsor,
+    SelfPromo,
+    Interaction,
+    Intro,
+    Outro
+}
+
+impl SponsorCategory {
+    pub (crate) const ALL: [Self; 5] = [Self::Sponsor, Self::SelfPromo, Self::Interaction, Self::Intro, Self::Outro];
+
+    // Name of the category in the SponsorBlock API.
+    pub (crate) fn api_name(&self) -> &'static str {
+        match self {
+            Self::Sponsor => "sponsor",
+            Self::SelfPromo => "selfpromo",
+            Self::Interaction => "interaction",
+            Self::Intro => "intro",
+            Self::Outro => "outro"
+        }
+    }
+
+    pub (crate) fn from_api_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|category| category.api_name() == name)
+    }
+}
+
+impl std::fmt::Display for SponsorCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Sponsor => "Sponsor",
+            Self::SelfPromo => "Self-promotion",
+            Self::Interaction => "Interaction reminder",
+            Self::Intro => "Intro",
+            Self::Outro => "Outro"
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+// Actions that can be bound to a key. Player actions only do something while a video is playing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub (crate) enum Shortcut {
+    PlayPause,
+    SeekBack,
+    SeekForward,
+    VolumeUp,
+    VolumeDown,
+    Fullscreen,
+    NextVideo,
+    PreviousVideo,
+    Back
+}
+
+impl Shortcut {
+    pub (crate) const ALL: [Self; 9] = [
+        Self::PlayPause,
+        Self::SeekBack,
+        Self::SeekForward,
+        Self::VolumeUp,
+        Self::VolumeDown,
+        Self::Fullscreen,
+        Self::NextVideo,
+        Self::PreviousVideo,
+        Self::Back
+    ];
+
+    pub (crate) fn default_key(&self) -> &'static str {
+        match self {
+            Self::PlayPause => "Space",
+            Self::SeekBack => "Left",
+            Self::SeekForward => "Right",
+            Self::VolumeUp => "Up",
+            Self::VolumeDown => "Down",
+            Self::Fullscreen => "F",
+            Self::NextVideo => "N",
+            Self::PreviousVideo => "P",
+            Self::Back => "Escape"
+        }
+    }
+}
+
+impl std::fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::PlayPause => "Play/Pause",
+            Self::SeekBack => "Back 5 seconds",
+            Self::SeekForward => "Forward 5 seconds",
+            Self::VolumeUp => "Volume up",
+            Self::VolumeDown => "Volume down",
+            Self::Fullscreen => "Fullscreen",
+            Self::NextVideo => "Next video",
+            Self::PreviousVideo => "Previous video",
+            Self::Back => "Go back"
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+// Names of the keys that shortcuts can be bound to. "None" leaves the action unbound.
+pub (crate) const KEY_NAMES: &[&str] = &[
+    "None", "Space", "Enter", "Escape", "Backspace", "Tab",
+    "Left", "Right", "Up", "Down", "Home", "End", "PageUp", "PageDown",
+    "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M",
+    "N", "O", "P", "Q", "R", "S", "T", "U", "V", "W", "X", "Y", "Z",
+    "0", "1", "2", "3", "4", "5", "6", "7", "8", "9"
+];
+
+const SETTINGS_FILE: &str = "settings.json";
+
+// Copy of the last settings file that loaded correctly.
+const BACKUP_FILE: &str = "settings.json.bak";
+
+// New settings are written here first, then moved over the settings file, so a crash can't leave it half-written.
+const TEMP_FILE: &str = "settings.json.tmp";
+
+// A settings file that couldn't be read is moved here, so it isn't lost when the defaults get saved.
+const CORRUPT_FILE: &str = "settings.json.corrupt";
+
+// Settings left out of diagnostics reports: every file path, since they usually contain the user's name, and the PIN.
+const REDACTED_FIELDS: &[&str] = &["yt_dlp_download_folder", "yt_dlp_config_file", "download_sound_file", "restricted_pin"];
+
+// Wrong restricted mode PINs allowed before having to wait, and how the wait grows after that.
+// The wait doubles with each wrong PIN, up to the max.
+const FREE_PIN_ATTEMPTS: u32 = 3;
+const PIN_WAIT_SECS: i64 = 30;
+const MAX_PIN_WAIT_SECS: i64 = 3600;
+
+// Stream quality used while the data saver is on.
+const DATA_SAVER_QUALITY: StreamQuality = StreamQuality::Height(360);
+
+// Result of reading the settings file.
+pub (crate) enum LoadedSettings {
+    Ok(PomeloSettings),
+
+    // No settings file yet, e.g. on the first run.
+    Missing,
+
+    // The settings file couldn't be read. Includes the backup, if it could be read.
+    Corrupt(PomeloError, Option<PomeloSettings>)
+}
+
+// A search, channel, or playlist pinned to the main menu.
+// Thumbnails are stored as urls, and downloaded into the cache when needed.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub (crate) enum Pin {
+    Search { query: String, search_type: SearchType },
+    Channel { id: String, name: String, thumbnail: Option<String> },
+    Playlist { id: String, title: String, thumbnail: Option<String> }
+}
+
+impl Pin {
+    // Identifies the pinned item. Channel and playlist ids double as thumbnail cache keys.
+    pub (crate) fn key(&self) -> String {
+        match self {
+            Self::Search { query, search_type } => format!("search:{}:{}", search_type, query),
+            Self::Channel { id, .. } | Self::Playlist { id, .. } => id.clone()
+        }
+    }
+
+    pub (crate) fn title(&self) -> &str {
+        match self {
+            Self::Search { query, .. } => query,
+            Self::Channel { name, .. } => name,
+            Self::Playlist { title, .. } => title
+        }
+    }
+
+    pub (crate) fn thumbnail(&self) -> Option<&str> {
+        match self {
+            Self::Search { .. } => None,
+            Self::Channel { thumbnail, .. } | Self::Playlist { thumbnail, .. } => thumbnail.as_deref()
+        }
+    }
+}
 
 // Settings that can be changed, directly or indirectly, by the user. These settings are persistant between runs.
+// Settings missing from the file (e.g. after an update) use their default values.
 #[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub (crate) struct PomeloSettings {
     window_size: (f32, f32),
     invidious_index: usize,
+
+    // Instances added by the user, e.g. self-hosted ones. They're listed after the official instances.
+    custom_instances: Vec<String>,
+    invidious_race: bool,
+    proxy_images: bool,
+
+    // Thumbnails are downloaded again once they're this old, in minutes. Channel pictures change now and then.
+    thumbnail_max_age: u64,
+
+    // Most space thumbnails saved to disk can take up, in megabytes.
+    thumbnail_cache_size: u64,
+
+    // How long Invidious responses are reused for, in minutes, and whether they're kept between runs.
+    response_cache_ttl: u64,
+    response_cache_disk: bool,
+
+    // Log files: size before a new one is started and most space they can take up in megabytes, and days they're kept for.
+    log_max_file_size: u64,
+    log_max_total_size: u64,
+    log_max_age: u64,
+
+    // Answer searches and pages from saved responses in the fixture folder instead of an instance.
+    offline_demo: bool,
     yt_dlp_use_nightly: bool,
     yt_dlp_download_folder: String,
+    yt_dlp_config_file: Option<String>,
+    yt_dlp_ignore_config: bool,
+    ask_download_name: bool,
+    download_sound: bool,
+    download_sound_file: Option<String>,
+    parallel_downloads: usize,
+    queue_policy: QueuePolicy,
     video_skip_on_error: bool,
+
+    // Keep voices at their normal pitch when playing faster or slower. Off plays the audio resampled, like a record.
+    pitch_correction: bool,
+
+    // Play Youtube's top recommendation when the queue runs out.
+    autoplay_related: bool,
+    record_watch_history: bool,
+
+    // Leave watched videos out of the feed, channel uploads, and playlists. Toggled from the status bar.
+    hide_watched: bool,
+    now_playing_popup: bool,
+    heatmap: bool,
+    stream_quality: StreamQuality,
+
+    // Use less data on metered connections: low quality streams and fewer, smaller thumbnails.
+    data_saver: bool,
+    sponsorblock: bool,
+    sponsorblock_categories: Vec<SponsorCategory>,
+    two_pane_layout: bool,
+    locale: Locale,
+    pins: Vec<Pin>,
+    theme: Option<String>,
+
+    // Accessibility: keep the player controls on screen, and use the high-contrast theme with bigger controls.
+    reduced_motion: bool,
+    high_contrast: bool,
+
+    // Salt and hash of the PIN that unlocks restricted mode, as "salt$hash". Restricted mode is on while there is one.
+    // Deleting it from settings.json turns restricted mode off, so the PIN only stops someone who can't edit files.
+    restricted_pin: Option<String>,
+
+    // Wrong PINs entered in a row, and when another one can be tried (Unix time).
+    // Kept here so restarting Pomelo doesn't reset the wait.
+    restricted_failures: u32,
+    restricted_locked_until: i64,
+
+    // Search type used last. The queries themselves are kept in the archive.
+    search_type: SearchType,
+    record_search_history: bool,
+
+    // Key bound to each shortcut, by name (see KEY_NAMES). Shortcuts missing from the map use their default key.
+    keymap: HashMap<Shortcut, String>
+}
+
+impl Default for PomeloSettings {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PomeloSettings {
@@ -18,9 +335,49 @@ impl PomeloSettings {
         Self {
             window_size: (500.0, 500.0),
             invidious_index: 0,
+            custom_instances: Vec::new(),
+            invidious_race: false,
+            proxy_images: false,
+            thumbnail_max_age: 60,
+            thumbnail_cache_size: 200,
+            response_cache_ttl: 5,
+            response_cache_disk: false,
+            offline_demo: false,
+            log_max_file_size: 5,
+            log_max_total_size: 50,
+            log_max_age: 30,
             yt_dlp_use_nightly: false,
             yt_dlp_download_folder: String::from("./downloads"),
-            video_skip_on_error: false
+            yt_dlp_config_file: None,
+            yt_dlp_ignore_config: false,
+            ask_download_name: false,
+            download_sound: false,
+            download_sound_file: None,
+            parallel_downloads: 2,
+            queue_policy: QueuePolicy::Fifo,
+            video_skip_on_error: false,
+            pitch_correction: true,
+            autoplay_related: false,
+            record_watch_history: true,
+            hide_watched: false,
+            now_playing_popup: false,
+            heatmap: true,
+            stream_quality: StreamQuality::Auto,
+            data_saver: false,
+            sponsorblock: false,
+            sponsorblock_categories: vec![SponsorCategory::Sponsor, SponsorCategory::Intro, SponsorCategory::Outro],
+            two_pane_layout: false,
+            locale: Locale::default(),
+            pins: Vec::new(),
+            theme: None,
+            reduced_motion: false,
+            high_contrast: false,
+            restricted_pin: None,
+            restricted_failures: 0,
+            restricted_locked_until: 0,
+            search_type: SearchType::Video,
+            record_search_history: true,
+            keymap: HashMap::new()
         }   
     }
 
@@ -40,6 +397,109 @@ impl PomeloSettings {
         self.invidious_index = index;
     }
 
+    pub (crate) fn custom_instances(&self) -> &[String] {
+        &self.custom_instances
+    }
+
+    pub (crate) fn add_custom_instance(&mut self, url: String) {
+        if !self.custom_instances.contains(&url) {
+            self.custom_instances.push(url);
+        }
+    }
+
+    pub (crate) fn remove_custom_instance(&mut self, url: &str) {
+        self.custom_instances.retain(|instance| instance != url);
+    }
+
+    // Send video info requests to several instances at once, and use whichever answers first.
+    pub (crate) fn invidious_race(&self) -> bool {
+        self.invidious_race
+    }
+
+    pub (crate) fn set_invidious_race(&mut self, race: bool) {
+        self.invidious_race = race;
+    }
+
+    // Load thumbnails and avatars through the Invidious instance, instead of straight from Youtube.
+    pub (crate) fn proxy_images(&self) -> bool {
+        self.proxy_images
+    }
+
+    pub (crate) fn set_proxy_images(&mut self, proxy: bool) {
+        self.proxy_images = proxy;
+    }
+
+    pub (crate) fn thumbnail_max_age(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.thumbnail_max_age * 60)
+    }
+
+    pub (crate) fn thumbnail_max_age_minutes(&self) -> u64 {
+        self.thumbnail_max_age
+    }
+
+    pub (crate) fn set_thumbnail_max_age(&mut self, minutes: u64) {
+        self.thumbnail_max_age = minutes;
+    }
+
+    pub (crate) fn thumbnail_cache_size(&self) -> u64 {
+        self.thumbnail_cache_size * 1024 * 1024
+    }
+
+    pub (crate) fn thumbnail_cache_size_mb(&self) -> u64 {
+        self.thumbnail_cache_size
+    }
+
+    pub (crate) fn set_thumbnail_cache_size(&mut self, megabytes: u64) {
+        self.thumbnail_cache_size = megabytes;
+    }
+
+    pub (crate) fn response_cache_ttl(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.response_cache_ttl * 60)
+    }
+
+    pub (crate) fn response_cache_ttl_minutes(&self) -> u64 {
+        self.response_cache_ttl
+    }
+
+    pub (crate) fn set_response_cache_ttl(&mut self, minutes: u64) {
+        self.response_cache_ttl = minutes;
+    }
+
+    pub (crate) fn response_cache_disk(&self) -> bool {
+        self.response_cache_disk
+    }
+
+    pub (crate) fn set_response_cache_disk(&mut self, persist: bool) {
+        self.response_cache_disk = persist;
+    }
+
+    pub (crate) fn log_limits(&self) -> LogLimits {
+        LogLimits {
+            max_file_size: self.log_max_file_size * 1024 * 1024,
+            max_total_size: self.log_max_total_size * 1024 * 1024,
+            max_age: std::time::Duration::from_secs(self.log_max_age * 24 * 60 * 60)
+        }
+    }
+
+    // Log limits as shown in the settings: megabytes, megabytes, and days.
+    pub (crate) fn log_limit_values(&self) -> (u64, u64, u64) {
+        (self.log_max_file_size, self.log_max_total_size, self.log_max_age)
+    }
+
+    pub (crate) fn set_log_limits(&mut self, max_file_size: u64, max_total_size: u64, max_age: u64) {
+        self.log_max_file_size = max_file_size.max(1);
+        self.log_max_total_size = max_total_size.max(1);
+        self.log_max_age = max_age.max(1);
+    }
+
+    pub (crate) fn offline_demo(&self) -> bool {
+        self.offline_demo
+    }
+
+    pub (crate) fn set_offline_demo(&mut self, offline: bool) {
+        self.offline_demo = offline;
+    }
+
     pub (crate) fn use_nightly(&self) -> bool {
         self.yt_dlp_use_nightly
     }
@@ -56,6 +516,76 @@ impl PomeloSettings {
         self.yt_dlp_download_folder = String::from(path);
     }
 
+    pub (crate) fn yt_dlp_config_file(&self) -> Option<&str> {
+        self.yt_dlp_config_file.as_deref()
+    }
+
+    pub (crate) fn set_yt_dlp_config_file(&mut self, path: Option<String>) {
+        self.yt_dlp_config_file = path;
+    }
+
+    pub (crate) fn yt_dlp_ignore_config(&self) -> bool {
+        self.yt_dlp_ignore_config
+    }
+
+    pub (crate) fn set_yt_dlp_ignore_config(&mut self, ignore: bool) {
+        self.yt_dlp_ignore_config = ignore;
+    }
+
+    // Arguments that tell yt-dlp which config file to use.
+    // With no arguments, yt-dlp reads the user's own config file, if they have one.
+    pub (crate) fn yt_dlp_config_args(&self) -> Vec<String> {
+        match &self.yt_dlp_config_file {
+            Some(path) => vec![String::from("--config-locations"), super::absolute_path(path)],
+            None if self.yt_dlp_ignore_config => vec![String::from("--ignore-config")],
+            None => Vec::new()
+        }
+    }
+
+    // Let the user edit the file name and folder before downloading a single video.
+    pub (crate) fn ask_download_name(&self) -> bool {
+        self.ask_download_name
+    }
+
+    pub (crate) fn set_ask_download_name(&mut self, ask: bool) {
+        self.ask_download_name = ask;
+    }
+
+    // Play a sound when a download finishes or fails.
+    pub (crate) fn download_sound(&self) -> bool {
+        self.download_sound
+    }
+
+    pub (crate) fn set_download_sound(&mut self, play: bool) {
+        self.download_sound = play;
+    }
+
+    // Sound file to play instead of the built-in tones.
+    pub (crate) fn download_sound_file(&self) -> Option<&str> {
+        self.download_sound_file.as_deref()
+    }
+
+    pub (crate) fn set_download_sound_file(&mut self, path: Option<String>) {
+        self.download_sound_file = path;
+    }
+
+    // Number of yt-dlp processes that can run at once. Other downloads wait in the queue.
+    pub (crate) fn parallel_downloads(&self) -> usize {
+        self.parallel_downloads.max(1)
+    }
+
+    pub (crate) fn set_parallel_downloads(&mut self, count: usize) {
+        self.parallel_downloads = count.max(1);
+    }
+
+    pub (crate) fn queue_policy(&self) -> QueuePolicy {
+        self.queue_policy
+    }
+
+    pub (crate) fn set_queue_policy(&mut self, policy: QueuePolicy) {
+        self.queue_policy = policy;
+    }
+
     pub (crate) fn video_skip_on_error(&self) -> bool {
         self.video_skip_on_error
     }
@@ -64,36 +594,311 @@ impl PomeloSettings {
         self.video_skip_on_error = skip;
     }
 
+    pub (crate) fn pitch_correction(&self) -> bool {
+        self.pitch_correction
+    }
+
+    pub (crate) fn set_pitch_correction(&mut self, enabled: bool) {
+        self.pitch_correction = enabled;
+    }
+
+    pub (crate) fn autoplay_related(&self) -> bool {
+        self.autoplay_related
+    }
+
+    pub (crate) fn set_autoplay_related(&mut self, autoplay: bool) {
+        self.autoplay_related = autoplay;
+    }
+
+    pub (crate) fn record_watch_history(&self) -> bool {
+        self.record_watch_history
+    }
+
+    pub (crate) fn set_record_watch_history(&mut self, record: bool) {
+        self.record_watch_history = record;
+    }
+
+    pub (crate) fn hide_watched(&self) -> bool {
+        self.hide_watched
+    }
+
+    pub (crate) fn set_hide_watched(&mut self, hide: bool) {
+        self.hide_watched = hide;
+    }
+
+    // Briefly show the title, channel, and thumbnail over the video when the queue moves on to the next one.
+    pub (crate) fn now_playing_popup(&self) -> bool {
+        self.now_playing_popup
+    }
+
+    pub (crate) fn set_now_playing_popup(&mut self, show: bool) {
+        self.now_playing_popup = show;
+    }
+
+    // Show which parts of a video get replayed the most above the seek bar. Needs yt-dlp.
+    pub (crate) fn heatmap(&self) -> bool {
+        self.heatmap
+    }
+
+    pub (crate) fn set_heatmap(&mut self, show: bool) {
+        self.heatmap = show;
+    }
+
+    // Quality to stream at. The data saver overrides the picked quality without replacing it.
+    pub (crate) fn stream_quality(&self) -> StreamQuality {
+        if self.data_saver {
+            DATA_SAVER_QUALITY
+        }
+        else {
+            self.stream_quality
+        }
+    }
+
+    pub (crate) fn set_stream_quality(&mut self, quality: StreamQuality) {
+        self.stream_quality = quality;
+    }
+
+    pub (crate) fn data_saver(&self) -> bool {
+        self.data_saver
+    }
+
+    pub (crate) fn set_data_saver(&mut self, enabled: bool) {
+        self.data_saver = enabled;
+    }
+
+    // Skip segments of Youtube videos that SponsorBlock users have marked, e.g. sponsor reads.
+    pub (crate) fn sponsorblock(&self) -> bool {
+        self.sponsorblock
+    }
+
+    pub (crate) fn set_sponsorblock(&mut self, enabled: bool) {
+        self.sponsorblock = enabled;
+    }
+
+    pub (crate) fn sponsorblock_categories(&self) -> &[SponsorCategory] {
+        &self.sponsorblock_categories
+    }
+
+    pub (crate) fn set_sponsorblock_category(&mut self, category: SponsorCategory, skip: bool) {
+        self.sponsorblock_categories.retain(|c| *c != category);
+
+        if skip {
+            self.sponsorblock_categories.push(category);
+        }
+    }
+
+    pub (crate) fn two_pane_layout(&self) -> bool {
+        self.two_pane_layout
+    }
+
+    pub (crate) fn set_two_pane_layout(&mut self, two_pane: bool) {
+        self.two_pane_layout = two_pane;
+    }
+
+    pub (crate) fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    pub (crate) fn set_locale(&mut self, locale: Locale) {
+        self.locale = locale;
+    }
+
+    pub (crate) fn pins(&self) -> &[Pin] {
+        &self.pins
+    }
+
+    pub (crate) fn is_pinned(&self, pin: &Pin) -> bool {
+        let key = pin.key();
+        self.pins.iter().any(|p| p.key() == key)
+    }
+
+    // Pin the item, or unpin it if it's already pinned.
+    pub (crate) fn toggle_pin(&mut self, pin: Pin) {
+        let key = pin.key();
+
+        match self.pins.iter().position(|p| p.key() == key) {
+            Some(index) => {self.pins.remove(index);},
+            None => self.pins.push(pin)
+        }
+    }
+
+    pub (crate) fn remove_pin(&mut self, index: usize) {
+        if index < self.pins.len() {
+            self.pins.remove(index);
+        }
+    }
+
+    pub (crate) fn theme(&self) -> Option<&str> {
+        self.theme.as_deref()
+    }
+
+    pub (crate) fn set_theme(&mut self, name: Option<String>) {
+        self.theme = name;
+    }
+
+    pub (crate) fn reduced_motion(&self) -> bool {
+        self.reduced_motion
+    }
+
+    pub (crate) fn set_reduced_motion(&mut self, enabled: bool) {
+        self.reduced_motion = enabled;
+    }
+
+    // The high-contrast theme takes the place of the selected theme while this is on.
+    pub (crate) fn high_contrast(&self) -> bool {
+        self.high_contrast
+    }
+
+    pub (crate) fn set_high_contrast(&mut self, enabled: bool) {
+        self.high_contrast = enabled;
+    }
+
+    // In restricted mode, settings and links are off limits, and only allowed channels can be played.
+    pub (crate) fn is_restricted(&self) -> bool {
+        self.restricted_pin.is_some()
+    }
+
+    // Turn on restricted mode with a PIN hash made by utils::hash_pin.
+    pub (crate) fn restrict(&mut self, pin_hash: String) {
+        self.restricted_pin = Some(pin_hash);
+        self.restricted_failures = 0;
+        self.restricted_locked_until = 0;
+    }
+
+    pub (crate) fn restricted_pin(&self) -> Option<&str> {
+        self.restricted_pin.as_deref()
+    }
+
+    // Seconds left before another PIN can be tried.
+    pub (crate) fn unlock_wait(&self) -> u64 {
+        (self.restricted_locked_until - chrono::Local::now().timestamp()).max(0) as u64
+    }
+
+    // Leave restricted mode if the PIN entered was right, or count it as a miss if it wasn't.
+    // After a few wrong PINs, each one makes the wait before the next try longer.
+    pub (crate) fn finish_unlock(&mut self, correct: bool) {
+        if correct {
+            self.restricted_pin = None;
+            self.restricted_failures = 0;
+            self.restricted_locked_until = 0;
+        }
+        else {
+            self.restricted_failures += 1;
+
+            if let Some(extra) = self.restricted_failures.checked_sub(FREE_PIN_ATTEMPTS) {
+                let wait = (PIN_WAIT_SECS << extra.min(16)).min(MAX_PIN_WAIT_SECS);
+                self.restricted_locked_until = chrono::Local::now().timestamp() + wait;
+            }
+        }
+    }
+
+    pub (crate) fn search_type(&self) -> SearchType {
+        self.search_type
+    }
+
+    pub (crate) fn set_search_type(&mut self, search_type: SearchType) {
+        self.search_type = search_type;
+    }
+
+    // Keep submitted queries in the archive, so they can be picked again from the search page.
+    pub (crate) fn record_search_history(&self) -> bool {
+        self.record_search_history
+    }
+
+    pub (crate) fn set_record_search_history(&mut self, record: bool) {
+        self.record_search_history = record;
+    }
+
+    pub (crate) fn shortcut_key(&self, shortcut: Shortcut) -> &str {
+        self.keymap.get(&shortcut).map_or(shortcut.default_key(), String::as_str)
+    }
+
+    pub (crate) fn set_shortcut_key(&mut self, shortcut: Shortcut, key: &str) {
+        self.keymap.insert(shortcut, String::from(key));
+    }
+
+    // The shortcut bound to the named key, if any.
+    pub (crate) fn shortcut_for_key(&self, key: &str) -> Option<Shortcut> {
+        Shortcut::ALL.into_iter().find(|shortcut| self.shortcut_key(*shortcut) == key)
+    }
+
     // Load settings from the settings.json file, if it exists.
-    pub (crate) fn load() -> Result<Self, PomeloError> {
-        use std::io::Read;
-
-        match std::fs::File::open("settings.json") {
-            Ok(mut file) => {
-                let mut buffer = String::new();
-                match file.read_to_string(&mut buffer) {
-                    Ok(_) => serde_json::from_str::<PomeloSettings>(buffer.as_str()).map_err(PomeloError::new),
-                    Err(e) => Err(PomeloError::new(e))
+    pub (crate) fn load() -> LoadedSettings {
+        let result = std::fs::read_to_string(SETTINGS_FILE)
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => None,
+                _ => Some(PomeloError::new(e))
+            })
+            .and_then(|buffer| serde_json::from_str::<PomeloSettings>(&buffer).map_err(|e| Some(PomeloError::new(e))));
+
+        match result {
+            Ok(settings) => LoadedSettings::Ok(settings),
+            Err(None) => LoadedSettings::Missing,
+            Err(Some(e)) => {
+                if let Err(e) = std::fs::rename(SETTINGS_FILE, CORRUPT_FILE) {
+                    warn!("Failed to set aside unreadable settings file: {}", e);
                 }
-            },
-            Err(e) => Err(PomeloError::new(e))
+
+                LoadedSettings::Corrupt(e, Self::load_backup())
+            }
         }
     }
 
+    // Load the settings saved before the last save, if there are any.
+    fn load_backup() -> Option<Self> {
+        let result = std::fs::read_to_string(BACKUP_FILE)
+            .map_err(PomeloError::new)
+            .and_then(|buffer| serde_json::from_str::<PomeloSettings>(&buffer).map_err(PomeloError::new));
+
+        match result {
+            Ok(settings) => Some(settings),
+            Err(e) => {
+                warn!("Failed to load settings backup: {}", e.error);
+                None
+            }
+        }
+    }
+
+    // Serialize settings to JSON with file paths and the restricted mode PIN removed, so they can be shared in bug reports.
+    pub (crate) fn to_redacted_json(&self) -> Result<String, PomeloError> {
+        let mut value = serde_json::to_value(self).map_err(PomeloError::new)?;
+
+        for key in REDACTED_FIELDS {
+            if let Some(field) = value.get_mut(*key).filter(|field| !field.is_null()) {
+                *field = serde_json::Value::from("<redacted>");
+            }
+        }
+
+        serde_json::to_string_pretty(&value).map_err(PomeloError::new)
+    }
+
     // Serialize settings to JSON and write to file.
+    // The previous settings file is kept as a backup, and the new one only replaces it once it's fully written.
     pub (crate) fn save(&self) {
+        if let Err(e) = self.write_atomic() {
+            error!("Failed to save settings: {}", e.error);
+        }
+    }
+
+    fn write_atomic(&self) -> Result<(), PomeloError> {
         use std::io::Write;
 
-        match std::fs::File::create("settings.json") {
-            Ok(mut file) => {
-                match serde_json::to_string_pretty(self) {
-                    Ok(pretty_json) => if let Err(e) = file.write_all(pretty_json.as_bytes()) {
-                        error!("Failed to save settings: {}", e);
-                    },
-                    Err(e) => error!("Failed to save settings: {}", e)
-                }
-            },
-            Err(e) => error!("Failed to save settings: {}", e)
+        let json = serde_json::to_string_pretty(self).map_err(PomeloError::new)?;
+
+        let mut file = std::fs::File::create(TEMP_FILE).map_err(PomeloError::new)?;
+        file.write_all(json.as_bytes()).map_err(PomeloError::new)?;
+        file.sync_all().map_err(PomeloError::new)?;
+
+        // Only readable settings are worth backing up.
+        let current_is_valid = std::fs::read_to_string(SETTINGS_FILE).ok()
+            .is_some_and(|buffer| serde_json::from_str::<PomeloSettings>(&buffer).is_ok());
+
+        if current_is_valid {
+            if let Err(e) = std::fs::copy(SETTINGS_FILE, BACKUP_FILE) {
+                warn!("Failed to back up settings: {}", e);
+            }
         }
+
+        std::fs::rename(TEMP_FILE, SETTINGS_FILE).map_err(PomeloError::new)
     }
 }
\ No newline at end of file
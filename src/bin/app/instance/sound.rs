@@ -0,0 +1,63 @@
+// Short sound cues, played when a download finishes or fails.
+// Cues play on their own thread, so they never hold up the UI.
+
+use std::time::Duration;
+
+use log::warn;
+
+// Notes for each cue, as (frequency in Hz, length in ms). Finished goes up, failed goes down.
+const FINISHED_NOTES: [(f32, u64); 2] = [(660.0, 120), (880.0, 180)];
+const FAILED_NOTES: [(f32, u64); 2] = [(440.0, 150), (330.0, 250)];
+
+// Volume of the built-in tones.
+const TONE_VOLUME: f32 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub (crate) enum SoundCue {
+    DownloadFinished,
+    DownloadFailed
+}
+
+// Play the cue. A custom sound file replaces the built-in tones for both cues.
+pub (crate) fn play(cue: SoundCue, custom_file: Option<String>) {
+    std::thread::spawn(move || {
+        if let Err(e) = play_blocking(cue, custom_file.as_deref()) {
+            warn!("Failed to play sound: {}", e);
+        }
+    });
+}
+
+fn play_blocking(cue: SoundCue, custom_file: Option<&str>) -> Result<(), String> {
+    use rodio::{Decoder, OutputStream, Sink, Source};
+    use rodio::source::SineWave;
+
+    let (_stream, handle) = OutputStream::try_default().map_err(|e| e.to_string())?;
+    let sink = Sink::try_new(&handle).map_err(|e| e.to_string())?;
+
+    match custom_file {
+        Some(path) => {
+            let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+            let source = Decoder::new(std::io::BufReader::new(file)).map_err(|e| e.to_string())?;
+            sink.append(source);
+        },
+
+        None => {
+            let notes = match cue {
+                SoundCue::DownloadFinished => FINISHED_NOTES,
+                SoundCue::DownloadFailed => FAILED_NOTES
+            };
+
+            for (frequency, length) in notes {
+                sink.append(
+                    SineWave::new(frequency)
+                        .take_duration(Duration::from_millis(length))
+                        .fade_in(Duration::from_millis(10))
+                        .amplify(TONE_VOLUME)
+                );
+            }
+        }
+    }
+
+    sink.sleep_until_end();
+    Ok(())
+}
@@ -0,0 +1,196 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use iced::{Color, Theme};
+use iced::theme::Palette;
+use iced::widget::slider;
+
+use super::PomeloError;
+
+// Folder where user themes are stored, each theme is a single JSON file.
+const THEME_DIR: &str = "./themes";
+
+// Layout of a theme file. Colors are hex strings, e.g. "#1e1e2e".
+#[derive(serde::Deserialize)]
+struct ThemeFile {
+    background: String,
+    text: String,
+    primary: String,
+    success: String,
+    danger: String,
+
+    #[serde(default = "default_radius")]
+    radius: f32,
+
+    #[serde(default)]
+    slider: SliderFile
+}
+
+#[derive(serde::Deserialize, Default)]
+struct SliderFile {
+    rail: Option<String>,
+    handle: Option<String>,
+    rail_width: Option<f32>,
+    handle_radius: Option<f32>
+}
+
+fn default_radius() -> f32 {
+    5.0
+}
+
+// Slider overrides from a theme file. Anything not set falls back to the default slider style.
+#[derive(Debug, Clone, Copy, Default)]
+pub (crate) struct SliderStyle {
+    rail: Option<Color>,
+    handle: Option<Color>,
+    rail_width: Option<f32>,
+    handle_radius: Option<f32>
+}
+
+impl SliderStyle {
+    pub (crate) fn style(&self, theme: &Theme, status: slider::Status) -> slider::Style {
+        let mut style = slider::default(theme, status);
+
+        if let Some(color) = self.rail {
+            style.rail.backgrounds.0 = color.into();
+        }
+
+        if let Some(width) = self.rail_width {
+            style.rail.width = width;
+        }
+
+        if let Some(color) = self.handle {
+            style.handle.background = color.into();
+        }
+
+        if let Some(radius) = self.handle_radius {
+            style.handle.shape = slider::HandleShape::Circle { radius };
+        }
+
+        style
+    }
+}
+
+// Accent color for the high-contrast theme, used for buttons and slider handles.
+const HIGH_CONTRAST_ACCENT: Color = Color::from_rgb8(0xff, 0xd7, 0x00);
+
+impl SliderStyle {
+    // Thick rail and a big handle, so the slider is easy to see and to grab.
+    pub (crate) fn high_contrast() -> Self {
+        Self {
+            rail: Some(Color::WHITE),
+            handle: Some(HIGH_CONTRAST_ACCENT),
+            rail_width: Some(8.0),
+            handle_radius: Some(12.0)
+        }
+    }
+}
+
+// White text on black, with bright accents. Used instead of the selected theme in high-contrast mode.
+pub (crate) fn high_contrast_theme() -> Theme {
+    Theme::custom(
+        String::from("High Contrast"),
+        Palette {
+            background: Color::BLACK,
+            text: Color::WHITE,
+            primary: HIGH_CONTRAST_ACCENT,
+            success: Color::from_rgb8(0x00, 0xff, 0x66),
+            danger: Color::from_rgb8(0xff, 0x40, 0x40)
+        }
+    )
+}
+
+// A user-defined theme loaded from the themes folder.
+pub (crate) struct PomeloTheme {
+    name: String,
+    theme: Theme,
+    radius: f32,
+    slider: SliderStyle,
+    modified: Option<SystemTime>
+}
+
+impl PomeloTheme {
+    // Load the theme with the given name from the themes folder.
+    pub (crate) fn load(name: &str) -> Result<Self, PomeloError> {
+        let path = theme_path(name);
+
+        let buffer = std::fs::read_to_string(&path).map_err(PomeloError::new)?;
+        let file = serde_json::from_str::<ThemeFile>(&buffer).map_err(PomeloError::new)?;
+
+        let palette = Palette {
+            background: parse_color(&file.background)?,
+            text: parse_color(&file.text)?,
+            primary: parse_color(&file.primary)?,
+            success: parse_color(&file.success)?,
+            danger: parse_color(&file.danger)?
+        };
+
+        let slider = SliderStyle {
+            rail: file.slider.rail.as_deref().map(parse_color).transpose()?,
+            handle: file.slider.handle.as_deref().map(parse_color).transpose()?,
+            rail_width: file.slider.rail_width,
+            handle_radius: file.slider.handle_radius
+        };
+
+        Ok(
+            Self {
+                name: String::from(name),
+                theme: Theme::custom(String::from(name), palette),
+                radius: file.radius,
+                slider,
+                modified: modified_time(&path)
+            }
+        )
+    }
+
+    // Check if the theme file was edited since it was loaded.
+    pub (crate) fn is_stale(&self) -> bool {
+        modified_time(&theme_path(&self.name)) != self.modified
+    }
+
+    pub (crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub (crate) fn theme(&self) -> Theme {
+        self.theme.clone()
+    }
+
+    pub (crate) fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    pub (crate) fn slider(&self) -> SliderStyle {
+        self.slider
+    }
+}
+
+// Get the names of all themes in the themes folder.
+pub (crate) fn available_themes() -> Vec<String> {
+    let mut themes: Vec<String> = match std::fs::read_dir(THEME_DIR) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+            .collect(),
+        Err(_) => Vec::new()
+    };
+
+    themes.sort();
+    themes
+}
+
+fn theme_path(name: &str) -> PathBuf {
+    Path::new(THEME_DIR).join(format!("{}.json", name))
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+fn parse_color(hex: &str) -> Result<Color, PomeloError> {
+    Color::parse(hex).ok_or_else(|| PomeloError::from(format!("Invalid theme color: {}", hex)))
+}
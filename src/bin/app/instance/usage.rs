@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use chrono::{Days, Local, Months, NaiveDate};
+use log::{error, warn};
+
+use super::PomeloError;
+
+const USAGE_FILE: &str = "usage.json";
+
+// How often usage is saved while downloading or streaming, so a crash loses at most this much of it.
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+// Number of bytes downloaded each day. Persistant between runs.
+// Days are stored as "YYYY-MM-DD" strings, so they sort by date.
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+pub (crate) struct BandwidthUsage {
+    days: BTreeMap<String, u64>,
+
+    // Whether there's usage that hasn't been saved yet, and when it was last saved.
+    #[serde(skip)]
+    unsaved: bool,
+    #[serde(skip)]
+    last_saved: Option<Instant>
+}
+
+impl BandwidthUsage {
+    // Load usage from the usage.json file. Starts from scratch if it can't be loaded.
+    pub (crate) fn load() -> Self {
+        let result = std::fs::read_to_string(USAGE_FILE)
+            .map_err(PomeloError::new)
+            .and_then(|buffer| serde_json::from_str(&buffer).map_err(PomeloError::new));
+
+        result.unwrap_or_else(|e| {
+            warn!("Failed to load bandwidth usage: {}", e.error);
+            Self::default()
+        })
+    }
+
+    pub (crate) fn save(&mut self) {
+        self.unsaved = false;
+        self.last_saved = Some(Instant::now());
+
+        match serde_json::to_string_pretty(self) {
+            Ok(json) => if let Err(e) = std::fs::write(USAGE_FILE, json) {
+                error!("Failed to save bandwidth usage: {}", e);
+            },
+            Err(e) => error!("Failed to save bandwidth usage: {}", e)
+        }
+    }
+
+    // Save if there's new usage and it hasn't been saved in a while.
+    pub (crate) fn save_if_due(&mut self) {
+        if self.unsaved && !self.last_saved.is_some_and(|time| time.elapsed() < SAVE_INTERVAL) {
+            self.save();
+        }
+    }
+
+    // Add to today's total.
+    pub (crate) fn add_bytes(&mut self, bytes: u64) {
+        if bytes > 0 {
+            *self.days.entry(day_key(today())).or_default() += bytes;
+            self.unsaved = true;
+        }
+    }
+
+    pub (crate) fn today(&self) -> u64 {
+        self.days.get(&day_key(today())).copied().unwrap_or_default()
+    }
+
+    // Total for the month containing the given date.
+    pub (crate) fn month_total(&self, date: NaiveDate) -> u64 {
+        let prefix = date.format("%Y-%m").to_string();
+
+        self.days.iter()
+            .filter(|(day, _)| day.starts_with(&prefix))
+            .map(|(_, bytes)| bytes)
+            .sum()
+    }
+
+    pub (crate) fn this_month(&self) -> u64 {
+        self.month_total(today())
+    }
+
+    pub (crate) fn last_month(&self) -> u64 {
+        today().checked_sub_months(Months::new(1))
+            .map_or(0, |date| self.month_total(date))
+    }
+
+    // Daily totals for the last n days, oldest first. Days without any usage are 0.
+    pub (crate) fn last_days(&self, n: u64) -> Vec<u64> {
+        let today = today();
+
+        (0..n).rev()
+            .filter_map(|i| today.checked_sub_days(Days::new(i)))
+            .map(|date| self.days.get(&day_key(date)).copied().unwrap_or_default())
+            .collect()
+    }
+}
+
+fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+fn day_key(date: NaiveDate) -> String {
+    date.format("%F").to_string()
+}
@@ -1,13 +1,16 @@
 mod pages;
 mod instance;
 
-use iced::window;
+use iced::{keyboard, window};
 use iced::{Size, Task};
 
-use log::warn;
+use log::{info, warn};
 
 use instance::PomeloInstance;
-use instance::settings::PomeloSettings;
+use instance::downloads::DownloadId;
+use instance::settings::{LoadedSettings, PomeloSettings};
+
+use crate::yt_fetch::instances::InvidiousInstance;
 
 // Youtube thumbnails, represented as a 2-tuple with the youtube id (String) and the image data (Handle).
 type Thumbnail = (String, iced::widget::image::Handle);
@@ -113,6 +116,54 @@ impl std::fmt::Display for DownloadQuality {
     }
 }
 
+// Subtitles saved next to downloaded videos, by language.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub (crate) enum DownloadSubtitles {
+    Off,
+    English,
+    Spanish,
+    French,
+    German,
+    All
+}
+
+impl DownloadSubtitles {
+    const ALL: [Self; 6] = [Self::Off, Self::English, Self::Spanish, Self::French, Self::German, Self::All];
+
+    // Value for yt-dlp's --sub-langs option.
+    fn languages(&self) -> Option<&str> {
+        match self {
+            Self::Off => None,
+            Self::English => Some("en.*"),
+            Self::Spanish => Some("es.*"),
+            Self::French => Some("fr.*"),
+            Self::German => Some("de.*"),
+            Self::All => Some("all")
+        }
+    }
+}
+
+impl Default for DownloadSubtitles {
+    fn default() -> Self {
+        Self::Off
+    }
+}
+
+impl std::fmt::Display for DownloadSubtitles {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Off => "None",
+            Self::English => "English",
+            Self::Spanish => "Spanish",
+            Self::French => "French",
+            Self::German => "German",
+            Self::All => "All languages"
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
 // Messages are used to update the state of the program.
 #[derive(Debug, Clone)]
 pub (crate) enum PomeloMessage {
@@ -126,18 +177,50 @@ pub (crate) enum PomeloMessage {
     VideoInfo(pages::VideoInfoMessage),
     PlaylistInfo(pages::PlaylistInfoMessage),
     Settings(pages::SettingsMessage),
+    Diagnostics(pages::DiagnosticsMessage),
+    History(pages::HistoryMessage),
+    SettingsRecovery(pages::SettingsRecoveryMessage),
+    Import(pages::ImportMessage),
+    WatchHistory(pages::WatchHistoryMessage),
+    Feed(pages::FeedMessage),
+    Library(pages::LibraryMessage),
+    Downloads(pages::DownloadsMessage),
+    RestrictedMode(pages::RestrictedModeMessage),
+    LocalPlaylists(pages::LocalPlaylistsMessage),
+    StatusBar(pages::StatusBarMessage),
 
     StartVideoDownload,
+    CopyDownloadCommand,
     SetDownloadFormat(DownloadFormat),
     SetDownloadQuality(DownloadQuality),
-    NextVideoChunk(String, Result<usize, PomeloError>),
-    VideoDownloadCancelled,
-    VideoDownloadComplete(Result<(), PomeloError>),
+    SetDownloadSubtitles(DownloadSubtitles),
+    CancelDownload(DownloadId),
+
+    // Add a video to a local playlist: playlist id, video id, and title.
+    AddToPlaylist(String, String, String),
+    PollDownloads,
     
     WindowResize((window::Id, Size)),
+    KeyPressed(keyboard::Key),
+    ToggleFullscreen,
+    ToggleMiniPlayer,
+
+    // Sent to the page that's playing, wherever it is in the stack. Used by the mini player window.
+    MiniPlayer(pages::VideoPlayerMessage),
 
     ThumbnailLoaded(Result<Thumbnail, PomeloError>),
+    ThumbnailFailed(String, PomeloError),
+    InstancesFetched(Result<Vec<InvidiousInstance>, PomeloError>),
+
+    ReloadTheme,
+    CopyToClipboard(String),
+    PlayLocalCopy(String),
+
+    // Mark a video as watched or unwatched: video id, and whether it's watched.
+    SetWatched(String, bool),
 
+    DismissError,
+    CancelLoad,
     Back,
     Home,
 
@@ -147,17 +230,30 @@ pub (crate) enum PomeloMessage {
 // The "heart" of Pomelo.
 pub (crate) struct PomeloApp {
     instance: PomeloInstance,
-    page_stack: Vec<Box<dyn pages::PomeloPage>>
+    page_stack: Vec<Box<dyn pages::PomeloPage>>,
+    window_id: window::Id,
+    mini_window: Option<window::Id>,
+
+    // Download progress last shown on the taskbar, in percent.
+    taskbar_progress: Option<u32>
 }
 
 impl PomeloApp {
-    pub (crate) fn new() -> (Self, Task<PomeloMessage>) {
+    // Opens the info page for the given video right away, if there is one.
+    pub (crate) fn new(video_id: Option<String>) -> (Self, Task<PomeloMessage>) {
         use iced::advanced::graphics::image::image_rs::ImageFormat;
             
+        let mut recovery = None;
+
         let settings = match PomeloSettings::load() {
-            Ok(s) => s,
-            Err(e) => {
+            LoadedSettings::Ok(s) => s,
+            LoadedSettings::Missing => {
+                info!("No settings file found, using defaults.");
+                PomeloSettings::new()
+            },
+            LoadedSettings::Corrupt(e, backup) => {
                 warn!("Failed to load settings, using defaults: {}", e.error);
+                recovery = Some(pages::SettingsRecoveryPage::new(e, backup));
                 PomeloSettings::new()
             }
         };
@@ -168,22 +264,62 @@ impl PomeloApp {
             icon: window::icon::from_file_data(include_bytes!("../../../icon.png"), Some(ImageFormat::Png))
                 .ok(),
             exit_on_close_request: true,
+
+            // Lets the dock match the window to pomelo.desktop, which launcher progress is sent for.
+            #[cfg(target_os = "linux")]
+            platform_specific: window::settings::PlatformSpecific {
+                application_id: String::from(crate::taskbar::APP_ID),
+                ..Default::default()
+            },
+
             ..Default::default()
         };
 
-        let (_, window) = window::open(window_settings);
+        let (window_id, window) = window::open(window_settings);
 
-        let app = PomeloApp {
+        let mut app = PomeloApp {
             instance: PomeloInstance::new(settings),
-            page_stack: vec![Box::new(pages::MainMenu {})]
+            page_stack: vec![Box::new(pages::MainMenu {})],
+            window_id,
+            mini_window: None,
+            taskbar_progress: None
         };
 
-        (app, window.map(|_| PomeloMessage::Init))
+        let mut tasks = vec![window.map(|_| PomeloMessage::Init)];
+
+        // Refresh the instance list in the background. The saved one is used until it arrives.
+        if !app.instance.settings().offline_demo() {
+            tasks.push(Task::perform(
+                async { crate::yt_fetch::instances::fetch().await.map_err(PomeloError::new) },
+                PomeloMessage::InstancesFetched
+            ));
+        }
+
+        // Ask what to do about the broken settings file before anything else.
+        if let Some(page) = recovery {
+            app.page_stack.push(Box::new(page));
+        }
+
+        if let Some(id) = video_id {
+            info!("Opening video from link: {}", id);
+
+            app.page_stack.push(Box::new(pages::VideoInfoPage::new()));
+            tasks.push(Task::done(pages::VideoInfoMessage::LoadVideo(id).into()));
+        }
+
+        (app, Task::batch(tasks))
     }
 
-    // Sets the title of the program window.
-    pub (crate) fn title(&self, _id: window::Id) -> String {
-        String::from("Pomelo")
+    // Sets the title of the program window, with the download progress if there is any.
+    pub (crate) fn title(&self, id: window::Id) -> String {
+        if Some(id) == self.mini_window {
+            return String::from("Pomelo - Mini Player");
+        }
+
+        match self.instance.downloads().progress() {
+            Some(progress) => format!("Pomelo - Downloading {:.0}%", progress * 100.0),
+            None => String::from("Pomelo")
+        }
     }
 
     // Update the state of the program.
@@ -191,21 +327,104 @@ impl PomeloApp {
         use pages::Navigation;
 
         match message {
-            PomeloMessage::WindowResize((_id, size)) => {
-                self.instance.settings_mut().set_window_size(size.width, size.height);
+            // The fullscreen size isn't saved, so the window goes back to its old size afterwards.
+            PomeloMessage::WindowResize((id, size)) => {
+                if id == self.window_id && !self.instance.is_fullscreen() {
+                    self.instance.settings_mut().set_window_size(size.width, size.height);
+                }
                 Task::none()
             },
     
             PomeloMessage::ThumbnailLoaded(result) => {
-                if let Ok((id, handle)) = result {
-                    self.instance.cache_mut().add_thumbnail(id, handle);
+                match result {
+                    Ok((id, handle)) => self.instance.cache_mut().add_thumbnail(id, handle),
+                    Err(e) => warn!("Failed to load image: {}", e.error)
                 }
                 Task::none()
             },
 
+            PomeloMessage::ThumbnailFailed(id, e) => {
+                warn!("Failed to load thumbnail for {}, using placeholder: {}", id, e.error);
+                self.instance.cache_mut().add_failed_thumbnail(id);
+                Task::none()
+            },
+
+            PomeloMessage::InstancesFetched(result) => {
+                match result {
+                    Ok(instances) => self.instance.set_invidious_instances(instances),
+                    Err(e) => warn!("Failed to fetch the Invidious instance list, using the saved one: {}", e.error)
+                }
+                Task::none()
+            },
+
+            PomeloMessage::KeyPressed(key) => self.on_key_press(key),
+            PomeloMessage::ToggleFullscreen => self.toggle_fullscreen(),
+            PomeloMessage::ToggleMiniPlayer => self.toggle_mini_player(),
+            PomeloMessage::MiniPlayer(msg) => self.update_mini_player(msg),
+
+            PomeloMessage::CopyToClipboard(text) => iced::clipboard::write(text),
+
+            PomeloMessage::PlayLocalCopy(id) => match pages::local_copy_player(&self.instance, &id) {
+                Some((task, page)) => {
+                    self.page_stack.push(page);
+                    task
+                },
+                None => {
+                    warn!("No local copy of {} to play.", id);
+                    Task::none()
+                }
+            },
+
+            PomeloMessage::SetWatched(id, watched) => {
+                self.instance.archive_mut().set_watched(&id, watched);
+                Task::none()
+            },
+
+            PomeloMessage::ReloadTheme => {
+                self.instance.reload_theme();
+                Task::none()
+            },
+
+            PomeloMessage::StatusBar(msg) => {
+                self.on_status_bar_message(msg);
+                Task::none()
+            },
+
+            PomeloMessage::AddToPlaylist(playlist, video, title) => {
+                info!("Adding video to playlist {}: {}", playlist, video);
+
+                self.instance.archive_mut().add_to_playlist(
+                    &playlist,
+                    instance::archive::PlaylistEntry { video, title, local: false }
+                );
+                Task::none()
+            },
+
+            PomeloMessage::CancelDownload(id) => {
+                self.instance.cancel_download(id);
+                Task::none()
+            },
+
+            PomeloMessage::PollDownloads => {
+                self.instance.poll_downloads();
+                self.update_taskbar()
+            },
+
+            // Closing the mini player only closes its window.
+            PomeloMessage::Close(id) if Some(id) == self.mini_window => {
+                self.mini_window = None;
+                self.instance.set_mini_player_open(false);
+                Task::none()
+            },
+
             PomeloMessage::Close(_id) => {
-                self.instance.cancel_download();
+                for page in self.page_stack.iter_mut() {
+                    page.on_close(&mut self.instance);
+                }
+
+                self.instance.cancel_all_downloads();
                 self.instance.settings().save();
+                self.instance.usage_mut().save();
 
                 iced::exit()
             },
@@ -227,24 +446,248 @@ impl PomeloApp {
                     Navigation::None => {}
                 }
 
-                command
+                // The mini player has nothing to show once the player is gone.
+                match self.mini_window {
+                    Some(id) if !self.page_stack.iter().any(|page| page.is_playing()) => Task::batch([command, window::close(id)]),
+                    _ => command
+                }
             }
         }
     }
 
-    // Draw the current page's UI.
-    pub (crate) fn view(&self, _id: window::Id) -> iced::Element<PomeloMessage> {
-        self.page_stack.last().unwrap().view(&self.instance)
+    // Use the user's selected theme, if there is one. High-contrast mode overrides it.
+    pub (crate) fn theme(&self, _id: window::Id) -> iced::Theme {
+        if self.instance.settings().high_contrast() {
+            return instance::theme::high_contrast_theme();
+        }
+
+        self.instance.theme()
+            .map(|theme| theme.theme())
+            .unwrap_or_default()
+    }
+
+    // Draw the current page's UI, with the status bar underneath. The status bar is left out in fullscreen.
+    pub (crate) fn view(&self, id: window::Id) -> iced::Element<PomeloMessage> {
+        use iced::Length;
+        use iced::widget::{column, Container};
+
+        if Some(id) == self.mini_window {
+            return self.mini_player_view();
+        }
+
+        if self.instance.is_fullscreen() {
+            return self.page_stack.last().unwrap().view(&self.instance);
+        }
+
+        column![
+            Container::new(self.page_stack.last().unwrap().view(&self.instance))
+                .height(Length::Fill),
+            pages::status_bar(&self.instance)
+        ].into()
+    }
+
+    // Jump to the page selected from the status bar.
+    fn on_status_bar_message(&mut self, message: pages::StatusBarMessage) {
+        use pages::StatusBarMessage;
+
+        match message {
+            StatusBarMessage::ToDownloads => self.page_stack.push(Box::new(pages::DownloadsPage::new())),
+            StatusBarMessage::ToNowPlaying => self.pop_until(|page| page.is_playing()),
+
+            StatusBarMessage::ToggleHideWatched => {
+                let settings = self.instance.settings_mut();
+                settings.set_hide_watched(!settings.hide_watched());
+            },
+            StatusBarMessage::ToSettings => if !self.instance.settings().is_restricted() {
+                self.page_stack.push(Box::new(pages::SettingsPage::new()))
+            }
+        }
+    }
+
+    // Show the overall download progress on the taskbar. It's only sent again once the percentage changes.
+    fn update_taskbar(&mut self) -> Task<PomeloMessage> {
+        let progress = self.instance.downloads().progress();
+        let percent = progress.map(|progress| (progress * 100.0) as u32);
+
+        if percent == self.taskbar_progress {
+            return Task::none();
+        }
+
+        self.taskbar_progress = percent;
+        crate::taskbar::set_progress(self.window_id, progress)
+    }
+
+    // Run the action bound to the key, if there is one.
+    // Player actions are sent to the current page, and are ignored by pages other than the player.
+    fn on_key_press(&mut self, key: keyboard::Key) -> Task<PomeloMessage> {
+        use instance::settings::Shortcut;
+        use pages::VideoPlayerMessage;
+
+        let Some(shortcut) = key_name(&key).and_then(|name| self.instance.settings().shortcut_for_key(&name)) else {
+            return Task::none();
+        };
+
+        let message = match shortcut {
+            Shortcut::PlayPause => VideoPlayerMessage::PlayToggle.into(),
+            Shortcut::SeekBack => VideoPlayerMessage::SeekBy(-SEEK_STEP).into(),
+            Shortcut::SeekForward => VideoPlayerMessage::SeekBy(SEEK_STEP).into(),
+            Shortcut::VolumeUp => VideoPlayerMessage::ChangeVolume(VOLUME_STEP).into(),
+            Shortcut::VolumeDown => VideoPlayerMessage::ChangeVolume(-VOLUME_STEP).into(),
+            Shortcut::NextVideo => VideoPlayerMessage::PlayNext.into(),
+            Shortcut::PreviousVideo => VideoPlayerMessage::PlayPrevious.into(),
+            Shortcut::Fullscreen => PomeloMessage::ToggleFullscreen,
+
+            // Leave fullscreen before leaving the page. The main menu has nowhere to go back to.
+            Shortcut::Back if self.instance.is_fullscreen() => PomeloMessage::ToggleFullscreen,
+            Shortcut::Back if self.page_stack.len() > 1 => PomeloMessage::Back,
+            Shortcut::Back => return Task::none()
+        };
+
+        self.update(message)
+    }
+
+    // Switch the window between fullscreen and windowed mode.
+    // Some platforms don't restore the window's size when leaving fullscreen, so it's set again from the settings.
+    fn toggle_fullscreen(&mut self) -> Task<PomeloMessage> {
+        let fullscreen = !self.instance.is_fullscreen();
+        self.instance.set_fullscreen(fullscreen);
+
+        info!("Fullscreen: {}", fullscreen);
+
+        if fullscreen {
+            window::change_mode(self.window_id, window::Mode::Fullscreen)
+        }
+        else {
+            let size = Size::from(self.instance.settings().window_size());
+
+            window::change_mode(self.window_id, window::Mode::Windowed)
+                .chain(window::resize(self.window_id, size))
+        }
+    }
+
+    // Open the mini player in a small window that stays on top of other windows, or close it if it's open.
+    fn toggle_mini_player(&mut self) -> Task<PomeloMessage> {
+        if let Some(id) = self.mini_window {
+            return window::close(id);
+        }
+
+        info!("Opening mini player.");
+
+        let (id, task) = window::open(window::Settings {
+            size: Size::new(400.0, 280.0),
+            min_size: Some(Size::new(240.0, 180.0)),
+            level: window::Level::AlwaysOnTop,
+            exit_on_close_request: true,
+            ..Default::default()
+        });
+
+        self.mini_window = Some(id);
+        self.instance.set_mini_player_open(true);
+
+        task.discard()
+    }
+
+    // Send a message from the mini player to the page that's playing, even if it isn't the current page.
+    // Player messages from the tasks it returns have to find their way back to it too.
+    fn update_mini_player(&mut self, message: pages::VideoPlayerMessage) -> Task<PomeloMessage> {
+        let Some(page) = self.page_stack.iter_mut().rev().find(|page| page.is_playing()) else {
+            return Task::none();
+        };
+
+        let (command, _) = page.update(&mut self.instance, PomeloMessage::VideoPlayer(message));
+
+        command.map(to_mini_player)
+    }
+
+    fn mini_player_view(&self) -> iced::Element<PomeloMessage> {
+        use iced::widget::{center, Text};
+
+        self.page_stack.iter().rev()
+            .find_map(|page| page.mini_view(&self.instance))
+            .map(|view| view.map(to_mini_player))
+            .unwrap_or_else(|| center(Text::new("Nothing is playing.")).into())
+    }
+
+    // Pop pages off the stack until the top page matches the predicate.
+    // Nothing happens if no page in the stack matches.
+    fn pop_until(&mut self, predicate: impl Fn(&dyn pages::PomeloPage) -> bool) {
+        if let Some(index) = self.page_stack.iter().rposition(|page| predicate(page.as_ref())) {
+            self.page_stack.truncate(index + 1);
+        }
     }
 
     // Handle user input.
     pub (crate) fn subscription(&self) -> iced::Subscription<PomeloMessage> {
+        use std::time::Duration;
+
+        // Poll the theme file for changes, so edits show up without restarting.
+        let theme_reload = if self.instance.theme().is_some() {
+            iced::time::every(Duration::from_secs(2)).map(|_| PomeloMessage::ReloadTheme)
+        } else {
+            iced::Subscription::none()
+        };
+
+        // Read the output of running downloads a few times a second.
+        let download_poll = if self.instance.active_downloads() > 0 {
+            iced::time::every(Duration::from_millis(100)).map(|_| PomeloMessage::PollDownloads)
+        } else {
+            iced::Subscription::none()
+        };
+
+        // Keys typed into text boxes are captured by them, so they never reach here.
+        let shortcuts = keyboard::on_key_press(|key, modifiers| {
+            modifiers.is_empty().then_some(PomeloMessage::KeyPressed(key))
+        });
+
         iced::Subscription::batch(
             [
                 window::resize_events().map(PomeloMessage::WindowResize),
+                shortcuts,
                 window::close_events().map(PomeloMessage::Close),
+                theme_reload,
+                download_poll,
                 self.page_stack.last().unwrap().subscription(&self.instance)
             ]
         )
     }
-}
\ No newline at end of file
+}
+
+// Route player messages to the page that's playing instead of the current page.
+fn to_mini_player(message: PomeloMessage) -> PomeloMessage {
+    match message {
+        PomeloMessage::VideoPlayer(msg) => PomeloMessage::MiniPlayer(msg),
+        message => message
+    }
+}
+
+// How far the seek shortcuts move the video, in seconds, and how much the volume shortcuts change the volume.
+const SEEK_STEP: f64 = 5.0;
+const VOLUME_STEP: f64 = 0.05;
+
+// Name of a key, as used in the keymap setting (see settings::KEY_NAMES).
+fn key_name(key: &keyboard::Key) -> Option<String> {
+    use keyboard::key::Named;
+
+    let name = match key {
+        keyboard::Key::Character(c) => return Some(c.to_uppercase()),
+        keyboard::Key::Named(named) => match named {
+            Named::Space => "Space",
+            Named::Enter => "Enter",
+            Named::Escape => "Escape",
+            Named::Backspace => "Backspace",
+            Named::Tab => "Tab",
+            Named::ArrowLeft => "Left",
+            Named::ArrowRight => "Right",
+            Named::ArrowUp => "Up",
+            Named::ArrowDown => "Down",
+            Named::Home => "Home",
+            Named::End => "End",
+            Named::PageUp => "PageUp",
+            Named::PageDown => "PageDown",
+            _ => return None
+        },
+        keyboard::Key::Unidentified => return None
+    };
+
+    Some(String::from(name))
+}
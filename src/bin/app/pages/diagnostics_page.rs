@@ -0,0 +1,354 @@
+use std::path::{Path, PathBuf};
+
+use iced::{Element, Length, Task};
+use iced::widget::{column, row, Button, Column, PickList, Scrollable, Text, TextInput};
+
+use log::{info, error, Level};
+
+use crate::LOG_DIR;
+use crate::app::PomeloError;
+
+use super::{ErrorBanner, FillElement, PomeloInstance, PomeloPage, Navigation, Msg};
+
+#[derive(Debug, Clone)]
+pub (crate) enum DiagnosticsMessage {
+    SelectLog(usize),
+    SetLevel(LevelFilter),
+    SetModule(String),
+    UpdateFilter(String),
+    ExportReport
+}
+
+impl From<DiagnosticsMessage> for Msg {
+    fn from(value: DiagnosticsMessage) -> Self {
+        Self::Diagnostics(value)
+    }
+}
+
+// Module filter entry that shows lines from every module.
+const ALL_MODULES: &str = "All modules";
+
+// Minimum severity of the log lines to show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub (crate) enum LevelFilter {
+    All,
+    Info,
+    Warn,
+    Error
+}
+
+impl LevelFilter {
+    const ALL: [Self; 4] = [Self::All, Self::Info, Self::Warn, Self::Error];
+
+    fn allows(&self, level: Option<Level>) -> bool {
+        let min = match self {
+            Self::All => return true,
+            Self::Info => Level::Info,
+            Self::Warn => Level::Warn,
+            Self::Error => Level::Error
+        };
+
+        level.is_some_and(|level| level <= min)
+    }
+}
+
+impl std::fmt::Display for LevelFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::All => "All",
+            Self::Info => "Info",
+            Self::Warn => "Warnings",
+            Self::Error => "Errors"
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+struct LogLine {
+    level: Option<Level>,
+    module: Option<String>,
+    text: String
+}
+
+// Lists log files from previous runs, and bundles the latest log with the current settings for bug reports.
+pub (crate) struct DiagnosticsPage {
+    logs: Vec<PathBuf>,
+    selected: Option<usize>,
+    lines: Vec<LogLine>,
+    level: LevelFilter,
+
+    // Modules that wrote to the selected log, e.g. "app::pages::video_player_page", and the one to show.
+    modules: Vec<String>,
+    module: String,
+    filter: String,
+    status: Option<String>,
+    error: Option<ErrorBanner>
+}
+
+impl DiagnosticsPage {
+    pub (crate) fn new() -> Self {
+        let mut page = Self {
+            logs: find_logs(),
+            selected: None,
+            lines: Vec::new(),
+            level: LevelFilter::All,
+            modules: vec![String::from(ALL_MODULES)],
+            module: String::from(ALL_MODULES),
+            filter: String::new(),
+            status: None,
+            error: None
+        };
+
+        if !page.logs.is_empty() {
+            page.select_log(0);
+        }
+
+        page
+    }
+}
+
+impl PomeloPage for DiagnosticsPage {
+    fn update(&mut self, instance: &mut PomeloInstance, message: Msg) -> (Task<Msg>, Navigation) {
+        match message {
+            Msg::Back => return (Task::none(), Navigation::Back),
+            Msg::DismissError => self.error = None,
+
+            Msg::Diagnostics(msg) => match msg {
+                DiagnosticsMessage::SelectLog(index) => self.select_log(index),
+                DiagnosticsMessage::SetLevel(level) => self.level = level,
+                DiagnosticsMessage::SetModule(module) => self.module = module,
+                DiagnosticsMessage::UpdateFilter(filter) => self.filter = filter,
+                DiagnosticsMessage::ExportReport => self.export_report(instance)
+            },
+
+            _ => ()
+        }
+
+        (Task::none(), Navigation::None)
+    }
+
+    fn view(&self, instance: &PomeloInstance) -> Element<Msg> {
+        let height = instance.settings().window_size().1 / 2.0;
+
+        let log_list = Column::with_children(
+            self.logs.iter().enumerate().map(|(i, path)| {
+                let name = path.file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+
+                Button::new(Text::new(name).size(12))
+                    .width(Length::Fill)
+                    .on_press_maybe(
+                        (self.selected != Some(i)).then(|| DiagnosticsMessage::SelectLog(i).into())
+                    )
+                    .into()
+            })
+        ).spacing(5);
+
+        let filter = self.filter.to_lowercase();
+
+        let log_lines = Column::with_children(
+            self.lines.iter()
+                .filter(|line|
+                    self.level.allows(line.level)
+                        && (self.module == ALL_MODULES || line.module.as_deref() == Some(self.module.as_str()))
+                        && (filter.is_empty() || line.text.to_lowercase().contains(&filter))
+                )
+                .map(|line| Text::new(line.text.as_str()).size(12).into())
+        );
+
+        let mut column = Column::new()
+            .spacing(25)
+            .align_x(iced::Alignment::Center);
+
+        column = column.push_maybe(self.error.as_ref().map(|banner| banner.view()));
+
+        column = column.extend([
+            row![
+                PickList::new(
+                    LevelFilter::ALL,
+                    Some(self.level),
+                    |level| DiagnosticsMessage::SetLevel(level).into()
+                ),
+
+                PickList::new(
+                    self.modules.as_slice(),
+                    Some(&self.module),
+                    |module| DiagnosticsMessage::SetModule(module).into()
+                ),
+
+                TextInput::new("Filter", &self.filter)
+                    .on_input(|s| DiagnosticsMessage::UpdateFilter(s).into())
+                    .width(300),
+
+                Button::new(Text::new("Export Report").center())
+                    .width(150)
+                    .on_press(DiagnosticsMessage::ExportReport.into())
+            ].spacing(10).into(),
+
+            row![
+                Scrollable::new(log_list).width(250).height(height),
+                Scrollable::new(log_lines).width(Length::Fill).height(height)
+            ].spacing(10).into()
+        ]);
+
+        column = column.push_maybe(self.status.as_deref().map(Text::new));
+
+        column.push(
+            Button::new(Text::new("Back").center())
+                .width(100)
+                .on_press(Msg::Back)
+        ).fill()
+    }
+
+    fn subscription(&self, _instance: &PomeloInstance) -> iced::Subscription<Msg> {
+        iced::Subscription::none()
+    }
+}
+
+impl DiagnosticsPage {
+    // Read the selected log file into memory, tagging each line with its log level and module.
+    // Lines without a level (e.g. multi-line messages) use the level and module of the line before them.
+    // Logs written before modules were logged have no modules, and only show up under all modules.
+    fn select_log(&mut self, index: usize) {
+        self.selected = Some(index);
+        self.lines.clear();
+        self.modules = vec![String::from(ALL_MODULES)];
+        self.module = String::from(ALL_MODULES);
+
+        match std::fs::read_to_string(&self.logs[index]) {
+            Ok(text) => {
+                let mut level = None;
+                let mut module = None;
+
+                for line in text.lines() {
+                    if let Some(line_level) = line_level(line) {
+                        level = Some(line_level);
+                        module = line_module(line);
+                    }
+
+                    self.lines.push(LogLine { level, module: module.clone(), text: String::from(line) });
+                }
+
+                let mut modules: Vec<String> = self.lines.iter().filter_map(|line| line.module.clone()).collect();
+                modules.sort();
+                modules.dedup();
+                self.modules.extend(modules);
+            },
+
+            Err(e) => {
+                error!("Failed to read log file: {}", e);
+                self.error = Some(ErrorBanner::new(PomeloError::new(e), None));
+            }
+        }
+    }
+
+    // Bundle the latest log and the current settings into a zip file chosen by the user.
+    fn export_report(&mut self, instance: &PomeloInstance) {
+        use rfd::FileDialog;
+
+        let Some(latest) = self.logs.first() else {
+            self.status = Some(String::from("No log files to export."));
+            return;
+        };
+
+        let maybe_path = FileDialog::new()
+            .add_filter("zip", &["zip"])
+            .set_file_name("pomelo-report.zip")
+            .save_file();
+
+        if let Some(path) = maybe_path {
+            match write_report(&path, latest, instance) {
+                Ok(_) => {
+                    info!("Diagnostics report saved to: {:?}", path);
+                    self.status = Some(format!("Report saved to {}", path.display()));
+                },
+
+                Err(e) => {
+                    error!("Failed to save diagnostics report: {}", e.error);
+                    self.error = Some(ErrorBanner::new(e, Some(DiagnosticsMessage::ExportReport.into())));
+                }
+            }
+        }
+    }
+}
+
+// Find all log files, newest first. Download logs are kept in their own folder, and left out.
+fn find_logs() -> Vec<PathBuf> {
+    let mut logs: Vec<PathBuf> = std::fs::read_dir(LOG_DIR)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .flat_map(|dir| std::fs::read_dir(dir.path()).into_iter().flatten())
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("log-"))
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+
+    // Log names contain their timestamp, so sorting by name is the same as sorting by date.
+    logs.sort_by(|a, b| b.file_name().cmp(&a.file_name()));
+    logs
+}
+
+// Module that wrote a line, without the crate name for Pomelo's own modules.
+// The module comes right after the level, which has no colon after it since modules were added.
+fn line_module(line: &str) -> Option<String> {
+    let mut words = line.split_whitespace().take(4);
+    words.find(|word| word.parse::<Level>().is_ok())?;
+
+    let target = words.next()?.strip_suffix(':')?;
+    let target = target.strip_prefix(concat!(env!("CARGO_CRATE_NAME"), "::")).unwrap_or(target);
+
+    Some(String::from(target))
+}
+
+// Find the log level of a line written by the logger, if it has one.
+fn line_level(line: &str) -> Option<Level> {
+    line.split_whitespace()
+        .take(4)
+        .find_map(|word| word.trim_end_matches(':').parse::<Level>().ok())
+}
+
+fn write_report(path: &Path, log: &Path, instance: &PomeloInstance) -> Result<(), PomeloError> {
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    // Folders and files that might contain the user's name.
+    let private_paths: Vec<String> = [
+        Some(String::from(instance.settings().download_folder())),
+        instance.settings().yt_dlp_config_file().map(String::from),
+        instance.settings().download_sound_file().map(String::from),
+        std::env::var("HOME").ok(),
+        std::env::var("USERPROFILE").ok()
+    ].into_iter().flatten().collect();
+
+    let log_text = std::fs::read_to_string(log).map_err(PomeloError::new)?;
+    let log_text = redact(&log_text, &private_paths);
+    let settings = redact(&instance.settings().to_redacted_json()?, &private_paths);
+
+    let file = std::fs::File::create(path).map_err(PomeloError::new)?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = SimpleFileOptions::default();
+
+    zip.start_file("log.txt", options).map_err(PomeloError::new)?;
+    zip.write_all(log_text.as_bytes()).map_err(PomeloError::new)?;
+
+    zip.start_file("settings.json", options).map_err(PomeloError::new)?;
+    zip.write_all(settings.as_bytes()).map_err(PomeloError::new)?;
+
+    zip.finish().map_err(PomeloError::new)?;
+
+    Ok(())
+}
+
+fn redact(text: &str, paths: &[String]) -> String {
+    paths.iter()
+        .filter(|path| !path.is_empty())
+        .fold(String::from(text), |text, path| {
+            text.replace(path.as_str(), "<redacted>")
+                .replace(&path.replace('\\', "/"), "<redacted>")
+        })
+}
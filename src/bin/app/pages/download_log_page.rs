@@ -0,0 +1,100 @@
+use iced::{Element, Length, Task};
+use iced::widget::{column, text, Button, Column, Scrollable, Text};
+
+use log::error;
+
+use crate::app::PomeloError;
+
+use super::{ErrorBanner, FillElement, PomeloInstance, PomeloPage, Navigation, Msg};
+
+// Progress is written on its own line, so long downloads have huge logs. Only the end is shown.
+const MAX_LOG_LINES: usize = 500;
+
+// Everything yt-dlp wrote for one download, opened from the Downloads page.
+pub (crate) struct DownloadLogPage {
+    title: String,
+    path: String,
+    lines: Vec<String>,
+    total_lines: usize,
+    error: Option<ErrorBanner>
+}
+
+impl DownloadLogPage {
+    pub (crate) fn new(title: String, path: String) -> Self {
+        let mut page = Self { title, path, lines: Vec::new(), total_lines: 0, error: None };
+
+        match std::fs::read_to_string(&page.path) {
+            Ok(log) => {
+                let lines: Vec<&str> = log.lines().collect();
+
+                page.total_lines = lines.len();
+                page.lines = lines[lines.len().saturating_sub(MAX_LOG_LINES)..].iter()
+                    .map(|line| String::from(*line))
+                    .collect();
+            },
+
+            Err(e) => {
+                error!("Failed to read download log {}: {}", page.path, e);
+                page.error = Some(ErrorBanner::new(PomeloError::new(e), None));
+            }
+        }
+
+        page
+    }
+}
+
+impl PomeloPage for DownloadLogPage {
+    fn update(&mut self, _instance: &mut PomeloInstance, message: Msg) -> (Task<Msg>, Navigation) {
+        match message {
+            Msg::Back => (Task::none(), Navigation::Back),
+
+            Msg::DismissError => {
+                self.error = None;
+                (Task::none(), Navigation::None)
+            },
+
+            _ => (Task::none(), Navigation::None)
+        }
+    }
+
+    fn view(&self, instance: &PomeloInstance) -> Element<Msg> {
+        // yt-dlp starts its error lines with "ERROR:".
+        let log_lines = Column::with_children(
+            self.lines.iter().map(|line| {
+                let line_text = Text::new(line.as_str()).size(12);
+
+                match line.starts_with("ERROR") {
+                    true => line_text.style(text::danger).into(),
+                    false => line_text.into()
+                }
+            })
+        );
+
+        let shown = match self.total_lines > self.lines.len() {
+            true => format!("Showing the last {} of {} lines. Full log: {}", self.lines.len(), self.total_lines, self.path),
+            false => self.path.clone()
+        };
+
+        column![Text::new(self.title.clone()).size(20)]
+            .push_maybe(self.error.as_ref().map(|banner| banner.view()))
+            .push(Text::new(shown).size(12))
+            .push(
+                Scrollable::new(log_lines)
+                    .width(Length::Fill)
+                    .height(instance.settings().window_size().1 * 3.0 / 4.0)
+            )
+            .push(
+                Button::new(Text::new("Back").center())
+                    .width(100)
+                    .on_press(Msg::Back)
+            )
+            .spacing(10)
+            .padding(25)
+            .align_x(iced::Alignment::Center)
+            .fill()
+    }
+
+    fn subscription(&self, _instance: &PomeloInstance) -> iced::Subscription<Msg> {
+        iced::Subscription::none()
+    }
+}
@@ -0,0 +1,386 @@
+use iced::{Element, Length, Task};
+use iced::widget::{column, row, text, Button, Column, ProgressBar, Scrollable, Text};
+
+use crate::app::instance::archive::PendingDownload;
+use crate::app::instance::downloads::{Download, DownloadId, DownloadStatus, QueuePolicy};
+use crate::utils::{format_bytes, secs_to_timestamp};
+
+use super::{ConditionalMessage, FillElement, PomeloInstance, PomeloPage, Navigation, Msg};
+use super::history_page::HistoryMessage;
+
+#[derive(Debug, Clone)]
+pub (crate) enum DownloadsMessage {
+    Pause(DownloadId),
+    Resume(DownloadId),
+    Retry(DownloadId),
+    MoveUp(DownloadId),
+    MoveDown(DownloadId),
+    ViewLog(DownloadId),
+    ClearFinished,
+    ResumeInterrupted(usize),
+    DiscardInterrupted(usize),
+    ResumeAllInterrupted,
+    SelectTab(DownloadsTab)
+}
+
+// This session's downloads, or the history of every download that has finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub (crate) enum DownloadsTab {
+    Downloads,
+    History
+}
+
+impl ConditionalMessage for DownloadsMessage {}
+
+impl From<DownloadsMessage> for Msg {
+    fn from(value: DownloadsMessage) -> Self {
+        Self::Downloads(value)
+    }
+}
+
+// Every download started this session, wherever it was started from.
+// Running downloads can be paused or cancelled, and failed ones retried. Queued downloads are listed in the order they'll start,
+// and can be moved up or down when the queue is ordered manually. Finished and failed ones have a log of yt-dlp's output.
+// Downloads left unfinished when Pomelo was last closed are listed first, and can be resumed or discarded.
+// The History tab has every download that finished in earlier sessions too, with weekly totals.
+pub (crate) struct DownloadsPage {
+    tab: DownloadsTab
+}
+
+impl DownloadsPage {
+    pub (crate) fn new() -> Self {
+        Self { tab: DownloadsTab::Downloads }
+    }
+
+    fn tabs(&self) -> Element<Msg> {
+        let tab_button = |text: &'static str, tab: DownloadsTab| Button::new(Text::new(text).center())
+            .width(100)
+            .on_press_maybe(DownloadsMessage::SelectTab(tab).on_condition(self.tab != tab));
+
+        row![
+            tab_button("Downloads", DownloadsTab::Downloads),
+            tab_button("History", DownloadsTab::History)
+        ].spacing(10).into()
+    }
+}
+
+impl PomeloPage for DownloadsPage {
+    fn update(&mut self, instance: &mut PomeloInstance, message: Msg) -> (Task<Msg>, Navigation) {
+        match message {
+            Msg::Back => return (Task::none(), Navigation::Back),
+            Msg::History(HistoryMessage::ClearHistory) => instance.history_mut().clear(),
+
+            Msg::Downloads(msg) => match msg {
+                DownloadsMessage::SelectTab(tab) => self.tab = tab,
+
+                DownloadsMessage::Pause(id) => instance.pause_download(id),
+                DownloadsMessage::Resume(id) => instance.resume_download(id),
+                DownloadsMessage::Retry(id) => instance.retry_download(id),
+                DownloadsMessage::MoveUp(id) => instance.move_download(id, false),
+                DownloadsMessage::MoveDown(id) => instance.move_download(id, true),
+
+                DownloadsMessage::ViewLog(id) => {
+                    use super::download_log_page::DownloadLogPage;
+
+                    let log = instance.downloads().get(id)
+                        .and_then(|download| Some((download.title().to_string(), download.log_path()?.to_string())));
+
+                    if let Some((title, path)) = log {
+                        return (Task::none(), Navigation::GoTo(Box::new(DownloadLogPage::new(title, path))));
+                    }
+                },
+
+                DownloadsMessage::ClearFinished => instance.clear_finished_downloads(),
+                DownloadsMessage::ResumeInterrupted(index) => instance.resume_interrupted(index),
+                DownloadsMessage::DiscardInterrupted(index) => instance.discard_interrupted(index),
+
+                DownloadsMessage::ResumeAllInterrupted => while !instance.interrupted_downloads().is_empty() {
+                    instance.resume_interrupted(0);
+                }
+            },
+
+            _ => ()
+        }
+
+        (Task::none(), Navigation::None)
+    }
+
+    fn view(&self, instance: &PomeloInstance) -> Element<Msg> {
+        if self.tab == DownloadsTab::History {
+            return column![
+                Text::new("Downloads").size(20),
+                self.tabs(),
+                super::history_page::history_element(instance),
+                Button::new(Text::new("Back").center())
+                    .width(100)
+                    .on_press(Msg::Back)
+            ]
+            .spacing(10)
+            .padding(25)
+            .align_x(iced::Alignment::Center)
+            .fill();
+        }
+
+        let downloads = instance.downloads().downloads();
+
+        let sections: [(&str, fn(&DownloadStatus) -> bool); 4] = [
+            ("Active", |status| *status == DownloadStatus::Running),
+            ("Queued", |status| matches!(status, DownloadStatus::Queued | DownloadStatus::Paused)),
+            ("Completed", |status| *status == DownloadStatus::Finished),
+            ("Failed", |status| matches!(status, DownloadStatus::Failed(_)))
+        ];
+
+        let interrupted = instance.interrupted_downloads();
+
+        let mut list = Column::new().spacing(10);
+
+        if !interrupted.is_empty() {
+            list = list
+                .push(
+                    row![
+                        Text::new(format!("Interrupted last session ({})", interrupted.len())).size(18),
+                        Button::new(Text::new("Resume All").size(12).center())
+                            .width(90)
+                            .on_press(DownloadsMessage::ResumeAllInterrupted.into())
+                    ].spacing(10).align_y(iced::Alignment::Center)
+                )
+                .extend(interrupted.iter().enumerate().map(|(i, pending)| interrupted_element(i, pending)));
+        }
+
+        for (name, filter) in sections {
+            let items: Vec<&Download> = match name {
+                "Queued" => instance.downloads().queue(instance.settings().queue_policy()),
+                _ => downloads.iter()
+                    .filter(|download| filter(download.status()))
+                    .collect()
+            };
+
+            if items.is_empty() {
+                continue;
+            }
+
+            list = list
+                .push(Text::new(format!("{} ({})", name, items.len())).size(18))
+                .extend(items.into_iter().map(|download| download_element(download, instance)));
+        }
+
+        let has_finished = downloads.iter().any(|download| !download.is_active());
+
+        let content: Element<Msg> = if downloads.is_empty() && interrupted.is_empty() {
+            Text::new("Nothing has been downloaded this session.").into()
+        }
+        else {
+            Scrollable::new(list)
+                .width(Length::Fill)
+                .height(instance.settings().window_size().1 * 3.0 / 4.0)
+                .into()
+        };
+
+        column![
+            Text::new("Downloads").size(20),
+            self.tabs()
+        ]
+        .push_maybe(speed_graph(instance))
+        .push(content)
+        .push(
+            row![
+                Button::new(Text::new("Clear Finished").center())
+                    .width(150)
+                    .on_press_maybe(DownloadsMessage::ClearFinished.on_condition(has_finished)),
+
+                Button::new(Text::new("Back").center())
+                    .width(100)
+                    .on_press(Msg::Back)
+            ].spacing(10)
+        )
+        .spacing(10)
+        .padding(25)
+        .align_x(iced::Alignment::Center)
+        .fill()
+    }
+
+    fn subscription(&self, _instance: &PomeloInstance) -> iced::Subscription<Msg> {
+        iced::Subscription::none()
+    }
+}
+
+// Combined download speed over the last few minutes, so slowdowns from throttling stand out.
+fn speed_graph<'a>(instance: &PomeloInstance) -> Option<Element<'a, Msg>> {
+    let history: Vec<u64> = instance.downloads().speed_history().iter().copied().collect();
+
+    let current = *history.last()?;
+    let peak = history.iter().copied().max().unwrap_or_default();
+
+    Some(
+        column![
+            super::bar_chart(&history, 40.0, 3.0),
+
+            Text::new(
+                format!(
+                    "Now: {}/s | Peak: {}/s",
+                    format_bytes(current),
+                    format_bytes(peak)
+                )
+            ).size(12)
+        ]
+        .spacing(5)
+        .align_x(iced::Alignment::Center)
+        .into()
+    )
+}
+
+// A download's thumbnail, title, channel and status, with buttons for what can be done with it.
+fn download_element<'a>(download: &Download, instance: &PomeloInstance) -> Element<'a, Msg> {
+    let id = download.id();
+
+    let cancel = Button::new(Text::new("Cancel").size(12).center())
+        .width(70)
+        .on_press(Msg::CancelDownload(id));
+
+    let mut info = column![Text::new(download.title().to_string())]
+        .push_maybe(download.channel().map(|channel| Text::new(channel.to_string()).size(12)))
+        .spacing(5)
+        .width(Length::Fill);
+    let mut buttons = row![].spacing(5);
+
+    match download.status() {
+        DownloadStatus::Running => {
+            let speed = download.speed()
+                .map(|speed| format!("{}/s", format_bytes(speed as u64)))
+                .unwrap_or_else(|| String::from("--"));
+
+            let eta = download.eta()
+                .map(|eta| secs_to_timestamp(eta, eta >= 3600))
+                .unwrap_or_else(|| String::from("--"));
+
+            info = info.extend([
+                ProgressBar::new(0.0..=1.0, download.fraction())
+                    .width(instance.settings().window_size().0 / 2.0)
+                    .height(10)
+                    .into(),
+
+                Text::new(
+                    format!(
+                        "{:.0}% | {} downloaded | {} | ETA {}",
+                        download.fraction() * 100.0,
+                        format_bytes(download.total_bytes()),
+                        speed,
+                        eta
+                    )
+                ).size(12).into()
+            ]);
+
+            buttons = buttons.extend([
+                Button::new(Text::new("Pause").size(12).center())
+                    .width(70)
+                    .on_press(DownloadsMessage::Pause(id).into())
+                    .into(),
+                cancel.into()
+            ]);
+        },
+
+        DownloadStatus::Queued => {
+            info = info.push(Text::new("Waiting for a free slot").size(12));
+
+            buttons = buttons.extend([
+                Button::new(Text::new("Pause").size(12).center())
+                    .width(70)
+                    .on_press(DownloadsMessage::Pause(id).into())
+                    .into(),
+                cancel.into()
+            ]);
+        },
+
+        DownloadStatus::Paused => {
+            info = info.push(
+                Text::new(format!("Paused at {:.0}%", download.fraction() * 100.0)).size(12)
+            );
+
+            buttons = buttons.extend([
+                Button::new(Text::new("Resume").size(12).center())
+                    .width(70)
+                    .on_press(DownloadsMessage::Resume(id).into())
+                    .into(),
+                cancel.into()
+            ]);
+        },
+
+        DownloadStatus::Finished => {
+            info = info.push(
+                Text::new(format!("{} | {}", format_bytes(download.total_bytes()), download.path())).size(12)
+            );
+        },
+
+        DownloadStatus::Failed(e) => {
+            info = info.push(Text::new(e.clone()).size(12).style(text::danger));
+
+            buttons = buttons.push(
+                Button::new(Text::new("Retry").size(12).center())
+                    .width(70)
+                    .on_press(DownloadsMessage::Retry(id).into())
+            );
+        }
+    }
+
+    if instance.settings().queue_policy() == QueuePolicy::Manual && download.is_active() && *download.status() != DownloadStatus::Running {
+        buttons = buttons.extend([
+            Button::new(Text::new("Up").size(12).center())
+                .width(40)
+                .on_press(DownloadsMessage::MoveUp(id).into())
+                .into(),
+            Button::new(Text::new("Down").size(12).center())
+                .width(40)
+                .on_press(DownloadsMessage::MoveDown(id).into())
+                .into()
+        ]);
+    }
+
+    // Finished and failed downloads keep their log, to see what yt-dlp did or why it failed.
+    if !download.is_active() && download.log_path().is_some() {
+        buttons = buttons.push(
+            Button::new(Text::new("View log").size(12).center())
+                .width(70)
+                .on_press(DownloadsMessage::ViewLog(id).into())
+        );
+    }
+
+    row![download_thumbnail(download, instance), info, buttons]
+        .spacing(10)
+        .align_y(iced::Alignment::Center)
+        .into()
+}
+
+// Cached thumbnail of what's being downloaded. Nothing is downloaded for this, so downloads of things
+// that haven't been seen this session get a placeholder.
+fn download_thumbnail<'a>(download: &Download, instance: &PomeloInstance) -> Element<'a, Msg> {
+    use iced::widget::Image;
+    use super::video_info_page::large_thumbnail_id;
+
+    let handle = download.thumbnail_id().and_then(|id| {
+        let cache = instance.cache();
+        cache.get_thumbnail(id).or_else(|| cache.get_thumbnail(&large_thumbnail_id(id)))
+    });
+
+    match handle {
+        Some(handle) => Image::new(handle).width(80).height(45).into(),
+        None => super::thumbnail_placeholder(download.title(), 80.0, 45.0)
+    }
+}
+
+// A download from the last session, with its folder and buttons to resume or forget it.
+fn interrupted_element<'a>(index: usize, pending: &PendingDownload) -> Element<'a, Msg> {
+    row![
+        column![
+            Text::new(pending.title.clone()),
+            Text::new(pending.path.clone()).size(12)
+        ].spacing(5).width(Length::Fill),
+
+        Button::new(Text::new("Resume").size(12).center())
+            .width(70)
+            .on_press(DownloadsMessage::ResumeInterrupted(index).into()),
+
+        Button::new(Text::new("Discard").size(12).center())
+            .width(70)
+            .on_press(DownloadsMessage::DiscardInterrupted(index).into())
+    ].spacing(5).align_y(iced::Alignment::Center).into()
+}
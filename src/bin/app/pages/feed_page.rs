@@ -0,0 +1,348 @@
+use iced::{Element, Length, Task};
+use iced::widget::{column, row, Button, Column, PickList, Row, Scrollable, Text, TextInput};
+use invidious::CommonVideo;
+use log::{info, warn};
+
+use crate::yt_fetch::instances;
+use crate::utils::format_relative_time;
+use crate::yt_fetch::{Fetcher, SearchResult, VideoFetcher};
+
+use super::{ConditionalMessage, FillElement, PomeloInstance, PomeloPage, Navigation, Msg};
+
+#[derive(Debug, Clone)]
+pub (crate) enum FeedMessage {
+    Refresh,
+    Loaded(Vec<CommonVideo>, Vec<String>),
+    ToVideo(usize),
+    SelectGroup(String),
+    ToggleGroups,
+    UpdateGroupName(String),
+    CreateGroup,
+    DeleteGroup(String),
+
+    // Channel id, and the group to move it into.
+    SetChannelGroup(String, String)
+}
+
+impl ConditionalMessage for FeedMessage {}
+
+impl From<FeedMessage> for Msg {
+    fn from(value: FeedMessage) -> Self {
+        Self::Feed(value)
+    }
+}
+
+// Group filter entry that shows uploads from every channel.
+const ALL_GROUPS: &str = "All groups";
+
+// Group picker entry for channels that aren't in a group.
+const NO_GROUP: &str = "No group";
+
+// Latest uploads from every subscribed channel, newest first. They can be narrowed down to one subscription group.
+// Redirects to the video info page when the user selects a video.
+pub (crate) struct FeedPage {
+    videos: Vec<CommonVideo>,
+    failed: Vec<String>,
+    loading: bool,
+    load_handle: Option<iced::task::Handle>,
+    group: String,
+
+    // Groups are managed in place of the feed.
+    managing_groups: bool,
+    group_name: String
+}
+
+impl FeedPage {
+    pub (crate) fn new() -> Self {
+        Self {
+            videos: Vec::new(),
+            failed: Vec::new(),
+            loading: false,
+            load_handle: None,
+            group: String::from(ALL_GROUPS),
+            managing_groups: false,
+            group_name: String::new()
+        }
+    }
+
+    // Fetch the first page of uploads for all subscriptions at once.
+    // Channels that fail to load are skipped, and their names are listed under the feed.
+    fn refresh(&mut self, instance: &PomeloInstance) -> (Task<Msg>, Navigation) {
+        let channels = instance.archive().subscriptions().to_vec();
+        let invid_instance = instances::url(instance.settings().invidious_index());
+
+        if let Some(handle) = self.load_handle.take() {
+            handle.abort();
+        }
+
+        info!("Loading feed for {} channels.", channels.len());
+
+        self.loading = true;
+
+        let (task, handle) = Task::perform(
+            async move {
+                let fetcher = Fetcher::new(invid_instance);
+
+                let results = futures::future::join_all(
+                    channels.iter().map(|channel| fetcher.get_channel_videos(&channel.id, None))
+                ).await;
+
+                let mut videos = Vec::new();
+                let mut failed = Vec::new();
+
+                for (channel, result) in channels.into_iter().zip(results) {
+                    match result {
+                        Ok(uploads) => videos.extend(uploads.videos),
+                        Err(e) => {
+                            warn!("Failed to load uploads for {}: {}", channel.name, e);
+                            failed.push(channel.name);
+                        }
+                    }
+                }
+
+                videos.sort_by(|a, b| b.published.cmp(&a.published));
+                (videos, failed)
+            },
+            |(videos, failed)| FeedMessage::Loaded(videos, failed).into()
+        ).abortable();
+
+        self.load_handle = Some(handle);
+
+        (task, Navigation::None)
+    }
+
+    fn on_loaded(&mut self, videos: Vec<CommonVideo>, failed: Vec<String>, instance: &PomeloInstance) -> (Task<Msg>, Navigation) {
+        self.loading = false;
+        self.load_handle = None;
+
+        let items = videos.iter()
+            .map(|video| SearchResult::Video(video.clone()))
+            .collect();
+
+        self.videos = videos;
+        self.failed = failed;
+
+        (super::item_thumbnail_commands(items, instance), Navigation::None)
+    }
+
+    // Move to the video info page. The feed entry is shown while the full details load.
+    fn to_video(&self, index: usize) -> (Task<Msg>, Navigation) {
+        use super::video_info_page::{VideoInfoMessage, VideoInfoPage};
+
+        let Some(video) = self.videos.get(index) else {
+            return (Task::none(), Navigation::None);
+        };
+
+        (
+            Task::done(VideoInfoMessage::LoadVideo(video.id.clone()).into()),
+            Navigation::GoTo(Box::new(VideoInfoPage::new_with_video(video.clone())))
+        )
+    }
+
+    fn get_feed_element(&self, instance: &PomeloInstance) -> Element<Msg> {
+        let locale = instance.settings().locale();
+        let now = chrono::Utc::now().timestamp() as u64;
+
+        let hide_watched = instance.settings().hide_watched();
+        let archive = instance.archive();
+
+        let rows = Column::with_children(
+            self.videos.iter().enumerate()
+                .filter(|(_, video)| !(hide_watched && archive.is_watched(&video.id)))
+                .filter(|(_, video)| self.group == ALL_GROUPS || archive.channel_group(&video.author_id) == Some(self.group.as_str()))
+                .map(|(i, video)| {
+                    let row: Row<Msg> = Row::new()
+                        .push_maybe(super::thumbnail(instance.cache(), &video.id, &video.author, 320.0, 180.0))
+                        .push(column![
+                            Text::new(video.title.as_str()),
+                            Text::new(video.author.as_str()),
+                            Text::new(format_relative_time(now.saturating_sub(video.published), locale))
+                        ]);
+
+                    row![
+                        Button::new(row)
+                            .width(Length::Fill)
+                            .on_press(FeedMessage::ToVideo(i).into()),
+
+                        super::watched_button(instance, &video.id)
+                    ]
+                    .spacing(10)
+                    .align_y(iced::Alignment::Center)
+                    .into()
+                })
+        ).spacing(10);
+
+        Scrollable::new(rows)
+            .width(Length::Fill)
+            .height(instance.settings().window_size().1 * 3.0 / 4.0)
+            .into()
+    }
+
+    fn delete_group(&mut self, name: &str, instance: &mut PomeloInstance) {
+        instance.archive_mut().delete_group(name);
+
+        if self.group == name {
+            self.group = String::from(ALL_GROUPS);
+        }
+    }
+
+    // New group name and the groups with delete buttons, followed by every subscribed channel with a picker for its group.
+    fn get_groups_element(&self, instance: &PomeloInstance) -> Element<Msg> {
+        let archive = instance.archive();
+        let name = self.group_name.trim();
+        let can_create = !name.is_empty() && name != ALL_GROUPS && name != NO_GROUP
+            && !archive.subscription_groups().iter().any(|group| group == name);
+
+        let groups = Column::with_children(
+            archive.subscription_groups().iter().map(|group| {
+                row![
+                    Text::new(group.as_str()).width(200),
+                    Button::new(Text::new("Delete").size(12).center())
+                        .width(75)
+                        .on_press(FeedMessage::DeleteGroup(group.clone()).into())
+                ].spacing(10).align_y(iced::Alignment::Center).into()
+            })
+        ).spacing(5);
+
+        let options: Vec<String> = std::iter::once(String::from(NO_GROUP))
+            .chain(archive.subscription_groups().iter().cloned())
+            .collect();
+
+        let channels = Column::with_children(
+            archive.subscriptions().iter().map(|channel| {
+                let id = channel.id.clone();
+                let group = archive.channel_group(&channel.id).unwrap_or(NO_GROUP);
+
+                row![
+                    Text::new(channel.name.as_str()).width(Length::Fill),
+                    PickList::new(
+                        options.clone(),
+                        Some(String::from(group)),
+                        move |group| FeedMessage::SetChannelGroup(id.clone(), group).into()
+                    ).width(200)
+                ].spacing(10).align_y(iced::Alignment::Center).into()
+            })
+        ).spacing(5);
+
+        column![
+            row![
+                TextInput::new("New group name", &self.group_name)
+                    .on_input(|name| FeedMessage::UpdateGroupName(name).into())
+                    .on_submit_maybe(FeedMessage::CreateGroup.on_condition(can_create))
+                    .width(300),
+
+                Button::new(Text::new("Add Group").center())
+                    .width(120)
+                    .on_press_maybe(FeedMessage::CreateGroup.on_condition(can_create))
+            ].spacing(10),
+
+            groups,
+
+            Scrollable::new(channels.width(Length::Fill))
+                .height(instance.settings().window_size().1 / 2.0)
+        ]
+        .spacing(10)
+        .align_x(iced::Alignment::Center)
+        .into()
+    }
+}
+
+impl PomeloPage for FeedPage {
+    fn update(&mut self, instance: &mut PomeloInstance, message: Msg) -> (Task<Msg>, Navigation) {
+        match message {
+            Msg::Back => {
+                if let Some(handle) = self.load_handle.take() {
+                    handle.abort();
+                }
+
+                return (Task::none(), Navigation::Back);
+            },
+
+            Msg::Feed(msg) => match msg {
+                FeedMessage::Refresh => return self.refresh(instance),
+                FeedMessage::Loaded(videos, failed) => return self.on_loaded(videos, failed, instance),
+                FeedMessage::ToVideo(index) => return self.to_video(index),
+                FeedMessage::SelectGroup(group) => self.group = group,
+                FeedMessage::ToggleGroups => self.managing_groups = !self.managing_groups,
+                FeedMessage::UpdateGroupName(name) => self.group_name = name,
+
+                FeedMessage::CreateGroup => {
+                    instance.archive_mut().create_group(self.group_name.trim());
+                    self.group_name.clear();
+                },
+
+                FeedMessage::DeleteGroup(name) => self.delete_group(&name, instance),
+
+                FeedMessage::SetChannelGroup(channel, group) => {
+                    let group = Some(group.as_str()).filter(|group| *group != NO_GROUP);
+                    instance.archive_mut().set_channel_group(&channel, group);
+                }
+            },
+
+            _ => ()
+        }
+
+        (Task::none(), Navigation::None)
+    }
+
+    fn view(&self, instance: &PomeloInstance) -> Element<Msg> {
+        let subscriptions = instance.archive().subscriptions().len();
+
+        let feed: Element<Msg> = if subscriptions == 0 {
+            Text::new("Subscribe to channels from their search results or uploads to see their videos here.").into()
+        }
+        else if self.managing_groups {
+            self.get_groups_element(instance)
+        }
+        else if self.loading && self.videos.is_empty() {
+            super::loading_skeleton(5)
+        }
+        else if self.videos.is_empty() {
+            Text::new("No uploads found.").into()
+        }
+        else {
+            self.get_feed_element(instance)
+        };
+
+        let groups: Vec<String> = std::iter::once(String::from(ALL_GROUPS))
+            .chain(instance.archive().subscription_groups().iter().cloned())
+            .collect();
+
+        column![
+            row![
+                Text::new(format!("Feed ({} channels)", subscriptions)).size(20),
+
+                PickList::new(groups, Some(self.group.clone()), |group| FeedMessage::SelectGroup(group).into()),
+
+                Button::new(Text::new(if self.managing_groups { "Done" } else { "Groups" }).center())
+                    .width(100)
+                    .on_press_maybe(FeedMessage::ToggleGroups.on_condition(subscriptions > 0))
+            ].spacing(10).align_y(iced::Alignment::Center),
+
+            feed
+        ]
+        .push_maybe(
+            (!self.failed.is_empty())
+                .then(|| Text::new(format!("Couldn't load: {}", self.failed.join(", "))).size(12))
+        )
+        .push(
+            row![
+                Button::new(Text::new(if self.loading { "Loading..." } else { "Refresh" }).center())
+                    .width(100)
+                    .on_press_maybe(FeedMessage::Refresh.on_condition(!self.loading && subscriptions > 0)),
+
+                Button::new(Text::new("Back").center())
+                    .width(100)
+                    .on_press(Msg::Back)
+            ].spacing(10)
+        )
+        .spacing(10)
+        .padding(25)
+        .align_x(iced::Alignment::Center)
+        .fill()
+    }
+
+    fn subscription(&self, _instance: &PomeloInstance) -> iced::Subscription<Msg> {
+        iced::Subscription::none()
+    }
+}
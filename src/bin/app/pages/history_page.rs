@@ -0,0 +1,84 @@
+use iced::{Element, Length};
+use iced::widget::{column, Button, Column, Scrollable, Text};
+
+use crate::utils::{format_bytes, secs_to_timestamp};
+use crate::app::instance::history::{HistoryEntry, WeekTotals};
+
+use super::{PomeloInstance, Msg};
+
+#[derive(Debug, Clone)]
+pub (crate) enum HistoryMessage {
+    ClearHistory
+}
+
+impl From<HistoryMessage> for Msg {
+    fn from(value: HistoryMessage) -> Self {
+        Self::History(value)
+    }
+}
+
+// Every finished download, with totals for each week. Shown on the Downloads page's History tab.
+pub (super) fn history_element<'a>(instance: &PomeloInstance) -> Element<'a, Msg> {
+    let height = instance.settings().window_size().1 / 3.0;
+    let history = instance.history();
+
+    if history.entries().next().is_none() {
+        return Text::new("Finished downloads will show up here.").into();
+    }
+
+    let weeks = Column::with_children(
+        history.weekly_totals().iter().map(week_element)
+    ).spacing(5);
+
+    let entries = Column::with_children(
+        history.entries().map(entry_element)
+    ).spacing(10);
+
+    column![
+        Text::new("Weekly Totals").size(18),
+        Scrollable::new(weeks).width(Length::Fill).height(height / 2.0),
+
+        Text::new("Downloads").size(18),
+        Scrollable::new(entries).width(Length::Fill).height(height),
+
+        Button::new(Text::new("Clear History").center())
+            .width(150)
+            .on_press(HistoryMessage::ClearHistory.into())
+    ]
+    .spacing(10)
+    .into()
+}
+
+fn week_element(week: &WeekTotals) -> Element<'static, Msg> {
+    Text::new(
+        format!(
+            "Week of {}: {} completed, {} failed, {} downloaded in {}",
+            week.start.format("%b %-d, %Y"),
+            week.completed,
+            week.failed,
+            format_bytes(week.bytes),
+            secs_to_timestamp(week.duration, true)
+        )
+    ).size(14).into()
+}
+
+fn entry_element(entry: &HistoryEntry) -> Element<'static, Msg> {
+    let status = match entry.error() {
+        Some(e) => format!("Failed: {}", e),
+        None => String::from("Completed")
+    };
+
+    column![
+        Text::new(String::from(entry.title())),
+        Text::new(
+            format!(
+                "{} | {} | {} | {}",
+                entry.started().format("%F %R"),
+                format_bytes(entry.bytes()),
+                secs_to_timestamp(entry.duration(), true),
+                status
+            )
+        ).size(12),
+        Text::new(String::from(entry.path())).size(12)
+    ].into()
+}
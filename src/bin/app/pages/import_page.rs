@@ -0,0 +1,269 @@
+use std::path::Path;
+
+use iced::Task;
+
+use log::{info, error};
+
+use crate::app::instance::absolute_path;
+use crate::app::instance::downloads::{DownloadId, DownloadStatus};
+use crate::app::{DownloadFormat, DownloadQuality, DownloadSubtitles, PomeloError};
+
+use super::{ErrorBanner, PomeloInstance, Navigation, Msg, VideoOrder};
+
+#[derive(Debug, Clone)]
+pub (crate) enum ImportMessage {
+    OpenFilePicker,
+    PlayVideos(VideoOrder),
+    AddToQueue
+}
+
+impl super::ConditionalMessage for ImportMessage {}
+
+impl From<ImportMessage> for Msg {
+    fn from(value: ImportMessage) -> Self {
+        Self::Import(value)
+    }
+}
+
+// Reads a text file of Youtube links or video ids, one per line, for lists made with other programs.
+// The videos can be played, added to the play queue, or downloaded together.
+#[derive(Default)]
+pub (crate) struct ImportPage {
+    file_name: Option<String>,
+    ids: Vec<String>,
+    skipped: usize,
+    queued: bool,
+    selected_format: DownloadFormat,
+    selected_quality: DownloadQuality,
+    selected_subtitles: DownloadSubtitles,
+    download: Option<DownloadId>,
+    error: Option<ErrorBanner>
+}
+
+impl super::PomeloPage for ImportPage {
+    fn update(&mut self, instance: &mut PomeloInstance, message: Msg) -> (Task<Msg>, Navigation) {
+        match message {
+            Msg::Back => return (Task::none(), Navigation::Back),
+            Msg::DismissError => self.error = None,
+            Msg::SetDownloadFormat(format) => self.selected_format = format,
+            Msg::SetDownloadQuality(quality) => self.selected_quality = quality,
+            Msg::SetDownloadSubtitles(subtitles) => self.selected_subtitles = subtitles,
+            Msg::StartVideoDownload => return self.start_download(instance),
+            Msg::CopyDownloadCommand => return self.copy_download_command(instance),
+
+            Msg::Import(msg) => match msg {
+                ImportMessage::OpenFilePicker => self.open_file_picker(),
+                ImportMessage::PlayVideos(order) => return self.play_videos(order),
+                ImportMessage::AddToQueue => self.add_to_queue(instance)
+            },
+
+            _ => ()
+        }
+
+        (Task::none(), Navigation::None)
+    }
+
+    fn view(&self, instance: &PomeloInstance) -> iced::Element<Msg> {
+        use iced::widget::{column, row, Button, Column, ProgressBar, Scrollable, Text};
+        use super::{download_element, ConditionalMessage, FillElement};
+
+        let mut column = Column::new().spacing(25).align_x(iced::Alignment::Center);
+
+        column = column.push(
+            Button::new(Text::new("Open URL List").center())
+                .width(200)
+                .on_press(ImportMessage::OpenFilePicker.into())
+        );
+
+        if let Some(file_name) = &self.file_name {
+            let mut summary = format!("{}: {} videos", file_name, self.ids.len());
+
+            if self.skipped > 0 {
+                summary.push_str(&format!(", {} lines skipped", self.skipped));
+            }
+
+            column = column.push(Text::new(summary));
+        }
+
+        column = column.push_maybe(self.error.as_ref().map(|banner| banner.view()));
+
+        if !self.ids.is_empty() {
+            column = column.push(
+                Scrollable::new(
+                    Column::with_children(self.ids.iter().map(|id| Text::new(id.clone()).into()))
+                ).height(instance.settings().window_size().1 / 3.0)
+            );
+
+            // Overall progress through the list, found from the video yt-dlp is working on.
+            let running = self.download
+                .and_then(|id| instance.downloads().get(id))
+                .filter(|download| *download.status() == DownloadStatus::Running);
+
+            if let Some(download) = running {
+                let index = download.current_video()
+                    .and_then(|id| self.ids.iter().position(|v| v == id))
+                    .unwrap_or_default();
+
+                column = column.extend([
+                    Text::new(format!("Downloading {} of {}", index + 1, self.ids.len())).into(),
+
+                    ProgressBar::new(0.0..=self.ids.len() as f32, index as f32)
+                        .width(instance.settings().window_size().0 / 2.0)
+                        .into()
+                ]);
+            }
+
+            column = column.push_maybe(self.download.and_then(|id| super::download_progress_element(instance, id)));
+
+            column = column.push(
+                column![
+                    row![
+                        Button::new(Text::new("Play").center())
+                            .width(100)
+                            .on_press(ImportMessage::PlayVideos(VideoOrder::Sequential(0)).into()),
+
+                        Button::new(Text::new("Shuffle").center())
+                            .width(100)
+                            .on_press(ImportMessage::PlayVideos(VideoOrder::Shuffled).into()),
+
+                        Button::new(Text::new(if self.queued {"Queued"} else {"Add to Queue"}).center())
+                            .width(150)
+                            .on_press_maybe(ImportMessage::AddToQueue.on_condition(!self.queued))
+                    ].spacing(10),
+
+                    download_element(&self.selected_format, &self.selected_quality, &self.selected_subtitles)
+                ].spacing(25).align_x(iced::Alignment::Center)
+            );
+        }
+
+        column.push(
+            Button::new(Text::new("Back").center())
+                .width(100)
+                .on_press(Msg::Back)
+        ).fill()
+    }
+
+    fn subscription(&self, _instance: &PomeloInstance) -> iced::Subscription<Msg> {
+        iced::Subscription::none()
+    }
+}
+
+impl ImportPage {
+    pub (crate) fn new() -> Self {
+        Default::default()
+    }
+
+    // Pick a text file and read the video ids from it.
+    fn open_file_picker(&mut self) {
+        use rfd::FileDialog;
+        use crate::utils::parse_url_list;
+
+        let Some(path) = FileDialog::new()
+            .add_filter("text", &["txt", "csv", "list"])
+            .set_directory(".")
+            .pick_file()
+        else {
+            return;
+        };
+
+        self.error = None;
+        self.queued = false;
+        self.file_name = path.file_name().map(|name| name.to_string_lossy().into_owned());
+
+        match std::fs::read_to_string(&path) {
+            Ok(text) => {
+                (self.ids, self.skipped) = parse_url_list(&text);
+                info!("Imported {} videos from {:?}, skipped {} lines.", self.ids.len(), path, self.skipped);
+            },
+
+            Err(e) => {
+                error!("Failed to read URL list: {}", e);
+                self.ids.clear();
+                self.skipped = 0;
+                self.error = Some(ErrorBanner::new(PomeloError::new(e), None));
+            }
+        }
+    }
+
+    // Move to the video player with the imported videos.
+    fn play_videos(&self, order: VideoOrder) -> (Task<Msg>, Navigation) {
+        use super::video_player_page::{VideoPlayerMessage, VideoPlayerPage};
+
+        let videos = self.ids.iter().cloned()
+            .map(|id| (id, false))
+            .collect();
+
+        let index = if let VideoOrder::Sequential(i) = order {i} else {0};
+
+        (
+            Task::done(VideoPlayerMessage::LoadVideo(index).into()),
+            Navigation::GoTo(Box::new(VideoPlayerPage::new(videos, order)))
+        )
+    }
+
+    // Titles aren't known until the videos are loaded, so the queue shows their ids.
+    fn add_to_queue(&mut self, instance: &mut PomeloInstance) {
+        for id in &self.ids {
+            if !instance.queue().contains(id) {
+                instance.queue_mut().push(id.clone(), id.clone());
+            }
+        }
+
+        self.queued = true;
+    }
+
+    // The folder named after the list that the videos would be downloaded to, and the arguments yt-dlp would be run with.
+    fn download_args(&self, instance: &PomeloInstance) -> (String, String, Vec<String>) {
+        use filenamify::filenamify;
+
+        let list_name = self.file_name.as_deref()
+            .map(|name| filenamify(name.rsplit_once('.').map_or(name, |(stem, _)| stem)))
+            .unwrap_or_else(|| String::from("imported"));
+
+        let out_path = format!("{}/{}/{}",
+            instance.settings().download_folder(),
+            if self.selected_format.is_audio() {"audio"} else {"videos"},
+            list_name
+        );
+
+        // yt-dlp runs in its own working directory, so it needs the full path.
+        let out_dir = absolute_path(&out_path);
+
+        let mut args: Vec<String> = [
+            "-P",
+            out_dir.as_str()
+        ].map(String::from).to_vec();
+
+        args.extend(super::format_args(&self.selected_format, &self.selected_quality, &self.selected_subtitles));
+
+        // Ids go after "--", so ones starting with a dash aren't read as options.
+        args.push(String::from("--"));
+        args.extend(self.ids.iter().cloned());
+
+        let title = format!("{} ({} videos)", list_name, self.ids.len());
+
+        (out_path, title, args)
+    }
+
+    fn copy_download_command(&self, instance: &PomeloInstance) -> (Task<Msg>, Navigation) {
+        let (_, _, args) = self.download_args(instance);
+        (Task::done(Msg::CopyToClipboard(instance.download_command_line(&args))), Navigation::None)
+    }
+
+    // Download every imported video with one yt-dlp process.
+    fn start_download(&mut self, instance: &mut PomeloInstance) -> (Task<Msg>, Navigation) {
+        self.error = None;
+
+        let (out_path, title, args) = self.download_args(instance);
+
+        info!("Downloading {} imported videos to {}", self.ids.len(), out_path);
+
+        if !Path::exists(Path::new(&out_path)) {
+            let _ = std::fs::create_dir_all(&out_path);
+        }
+
+        self.download = Some(instance.enqueue_download(title, None, out_path, args, None));
+
+        (Task::none(), Navigation::None)
+    }
+}
@@ -0,0 +1,640 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::Path;
+
+use iced::{Element, Length, Task};
+use iced::widget::{column, row, stack, Button, Checkbox, Column, Container, PickList, ProgressBar, Row, Scrollable, Text};
+
+use log::{error, info};
+
+use crate::app::{DownloadFormat, DownloadQuality, DownloadSubtitles, PomeloError};
+use crate::app::instance::archive::{ArchivedVideo, PlaylistEntry};
+use crate::utils::secs_to_timestamp;
+
+use super::{ConditionalMessage, ErrorBanner, FillElement, PlaylistChoice, PomeloInstance, PomeloPage, Navigation, Msg, VideoOrder};
+
+// Extensions of audio-only downloads, for files that aren't in an "audio" folder.
+const AUDIO_EXTENSIONS: &[&str] = &["mp3", "m4a", "opus", "ogg", "flac", "wav", "aac"];
+
+// Videos, or audio-only downloads grouped into albums.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub (crate) enum LibraryTab {
+    Videos,
+    Music
+}
+
+#[derive(Debug, Clone)]
+pub (crate) enum LibraryMessage {
+    Play(usize),
+    Resume(usize),
+    SelectTab(LibraryTab),
+
+    // Play every music file, or only one album's, in order or shuffled.
+    PlayMusic(Option<String>, VideoOrder),
+
+    // Selection mode, and the actions for the selected videos.
+    ToggleSelecting,
+    Select(usize, bool),
+    SelectAll,
+    AddToPlaylist(String),
+    MarkWatched,
+    DeleteFiles,
+    ConfirmDelete,
+    SetRedownloadQuality(DownloadQuality),
+    Redownload
+}
+
+impl ConditionalMessage for LibraryMessage {}
+
+impl From<LibraryMessage> for Msg {
+    fn from(value: LibraryMessage) -> Self {
+        Self::Library(value)
+    }
+}
+
+// Downloaded videos, as cards that play their files.
+// Files that were stopped part way through are listed first, with their progress over the thumbnail and Resume as the main button.
+// The watch history keeps positions by file url, so cards only show progress from playing the file, not the stream.
+// In selection mode, cards get checkboxes instead, and the selected videos can be handled all at once.
+pub (crate) struct LibraryPage {
+    videos: Vec<(ArchivedVideo, String)>,
+    tab: LibraryTab,
+
+    selecting: bool,
+    selected: BTreeSet<usize>,
+
+    // Deleting asks once more before any files are removed.
+    confirm_delete: bool,
+    redownload_quality: DownloadQuality,
+    status: Option<String>,
+    error: Option<ErrorBanner>
+}
+
+impl LibraryPage {
+    pub (crate) fn new(instance: &PomeloInstance) -> Self {
+        Self {
+            videos: library_videos(instance),
+            tab: LibraryTab::Videos,
+            selecting: false,
+            selected: BTreeSet::new(),
+            confirm_delete: false,
+            redownload_quality: DownloadQuality::default(),
+            status: None,
+            error: None
+        }
+    }
+
+    // Videos shown on the current tab, by index.
+    fn tab_indices(&self) -> Vec<usize> {
+        let music = self.tab == LibraryTab::Music;
+
+        (0..self.videos.len())
+            .filter(|i| is_audio(&self.videos[*i].0.file_path) == music)
+            .collect()
+    }
+
+    // Music files by album, which is the folder they were downloaded to: the channel's name, or the playlist's.
+    // Albums are sorted by name, and their files by file name.
+    fn albums(&self) -> BTreeMap<String, Vec<usize>> {
+        let mut albums: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+
+        for (i, (video, _)) in self.videos.iter().enumerate().filter(|(_, (video, _))| is_audio(&video.file_path)) {
+            albums.entry(album_name(&video.file_path)).or_default().push(i);
+        }
+
+        for files in albums.values_mut() {
+            files.sort_by(|a, b| self.videos[*a].0.file_path.cmp(&self.videos[*b].0.file_path));
+        }
+
+        albums
+    }
+
+    // Play an album's files one after another, or every album's if none is given.
+    fn play_music(&self, album: Option<&str>, order: VideoOrder) -> (Task<Msg>, Navigation) {
+        use super::video_player_page::{VideoPlayerMessage, VideoPlayerPage};
+
+        let files: VecDeque<(String, bool)> = self.albums().into_iter()
+            .filter(|(name, _)| album.is_none() || album == Some(name.as_str()))
+            .flat_map(|(_, files)| files)
+            .map(|i| (self.videos[i].1.clone(), true))
+            .collect();
+
+        if files.is_empty() {
+            return (Task::none(), Navigation::None);
+        }
+
+        (
+            Task::done(VideoPlayerMessage::LoadVideo(0).into()),
+            Navigation::GoTo(Box::new(VideoPlayerPage::new(files, order)))
+        )
+    }
+
+    fn selected_videos(&self) -> impl Iterator<Item = &(ArchivedVideo, String)> {
+        self.selected.iter().filter_map(|i| self.videos.get(*i))
+    }
+
+    // Add the selected files to a local playlist, in library order.
+    fn add_to_playlist(&mut self, instance: &mut PomeloInstance, playlist: &str) {
+        let entries: Vec<PlaylistEntry> = self.selected_videos()
+            .map(|(video, url)| PlaylistEntry { video: url.clone(), title: video.title.clone(), local: true })
+            .collect();
+
+        let count = entries.len();
+
+        for entry in entries {
+            instance.archive_mut().add_to_playlist(playlist, entry);
+        }
+
+        let title = instance.archive().playlist(playlist).map_or("playlist", |playlist| playlist.title.as_str());
+        self.status = Some(format!("Added {} videos to {}.", count, title));
+    }
+
+    fn mark_watched(&mut self, instance: &mut PomeloInstance) {
+        let ids: Vec<String> = self.selected_videos().map(|(video, _)| video.id.clone()).collect();
+
+        for id in &ids {
+            instance.archive_mut().set_watched(id, true);
+        }
+
+        self.status = Some(format!("Marked {} videos as watched.", ids.len()));
+    }
+
+    // Remove the selected files from the disk and the archive. Files that are already gone are only removed from the archive.
+    fn delete_files(&mut self, instance: &mut PomeloInstance) {
+        let mut deleted = 0;
+        let mut failed = Vec::new();
+
+        let videos: Vec<ArchivedVideo> = self.selected_videos().map(|(video, _)| video.clone()).collect();
+
+        for video in videos {
+            match std::fs::remove_file(&video.file_path) {
+                Ok(_) => deleted += 1,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => (),
+                Err(e) => {
+                    error!("Failed to delete {}: {}", video.file_path, e);
+                    failed.push(video.title);
+                    continue;
+                }
+            }
+
+            instance.archive_mut().remove_video(&video.id);
+        }
+
+        info!("Deleted {} downloaded files.", deleted);
+
+        if !failed.is_empty() {
+            let e = PomeloError::from(format!("Couldn't delete: {}", failed.join(", ")));
+            self.error = Some(ErrorBanner::new(e, None));
+        }
+
+        self.status = Some(format!("Deleted {} files.", deleted));
+        self.videos = library_videos(instance);
+        self.selected.clear();
+    }
+
+    // Download the selected videos again over their files, in the same format and folder.
+    fn redownload(&mut self, instance: &mut PomeloInstance) {
+        let downloads: Vec<(String, String, String, Vec<String>)> = self.selected_videos()
+            .filter_map(|(video, _)| {
+                let (folder, args) = redownload_args(video, &self.redownload_quality)?;
+                Some((video.title.clone(), video.author.clone(), folder, args))
+            })
+            .collect();
+
+        let count = downloads.len();
+
+        for (title, author, folder, args) in downloads {
+            info!("Downloading \"{}\" again.", title);
+            instance.enqueue_download(title, Some(author), folder, args, None);
+        }
+
+        self.status = Some(format!("Queued {} downloads.", count));
+    }
+
+    fn music_list<'a>(&'a self) -> Element<'a, Msg> {
+        let albums = self.albums();
+
+        if albums.is_empty() {
+            return Text::new("Audio downloads will show up here.").into();
+        }
+
+        let play_buttons = |album: Option<String>| row![
+            Button::new(Text::new("Play All").size(12).center())
+                .width(90)
+                .on_press(LibraryMessage::PlayMusic(album.clone(), VideoOrder::Sequential(0)).into()),
+
+            Button::new(Text::new("Shuffle").size(12).center())
+                .width(90)
+                .on_press(LibraryMessage::PlayMusic(album, VideoOrder::Shuffled).into())
+        ].spacing(10);
+
+        let mut list = Column::new().spacing(5);
+
+        for (album, files) in albums {
+            list = list
+                .push(
+                    row![
+                        Text::new(format!("{} ({})", album, files.len())).size(16).width(Length::Fill),
+                        play_buttons(Some(album.clone()))
+                    ].spacing(10).align_y(iced::Alignment::Center)
+                )
+                .extend(files.into_iter().map(|i| self.track(i)));
+        }
+
+        column![
+            play_buttons(None),
+            Scrollable::new(list.width(Length::Fill))
+                .height(Length::Fill)
+        ].spacing(10).align_x(iced::Alignment::Center).into()
+    }
+
+    // A music file's row in its album, with a checkbox in selection mode or a play button otherwise.
+    fn track<'a>(&'a self, index: usize) -> Element<'a, Msg> {
+        let (video, _) = &self.videos[index];
+
+        let action: Element<Msg> = if self.selecting {
+            Checkbox::new("", self.selected.contains(&index))
+                .on_toggle(move |checked| LibraryMessage::Select(index, checked).into())
+                .into()
+        }
+        else {
+            Button::new(Text::new("Play").size(12).center())
+                .width(70)
+                .on_press(LibraryMessage::Play(index).into())
+                .into()
+        };
+
+        row![
+            action,
+            Text::new(video.title.as_str()).width(Length::Fill),
+            Text::new(video.author.as_str()).size(12)
+        ]
+        .spacing(10)
+        .padding([0, 15])
+        .align_y(iced::Alignment::Center)
+        .into()
+    }
+
+    fn tabs(&self) -> Element<Msg> {
+        let tab_button = |text: &'static str, tab: LibraryTab| Button::new(Text::new(text).center())
+            .width(100)
+            .on_press_maybe(LibraryMessage::SelectTab(tab).on_condition(self.tab != tab));
+
+        row![
+            tab_button("Videos", LibraryTab::Videos),
+            tab_button("Music", LibraryTab::Music)
+        ].spacing(10).into()
+    }
+
+    fn selection_bar<'a>(&'a self, instance: &PomeloInstance) -> Element<'a, Msg> {
+        let any = !self.selected.is_empty();
+
+        let choices: Vec<PlaylistChoice> = instance.archive().playlists().iter()
+            .map(|playlist| PlaylistChoice { id: playlist.id.clone(), title: playlist.title.clone() })
+            .collect();
+
+        let delete_label = if self.confirm_delete {
+            format!("Delete {} files?", self.selected.len())
+        } else {
+            String::from("Delete Files")
+        };
+
+        let delete_message = if self.confirm_delete { LibraryMessage::ConfirmDelete } else { LibraryMessage::DeleteFiles };
+
+        row![
+            Text::new(format!("{} selected", self.selected.len())).width(90),
+
+            Button::new(Text::new("Select All").size(12).center())
+                .width(90)
+                .on_press(LibraryMessage::SelectAll.into()),
+
+            PickList::new(choices, None::<PlaylistChoice>, |choice| LibraryMessage::AddToPlaylist(choice.id).into())
+                .placeholder("Add to Playlist")
+                .width(150),
+
+            Button::new(Text::new("Mark Watched").size(12).center())
+                .width(110)
+                .on_press_maybe(LibraryMessage::MarkWatched.on_condition(any)),
+
+            PickList::new(
+                DownloadQuality::ALL,
+                Some(&self.redownload_quality),
+                |quality| LibraryMessage::SetRedownloadQuality(quality).into()
+            ).width(90),
+
+            Button::new(Text::new("Re-download").size(12).center())
+                .width(100)
+                .on_press_maybe(LibraryMessage::Redownload.on_condition(any)),
+
+            Button::new(Text::new(delete_label).size(12).center())
+                .width(120)
+                .style(iced::widget::button::danger)
+                .on_press_maybe(delete_message.on_condition(any))
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center)
+        .into()
+    }
+
+    // Thumbnails saved in the archive when the videos were downloaded.
+    pub (crate) fn load_thumbnails(&self, instance: &PomeloInstance) -> Task<Msg> {
+        Task::batch(
+            self.videos.iter()
+                .map(|(video, _)| super::load_image_thumbnail(video.id.clone(), video.thumbnail.as_deref(), instance))
+        )
+    }
+
+    fn play(&self, index: usize, position: f64) -> (Task<Msg>, Navigation) {
+        use super::video_player_page::{VideoPlayerMessage, VideoPlayerPage};
+
+        let Some((_, url)) = self.videos.get(index) else {
+            return (Task::none(), Navigation::None);
+        };
+
+        let page = VideoPlayerPage::new(VecDeque::from([(url.clone(), true)]), VideoOrder::Sequential(0))
+            .with_start_position(position);
+
+        (
+            Task::done(VideoPlayerMessage::LoadVideo(0).into()),
+            Navigation::GoTo(Box::new(page))
+        )
+    }
+
+    fn card<'a>(&'a self, index: usize, instance: &PomeloInstance) -> Element<'a, Msg> {
+        let (video, url) = &self.videos[index];
+        let progress = instance.archive().watch_progress(url);
+
+        let thumbnail = super::thumbnail(instance.cache(), &video.id, &video.author, 160.0, 90.0)
+            .unwrap_or_else(|| super::thumbnail_placeholder(&video.author, 160.0, 90.0));
+
+        // Progress bar along the bottom of the thumbnail. Files stopped before their length was saved don't get one.
+        let overlay = progress
+            .filter(|(_, duration)| *duration > 0.0)
+            .map(|(position, duration)|
+                Container::new(ProgressBar::new(0.0..=duration as f32, position as f32).height(6))
+                    .width(160)
+                    .height(90)
+                    .align_y(iced::alignment::Vertical::Bottom)
+                    .into()
+            );
+
+        let (primary, secondary) = match progress {
+            Some((position, _)) => (
+                Button::new(Text::new(format!("Resume from {}", secs_to_timestamp(position as u64, position >= 3600.0))).size(12).center())
+                    .width(150)
+                    .on_press(LibraryMessage::Resume(index).into()),
+
+                Some(
+                    Button::new(Text::new("Start over").size(12).center())
+                        .width(90)
+                        .style(iced::widget::button::secondary)
+                        .on_press(LibraryMessage::Play(index).into())
+                )
+            ),
+
+            None => (
+                Button::new(Text::new("Play").size(12).center())
+                    .width(150)
+                    .on_press(LibraryMessage::Play(index).into()),
+
+                None
+            )
+        };
+
+        let checkbox = self.selecting.then(|| {
+            Checkbox::new("", self.selected.contains(&index))
+                .on_toggle(move |checked| LibraryMessage::Select(index, checked).into())
+        });
+
+        // Selected videos are handled from the selection bar, so the play buttons are hidden.
+        let (primary, secondary) = if self.selecting { (None, None) } else { (Some(primary), secondary) };
+
+        Row::new()
+            .push_maybe(checkbox)
+            .push(Container::new(stack![thumbnail].extend(overlay)).width(160).height(90))
+            .push(
+                column![
+                    Text::new(video.title.as_str()),
+                    Text::new(video.author.as_str()).size(12)
+                ].width(Length::Fill)
+            )
+            .push_maybe(primary)
+            .push_maybe(secondary)
+            .spacing(10)
+            .align_y(iced::Alignment::Center)
+            .into()
+    }
+}
+
+impl PomeloPage for LibraryPage {
+    fn update(&mut self, instance: &mut PomeloInstance, message: Msg) -> (Task<Msg>, Navigation) {
+        match message {
+            Msg::Back => return (Task::none(), Navigation::Back),
+            Msg::DismissError => self.error = None,
+
+            Msg::Library(msg) => match msg {
+                LibraryMessage::Play(index) => return self.play(index, 0.0),
+
+                LibraryMessage::Resume(index) => {
+                    let position = self.videos.get(index)
+                        .and_then(|(_, url)| instance.archive().watch_position(url))
+                        .unwrap_or(0.0);
+
+                    return self.play(index, position);
+                },
+
+                LibraryMessage::SelectTab(tab) => {
+                    self.tab = tab;
+                    self.selected.clear();
+                    self.confirm_delete = false;
+                },
+
+                LibraryMessage::PlayMusic(album, order) => return self.play_music(album.as_deref(), order),
+
+                LibraryMessage::ToggleSelecting => {
+                    self.selecting = !self.selecting;
+                    self.selected.clear();
+                    self.confirm_delete = false;
+                },
+
+                LibraryMessage::Select(index, checked) => {
+                    if checked {
+                        self.selected.insert(index);
+                    }
+                    else {
+                        self.selected.remove(&index);
+                    }
+
+                    self.confirm_delete = false;
+                },
+
+                LibraryMessage::SelectAll => {
+                    self.selected = self.tab_indices().into_iter().collect();
+                    self.confirm_delete = false;
+                },
+
+                LibraryMessage::AddToPlaylist(playlist) => self.add_to_playlist(instance, &playlist),
+                LibraryMessage::MarkWatched => self.mark_watched(instance),
+                LibraryMessage::DeleteFiles => self.confirm_delete = true,
+
+                LibraryMessage::ConfirmDelete => {
+                    self.confirm_delete = false;
+                    self.delete_files(instance);
+                },
+
+                LibraryMessage::SetRedownloadQuality(quality) => self.redownload_quality = quality,
+                LibraryMessage::Redownload => self.redownload(instance)
+            },
+
+            _ => ()
+        }
+
+        (Task::none(), Navigation::None)
+    }
+
+    fn view(&self, instance: &PomeloInstance) -> Element<Msg> {
+        let (in_progress, rest): (Vec<usize>, Vec<usize>) = self.tab_indices().into_iter()
+            .partition(|i| instance.archive().watch_progress(&self.videos[*i].1).is_some());
+
+        let empty = in_progress.is_empty() && rest.is_empty();
+        let mut cards = Column::new().spacing(10);
+
+        if !in_progress.is_empty() {
+            cards = cards
+                .push(Text::new("Continue Watching").size(16))
+                .extend(in_progress.into_iter().map(|i| self.card(i, instance)))
+                .push(Text::new("Downloads").size(16));
+        }
+
+        cards = cards.extend(rest.into_iter().map(|i| self.card(i, instance)));
+
+        let list: Element<Msg> = if self.tab == LibraryTab::Music {
+            Container::new(self.music_list())
+                .height(instance.settings().window_size().1 * 3.0 / 4.0)
+                .into()
+        }
+        else if empty {
+            Text::new("Downloaded videos will show up here.").into()
+        }
+        else {
+            Scrollable::new(cards.width(Length::Fill))
+                .height(instance.settings().window_size().1 * 3.0 / 4.0)
+                .into()
+        };
+
+        Column::new()
+            .push_maybe(self.error.as_ref().map(|banner| banner.view()))
+            .push(Text::new("Library").size(20))
+            .push(self.tabs())
+            .push_maybe(self.selecting.then(|| self.selection_bar(instance)))
+            .push(list)
+            .push_maybe(self.status.as_deref().map(|status| Text::new(status).size(12)))
+            .push(
+                row![
+                    Button::new(Text::new(if self.selecting { "Done" } else { "Select" }).center())
+                        .width(100)
+                        .on_press_maybe(LibraryMessage::ToggleSelecting.on_condition(!self.videos.is_empty())),
+
+                    Button::new(Text::new("Back").center())
+                        .width(100)
+                        .on_press(Msg::Back)
+                ].spacing(10)
+            )
+            .spacing(10)
+            .padding(25)
+            .align_x(iced::Alignment::Center)
+            .fill()
+    }
+
+    fn subscription(&self, _instance: &PomeloInstance) -> iced::Subscription<Msg> {
+        iced::Subscription::none()
+    }
+}
+
+// Audio-only downloads are kept in "audio" folders, and can also be told apart by their extension.
+fn is_audio(file_path: &str) -> bool {
+    let path = Path::new(file_path);
+
+    path.components().any(|part| part.as_os_str() == "audio")
+        || path.extension().is_some_and(|ext| AUDIO_EXTENSIONS.iter().any(|audio| ext.eq_ignore_ascii_case(audio)))
+}
+
+fn album_name(file_path: &str) -> String {
+    Path::new(file_path).parent()
+        .and_then(Path::file_name)
+        .map_or_else(String::new, |name| name.to_string_lossy().into_owned())
+}
+
+// Downloaded videos with files that are still there, with the file url their positions are saved under.
+fn library_videos(instance: &PomeloInstance) -> Vec<(ArchivedVideo, String)> {
+    instance.archive().archived_videos().into_iter()
+        .filter_map(|video| {
+            let url = url::Url::from_file_path(&video.file_path).ok()?;
+            Some((video, url.to_string()))
+        })
+        .collect()
+}
+
+// Folder and yt-dlp arguments for downloading a video over its file again.
+// The format comes from the file's extension. Files in other formats are downloaded as mp4 next to the old one.
+fn redownload_args(video: &ArchivedVideo, quality: &DownloadQuality) -> Option<(String, Vec<String>)> {
+    let path = Path::new(&video.file_path);
+    let folder = path.parent()?.to_string_lossy().into_owned();
+    let stem = path.file_stem()?.to_string_lossy().replace('%', "%%");
+    let ext = path.extension().map(|ext| ext.to_string_lossy().to_lowercase()).unwrap_or_default();
+
+    let format = DownloadFormat::ALL.into_iter()
+        .find(|format| format.as_ext() == ext)
+        .unwrap_or_default();
+
+    let mut args: Vec<String> = [
+        "-P",
+        folder.as_str(),
+        "-o",
+        &format!("{}.%(ext)s", stem),
+        "--force-overwrites"
+    ].map(String::from).to_vec();
+
+    args.extend(super::format_args(&format, quality, &DownloadSubtitles::Off));
+
+    // Ids go after "--", so ones starting with a dash aren't read as options.
+    args.push(String::from("--"));
+    args.push(video.id.clone());
+
+    Some((folder, args))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::DownloadQuality;
+    use crate::app::instance::archive::ArchivedVideo;
+
+    use super::{album_name, is_audio, redownload_args};
+
+    #[test]
+    fn test_music_files() {
+        assert!(is_audio("/downloads/audio/Pomelo Demo/Pomelo song.webm"));
+        assert!(is_audio("/downloads/videos/Pomelo Demo/Pomelo song.M4A"));
+        assert!(!is_audio("/downloads/videos/Pomelo Demo/Growing pomelos at home.mp4"));
+
+        assert_eq!(album_name("/downloads/playlists/audio/Pomelo Demo - Fruit Songs/Pomelo song.mp3"), "Pomelo Demo - Fruit Songs");
+    }
+
+    #[test]
+    fn test_redownload_args() {
+        let video = ArchivedVideo {
+            id: String::from("dQw4w9WgXcQ"),
+            title: String::from("Growing pomelos at home"),
+            author: String::from("Pomelo Demo"),
+            channel_id: None,
+            file_path: String::from("/downloads/audio/Pomelo Demo/100% pomelo.mp3"),
+            thumbnail: None
+        };
+
+        let (folder, args) = redownload_args(&video, &DownloadQuality::default()).unwrap();
+
+        assert_eq!(folder, "/downloads/audio/Pomelo Demo");
+        assert_eq!(args[..5], ["-P", "/downloads/audio/Pomelo Demo", "-o", "100%% pomelo.%(ext)s", "--force-overwrites"]);
+        assert!(args.windows(3).any(|window| window == ["-x", "--audio-format", "mp3"]));
+        assert_eq!(args[args.len() - 2..], ["--", "dQw4w9WgXcQ"]);
+    }
+}
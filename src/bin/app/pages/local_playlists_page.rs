@@ -0,0 +1,239 @@
+use iced::{Element, Length, Task};
+use iced::widget::{column, row, Button, Column, Scrollable, Text, TextInput};
+
+use log::info;
+
+use crate::app::instance::archive::LocalPlaylist;
+
+use super::{ConditionalMessage, FillElement, PomeloInstance, PomeloPage, Navigation, Msg};
+
+#[derive(Debug, Clone)]
+pub (crate) enum LocalPlaylistsMessage {
+    UpdateName(String),
+    Create,
+    Open(String),
+    Close,
+    Delete(String),
+    Play(String),
+    Remove(usize),
+    Move(usize, bool)
+}
+
+impl ConditionalMessage for LocalPlaylistsMessage {}
+
+impl From<LocalPlaylistsMessage> for Msg {
+    fn from(value: LocalPlaylistsMessage) -> Self {
+        Self::LocalPlaylists(value)
+    }
+}
+
+// Playlists made in Pomelo. Lists every playlist, or the videos of the one that's open.
+// Videos are added from search results and video pages.
+pub (crate) struct LocalPlaylistsPage {
+    name_input: String,
+    open: Option<String>
+}
+
+impl LocalPlaylistsPage {
+    pub (crate) fn new() -> Self {
+        Self { name_input: String::new(), open: None }
+    }
+
+    // Play every video in the playlist, in order.
+    fn play(&self, id: &str, instance: &PomeloInstance) -> (Task<Msg>, Navigation) {
+        use super::VideoOrder;
+        use super::video_player_page::{VideoPlayerMessage, VideoPlayerPage};
+
+        let Some(playlist) = instance.archive().playlist(id).filter(|playlist| !playlist.videos.is_empty()) else {
+            return (Task::none(), Navigation::None);
+        };
+
+        info!("Playing local playlist: {}", playlist.title);
+
+        let videos = playlist.videos.iter()
+            .map(|entry| (entry.video.clone(), entry.local))
+            .collect();
+
+        let titles = playlist.videos.iter()
+            .map(|entry| (entry.video.clone(), entry.title.clone()));
+
+        (
+            Task::done(VideoPlayerMessage::LoadVideo(0).into()),
+            Navigation::GoTo(Box::new(VideoPlayerPage::new(videos, VideoOrder::Sequential(0)).with_titles(titles)))
+        )
+    }
+}
+
+impl PomeloPage for LocalPlaylistsPage {
+    fn update(&mut self, instance: &mut PomeloInstance, message: Msg) -> (Task<Msg>, Navigation) {
+        match message {
+            // Back from an open playlist goes to the list of playlists first.
+            Msg::Back if self.open.is_some() => self.open = None,
+            Msg::Back => return (Task::none(), Navigation::Back),
+
+            Msg::LocalPlaylists(msg) => match msg {
+                LocalPlaylistsMessage::UpdateName(name) => self.name_input = name,
+
+                LocalPlaylistsMessage::Create => if !self.name_input.trim().is_empty() {
+                    instance.archive_mut().create_playlist(self.name_input.trim());
+                    self.name_input.clear();
+                },
+
+                LocalPlaylistsMessage::Open(id) => self.open = Some(id),
+                LocalPlaylistsMessage::Close => self.open = None,
+                LocalPlaylistsMessage::Delete(id) => instance.archive_mut().delete_playlist(&id),
+                LocalPlaylistsMessage::Play(id) => return self.play(&id, instance),
+
+                LocalPlaylistsMessage::Remove(index) => if let Some(id) = &self.open {
+                    instance.archive_mut().remove_from_playlist(id, index);
+                },
+
+                LocalPlaylistsMessage::Move(index, up) => if let Some(id) = &self.open {
+                    instance.archive_mut().move_in_playlist(id, index, up);
+                }
+            },
+
+            _ => ()
+        }
+
+        (Task::none(), Navigation::None)
+    }
+
+    fn view(&self, instance: &PomeloInstance) -> Element<Msg> {
+        let open = self.open.as_deref().and_then(|id| instance.archive().playlist(id));
+
+        let content = match open {
+            Some(playlist) => playlist_element(playlist, instance),
+            None => playlists_element(&self.name_input, instance)
+        };
+
+        column![
+            content,
+
+            Button::new(Text::new("Back").center())
+                .width(100)
+                .on_press(Msg::Back)
+        ]
+        .spacing(10)
+        .padding(25)
+        .align_x(iced::Alignment::Center)
+        .fill()
+    }
+
+    fn subscription(&self, _instance: &PomeloInstance) -> iced::Subscription<Msg> {
+        iced::Subscription::none()
+    }
+}
+
+// Every playlist, with a box for naming a new one.
+fn playlists_element<'a>(name_input: &str, instance: &'a PomeloInstance) -> Element<'a, Msg> {
+    let playlists = instance.archive().playlists();
+
+    let rows = Column::with_children(
+        playlists.iter().map(|playlist| {
+            row![
+                column![
+                    Text::new(playlist.title.clone()),
+                    Text::new(format!("{} videos", playlist.videos.len())).size(12)
+                ].width(Length::Fill),
+
+                Button::new(Text::new("Open").size(12).center())
+                    .width(70)
+                    .on_press(LocalPlaylistsMessage::Open(playlist.id.clone()).into()),
+
+                Button::new(Text::new("Play").size(12).center())
+                    .width(70)
+                    .on_press_maybe(
+                        LocalPlaylistsMessage::Play(playlist.id.clone()).on_condition(!playlist.videos.is_empty())
+                    ),
+
+                Button::new(Text::new("Delete").size(12).center())
+                    .width(70)
+                    .on_press(LocalPlaylistsMessage::Delete(playlist.id.clone()).into())
+            ].spacing(5).align_y(iced::Alignment::Center).into()
+        })
+    ).spacing(10);
+
+    let list: Element<Msg> = if playlists.is_empty() {
+        Text::new("No playlists yet.").into()
+    }
+    else {
+        Scrollable::new(rows)
+            .width(Length::Fill)
+            .height(instance.settings().window_size().1 / 2.0)
+            .into()
+    };
+
+    let can_create = !name_input.trim().is_empty();
+
+    column![
+        Text::new("Playlists").size(20),
+
+        list,
+
+        row![
+            TextInput::new("New playlist name", name_input)
+                .on_input(|name| LocalPlaylistsMessage::UpdateName(name).into())
+                .on_submit_maybe(LocalPlaylistsMessage::Create.on_condition(can_create))
+                .width(300),
+
+            Button::new(Text::new("Create").center())
+                .width(100)
+                .on_press_maybe(LocalPlaylistsMessage::Create.on_condition(can_create))
+        ].spacing(10)
+    ].spacing(10).align_x(iced::Alignment::Center).into()
+}
+
+// Videos of a playlist, with buttons for moving and removing them.
+fn playlist_element<'a>(playlist: &'a LocalPlaylist, instance: &'a PomeloInstance) -> Element<'a, Msg> {
+    let last = playlist.videos.len().saturating_sub(1);
+
+    let rows = Column::with_children(
+        playlist.videos.iter().enumerate().map(|(i, entry)| {
+            let small_button = |label: &'static str, message: Option<Msg>| {
+                Button::new(Text::new(label).size(12).center())
+                    .width(30)
+                    .on_press_maybe(message)
+            };
+
+            row![]
+                .push_maybe(
+                    super::thumbnail(instance.cache(), &entry.video, &entry.title, 80.0, 45.0)
+                        .map(|thumbnail| iced::widget::Container::new(thumbnail).width(80).height(45))
+                )
+                .push(Text::new(format!("{}. {}", i + 1, entry.title)).width(Length::Fill))
+                .push(small_button("^", LocalPlaylistsMessage::Move(i, true).on_condition(i > 0)))
+                .push(small_button("v", LocalPlaylistsMessage::Move(i, false).on_condition(i < last)))
+                .push(small_button("X", Some(LocalPlaylistsMessage::Remove(i).into())))
+                .spacing(5)
+                .align_y(iced::Alignment::Center)
+                .into()
+        })
+    ).spacing(10);
+
+    let list: Element<Msg> = if playlist.videos.is_empty() {
+        Text::new("This playlist is empty. Add videos from search results or video pages.").into()
+    }
+    else {
+        Scrollable::new(rows)
+            .width(Length::Fill)
+            .height(instance.settings().window_size().1 / 2.0)
+            .into()
+    };
+
+    column![
+        Text::new(playlist.title.clone()).size(20),
+
+        list,
+
+        row![
+            Button::new(Text::new("Play").center())
+                .width(100)
+                .on_press_maybe(LocalPlaylistsMessage::Play(playlist.id.clone()).on_condition(!playlist.videos.is_empty())),
+
+            Button::new(Text::new("All Playlists").center())
+                .width(120)
+                .on_press(LocalPlaylistsMessage::Close.into())
+        ].spacing(10)
+    ].spacing(10).align_x(iced::Alignment::Center).into()
+}
@@ -2,17 +2,37 @@ use iced::Task;
 
 use crate::app::pages::local_video_page::LocalVideoPage;
 
+use log::info;
+
+use crate::app::instance::archive::{Favorite, FavoriteKind, PlayerSession};
+use crate::app::instance::settings::Pin;
+
 use super::{Navigation, PomeloPage, PomeloInstance, Msg};
 
 // Main menu, the first page that's loaded when the program starts.
-// Redirects to the Settings, Search, and Video Player pages.
+// Offers to resume what was playing when Pomelo was last closed.
+// Redirects to the Now Playing, Settings, Restricted Mode, Search, Import, Feed, Watch History, Playlists, Library, Downloads, and Video Player pages.
+// Favorite channels and playlists are listed underneath, followed by pinned searches, channels, and playlists as tiles.
 pub (crate) struct MainMenu;
 
 #[derive(Debug, Clone)]
 pub (crate) enum MainMenuMessage {
     LocalVideo,
+    ImportList,
     Search,
-    Settings
+    WatchHistory,
+    Playlists,
+    Library,
+    Feed,
+    Downloads,
+    NowPlaying,
+    Settings,
+    RestrictedMode,
+    ResumeSession,
+    DismissSession,
+    OpenPin(usize),
+    Unpin(usize),
+    OpenFavorite(usize)
 }
 
 impl From<MainMenuMessage> for Msg {
@@ -23,38 +43,157 @@ impl From<MainMenuMessage> for Msg {
 
 impl PomeloPage for MainMenu {
     
-    fn update(&mut self, _instance: &mut PomeloInstance, message: Msg) -> (Task<Msg>, Navigation) {
+    fn update(&mut self, instance: &mut PomeloInstance, message: Msg) -> (Task<Msg>, Navigation) {
         use super::search_page::SearchPage;
         use super::settings_page::SettingsPage;
+        use super::video_player_page::VideoPlayerPage;
+        use super::import_page::ImportPage;
+        use super::watch_history_page::WatchHistoryPage;
+        use super::feed_page::{FeedMessage, FeedPage};
+        use super::downloads_page::DownloadsPage;
+        use super::restricted_mode_page::RestrictedModePage;
+        use super::local_playlists_page::LocalPlaylistsPage;
+        use super::library_page::LibraryPage;
+
+        match message {
+            // Load pin thumbnails on startup.
+            Msg::Init => {
+                let tasks: Vec<Task<Msg>> = instance.settings().pins().iter()
+                    .map(|pin| super::load_pin_thumbnail(pin, instance))
+                    .collect();
 
-        if let Msg::MainMenu(msg) = message {
-            match msg {
+                return (Task::batch(tasks), Navigation::None);
+            },
+
+            Msg::MainMenu(msg) => match msg {
                 MainMenuMessage::LocalVideo => return go_to_page(LocalVideoPage::new()),
-                MainMenuMessage::Search => return go_to_page(SearchPage::new()),
-                MainMenuMessage::Settings => return go_to_page(SettingsPage::new())
-            }
+                MainMenuMessage::ImportList => return go_to_page(ImportPage::new()),
+                MainMenuMessage::Search => return go_to_page(SearchPage::new(instance)),
+                MainMenuMessage::WatchHistory => return go_to_page(WatchHistoryPage::new(instance)),
+                MainMenuMessage::Playlists => return go_to_page(LocalPlaylistsPage::new()),
+                MainMenuMessage::Library => {
+                    let page = LibraryPage::new(instance);
+                    return (page.load_thumbnails(instance), Navigation::GoTo(Box::new(page)));
+                },
+                MainMenuMessage::Feed => return (
+                    Task::done(FeedMessage::Refresh.into()),
+                    Navigation::GoTo(Box::new(FeedPage::new()))
+                ),
+                MainMenuMessage::Downloads => return go_to_page(DownloadsPage::new()),
+                MainMenuMessage::NowPlaying => return go_to_page(VideoPlayerPage::empty()),
+                MainMenuMessage::Settings => if !instance.settings().is_restricted() {
+                    return go_to_page(SettingsPage::new());
+                },
+                MainMenuMessage::RestrictedMode => return go_to_page(RestrictedModePage::new()),
+
+                MainMenuMessage::ResumeSession => if let Some(session) = instance.take_last_session() {
+                    return resume_session(session);
+                },
+
+                MainMenuMessage::DismissSession => instance.dismiss_last_session(),
+
+                MainMenuMessage::OpenPin(index) => if let Some(pin) = instance.settings().pins().get(index) {
+                    return open_pin(pin.clone());
+                },
+
+                MainMenuMessage::Unpin(index) => instance.settings_mut().remove_pin(index),
+
+                MainMenuMessage::OpenFavorite(index) => if let Some(favorite) = instance.archive().favorites().get(index) {
+                    return open_pin(favorite_pin(favorite));
+                }
+            },
+
+            _ => ()
         }
         (Task::none(), Navigation::None)
     }
 
-    fn view(&self, _instance: &PomeloInstance) -> iced::Element<Msg> {
-        use iced::widget::{Button, Text};
+    fn view(&self, instance: &PomeloInstance) -> iced::Element<Msg> {
+        use iced::widget::{Button, Column, Text};
         use super::FillElement;
 
+        let now_playing_text = match instance.queue().len() {
+            0 => String::from("Now Playing"),
+            n => format!("Now Playing ({} queued)", n)
+        };
+
+        let downloads_text = match (instance.active_downloads(), instance.interrupted_downloads().len()) {
+            (0, 0) => String::from("Downloads"),
+            (0, n) => format!("Downloads ({} interrupted)", n),
+            (n, _) => format!("Downloads ({} active)", n)
+        };
+
+        // Settings and imports are locked in restricted mode, and the settings button is swapped out for an unlock button.
+        let restricted = instance.settings().is_restricted();
+
+        let (settings_text, settings_message) = match restricted {
+            true => ("Unlock", MainMenuMessage::RestrictedMode),
+            false => ("Settings", MainMenuMessage::Settings)
+        };
+
+        let padding = super::button_padding(instance);
+
         // Draw buttons
-        iced::widget::column![
+        let buttons = iced::widget::column![
+            Button::new(Text::new(now_playing_text).center())
+                .width(200)
+                .padding(padding)
+                .on_press(MainMenuMessage::NowPlaying.into()),
+
             Button::new(Text::new("Play from Computer").center())
                 .width(200)
+                .padding(padding)
                 .on_press(MainMenuMessage::LocalVideo.into()),
 
             Button::new(Text::new("Play from Youtube").center())
                 .width(200)
+                .padding(padding)
                 .on_press(MainMenuMessage::Search.into()),
 
-            Button::new(Text::new("Settings").center())
+            Button::new(Text::new("Import URL List").center())
                 .width(200)
-                .on_press(MainMenuMessage::Settings.into())
-        ].spacing(25).fill()
+                .padding(padding)
+                .on_press_maybe((!restricted).then_some(MainMenuMessage::ImportList.into())),
+
+            Button::new(Text::new("Feed").center())
+                .width(200)
+                .padding(padding)
+                .on_press(MainMenuMessage::Feed.into()),
+
+            Button::new(Text::new("Watch History").center())
+                .width(200)
+                .padding(padding)
+                .on_press(MainMenuMessage::WatchHistory.into()),
+
+            Button::new(Text::new("Playlists").center())
+                .width(200)
+                .padding(padding)
+                .on_press(MainMenuMessage::Playlists.into()),
+
+            Button::new(Text::new("Library").center())
+                .width(200)
+                .padding(padding)
+                .on_press(MainMenuMessage::Library.into()),
+
+            Button::new(Text::new(downloads_text).center())
+                .width(200)
+                .padding(padding)
+                .on_press(MainMenuMessage::Downloads.into()),
+
+            Button::new(Text::new(settings_text).center())
+                .width(200)
+                .padding(padding)
+                .on_press(settings_message.into())
+        ].spacing(25);
+
+        Column::new()
+            .spacing(50)
+            .align_x(iced::Alignment::Center)
+            .push_maybe(instance.last_session().and_then(session_prompt))
+            .push(buttons)
+            .push_maybe((!instance.archive().favorites().is_empty()).then(|| favorites_element(instance)))
+            .push_maybe((!instance.settings().pins().is_empty()).then(|| pin_tiles(instance)))
+            .fill()
     }
 
     fn subscription(&self, _instance: &PomeloInstance) -> iced::Subscription<Msg> {
@@ -64,4 +203,162 @@ impl PomeloPage for MainMenu {
 
 fn go_to_page(page: impl PomeloPage + 'static) -> (Task<Msg>, Navigation) {
     (Task::none(), Navigation::GoTo(Box::new(page)))
+}
+
+// Open the player with the last session's videos, starting where playback was stopped.
+fn resume_session(session: PlayerSession) -> (Task<Msg>, Navigation) {
+    use super::VideoOrder;
+    use super::video_player_page::{VideoPlayerMessage, VideoPlayerPage};
+
+    info!("Resuming the last session at video {} of {}", session.index + 1, session.videos.len());
+
+    let videos = session.videos.iter()
+        .map(|entry| (entry.video.clone(), entry.local))
+        .collect();
+
+    let titles = session.videos.into_iter()
+        .map(|entry| (entry.video, entry.title));
+
+    let player = VideoPlayerPage::new(videos, VideoOrder::Sequential(session.index))
+        .with_titles(titles)
+        .with_start_position(session.position);
+
+    (
+        Task::done(VideoPlayerMessage::LoadVideo(session.index).into()),
+        Navigation::GoTo(Box::new(player))
+    )
+}
+
+// Offer to pick up what was playing when Pomelo was last closed.
+fn session_prompt(session: &PlayerSession) -> Option<iced::Element<Msg>> {
+    use iced::widget::{row, Button, Text};
+
+    let video = session.current()?;
+    let position = crate::utils::secs_to_timestamp(session.position as u64, session.position >= 3600.0);
+
+    Some(
+        row![
+            Text::new(format!("You were watching {} at {} \u{2014} Resume?", video.title, position)),
+
+            Button::new(Text::new("Resume").center())
+                .width(100)
+                .on_press(MainMenuMessage::ResumeSession.into()),
+
+            Button::new(Text::new("Dismiss").center())
+                .width(100)
+                .on_press(MainMenuMessage::DismissSession.into())
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center)
+        .into()
+    )
+}
+
+// Go to the pinned search, channel, or playlist.
+fn open_pin(pin: Pin) -> (Task<Msg>, Navigation) {
+    use crate::yt_fetch::SearchType;
+    use super::search_results_page::{SearchResultsMessage, SearchResultsPage};
+    use super::playlist_info_page::{PlaylistInfoMessage, PlaylistInfoPage};
+
+    match pin {
+        Pin::Search { query, search_type } => (
+            Task::done(SearchResultsMessage::StartSearch.into()),
+            Navigation::GoTo(Box::new(SearchResultsPage::new(query, search_type)))
+        ),
+
+        Pin::Channel { id, .. } => (
+            Task::done(SearchResultsMessage::StartSearch.into()),
+            Navigation::GoTo(Box::new(SearchResultsPage::new(id, SearchType::ChannelUploads)))
+        ),
+
+        Pin::Playlist { id, .. } => (
+            Task::done(PlaylistInfoMessage::LoadPlaylist(id).into()),
+            Navigation::GoTo(Box::new(PlaylistInfoPage::new()))
+        )
+    }
+}
+
+// Favorites open the same pages as pins.
+fn favorite_pin(favorite: &Favorite) -> Pin {
+    match favorite.kind {
+        FavoriteKind::Channel => Pin::Channel {
+            id: favorite.id.clone(),
+            name: favorite.title.clone(),
+            thumbnail: favorite.thumbnail.clone()
+        },
+        FavoriteKind::Playlist => Pin::Playlist {
+            id: favorite.id.clone(),
+            title: favorite.title.clone(),
+            thumbnail: favorite.thumbnail.clone()
+        }
+    }
+}
+
+// Favorite channels and playlists as a row of buttons, wrapping onto new rows as needed.
+fn favorites_element(instance: &PomeloInstance) -> iced::Element<Msg> {
+    use iced::widget::{column, Button, Row, Text};
+
+    let buttons = instance.archive().favorites().iter()
+        .enumerate()
+        .map(|(i, favorite)| {
+            let kind = match favorite.kind {
+                FavoriteKind::Channel => "Channel",
+                FavoriteKind::Playlist => "Playlist"
+            };
+
+            Button::new(
+                column![
+                    Text::new(favorite.title.clone()).size(14),
+                    Text::new(kind).size(12)
+                ].align_x(iced::Alignment::Center)
+            )
+            .on_press(MainMenuMessage::OpenFavorite(i).into())
+            .into()
+        });
+
+    column![
+        Text::new("Favorites").size(18),
+        Row::with_children(buttons).spacing(10).wrap()
+    ].spacing(10).align_x(iced::Alignment::Center).into()
+}
+
+// Quick-launch tiles for pinned items, wrapping onto new rows as needed.
+fn pin_tiles(instance: &PomeloInstance) -> iced::Element<Msg> {
+    use iced::widget::{column, Button, Image, Row, Text};
+
+    let tiles = instance.settings().pins().iter()
+        .enumerate()
+        .map(|(i, pin)| {
+            let image: iced::Element<Msg> = match instance.cache().get_thumbnail(&pin.key()) {
+                Some(handle) => Image::new(handle).width(160).height(90).into(),
+                None => super::thumbnail_placeholder(pin.title(), 160.0, 90.0)
+            };
+
+            let kind = match pin {
+                Pin::Search { .. } => "Search",
+                Pin::Channel { .. } => "Channel",
+                Pin::Playlist { .. } => "Playlist"
+            };
+
+            column![
+                Button::new(
+                    column![
+                        image,
+                        Text::new(pin.title().to_string()).size(14),
+                        Text::new(kind).size(12)
+                    ].spacing(5).align_x(iced::Alignment::Center)
+                )
+                .width(180)
+                .on_press(MainMenuMessage::OpenPin(i).into()),
+
+                Button::new(Text::new("Unpin").size(12).center())
+                    .style(iced::widget::button::text)
+                    .on_press(MainMenuMessage::Unpin(i).into())
+            ].align_x(iced::Alignment::Center).into()
+        });
+
+    Row::with_children(tiles)
+        .spacing(15)
+        .wrap()
+        .into()
 }
\ No newline at end of file
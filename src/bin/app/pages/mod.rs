@@ -6,16 +6,29 @@ mod search_page;
 mod search_results_page;
 mod video_info_page;
 mod playlist_info_page;
-
-use std::io::BufReader;
-use std::process::{ChildStderr, ChildStdout};
+mod status_bar;
+mod diagnostics_page;
+mod history_page;
+mod settings_recovery_page;
+mod import_page;
+mod watch_history_page;
+mod watch_stats_page;
+mod feed_page;
+mod downloads_page;
+mod download_log_page;
+mod restricted_mode_page;
+mod local_playlists_page;
+mod library_page;
 
 use iced::{Element, Length, Subscription, Task};
 
-use crate::app::{DownloadFormat, DownloadQuality, PomeloError};
-use crate::yt_fetch::{SearchResult, SearchResults};
+use crate::app::{DownloadFormat, DownloadQuality, DownloadSubtitles, PomeloError};
+use crate::yt_fetch::{ImageHost, SearchResult, SearchResults};
 
+use super::instance::downloads::{DownloadId, DownloadStatus};
 use super::instance::cache::PomeloCache;
+use super::instance::settings::Pin;
+use super::instance::theme::SliderStyle;
 use super::instance::PomeloInstance;
 
 pub (crate) use self::{
@@ -23,14 +36,35 @@ pub (crate) use self::{
     local_video_page::LocalVideoMessage,
     search_page::SearchMessage,
     search_results_page::SearchResultsMessage,
-    video_info_page::VideoInfoMessage,
+    video_info_page::{VideoInfoMessage, VideoInfoPage},
     playlist_info_page::PlaylistInfoMessage,
     video_player_page::VideoPlayerMessage,
-    settings_page::SettingsMessage
+    settings_page::{SettingsMessage, SettingsPage},
+    diagnostics_page::DiagnosticsMessage,
+    history_page::HistoryMessage,
+    settings_recovery_page::{SettingsRecoveryMessage, SettingsRecoveryPage},
+    import_page::ImportMessage,
+    watch_history_page::WatchHistoryMessage,
+    feed_page::FeedMessage,
+    downloads_page::{DownloadsMessage, DownloadsPage},
+    restricted_mode_page::{RestrictedModeMessage, RestrictedModePage},
+    local_playlists_page::LocalPlaylistsMessage,
+    library_page::LibraryMessage,
+    status_bar::{StatusBarMessage, status_bar}
 };
 
 type Msg = crate::app::PomeloMessage;
 
+// Thumbnail widths to download, in pixels. The video info page shows a bigger thumbnail than list rows do.
+const ROW_THUMBNAIL_WIDTH: u32 = 320;
+const LARGE_THUMBNAIL_WIDTH: u32 = 640;
+
+// With the data saver on, only the first few items of a list get thumbnails, roughly the ones that fit on screen.
+const DATA_SAVER_THUMBNAILS: usize = 6;
+
+// Padding around button labels in high-contrast mode, to make buttons easier to hit.
+const HIGH_CONTRAST_PADDING: f32 = 12.0;
+
 // Companion to Messages, used to redirect to different pages.
 pub (crate) enum Navigation {
     GoTo(Box<dyn PomeloPage>),
@@ -52,6 +86,19 @@ pub (crate) trait PomeloPage {
     fn update(&mut self, instance: &mut PomeloInstance, message: Msg) -> (Task<Msg>, Navigation);
     fn view(&self, instance: &PomeloInstance) -> Element<Msg>;
     fn subscription(&self, instance: &PomeloInstance) -> Subscription<Msg>;
+
+    // Used by the status bar to find the page that's playing a video.
+    fn is_playing(&self) -> bool {
+        false
+    }
+
+    // Smaller view for the mini player window, for pages that play videos.
+    fn mini_view(&self, _instance: &PomeloInstance) -> Option<Element<Msg>> {
+        None
+    }
+
+    // Called on every page in the stack when the window is closing, for saving anything that isn't saved yet.
+    fn on_close(&mut self, _instance: &mut PomeloInstance) {}
 }
 
 // Convenience trait for expanding UI elements to fit the whole screen.
@@ -95,29 +142,225 @@ trait ConditionalMessage {
 
 impl ConditionalMessage for Msg {}
 
-// Collection of information and readers for a video/playlist download.
-// Might want to move up to app module later, and make this a part of PomeloInstance
-struct DownloadInfo {
-    path: String,
-    stdout: BufReader<ChildStdout>,
-    stderr: BufReader<ChildStderr>,
-    progress: usize,
-    length: usize
-}
-
-impl DownloadInfo {
-    fn new(path: String, stdout: BufReader<ChildStdout>, stderr: BufReader<ChildStderr>) -> Self {
-        Self {
-            path,
-            stdout,
-            stderr,
-            progress: 0,
-            length: 0
-        }
+fn button_padding(instance: &PomeloInstance) -> iced::Padding {
+    if instance.settings().high_contrast() {
+        iced::Padding::new(HIGH_CONTRAST_PADDING)
+    }
+    else {
+        iced::widget::button::DEFAULT_PADDING
+    }
+}
+
+// A local playlist, as shown in the "Add to Playlist" picker.
+#[derive(Debug, Clone, PartialEq)]
+struct PlaylistChoice {
+    id: String,
+    title: String
+}
+
+impl std::fmt::Display for PlaylistChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.title)
+    }
+}
+
+// Picker for adding a Youtube video to one of the local playlists. Hidden if there aren't any playlists.
+fn add_to_playlist_picker<'a>(instance: &PomeloInstance, video: &str, title: &str) -> Option<Element<'a, Msg>> {
+    use iced::widget::PickList;
+
+    let playlists = instance.archive().playlists();
+
+    if playlists.is_empty() {
+        return None;
+    }
+
+    let choices: Vec<PlaylistChoice> = playlists.iter()
+        .map(|playlist| PlaylistChoice { id: playlist.id.clone(), title: playlist.title.clone() })
+        .collect();
+
+    let video = String::from(video);
+    let title = String::from(title);
+
+    Some(
+        PickList::new(choices, None::<PlaylistChoice>, move |choice| Msg::AddToPlaylist(choice.id, video.clone(), title.clone()))
+            .placeholder("Add to Playlist")
+            .width(150)
+            .into()
+    )
+}
+
+// Slider look from the theme, or the bigger high-contrast slider.
+fn slider_style(instance: &PomeloInstance) -> SliderStyle {
+    if instance.settings().high_contrast() {
+        SliderStyle::high_contrast()
+    }
+    else {
+        instance.theme()
+            .map(|theme| theme.slider())
+            .unwrap_or_default()
+    }
+}
+
+// Progress bar, status, and cancel button for a download the page started.
+// Finished downloads just show whether they worked.
+fn download_progress_element<'a>(instance: &PomeloInstance, id: DownloadId) -> Option<Element<'a, Msg>> {
+    use iced::widget::{column, text, Button, ProgressBar, Text};
+
+    let download = instance.downloads().get(id)?;
+
+    let element: Element<'a, Msg> = match download.status() {
+        DownloadStatus::Queued => column![
+            Text::new(format!("Queued: {}", download.title())),
+            Button::new(Text::new("Cancel").center())
+                .width(100)
+                .on_press(Msg::CancelDownload(id))
+        ].spacing(10).align_x(iced::Alignment::Center).into(),
+
+        DownloadStatus::Paused => column![
+            Text::new(format!("Paused: {}", download.title())),
+            Button::new(Text::new("Cancel").center())
+                .width(100)
+                .on_press(Msg::CancelDownload(id))
+        ].spacing(10).align_x(iced::Alignment::Center).into(),
+
+        DownloadStatus::Running => column![
+            Text::new(format!("Downloading: {}", download.title())),
+            ProgressBar::new(0.0..=1.0, download.fraction())
+                .width(instance.settings().window_size().0 / 2.0),
+            Button::new(Text::new("Cancel").center())
+                .width(100)
+                .on_press(Msg::CancelDownload(id))
+        ].spacing(10).align_x(iced::Alignment::Center).into(),
+
+        DownloadStatus::Finished => Text::new(format!("Downloaded to {}", download.path()))
+            .style(text::success)
+            .into(),
+
+        DownloadStatus::Failed(e) => Text::new(format!("Download failed: {}", e))
+            .style(text::danger)
+            .into()
+    };
+
+    Some(element)
+}
+
+// Simple bar chart, with the bars scaled to the largest value.
+fn bar_chart<'a>(values: &[u64], height: f32, bar_width: f32) -> Element<'a, Msg> {
+    use iced::widget::{container, Container, Row, Space};
+
+    let max = values.iter().copied().max().unwrap_or_default().max(1);
+
+    Row::with_children(
+        values.iter().map(|value| {
+            let bar_height = (*value as f32 / max as f32 * height).max(1.0);
+
+            Container::new(Space::new(bar_width, bar_height))
+                .style(|theme: &iced::Theme| container::Style {
+                    background: Some(theme.palette().primary.into()),
+                    ..Default::default()
+                })
+                .into()
+        })
+    )
+    .spacing(2)
+    .height(height)
+    .align_y(iced::Alignment::End)
+    .into()
+}
+
+// Dismissible error message, with an optional action for retrying the operation that failed.
+#[derive(Debug, Clone)]
+struct ErrorBanner {
+    error: PomeloError,
+    retry: Option<Msg>
+}
+
+impl ErrorBanner {
+    fn new(error: PomeloError, retry: Option<Msg>) -> Self {
+        Self { error, retry }
+    }
+
+    fn view(&self) -> Element<Msg> {
+        use iced::widget::{container, horizontal_space, row, Button, Container, Text};
+
+        let mut row = row![
+            Text::new(&self.error.error),
+            horizontal_space()
+        ].spacing(10).align_y(iced::Alignment::Center);
+
+        row = row.push_maybe(
+            self.retry.clone().map(|msg|
+                Button::new(Text::new("Retry").center())
+                    .width(100)
+                    .on_press(msg)
+            )
+        );
+
+        row = row.push(
+            Button::new(Text::new("Dismiss").center())
+                .width(100)
+                .on_press(Msg::DismissError)
+        );
+
+        Container::new(row)
+            .width(Length::Fill)
+            .padding(10)
+            .style(|theme: &iced::Theme| container::Style {
+                background: Some(theme.extended_palette().danger.weak.color.into()),
+                text_color: Some(theme.extended_palette().danger.weak.text),
+                border: iced::Border {
+                    radius: iced::border::Radius::new(5),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .into()
     }
 }
 
-fn download_element<'a>(format: &'a DownloadFormat, quality: &'a DownloadQuality) -> iced::Element<'a, Msg> {
+// Placeholder rows drawn while a page waits on Invidious, with a button for cancelling the request.
+fn loading_skeleton<'a>(rows: usize) -> Element<'a, Msg> {
+    use iced::Alignment;
+    use iced::widget::{column, container, row, Button, Column, Container, Space, Text};
+
+    let placeholder = |width: f32, height: f32| -> Element<'a, Msg> {
+        Container::new(Space::new(width, height))
+            .style(|theme: &iced::Theme| container::Style {
+                background: Some(theme.extended_palette().background.strong.color.into()),
+                border: iced::Border {
+                    radius: iced::border::Radius::new(5),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .into()
+    };
+
+    let mut list = Column::new().spacing(10);
+
+    for _ in 0..rows {
+        list = list.push(
+            row![
+                placeholder(160.0, 90.0),
+                column![
+                    placeholder(300.0, 16.0),
+                    placeholder(200.0, 16.0),
+                    placeholder(100.0, 16.0)
+                ].spacing(10)
+            ].spacing(10)
+        );
+    }
+
+    column![
+        Text::new("Loading..."),
+        list,
+        Button::new(Text::new("Cancel").center())
+            .width(100)
+            .on_press(Msg::CancelLoad)
+    ].spacing(25).align_x(Alignment::Center).fill()
+}
+
+fn download_element<'a>(format: &'a DownloadFormat, quality: &'a DownloadQuality, subtitles: &'a DownloadSubtitles) -> iced::Element<'a, Msg> {
     use iced::widget::{column, Row, Button, Text};
 
     let mut row = Row::new().spacing(10);
@@ -140,16 +383,56 @@ fn download_element<'a>(format: &'a DownloadFormat, quality: &'a DownloadQuality
         ).on_condition(!format.is_audio())
     );
 
+    row = row.push_maybe(
+        labeled_picklist(
+            "Subtitles",
+            DownloadSubtitles::ALL,
+            subtitles.clone(),
+            Msg::SetDownloadSubtitles
+        ).on_condition(!format.is_audio())
+    );
+
     column![
-        Button::new(Text::new("Download").center())
-            .width(100)
-            .on_press(Msg::StartVideoDownload),
+        iced::widget::row![
+            Button::new(Text::new("Download").center())
+                .width(100)
+                .on_press(Msg::StartVideoDownload),
+
+            Button::new(Text::new("Copy as yt-dlp command").center())
+                .width(200)
+                .on_press(Msg::CopyDownloadCommand)
+        ].spacing(10),
 
         row
 
     ].align_x(iced::Alignment::Center).into()
 }
 
+// yt-dlp options for downloading in the selected format and quality.
+// Subtitles are saved as separate files next to the video, so the player can pick them up.
+fn format_args(format: &DownloadFormat, quality: &DownloadQuality, subtitles: &DownloadSubtitles) -> Vec<String> {
+    let ext = format.as_ext();
+
+    if format.is_audio() {
+        return ["-x", "--audio-format", ext].map(String::from).to_vec();
+    }
+
+    let mut args = vec![
+        String::from("-S"),
+        format!("res:{}", quality.num()),
+        String::from("-f"),
+        format!("b[height={}]/bv[height={}]+ba", ext, quality.num()),
+        String::from("--remux-video"),
+        String::from(ext)
+    ];
+
+    if let Some(languages) = subtitles.languages() {
+        args.extend(["--write-subs", "--sub-langs", languages].map(String::from));
+    }
+
+    args
+}
+
 fn labeled_picklist<'a, L, T, V>(text: &'a str, list: L, select: V, on_select: impl Fn(T) -> Msg + 'a) -> iced::Element<Msg> 
     where 
         L: std::borrow::Borrow<[T]> + 'a,
@@ -165,13 +448,161 @@ fn labeled_picklist<'a, L, T, V>(text: &'a str, list: L, select: V, on_select: i
     ].spacing(5).align_x(Alignment::Center).into()
 }
 
+// Thumbnail for an item, or a placeholder with the first letter of its name if the thumbnail couldn't be downloaded.
+// Nothing is shown while the thumbnail is still loading.
+// The placeholder is sized like a medium thumbnail (320x180).
+pub (crate) fn thumbnail<'a>(cache: &PomeloCache, id: &str, name: &str, width: f32, height: f32) -> Option<Element<'a, Msg>> {
+    use iced::widget::Image;
+
+    if let Some(handle) = cache.get_thumbnail(id) {
+        return Some(Image::new(handle).into());
+    }
+
+    if !cache.thumbnail_failed(id) {
+        return None;
+    }
+
+    Some(thumbnail_placeholder(name, width, height))
+}
+
+// Box with the first letter of the name, used in place of a thumbnail.
+pub (crate) fn thumbnail_placeholder<'a>(name: &str, width: f32, height: f32) -> Element<'a, Msg> {
+    use iced::widget::{container, Container, Text};
+
+    let initial = name.chars()
+        .find(|c| c.is_alphanumeric())
+        .map(|c| c.to_uppercase().to_string())
+        .unwrap_or_else(|| String::from("?"));
+
+    Container::new(Text::new(initial).size(height / 2.0))
+        .center_x(width)
+        .center_y(height)
+        .style(|theme: &iced::Theme| container::Style {
+            background: Some(theme.extended_palette().background.strong.color.into()),
+            text_color: Some(theme.extended_palette().background.strong.text),
+            ..Default::default()
+        })
+        .into()
+}
+
+// Badge for videos that have already been downloaded.
+fn downloaded_badge<'a>(instance: &PomeloInstance, id: &str) -> Option<Element<'a, Msg>> {
+    use iced::widget::{text, Text};
+
+    instance.archive().local_file(id)
+        .map(|_| Text::new("Downloaded ✓").size(12).style(text::success).into())
+}
+
+// Button for playing the downloaded copy of a video instead of streaming it.
+fn play_local_button<'a>(instance: &PomeloInstance, id: &str) -> Option<Element<'a, Msg>> {
+    use iced::widget::{Button, Text};
+
+    instance.archive().local_file(id).map(|_| {
+        Button::new(Text::new("Play local copy").size(12).center())
+            .width(90)
+            .on_press(Msg::PlayLocalCopy(String::from(id)))
+            .into()
+    })
+}
+
+// Button for marking a video as watched or unwatched without playing it.
+fn watched_button<'a>(instance: &PomeloInstance, id: &str) -> Element<'a, Msg> {
+    use iced::widget::{Button, Text};
+
+    let watched = instance.archive().is_watched(id);
+
+    Button::new(Text::new(if watched { "Mark unwatched" } else { "Mark watched" }).size(12).center())
+        .width(110)
+        .on_press(Msg::SetWatched(String::from(id), !watched))
+        .into()
+}
+
+// Player for the downloaded copy of a video, if it's in the archive.
+pub (crate) fn local_copy_player(instance: &PomeloInstance, id: &str) -> Option<(Task<Msg>, Box<dyn PomeloPage>)> {
+    use std::collections::VecDeque;
+    use video_player_page::{VideoPlayerMessage, VideoPlayerPage};
+
+    let path = instance.archive().local_file(id)?;
+    let url = url::Url::from_file_path(path).ok()?;
+
+    log::info!("Playing local copy of {}: {}", id, path);
+
+    Some((
+        Task::done(VideoPlayerMessage::LoadVideo(0).into()),
+        Box::new(VideoPlayerPage::new(VecDeque::from([(url.to_string(), true)]), VideoOrder::Sequential(0)))
+    ))
+}
+
+// The user's selected Invidious instance, followed by the next ones in the list.
+fn preferred_instances(instance: &PomeloInstance, count: usize) -> Vec<String> {
+    use crate::yt_fetch::instances;
+
+    let total = instances::count();
+
+    (0..count.min(total))
+        .map(|i| instances::url((instance.settings().invidious_index() + i) % total))
+        .collect()
+}
+
+// Where images should be loaded from, going by the user's settings.
+fn image_host(instance: &PomeloInstance) -> ImageHost {
+    use crate::yt_fetch::instances;
+
+    ImageHost {
+        instance: instances::url(instance.settings().invidious_index()),
+        proxy: instance.settings().proxy_images()
+    }
+}
+
+// Download a pinned item's thumbnail, if it has one that isn't cached yet.
+pub (crate) fn load_pin_thumbnail(pin: &Pin, instance: &PomeloInstance) -> Task<Msg> {
+    load_image_thumbnail(pin.key(), pin.thumbnail(), instance)
+}
+
+// Download a thumbnail from its url and cache it under the key, if it isn't cached yet.
+fn load_image_thumbnail(key: String, url: Option<&str>, instance: &PomeloInstance) -> Task<Msg> {
+    use crate::yt_fetch::download_image;
+
+    match url {
+        Some(url) if needs_thumbnail(&key, instance) => {
+            let url = String::from(url);
+            let host = image_host(instance);
+
+            Task::perform(
+                async move { download_image(&url, &host).await },
+                move |result| match result {
+                    Ok(handle) => Msg::ThumbnailLoaded(Ok((key, handle))),
+                    Err(e) => Msg::ThumbnailFailed(key, PomeloError::new(e))
+                }
+            )
+        },
+        _ => Task::none()
+    }
+}
+
 // Load thumbnails asyncronously
-fn batch_thumbnail_commands(search: &SearchResults, cache: &PomeloCache) -> Task<Msg> {
+fn batch_thumbnail_commands(search: &SearchResults, instance: &PomeloInstance) -> Task<Msg> {
+    item_thumbnail_commands(search.get_results(), instance)
+}
+
+// Thumbnails are downloaded if they aren't cached, or if the cached one is old.
+// Old thumbnails stay on screen until the new ones arrive.
+fn needs_thumbnail(id: &str, instance: &PomeloInstance) -> bool {
+    !instance.cache().has_thumbnail(id)
+        || instance.cache().thumbnail_is_stale(id, instance.settings().thumbnail_max_age())
+}
+
+// Load thumbnails for any list of items, skipping the ones that are already cached.
+fn item_thumbnail_commands(items: Vec<SearchResult>, instance: &PomeloInstance) -> Task<Msg> {
     use crate::yt_fetch::download_thumbnail;
 
+    let host = image_host(instance);
+
+    let limit = if instance.settings().data_saver() { DATA_SAVER_THUMBNAILS } else { usize::MAX };
+
     let mut commands: Vec<Task<Msg>> = Vec::new();
     
-    for item in search.get_results().into_iter() {
+    for item in items.into_iter().take(limit) {
         let id = match &item {
             SearchResult::Video(video) => video.id.clone(),
             SearchResult::Channel(channel) => channel.id.clone(),
@@ -179,18 +610,17 @@ fn batch_thumbnail_commands(search: &SearchResults, cache: &PomeloCache) -> Task
             SearchResult::PlaylistVideo(video) => video.id.clone()
         };
 
-        if !cache.has_thumbnail(&id) {
+        if needs_thumbnail(&id, instance) {
+            let host = host.clone();
+
             commands.push(Task::perform(
                 async move {
-                    (id, download_thumbnail(&item, 4).await)
+                    (id, download_thumbnail(&item, ROW_THUMBNAIL_WIDTH, &host).await)
                 },
                 
-                |(id, result)| {
-                    let out = match result {
-                        Ok(handle) => Ok((id, handle)),
-                        Err(e) => Err(PomeloError::new(e))
-                    };
-                    Msg::ThumbnailLoaded(out)
+                |(id, result)| match result {
+                    Ok(handle) => Msg::ThumbnailLoaded(Ok((id, handle))),
+                    Err(e) => Msg::ThumbnailFailed(id, PomeloError::new(e))
                 }
             ));
         }
@@ -1,4 +1,3 @@
-use std::io::BufRead;
 use std::path::Path;
 
 use iced::{Task, Length};
@@ -8,20 +7,30 @@ use invidious::universal::Playlist;
 
 use log::{info, error};
 
-use crate::app::instance::cache::PomeloCache;
-use crate::app::{DownloadFormat, DownloadQuality, PomeloError};
-use crate::INVID_INSTANCES;
+use crate::app::instance::archive::{Favorite, FavoriteKind, PlaylistDiff, SnapshotVideo};
+use crate::app::instance::absolute_path;
+use crate::app::instance::downloads::DownloadId;
+use crate::app::instance::settings::Pin;
+use crate::app::{DownloadFormat, DownloadQuality, DownloadSubtitles, PomeloError};
+use crate::yt_fetch::instances;
+use crate::yt_fetch::Unavailable;
 
-use super::{PomeloInstance, DownloadInfo, Msg, Navigation};
+use super::{PomeloInstance, ErrorBanner, Msg, Navigation};
 use super::VideoOrder;
 
 #[derive(Debug, Clone)]
 pub (crate) enum PlaylistInfoMessage {
     LoadPlaylist(String),
-    LoadComplete(Box<Result<Playlist, PomeloError>>),
-    ToVideo(VideoOrder)
+    LoadPage(usize),
+    LoadComplete(usize, Box<Result<Playlist, PomeloError>>),
+    ToVideo(VideoOrder),
+    DownloadVideo(usize),
+    TogglePin,
+    ToggleFavorite
 }
 
+impl super::ConditionalMessage for PlaylistInfoMessage {}
+
 impl From<PlaylistInfoMessage> for Msg {
     fn from(value: PlaylistInfoMessage) -> Self {
         Msg::PlaylistInfo(value)
@@ -35,34 +44,72 @@ pub (crate) struct PlaylistInfoPage {
     videos: Vec<String>,
     selected_format: DownloadFormat,
     selected_quality: DownloadQuality,
-    downloading: bool,
-    download_info: Option<DownloadInfo>,
-    download_index: usize,
-    error: Option<PomeloError>
+    selected_subtitles: DownloadSubtitles,
+    playlist_id: String,
+
+    // Downloads started from this page, for the whole playlist or single videos.
+    downloads: Vec<DownloadId>,
+    error: Option<ErrorBanner>,
+    load_handle: Option<iced::task::Handle>,
+
+    // Page being fetched. Large playlists come in several pages, and are shown as each one arrives.
+    loading_page: Option<usize>,
+
+    // Changes since the playlist was last opened, if it was opened before.
+    diff: Option<PlaylistDiff>,
+
+    // Entries that can't be played, in playlist order. These are left out of the player's list.
+    unavailable: Vec<Option<Unavailable>>
 }
 
 impl super::PomeloPage for PlaylistInfoPage {
     fn update(&mut self, instance: &mut PomeloInstance, message: Msg) -> (Task<Msg>, Navigation) {
 
         match message {
-            Msg::Back => return (Task::none(), Navigation::Back),
-            Msg::Home => return (Task::none(), Navigation::Home),
+            Msg::Back => {
+                self.stop_loading();
+                return (Task::none(), Navigation::Back);
+            },
+
+            Msg::Home => {
+                self.stop_loading();
+                return (Task::none(), Navigation::Home);
+            },
+
+            Msg::DismissError => self.error = None,
+            Msg::CancelLoad => return self.cancel_load(),
             Msg::SetDownloadFormat(format) => self.selected_format = format,
             Msg::SetDownloadQuality(quality) => self.selected_quality = quality,
-            Msg::StartVideoDownload => return self.start_download(instance),
-            Msg::NextVideoChunk(line, result) => return self.on_next_chunk(line, result),
-            Msg::VideoDownloadCancelled => return on_download_cancelled(instance),
-            Msg::VideoDownloadComplete(result) => self.on_download_complete(result),
+            Msg::SetDownloadSubtitles(subtitles) => self.selected_subtitles = subtitles,
+            Msg::StartVideoDownload => return self.start_download(instance, None),
+            Msg::CopyDownloadCommand => return self.copy_download_command(instance),
 
             Msg::PlaylistInfo(msg) => match msg {
-                PlaylistInfoMessage::LoadPlaylist(id) 
+                PlaylistInfoMessage::LoadPlaylist(id)
                     => return self.load_playlist(id, instance.settings().invidious_index()),
 
-                PlaylistInfoMessage::LoadComplete(result)
-                    => return self.on_load_complete(*result, instance.cache()),
+                PlaylistInfoMessage::LoadPage(page)
+                    => return (self.load_page(page, instance.settings().invidious_index()), Navigation::None),
+
+                PlaylistInfoMessage::LoadComplete(page, result)
+                    => return self.on_load_complete(page, *result, instance),
 
                 PlaylistInfoMessage::ToVideo(order)
                     => return self.go_to_video(order),
+
+                PlaylistInfoMessage::DownloadVideo(index)
+                    => return self.start_download(instance, Some(index)),
+
+                PlaylistInfoMessage::TogglePin => if let Some(pin) = self.pin() {
+                    let task = super::load_pin_thumbnail(&pin, instance);
+
+                    instance.settings_mut().toggle_pin(pin);
+                    return (task, Navigation::None);
+                },
+
+                PlaylistInfoMessage::ToggleFavorite => if let Some(favorite) = self.favorite() {
+                    instance.archive_mut().toggle_favorite(favorite);
+                }
             }
 
             _ => ()
@@ -72,7 +119,7 @@ impl super::PomeloPage for PlaylistInfoPage {
     }
 
     fn view(&self, instance: &PomeloInstance) -> iced::Element<Msg> {
-        use iced::widget::{row, ProgressBar, Button, Scrollable};
+        use iced::widget::{row, Button, Scrollable};
         use super::{download_element, ConditionalMessage, FillElement};
         
         let mut column = Column::new().spacing(10).align_x(iced::Alignment::Center);
@@ -81,78 +128,87 @@ impl super::PomeloPage for PlaylistInfoPage {
             Some(playlist) => {
 
                 column = column.push(self.create_playlist_element(playlist, instance));
-                    
-                if let Some(e) = &self.error {
-                    column = column.push(Text::new(&e.error));
+
+                column = column.push_maybe(self.error.as_ref().map(|banner| banner.view()));
+
+                // Draw progress for downloads started from this page.
+                // Whole-playlist downloads also list how far along each video is.
+                for id in self.downloads.iter() {
+                    column = column
+                        .push_maybe(super::download_progress_element(instance, *id))
+                        .push_maybe(self.get_checklist_element(playlist, *id, instance));
                 }
 
-                // Draw download progress bars and cancel button
-                if self.downloading {
+                // Draw playback and download buttons.
+                let pin_text = match self.pin() {
+                    Some(pin) if instance.settings().is_pinned(&pin) => "Unpin",
+                    _ => "Pin to menu"
+                };
+
+                let favorite_text = if instance.archive().is_favorite(&self.playlist_id) {
+                    "Unfavorite"
+                } else {
+                    "Favorite"
+                };
+
+                column = column.push(
+                    column![
+                        row![
+                            Button::new(Text::new("Shuffle").center())
+                                .width(100)
+                                .on_press(
+                                    PlaylistInfoMessage::ToVideo(VideoOrder::Shuffled).into()
+                                ),
 
-                    let info = self.download_info.as_ref().unwrap();
+                            Button::new(Text::new("Reverse").center())
+                                .width(100)
+                                .on_press(
+                                    PlaylistInfoMessage::ToVideo(VideoOrder::Reversed).into()
+                                )
+                        ].spacing(10),
 
-                    column = column.extend(
-                        vec![     
-                            ProgressBar::new(
-                                0.0..=playlist.video_count as f32,
-                                self.download_index as f32
-                            ).width(instance.settings().window_size().0 / 2.0).into(),
+                        download_element(&self.selected_format, &self.selected_quality, &self.selected_subtitles),
 
-                            ProgressBar::new(
-                                0.0..=info.length as f32,
-                                info.progress as f32
-                            ).width(instance.settings().window_size().0 / 2.0).into(),
+                        column![
+                            Button::new(Text::new(pin_text).center())
+                                .width(100)
+                                .on_press(PlaylistInfoMessage::TogglePin.into()),
 
-                            Button::new(Text::new("Cancel").center())
+                            Button::new(Text::new(favorite_text).center())
                                 .width(100)
-                                .on_press(Msg::VideoDownloadCancelled)
-                                .into()
-                        ]
-                    );
-                }
+                                .on_press(PlaylistInfoMessage::ToggleFavorite.into()),
 
-                // Draw playback and download buttons.
-                else {      
-                    column = column.push(
-                        column![
-                            row![
-                                Button::new(Text::new("Shuffle").center())
-                                    .width(100)
-                                    .on_press(
-                                        PlaylistInfoMessage::ToVideo(VideoOrder::Shuffled).into()
-                                    ),
-
-                                Button::new(Text::new("Reverse").center())
-                                    .width(100)
-                                    .on_press(
-                                        PlaylistInfoMessage::ToVideo(VideoOrder::Reversed).into()
-                                    )
-                            ].spacing(10),
-
-                            download_element(&self.selected_format, &self.selected_quality),
-
-                            column![
-                                Button::new(Text::new("Back").center())
-                                    .width(100)
-                                    .on_press_maybe(
-                                        Msg::Back.on_condition(
-                                            !self.downloading && (self.playlist.is_some() || self.error.is_some())
-                                        )
-                                    ),
-
-                                Button::new(Text::new("Home").center())
-                                    .width(100)
-                                    .on_press_maybe(
-                                        Msg::Home.on_condition(
-                                            !self.downloading && (self.playlist.is_some() || self.error.is_some())
-                                        )
-                                    )
-                            ].spacing(25)
-                        ].spacing(50).align_x(iced::Alignment::Center)
-                    );
-                }
+                            Button::new(Text::new("Back").center())
+                                .width(100)
+                                .on_press_maybe(
+                                    Msg::Back.on_condition(self.playlist.is_some() || self.error.is_some())
+                                ),
+
+                            Button::new(Text::new("Home").center())
+                                .width(100)
+                                .on_press_maybe(
+                                    Msg::Home.on_condition(self.playlist.is_some() || self.error.is_some())
+                                )
+                        ].spacing(25)
+                    ].spacing(50).align_x(iced::Alignment::Center)
+                );
             },
-            None => column = column.push("Loading...")
+            // Playlist info failed to load.
+            None if self.error.is_some() => column = column.extend([
+                self.error.as_ref().unwrap().view(),
+
+                Button::new(Text::new("Back").center())
+                    .width(100)
+                    .on_press(Msg::Back)
+                    .into(),
+
+                Button::new(Text::new("Home").center())
+                    .width(100)
+                    .on_press(Msg::Home)
+                    .into()
+            ]).spacing(25),
+
+            None => return super::loading_skeleton(5)
         }
 
         Scrollable::new(column.width(iced::Length::Fill)).fill()
@@ -168,44 +224,116 @@ impl PlaylistInfoPage {
         Default::default()
     }
 
-    // Get info for the playlist with the given id from Indivious
-    fn load_playlist(&self, id: String, instance_index: usize) -> (Task<Msg>, Navigation) {
-        use crate::yt_fetch::VideoFetcher;
-
+    // Get info for the playlist with the given id from Indivious, starting with its first page of videos.
+    fn load_playlist(&mut self, id: String, instance_index: usize) -> (Task<Msg>, Navigation) {
         info!("Loading playlist info from id: {}", id);
 
-        let downloader = VideoFetcher::new(String::from(INVID_INSTANCES[instance_index].0));
-        (
-            Task::perform(
-                async move {
-                    downloader.get_playlist_videos(&id).await.map_err(PomeloError::new)
-                },
-                |result| PlaylistInfoMessage::LoadComplete(Box::new(result)).into()
-            ),
-            Navigation::None
-        )
+        self.playlist_id = id;
+        self.playlist = None;
+        self.diff = None;
+
+        (self.load_page(1, instance_index), Navigation::None)
     }
 
-    // Handles the result from loading playlist info. Starts loading thumbnails if it was successful.
-    fn on_load_complete(&mut self, result: Result<Playlist, PomeloError>, cache: &PomeloCache) -> (Task<Msg>, Navigation) {
-        use crate::yt_fetch::SearchResults;
-
-        let command = match result {
-            Ok(playlist) => {
-                self.playlist = Some(playlist.clone());
-                self.videos = playlist.videos.iter()
-                    .map(|v| v.id.clone())
-                    .collect();
-                super::batch_thumbnail_commands(&SearchResults::PlaylistVideos(playlist.clone()), cache)
+    // Fetch one page of the playlist's videos.
+    fn load_page(&mut self, page: usize, instance_index: usize) -> Task<Msg> {
+        use crate::yt_fetch::{Fetcher, VideoFetcher};
+
+        self.error = None;
+        self.loading_page = Some(page);
+
+        let id = self.playlist_id.clone();
+        let downloader = Fetcher::new(instances::url(instance_index));
+
+        let (task, handle) = Task::perform(
+            async move {
+                downloader.get_playlist_videos(&id, page).await.map_err(PomeloError::new)
             },
+            move |result| PlaylistInfoMessage::LoadComplete(page, Box::new(result)).into()
+        ).abortable();
+
+        self.load_handle = Some(handle);
+
+        task
+    }
+
+    fn stop_loading(&mut self) {
+        if let Some(handle) = self.load_handle.take() {
+            handle.abort();
+        }
+
+        self.loading_page = None;
+    }
+
+    // Stop loading the playlist and go back to the previous page.
+    fn cancel_load(&mut self) -> (Task<Msg>, Navigation) {
+        self.stop_loading();
+        (Task::none(), Navigation::Back)
+    }
+
+    // Handles a page of playlist videos. Its thumbnails start loading, and the next page is requested if there's more.
+    // Once the whole playlist is in, it's compared with the snapshot from the last visit, then the snapshot is replaced.
+    fn on_load_complete(&mut self, page: usize, result: Result<Playlist, PomeloError>, instance: &mut PomeloInstance) -> (Task<Msg>, Navigation) {
+        use crate::yt_fetch::SearchResult;
+
+        self.load_handle = None;
+        self.loading_page = None;
+
+        let playlist = match result {
+            Ok(playlist) => playlist,
             Err(e) => {
-                error!("Failed to load playlist info: {}", e.error);
-                self.error = Some(e);
-                Task::none()
+                error!("Failed to load page {} of playlist: {}", page, e.error);
+
+                // The first page is the playlist's info, later pages just retry themselves.
+                let retry = match page {
+                    1 => PlaylistInfoMessage::LoadPlaylist(self.playlist_id.clone()),
+                    _ => PlaylistInfoMessage::LoadPage(page)
+                };
+
+                self.error = Some(ErrorBanner::new(e, Some(retry.into())));
+                return (Task::none(), Navigation::None);
             }
         };
 
-        (command, Navigation::None)
+        let new_videos = playlist.videos.clone();
+
+        match self.playlist.as_mut() {
+            Some(loaded) if page > 1 => loaded.videos.extend(playlist.videos),
+            _ => self.playlist = Some(playlist)
+        }
+
+        let Some(playlist) = self.playlist.as_ref() else {
+            return (Task::none(), Navigation::None);
+        };
+
+        self.unavailable = playlist.videos.iter().map(Unavailable::of).collect();
+        self.videos = playlist.videos.iter()
+            .filter(|v| Unavailable::of(v).is_none())
+            .map(|v| v.id.clone())
+            .collect();
+
+        let thumbnails = super::item_thumbnail_commands(
+            new_videos.iter().cloned().map(SearchResult::PlaylistVideo).collect(),
+            instance
+        );
+
+        // An empty page means the end was reached, even if the video count said otherwise.
+        if !new_videos.is_empty() && playlist.videos.len() < playlist.video_count as usize {
+            let next = self.load_page(page + 1, instance.settings().invidious_index());
+            return (Task::batch([thumbnails, next]), Navigation::None);
+        }
+
+        if self.videos.len() < playlist.videos.len() {
+            info!("Skipping {} unavailable videos in playlist.", playlist.videos.len() - self.videos.len());
+        }
+
+        let snapshot: Vec<SnapshotVideo> = playlist.videos.iter()
+            .map(|v| SnapshotVideo { id: v.id.clone(), title: v.title.clone() })
+            .collect();
+
+        self.diff = instance.archive().update_playlist_snapshot(&self.playlist_id, &snapshot);
+
+        (thumbnails, Navigation::None)
     }
 
     // Move to the video player, play videos in given order.
@@ -216,19 +344,24 @@ impl PlaylistInfoPage {
             .map(|v| (v, false))
             .collect();
 
+        let titles = self.playlist.iter()
+            .flat_map(|playlist| playlist.videos.iter())
+            .map(|video| (video.id.clone(), video.title.clone()));
+
         let index = if let VideoOrder::Sequential(i) = order {i} else {0};
 
         (
             Task::done(VideoPlayerMessage::LoadVideo(index).into()),
-            Navigation::GoTo(Box::new(VideoPlayerPage::new(videos, order)))
+            Navigation::GoTo(Box::new(VideoPlayerPage::new(videos, order).with_titles(titles)))
         )
     }
 
-    // Setup yt-dlp process for downmloading the playlist.
-    fn start_download(&mut self, instance: &mut PomeloInstance) -> (Task<Msg>, Navigation) {
+    // Where the playlist, or a single video from it if an index is given, would be downloaded to.
+    // Also gives the title shown while downloading, and the arguments yt-dlp would be run with.
+    fn download_args(&self, instance: &PomeloInstance, video_index: Option<usize>) -> Option<(String, String, Vec<String>)> {
         use filenamify::filenamify;
 
-        let playlist = self.playlist.as_ref().unwrap();
+        let playlist = self.playlist.as_ref()?;
         let channel = filenamify(&playlist.author);
         let title = filenamify(&playlist.title);
         let out_path = format!("{}/playlists/{}/{} - {}",
@@ -238,158 +371,242 @@ impl PlaylistInfoPage {
             title
         );
 
-        let mut args = vec![
-            &playlist.id,
-            "-P",
-            &out_path,
-            "-q",
-            "--no-warnings",
-            "--progress",
-            "--newline",
-            "--progress-template",
-            "download:%(info.playlist_index)s|%(progress.downloaded_bytes)s|%(progress.total_bytes)s|%(progress.fragment_index)s|%(progress.fragment_count)s",
-            "--output",
-            "%(playlist_index)s - %(title)s [%(id)s].%(ext)s"
-        ];
-
-        if !Path::exists(Path::new(&out_path)) {
-            let _ = std::fs::create_dir(&out_path);
-        }
+        // Single videos keep the playlist's naming scheme, so they line up with a full download later.
+        let (target, download_title, output) = match video_index {
+            Some(i) => (
+                playlist.videos[i].id.clone(),
+                playlist.videos[i].title.clone(),
+                format!("{} - %(title)s [%(id)s].%(ext)s", i + 1)
+            ),
 
-        let ext = self.selected_format.as_ext();
-        let quality: String;
-        let v_filter: String;
+            None => (
+                playlist.id.clone(),
+                playlist.title.clone(),
+                String::from("%(playlist_index)s - %(title)s [%(id)s].%(ext)s")
+            )
+        };
 
-        if self.selected_format.is_audio() {
-            args.extend([
-                "-x",
-                "--audio-format",
-                ext
-            ]);
-        }
-        else {
-            let q = self.selected_quality.num().to_string();
-            v_filter = format!("b[height={}]/bv[height={}]+ba", ext, q);
-            quality = format!("res:{}", self.selected_quality.num());
-
-            args.extend([
-                "-S",
-                &quality,
-                "-f",
-                &v_filter,
-                "--remux-video",
-                ext
-            ]);
-        }
+        // yt-dlp runs in its own working directory, so it needs the full path.
+        let out_dir = absolute_path(&out_path);
 
-        let command = match instance.create_download_process(&args) {
-            Ok((mut stdout, stderr)) => {
-                let mut output = String::new();
-                let result = stdout.read_line(&mut output);
+        let mut args: Vec<String> = [
+            target.as_str(),
+            "-P",
+            out_dir.as_str(),
+            "--output",
+            output.as_str()
+        ].map(String::from).to_vec();
 
-                self.downloading = true;
-                self.download_info = Some(DownloadInfo::new(out_path, stdout, stderr));
+        args.extend(super::format_args(&self.selected_format, &self.selected_quality, &self.selected_subtitles));
 
-                Task::done(Msg::NextVideoChunk(output, result.map_err(PomeloError::new)))
-            },
+        Some((out_path, download_title, args))
+    }
 
-            Err(e) => Task::done(Msg::VideoDownloadComplete(Err(e)))
+    // Copy the command for downloading the whole playlist with the current settings.
+    fn copy_download_command(&self, instance: &PomeloInstance) -> (Task<Msg>, Navigation) {
+        let task = match self.download_args(instance, None) {
+            Some((_, _, args)) => Task::done(Msg::CopyToClipboard(instance.download_command_line(&args))),
+            None => Task::none()
         };
 
-        (command, Navigation::None)
+        (task, Navigation::None)
     }
 
-    // Called when yt-dlp collects a chunk of bytes. Info from yt-dlp is used to update UI during download.
-    fn on_next_chunk(&mut self, output: String, result: Result<usize, PomeloError>) -> (Task<Msg>, Navigation) {
-        let command = match result {
-            Ok(index) => match index {
-                0 => Task::done(Msg::VideoDownloadComplete(Ok(()))),
-                _ => {
+    // Queue a download of the playlist, or a single video from it if an index is given.
+    fn start_download(&mut self, instance: &mut PomeloInstance, video_index: Option<usize>) -> (Task<Msg>, Navigation) {
+        self.error = None;
 
-                    let info = self.download_info.as_mut().unwrap();
-
-                    // Read formatted progress string from yt-dlp
-                    let nums: Vec<usize> = output
-                        .trim()
-                        .split('|')
-                        .map(|s| s.parse().unwrap_or_default())
-                        .collect();
-
-                    self.download_index = nums[0];
+        let Some((out_path, download_title, args)) = self.download_args(instance, video_index) else {
+            return (Task::none(), Navigation::None);
+        };
 
-                    if nums[2] != 0 {
-                        info.progress = nums[1];
-                        info.length = nums[2];
-                    }
+        if !Path::exists(Path::new(&out_path)) {
+            let _ = std::fs::create_dir(&out_path);
+        }
 
-                    else {
-                        info.progress = nums[3];
-                        info.length = nums[4];
-                    }
+        let channel = self.playlist.as_ref().map(|playlist| playlist.author.clone());
 
-                    let mut output = String::new();
-                    let result = info.stdout
-                        .read_line(&mut output)
-                        .map_err(PomeloError::new);
+        // Only the loaded pages of a long playlist are counted, which is still enough to tell it's long.
+        let duration = self.playlist.as_ref().map(|playlist| match video_index {
+            Some(i) => u64::from(playlist.videos[i].length),
+            None => playlist.videos.iter().map(|video| u64::from(video.length)).sum()
+        });
 
-                    Task::done(Msg::NextVideoChunk(output, result))
-                }
-            },
+        self.downloads.push(instance.enqueue_download(download_title, channel, out_path, args, duration));
 
-            Err(e) => Task::done(Msg::VideoDownloadComplete(Err(e)))
-        };
+        (Task::none(), Navigation::None)
+    }
 
-        (command, Navigation::None)
+    // The playlist as a main menu pin, using its first video's thumbnail.
+    fn pin(&self) -> Option<Pin> {
+        self.playlist.as_ref().map(|playlist| Pin::Playlist {
+            id: self.playlist_id.clone(),
+            title: playlist.title.clone(),
+            thumbnail: playlist.videos.first()
+                .and_then(|video| video.thumbnails.get(4).or(video.thumbnails.last()))
+                .map(|thumbnail| thumbnail.url.clone())
+        })
     }
 
-    // Download has finished, or the download was stopped by an error or by the user.
-    fn on_download_complete(&mut self, result: Result<(), PomeloError>) {
-        self.downloading = false;
+    // The playlist as a favorite, with the same title and thumbnail as its pin.
+    fn favorite(&self) -> Option<Favorite> {
+        self.pin().map(|pin| Favorite {
+            id: pin.key(),
+            kind: FavoriteKind::Playlist,
+            title: pin.title().to_string(),
+            thumbnail: pin.thumbnail().map(String::from)
+        })
+    }
 
-        if let Err(e) = result {
-            self.error = Some(e);
-        }
+    // State of every video in a whole-playlist download, while it's running or paused.
+    fn get_checklist_element(&self, playlist: &Playlist, id: DownloadId, instance: &PomeloInstance) -> Option<iced::Element<Msg>> {
+        use iced::widget::{row, text, Scrollable};
+        use crate::app::instance::downloads::VideoState;
 
-        else {
-            let info = self.download_info.take().unwrap();
+        let download = instance.downloads().get(id)
+            .filter(|download| download.is_active())
+            .filter(|download| download.args().first() == Some(&self.playlist_id))?;
 
-            if let Some(Ok(line)) = info.stderr.lines().last() {
-                error!("Download failed: {}", line);
-                self.error = Some(PomeloError::from(line));
-            }
+        let mut done = 0;
 
-            else {
-                info!("Video downloaded to file: {:?}", Path::new(&info.path));
-            }
-        }
+        let rows = Column::with_children(
+            playlist.videos.iter().enumerate().map(|(i, video)| {
+                let (label, style): (&str, fn(&iced::Theme) -> text::Style) =
+                    if self.unavailable.get(i).copied().flatten().is_some() {
+                        ("Skipped", text::secondary)
+                    }
+                    else {
+                        match download.video_state(&video.id) {
+                            VideoState::Pending => ("Waiting", text::secondary),
+                            VideoState::Downloading => ("Downloading", text::primary),
+                            VideoState::Done => {
+                                done += 1;
+                                ("Done", text::success)
+                            },
+                            VideoState::Failed => ("Failed", text::danger)
+                        }
+                    };
+
+                row![
+                    Text::new(label).size(12).width(90).style(style),
+                    Text::new(format!("{}. {}", i + 1, video.title)).size(12)
+                ].spacing(10).into()
+            })
+        ).spacing(2);
+
+        Some(
+            column![
+                Text::new(format!("{} of {} videos done", done, playlist.videos.len())).size(12),
+                Scrollable::new(rows)
+                    .width(instance.settings().window_size().0 / 2.0)
+                    .height(150)
+            ].spacing(5).into()
+        )
     }
 
     // Generates a scrollable list of playlist videos.
+    // Each video has its own download button, which uses the selected format and quality.
+    // Deleted, private, and blocked videos are badged, and can't be played or downloaded.
+    // Videos that are already downloaded are badged too, and can be played from the local copy.
+    // Videos being downloaded show the progress of their current file.
     fn create_playlist_element(&self, playlist: &Playlist, instance: &PomeloInstance) -> iced::Element<Msg> {
-        use iced::widget::{Row, Button, Scrollable, Image};
+        use iced::widget::{row, text, Row, Button, ProgressBar, Scrollable};
+        use crate::app::instance::downloads::DownloadStatus;
+        use super::{thumbnail, ConditionalMessage};
     
         let mut vids = Column::<Msg>::new().spacing(10);
+
+        let unavailable_count = self.unavailable.iter().flatten().count();
+
+        if unavailable_count > 0 {
+            vids = vids.push(
+                Text::new(format!("{} unavailable videos will be skipped", unavailable_count)).style(text::danger)
+            );
+        }
+
+        // Position of the video in the player's list, which leaves out unavailable videos.
+        let mut player_index = 0;
+
         for (i, video) in playlist.videos.iter().enumerate() {
+            let unavailable = self.unavailable.get(i).copied().flatten();
+
+            // Hidden watched videos are still played in order, so they keep their place in the player's list.
+            if instance.settings().hide_watched() && instance.archive().is_watched(&video.id) {
+                if unavailable.is_none() {
+                    player_index += 1;
+                }
+
+                continue;
+            }
+
             let mut row: Row<Msg> = Row::new();
     
-            if let Some(handle) = instance.cache().get_thumbnail(&video.id) {
-                row = row.push(Image::new(handle.clone()));
-            }
+            row = row.push_maybe(thumbnail(instance.cache(), &video.id, &video.author, 320.0, 180.0));
     
+            let is_new = self.diff.as_ref().is_some_and(|diff| diff.added.contains(&video.id));
+
+            // Progress of this video, if one of the page's downloads is working on it.
+            let progress = self.downloads.iter()
+                .filter_map(|id| instance.downloads().get(*id))
+                .find(|download| *download.status() == DownloadStatus::Running && download.current_video() == Some(video.id.as_str()))
+                .map(|download| download.fraction());
+
             row = row.push(
                 column![
                     Text::new(format!("{}. {}", i+1, video.title.clone())),
                     Text::new(video.author.clone())
                 ]
+                .push_maybe(is_new.then(|| Text::new("New since last visit").size(12).style(text::success)))
+                .push_maybe(unavailable.map(|reason| Text::new(reason.label()).size(12).style(text::danger)))
+                .push_maybe(super::downloaded_badge(instance, &video.id))
+                .push_maybe(progress.map(|fraction| ProgressBar::new(0.0..=1.0, fraction).width(200).height(8)))
             );
     
             vids = vids.push(
-                Button::new(row)
-                    .width(Length::Fill)
-                    .on_press(PlaylistInfoMessage::ToVideo(VideoOrder::Sequential(i)).into())
-            );        
+                row![
+                    Button::new(row)
+                        .width(Length::Fill)
+                        .on_press_maybe(
+                            PlaylistInfoMessage::ToVideo(VideoOrder::Sequential(player_index))
+                                .on_condition(unavailable.is_none())
+                        ),
+
+                    Button::new(Text::new("Download").size(12).center())
+                        .width(90)
+                        .on_press_maybe(
+                            PlaylistInfoMessage::DownloadVideo(i).on_condition(unavailable.is_none())
+                        )
+                ]
+                .push_maybe(super::play_local_button(instance, &video.id))
+                .push(super::watched_button(instance, &video.id))
+                .spacing(5)
+                .align_y(iced::Alignment::Center)
+            );
+
+            if unavailable.is_none() {
+                player_index += 1;
+            }
         }
     
+        // More pages are on the way.
+        if self.loading_page.is_some() {
+            vids = vids.push(
+                Text::new(format!("Loading... {} of ~{} loaded", playlist.videos.len(), playlist.video_count))
+                    .size(12)
+            );
+        }
+
+        // Videos that were taken out of the playlist since the last visit.
+        let removed = self.diff.as_ref()
+            .filter(|diff| !diff.removed.is_empty())
+            .map(|diff| {
+                column![Text::new(format!("Removed since last visit ({})", diff.removed.len())).style(text::danger)]
+                    .extend(diff.removed.iter().map(|video| Text::new(video.title.clone()).size(12).into()))
+                    .spacing(5)
+            });
+
+        vids = vids.push_maybe(removed);
+
         Scrollable::new(vids)
             .width(Length::Fill)
             .height(instance.settings().window_size().1 / 2.0)
@@ -397,12 +614,40 @@ impl PlaylistInfoPage {
     }
 }
 
-// The download was cancelled by the user.
-fn on_download_cancelled(instance: &mut PomeloInstance) -> (Task<Msg>, Navigation) {
-    instance.cancel_download();
 
-    (
-        Task::done(Msg::VideoDownloadComplete(Err(PomeloError::from("Cancelled by user.")))),
-        Navigation::None
-    )
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use crate::app::instance::archive::Archive;
+    use crate::app::instance::settings::PomeloSettings;
+    use crate::app::pages::PomeloPage;
+    use crate::app::PomeloError;
+    use crate::yt_fetch::VideoFetcher;
+    use crate::yt_fetch::fixtures::{FixtureFetcher, DEMO_FOLDER};
+
+    use super::{PlaylistInfoMessage, PlaylistInfoPage, PomeloInstance};
+
+    #[test]
+    fn test_playlist_pages() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let fetcher = FixtureFetcher::new(DEMO_FOLDER);
+        let mut instance = PomeloInstance::with_archive(PomeloSettings::default(), Archive::in_memory());
+        let mut page = PlaylistInfoPage::new();
+
+        page.playlist_id = String::from("PLpomeloDemoPlaylist");
+
+        let first = runtime.block_on(fetcher.get_playlist_videos(&page.playlist_id, 1)).map_err(PomeloError::new);
+        let _ = page.update(&mut instance, PlaylistInfoMessage::LoadComplete(1, Box::new(first)).into());
+
+        // The playlist says it has more videos than the first page holds, so the next page is loaded.
+        assert_eq!(page.videos.len(), 3);
+        assert_eq!(page.loading_page, Some(2));
+
+        let second = runtime.block_on(fetcher.get_playlist_videos(&page.playlist_id, 2)).map_err(PomeloError::new);
+        let _ = page.update(&mut instance, PlaylistInfoMessage::LoadComplete(2, Box::new(second)).into());
+
+        // An empty page ends the playlist without repeating any videos.
+        assert!(page.error.is_none());
+        assert_eq!(page.videos.len(), 3);
+        assert_eq!(page.loading_page, None);
+    }
+}
@@ -0,0 +1,252 @@
+use iced::{Element, Length, Task};
+use iced::widget::{column, row, text, Button, Column, Scrollable, Text, TextInput};
+
+use log::{error, info, warn};
+
+use crate::app::PomeloError;
+
+use super::{ConditionalMessage, FillElement, PomeloInstance, PomeloPage, Navigation, Msg};
+
+// PINs shorter than this are too easy to guess.
+const MIN_PIN_LENGTH: usize = 4;
+
+#[derive(Debug, Clone)]
+pub (crate) enum RestrictedModeMessage {
+    UpdatePin(String),
+    TurnOn,
+    Unlock,
+    PinHashed(Result<String, PomeloError>),
+    PinChecked(Result<bool, PomeloError>),
+    Disallow(String)
+}
+
+impl ConditionalMessage for RestrictedModeMessage {}
+
+impl From<RestrictedModeMessage> for Msg {
+    fn from(value: RestrictedModeMessage) -> Self {
+        Self::RestrictedMode(value)
+    }
+}
+
+// Turns restricted mode on with a new PIN, or off again with the same PIN.
+// While it's off, the channels allowed in restricted mode are listed here so they can be removed.
+pub (crate) struct RestrictedModePage {
+    pin_input: String,
+    error: Option<String>,
+
+    // A PIN is being hashed or checked. It takes a moment, so it's done in a task.
+    working: bool
+}
+
+impl RestrictedModePage {
+    pub (crate) fn new() -> Self {
+        Self { pin_input: String::new(), error: None, working: false }
+    }
+}
+
+impl PomeloPage for RestrictedModePage {
+    fn update(&mut self, instance: &mut PomeloInstance, message: Msg) -> (Task<Msg>, Navigation) {
+        let msg = match message {
+            Msg::Back => return (Task::none(), Navigation::Back),
+            Msg::RestrictedMode(msg) => msg,
+            _ => return (Task::none(), Navigation::None)
+        };
+
+        match msg {
+            // Only digits can be typed into the PIN box.
+            RestrictedModeMessage::UpdatePin(pin) => if pin.chars().all(|c| c.is_ascii_digit()) {
+                self.pin_input = pin;
+            },
+
+            RestrictedModeMessage::TurnOn => if self.pin_input.len() >= MIN_PIN_LENGTH && !self.working {
+                self.working = true;
+
+                let pin = self.pin_input.clone();
+                return (
+                    Task::perform(
+                        run_blocking(move || crate::utils::hash_pin(&pin)),
+                        |hash| RestrictedModeMessage::PinHashed(hash).into()
+                    ),
+                    Navigation::None
+                );
+            },
+
+            RestrictedModeMessage::PinHashed(result) => {
+                self.working = false;
+
+                let hash = match result {
+                    Ok(hash) => hash,
+                    Err(e) => {
+                        error!("Failed to hash the restricted mode PIN: {}", e.error);
+                        self.error = Some(String::from("Couldn't set the PIN, try again."));
+                        return (Task::none(), Navigation::None);
+                    }
+                };
+
+                info!("Turning on restricted mode.");
+
+                instance.settings_mut().restrict(hash);
+                instance.settings().save();
+
+                // The settings page this was opened from is off limits now.
+                return (Task::none(), Navigation::Home);
+            },
+
+            RestrictedModeMessage::Unlock => {
+                if self.working {
+                    return (Task::none(), Navigation::None);
+                }
+
+                let wait = instance.settings().unlock_wait();
+
+                if wait > 0 {
+                    self.pin_input.clear();
+                    self.error = Some(format!("Too many wrong PINs. Try again in {}.", format_wait(wait)));
+                    return (Task::none(), Navigation::None);
+                }
+
+                let Some(stored) = instance.settings().restricted_pin().map(String::from) else {
+                    return (Task::none(), Navigation::None);
+                };
+
+                self.working = true;
+                self.error = None;
+
+                let pin = self.pin_input.clone();
+                return (
+                    Task::perform(
+                        run_blocking(move || crate::utils::verify_pin(&pin, &stored)),
+                        |correct| RestrictedModeMessage::PinChecked(correct).into()
+                    ),
+                    Navigation::None
+                );
+            },
+
+            RestrictedModeMessage::PinChecked(result) => {
+                self.working = false;
+
+                // A check that didn't finish doesn't count as a wrong PIN.
+                let correct = match result {
+                    Ok(correct) => correct,
+                    Err(e) => {
+                        error!("Failed to check the restricted mode PIN: {}", e.error);
+                        self.error = Some(String::from("Couldn't check the PIN, try again."));
+                        return (Task::none(), Navigation::None);
+                    }
+                };
+
+                instance.settings_mut().finish_unlock(correct);
+                instance.settings().save();
+
+                if correct {
+                    info!("Turned off restricted mode.");
+                    return (Task::none(), Navigation::Back);
+                }
+
+                warn!("Wrong PIN entered for restricted mode.");
+
+                self.pin_input.clear();
+                self.error = Some(match instance.settings().unlock_wait() {
+                    0 => String::from("Wrong PIN."),
+                    wait => format!("Wrong PIN. Try again in {}.", format_wait(wait))
+                });
+            },
+
+            RestrictedModeMessage::Disallow(id) => instance.archive_mut().disallow_channel(&id)
+        }
+
+        (Task::none(), Navigation::None)
+    }
+
+    fn view(&self, instance: &PomeloInstance) -> Element<Msg> {
+        let restricted = instance.settings().is_restricted();
+
+        let submit = if restricted { RestrictedModeMessage::Unlock } else { RestrictedModeMessage::TurnOn };
+        let pin_ready = self.pin_input.len() >= MIN_PIN_LENGTH && !self.working;
+
+        let mut column = column![
+            Text::new("Restricted Mode").size(20),
+
+            Text::new(
+                if restricted {
+                    "Enter the PIN to turn off restricted mode."
+                } else {
+                    "Restricted mode hides the settings, turns off links, and only plays videos from allowed channels.\n\
+                    Channels can be allowed from their pages. Pick a PIN of at least 4 digits to turn it on."
+                }
+            ).center(),
+
+            row![
+                TextInput::new("PIN", &self.pin_input)
+                    .secure(true)
+                    .on_input(|pin| RestrictedModeMessage::UpdatePin(pin).into())
+                    .on_submit_maybe(submit.clone().on_condition(pin_ready))
+                    .width(150),
+
+                Button::new(Text::new(if restricted { "Unlock" } else { "Turn On" }).center())
+                    .width(100)
+                    .on_press_maybe(submit.on_condition(pin_ready))
+            ].spacing(10)
+        ]
+        .push_maybe(self.error.as_deref().map(|error| Text::new(error.to_string()).style(text::danger)));
+
+        if !restricted {
+            column = column.push(allowed_channels_element(instance));
+        }
+
+        column.push(
+            Button::new(Text::new("Back").center())
+                .width(100)
+                .on_press(Msg::Back)
+        )
+        .spacing(15)
+        .padding(25)
+        .align_x(iced::Alignment::Center)
+        .fill()
+    }
+
+    fn subscription(&self, _instance: &PomeloInstance) -> iced::Subscription<Msg> {
+        iced::Subscription::none()
+    }
+}
+
+// Channels that can be played in restricted mode, with buttons to remove them.
+fn allowed_channels_element(instance: &PomeloInstance) -> Element<Msg> {
+    let channels = instance.archive().allowed_channels();
+
+    if channels.is_empty() {
+        return Text::new("No channels are allowed yet.").into();
+    }
+
+    let rows = Column::with_children(
+        channels.iter().map(|(id, name)| {
+            row![
+                Text::new(name.clone()).width(Length::Fill),
+
+                Button::new(Text::new("Remove").size(12).center())
+                    .width(70)
+                    .on_press(RestrictedModeMessage::Disallow(id.clone()).into())
+            ].spacing(10).align_y(iced::Alignment::Center).into()
+        })
+    ).spacing(5);
+
+    column![
+        Text::new(format!("Allowed channels ({})", channels.len())).size(18),
+        Scrollable::new(rows)
+            .width(instance.settings().window_size().0 / 2.0)
+            .height(instance.settings().window_size().1 / 3.0)
+    ].spacing(10).align_x(iced::Alignment::Center).into()
+}
+
+// Run slow work, like hashing a PIN, on a blocking thread so the UI doesn't freeze.
+async fn run_blocking<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> Result<T, PomeloError> {
+    tokio::task::spawn_blocking(work).await.map_err(PomeloError::new)
+}
+
+// Wait before the next PIN can be tried, in minutes once it's over a minute.
+fn format_wait(seconds: u64) -> String {
+    match seconds {
+        0..=60 => format!("{} seconds", seconds),
+        _ => format!("{} minutes", seconds.div_ceil(60))
+    }
+}
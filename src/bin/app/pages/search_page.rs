@@ -1,14 +1,37 @@
-use iced::Task;
+use std::time::Duration;
 
-use crate::yt_fetch::SearchType;
+use iced::{keyboard, Task};
+
+use log::warn;
+
+use crate::yt_fetch::instances;
+use crate::app::PomeloError;
+use crate::yt_fetch::{Fetcher, SearchType, VideoFetcher};
 
 use super::{PomeloInstance, PomeloPage, Navigation, Msg};
 
+// Suggestions are fetched once typing stops for this long, instead of on every key press.
+const SUGGESTION_DELAY: Duration = Duration::from_millis(300);
+const MAX_SUGGESTIONS: usize = 8;
+
+// Number of past queries listed under the search box.
+const RECENT_SEARCHES: usize = 5;
+
 #[derive(Debug, Clone)]
 pub (crate) enum SearchMessage {
     UpdateInput(String),
     SetSearchType(SearchType),
-    SubmitQuery
+    PickRecent(String),
+    RemoveRecent(String),
+    ClearRecent,
+    SubmitQuery,
+
+    // Suggestions are numbered by the input they were requested for, so late answers for old input are dropped.
+    FetchSuggestions(u64),
+    SuggestionsLoaded(u64, Result<Vec<String>, PomeloError>),
+    PickSuggestion(String),
+    SelectSuggestion(isize),
+    ClearSuggestions
 }
 
 impl From<SearchMessage> for Msg {
@@ -18,31 +41,109 @@ impl From<SearchMessage> for Msg {
 }
 
 // Page for search queries. Can be used to play videos directly, or to search for videos/channels/playlists from Youtube.
+// Starts with the last search that was made, so coming back to the page doesn't lose it.
 pub (crate) struct SearchPage {
     search_input: String,
     search_type: SearchType,
+
+    // Latest queries of the search type, from the archive.
+    recent: Vec<String>,
+
+    // Suggestions for the current input, and the one picked with the arrow keys.
+    suggestions: Vec<String>,
+    selected: Option<usize>,
+    input_number: u64
 }
 
 impl SearchPage {
-    pub (crate) fn new() -> Self {
+    pub (crate) fn new(instance: &PomeloInstance) -> Self {
+        let search_type = instance.settings().search_type();
+        let recent = instance.archive().recent_searches(search_type, RECENT_SEARCHES);
+
         Self {
-            search_input: String::new(),
-            search_type: SearchType::Video
+            search_input: recent.first().cloned().unwrap_or_default(),
+            search_type,
+            recent,
+            suggestions: Vec::new(),
+            selected: None,
+            input_number: 0
         }
     }
 }
 
 impl PomeloPage for SearchPage {
-    fn update(&mut self, _instance: &mut PomeloInstance, message: Msg) -> (Task<Msg>, Navigation) {
+    fn update(&mut self, instance: &mut PomeloInstance, message: Msg) -> (Task<Msg>, Navigation) {
         if let Msg::Back = message {
             return (Task::none(), Navigation::Back);
         }
 
         else if let Msg::Search(msg) = message {
             match msg {
-                SearchMessage::UpdateInput(s) => self.search_input = s,
-                SearchMessage::SetSearchType(s_type) => self.search_type = s_type,
-                SearchMessage::SubmitQuery => return self.submit_query()
+                SearchMessage::UpdateInput(s) => {
+                    self.search_input = s;
+                    return (self.wait_for_typing(), Navigation::None);
+                },
+
+                SearchMessage::SetSearchType(s_type) => {
+                    self.search_type = s_type;
+                    self.recent = instance.archive().recent_searches(s_type, RECENT_SEARCHES);
+                },
+
+                SearchMessage::RemoveRecent(query) => {
+                    instance.archive().remove_search(&query, self.search_type);
+                    self.recent = instance.archive().recent_searches(self.search_type, RECENT_SEARCHES);
+                },
+
+                SearchMessage::ClearRecent => {
+                    instance.archive().clear_search_history();
+                    self.recent.clear();
+                },
+
+                // Search for the suggestion picked with the arrow keys, if there is one.
+                SearchMessage::SubmitQuery => {
+                    if let Some(suggestion) = self.selected.and_then(|i| self.suggestions.get(i)) {
+                        self.search_input = suggestion.clone();
+                    }
+
+                    return self.submit_query(instance);
+                },
+
+                SearchMessage::PickRecent(query) | SearchMessage::PickSuggestion(query) => {
+                    self.search_input = query;
+                    return self.submit_query(instance);
+                },
+
+                SearchMessage::FetchSuggestions(number) => if number == self.input_number {
+                    return (self.fetch_suggestions(instance), Navigation::None);
+                },
+
+                SearchMessage::SuggestionsLoaded(number, result) => if number == self.input_number {
+                    match result {
+                        Ok(mut suggestions) => {
+                            suggestions.truncate(MAX_SUGGESTIONS);
+                            self.suggestions = suggestions;
+                        },
+                        Err(e) => warn!("Failed to get search suggestions: {}", e.error)
+                    }
+
+                    self.selected = None;
+                },
+
+                // Move the selection up or down, wrapping around at either end.
+                SearchMessage::SelectSuggestion(step) => if !self.suggestions.is_empty() {
+                    let len = self.suggestions.len() as isize;
+
+                    self.selected = Some(match self.selected {
+                        Some(i) => (i as isize + step).rem_euclid(len) as usize,
+                        None if step < 0 => self.suggestions.len() - 1,
+                        None => 0
+                    });
+                },
+
+                SearchMessage::ClearSuggestions => {
+                    self.suggestions.clear();
+                    self.selected = None;
+                }
             }
         }
 
@@ -53,7 +154,12 @@ impl PomeloPage for SearchPage {
         use iced::widget::{column, row, TextInput, Radio, Button, Text};
         use super::FillElement;
 
-        let input = TextInput::new("Search or Enter Youtube URL", &self.search_input)
+        let placeholder = match instance.settings().is_restricted() {
+            true => "Search",
+            false => "Search or Enter Youtube URL"
+        };
+
+        let input = TextInput::new(placeholder, &self.search_input)
             .on_input(|s| SearchMessage::UpdateInput(s).into())
             .on_submit(SearchMessage::SubmitQuery.into())
             .padding(10)
@@ -61,8 +167,11 @@ impl PomeloPage for SearchPage {
 
         let set_search_type = |s_type| SearchMessage::SetSearchType(s_type).into();
 
+        // Suggestions take the place of the recent searches while there are any.
         column![
-            input,
+            column![input]
+                .push_maybe(self.suggestions_element(instance).or_else(|| self.recent_element(instance))),
+
             row![
                 Radio::<Msg>::new(
                     "Videos",
@@ -96,35 +205,178 @@ impl PomeloPage for SearchPage {
 
     }
 
+    // Arrow keys move through the suggestions while they're shown, and escape hides them.
     fn subscription(&self, _instance: &PomeloInstance) -> iced::Subscription<Msg> {
-        iced::Subscription::none()
+        use keyboard::key::Named;
+
+        if self.suggestions.is_empty() {
+            return iced::Subscription::none();
+        }
+
+        keyboard::on_key_press(|key, _| match key {
+            keyboard::Key::Named(Named::ArrowDown) => Some(SearchMessage::SelectSuggestion(1).into()),
+            keyboard::Key::Named(Named::ArrowUp) => Some(SearchMessage::SelectSuggestion(-1).into()),
+            keyboard::Key::Named(Named::Escape) => Some(SearchMessage::ClearSuggestions.into()),
+            _ => None
+        })
     }
 }
 
 impl SearchPage {
-    
-    // Move to video info page if query is a URL, otherwise move to search results page with query.
-    fn submit_query(&self) -> (Task<Msg>, Navigation) {
+
+    // Start over on the suggestions for new input, and ask for them once the user stops typing.
+    // Links don't get suggestions.
+    fn wait_for_typing(&mut self) -> Task<Msg> {
+        self.input_number += 1;
+        self.suggestions.clear();
+        self.selected = None;
+
+        let query = self.search_input.trim();
+
+        if query.is_empty() || query.starts_with("https://") {
+            return Task::none();
+        }
+
+        let number = self.input_number;
+
+        Task::perform(
+            tokio::time::sleep(SUGGESTION_DELAY),
+            move |_| SearchMessage::FetchSuggestions(number).into()
+        )
+    }
+
+    fn fetch_suggestions(&self, instance: &PomeloInstance) -> Task<Msg> {
+        let fetcher = Fetcher::new(instances::url(instance.settings().invidious_index()));
+        let query = self.search_input.trim().to_string();
+        let number = self.input_number;
+
+        Task::perform(
+            async move {
+                fetcher.get_search_suggestions(&query).await
+                    .map_err(PomeloError::new)
+            },
+            move |result| SearchMessage::SuggestionsLoaded(number, result).into()
+        )
+    }
+
+    // Past queries under the search box, each with a button to forget it.
+    fn recent_element(&self, instance: &PomeloInstance) -> Option<iced::Element<Msg>> {
+        use iced::widget::{button, row, Button, Column, Text};
+
+        if self.recent.is_empty() {
+            return None;
+        }
+
+        let width = instance.settings().window_size().0 / 2.0;
+
+        let entries = self.recent.iter()
+            .map(|query| {
+                row![
+                    Button::new(Text::new(query.clone()))
+                        .width(width - 40.0)
+                        .style(button::text)
+                        .on_press(SearchMessage::PickRecent(query.clone()).into()),
+
+                    Button::new(Text::new("X").size(12).center())
+                        .width(30)
+                        .style(button::text)
+                        .on_press(SearchMessage::RemoveRecent(query.clone()).into())
+                ].spacing(10).align_y(iced::Alignment::Center).into()
+            });
+
+        Some(
+            Column::with_children(entries)
+                .push(
+                    Button::new(Text::new("Clear All").size(12).center())
+                        .style(button::text)
+                        .on_press(SearchMessage::ClearRecent.into())
+                )
+                .align_x(iced::Alignment::End)
+                .into()
+        )
+    }
+
+    // List of suggestions under the search box, with the selected one highlighted.
+    fn suggestions_element(&self, instance: &PomeloInstance) -> Option<iced::Element<Msg>> {
+        use iced::widget::{button, Button, Column, Text};
+
+        if self.suggestions.is_empty() {
+            return None;
+        }
+
+        let buttons = self.suggestions.iter()
+            .enumerate()
+            .map(|(i, suggestion)| {
+                Button::new(Text::new(suggestion.clone()))
+                    .width(instance.settings().window_size().0 / 2.0)
+                    .style(if self.selected == Some(i) { button::primary } else { button::text })
+                    .on_press(SearchMessage::PickSuggestion(suggestion.clone()).into())
+                    .into()
+            });
+
+        Some(Column::with_children(buttons).into())
+    }
+
+    // Move to the page for a Youtube link, or to the search results page for anything else.
+    // Video links go to the video info page, with the link's timestamp kept for when the video is played.
+    // Searches are remembered for the next time the page is opened.
+    fn submit_query(&self, instance: &mut PomeloInstance) -> (Task<Msg>, Navigation) {
+        use crate::utils::{parse_youtube_link, YoutubeLink};
         use super::video_info_page::{VideoInfoMessage, VideoInfoPage};
+        use super::playlist_info_page::{PlaylistInfoMessage, PlaylistInfoPage};
         use super::search_results_page::{SearchResultsMessage, SearchResultsPage};
 
-        if self.search_input.starts_with("https://") {
-            let query = rusty_ytdl::get_video_id(&self.search_input).unwrap();
+        let search = |query: String, s_type| (
+            Task::done(SearchResultsMessage::StartSearch.into()),
+            Navigation::GoTo(Box::new(SearchResultsPage::new(query, s_type)))
+        );
 
-            (
-                Task::done(VideoInfoMessage::LoadVideo(query).into()),
-                Navigation::GoTo(Box::new(VideoInfoPage::new()))
-            )
-        }
+        // Links could lead anywhere, so they're searched as text in restricted mode.
+        let link = match instance.settings().is_restricted() {
+            true => None,
+            false => parse_youtube_link(&self.search_input)
+        };
+
+        match link {
+            Some(YoutubeLink::Video { id, start }) => {
+                let mut page = VideoInfoPage::new();
+
+                if let Some(start) = start {
+                    page = page.with_start_position(start as f64);
+                }
+
+                (
+                    Task::done(VideoInfoMessage::LoadVideo(id).into()),
+                    Navigation::GoTo(Box::new(page))
+                )
+            },
 
-        else {
-            let query = self.search_input.clone();
-            let s_type = self.search_type;
+            Some(YoutubeLink::Playlist(id)) => (
+                Task::done(PlaylistInfoMessage::LoadPlaylist(id).into()),
+                Navigation::GoTo(Box::new(PlaylistInfoPage::new()))
+            ),
 
-            (
-                Task::done(SearchResultsMessage::StartSearch.into()),
-                Navigation::GoTo(Box::new(SearchResultsPage::new(query, s_type)))
-            )
+            Some(YoutubeLink::Channel(id)) => search(id, SearchType::ChannelUploads),
+
+            // Invidious needs the channel id, so search for the channel by name instead.
+            Some(YoutubeLink::ChannelName(name)) => search(name, SearchType::Channel),
+
+            None if self.search_input.starts_with("https://") && !instance.settings().is_restricted() => {
+                warn!("Not a recognized Youtube link, searching for it instead: {}", self.search_input);
+                search(self.search_input.clone(), self.search_type)
+            },
+
+            None => {
+                let query = self.search_input.trim();
+
+                instance.settings_mut().set_search_type(self.search_type);
+
+                if !query.is_empty() && instance.settings().record_search_history() {
+                    instance.archive().add_search(query, self.search_type);
+                }
+
+                search(self.search_input.clone(), self.search_type)
+            }
         }
     }
 }
\ No newline at end of file
@@ -1,24 +1,29 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use iced::{Task, Length, Element};
 use iced::widget::{column, row, Column, Row, Text, Button, Image};
-use iced::widget::image::Handle;
 use invidious::CommonVideo;
-use log::{info, error};
+use log::{info, warn, error};
 
 
-use crate::INVID_INSTANCES;
+use crate::yt_fetch::instances;
+use crate::utils::{format_compact, format_date, format_number, format_relative_time, Locale};
 use crate::app::PomeloError;
-use crate::app::instance::cache::PomeloCache;
-use crate::yt_fetch::{SearchResult, SearchResults, SearchType, VideoFetcher};
+use crate::app::instance::archive::{Archive, ChannelPreference, Favorite, FavoriteKind, SubscribedChannel};
+use crate::app::instance::settings::{Pin, PlaybackSpeed, StreamQuality};
+use crate::yt_fetch::{ChannelInfo, Fetcher, SearchResult, SearchResults, SearchType, VideoFetcher};
 
-use super::{FillElement, PomeloInstance, Navigation, Msg};
+use super::{ErrorBanner, FillElement, PomeloInstance, PomeloPage, Navigation, Msg, VideoOrder};
+use super::video_info_page::VideoInfoPage;
+
+// Minimum window width for showing the selected video next to the results.
+const TWO_PANE_MIN_WIDTH: f32 = 1000.0;
 
 // Convenience trait for grabbing info about a search item.
 // Playlist videos are handled on a separate page, so they're listed as unreachable here.
 trait YoutubeInfo {
     fn id(&self) -> String;
-    fn info(&self) -> Vec<String>;
+    fn info(&self, locale: Locale) -> Vec<String>;
 }
 
 impl YoutubeInfo for SearchResult {
@@ -31,17 +36,21 @@ impl YoutubeInfo for SearchResult {
         }
     }
 
-    fn info(&self) -> Vec<String> {
+    fn info(&self, locale: Locale) -> Vec<String> {
         match self {
             Self::Video(v) => vec![
                 v.title.clone(),
                 v.author.clone(),
-                format!("{} Views", v.views)
+                format!(
+                    "{} Views • {}",
+                    format_compact(v.views, locale),
+                    format_relative_time(seconds_since(v.published), locale)
+                )
             ],
             Self::Channel(ch) => vec![
                 ch.name.clone(),
                 ch.description.clone(),
-                format!("{} Subscribers", ch.subscribers)
+                format!("{} Subscribers", format_compact(ch.subscribers as u64, locale))
             ],
             Self::Playlist(p) => vec![
                 p.title.clone(),
@@ -60,7 +69,24 @@ pub (crate) enum SearchResultsMessage {
     NewPage(usize),
     ToVideo(CommonVideo),
     ToChannelVideos(String),
-    ToPlaylistVideos(String)
+    ToPlaylistVideos(String),
+    SetChannelTab(ChannelTab),
+    ChannelInfoLoaded(Box<Result<ChannelInfo, PomeloError>>),
+    PlayUploads(VideoOrder),
+    TogglePin,
+    ToggleSubscription(SubscribedChannel),
+    ToggleAllowed(SubscribedChannel),
+    ToggleFavorite(Favorite),
+    AddToQueue(String, String),
+    SetPreference(ChannelPreference),
+    CloseDetail
+}
+
+// Tabs shown when viewing a channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub (crate) enum ChannelTab {
+    Uploads,
+    About
 }
 
 impl From<SearchResultsMessage> for Msg {
@@ -71,19 +97,71 @@ impl From<SearchResultsMessage> for Msg {
 
 impl super::ConditionalMessage for SearchResultsMessage {}
 
+// Entry in a channel preference picker. Default leaves the choice to the player.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PreferenceOption<T> {
+    Default,
+    Set(T)
+}
+
+impl<T> PreferenceOption<T> {
+    fn from_option(value: Option<T>) -> Self {
+        value.map_or(Self::Default, Self::Set)
+    }
+
+    fn into_option(self) -> Option<T> {
+        match self {
+            Self::Default => None,
+            Self::Set(value) => Some(value)
+        }
+    }
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for PreferenceOption<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => write!(f, "Default"),
+            Self::Set(value) => write!(f, "{}", value)
+        }
+    }
+}
+
 // Displays the results of a search query.
 // Redirects to itself when the user selects a channel or navigates to another search page.
 // Redirects to video or playlist info page when the user selects a video/playlist.
+// With the two-pane layout, selected videos are shown next to the results instead.
 pub (crate) struct SearchResultsPage {
     query: String,
     search_type: SearchType,
-    search_results: Option<Result<SearchResults, PomeloError>>,
+    search_results: Option<SearchResults>,
+    error: Option<ErrorBanner>,
     page_number: usize,
-    continuation: HashMap<usize, String>
+    continuation: HashMap<usize, String>,
+
+    // Ids of the results on each page, so results repeated from earlier pages can be left out.
+    page_ids: HashMap<usize, HashSet<String>>,
+
+    // Page to get back to after channel continuation tokens stopped working, while the pages before it are refetched.
+    recover_to: Option<usize>,
+    load_handle: Option<iced::task::Handle>,
+    channel_tab: ChannelTab,
+    channel_info: Option<ChannelInfo>,
+    detail: Option<VideoInfoPage>
 }
 
-impl super::PomeloPage for SearchResultsPage {
+impl PomeloPage for SearchResultsPage {
     fn update(&mut self, instance: &mut PomeloInstance, message: Msg) -> (Task<Msg>, Navigation) {
+        // Messages for the video in the detail pane.
+        if let Some(detail) = self.detail.as_mut() {
+            if is_detail_message(&message) {
+                return detail.update(instance, message);
+            }
+
+            if let Msg::DismissError = message {
+                let _ = detail.update(instance, Msg::DismissError);
+            }
+        }
+
         if let Msg::Back = message {
             return (Task::none(), Navigation::Back);
         }
@@ -92,25 +170,88 @@ impl super::PomeloPage for SearchResultsPage {
             return (Task::none(), Navigation::Home);
         }
 
+        else if let Msg::DismissError = message {
+            self.error = None;
+        }
+
+        else if let Msg::CancelLoad = message {
+            if let Some(handle) = self.load_handle.take() {
+                handle.abort();
+            }
+
+            return (Task::none(), Navigation::Back);
+        }
+
         else if let Msg::SearchResults(msg) = message {
             match msg {
                 SearchResultsMessage::StartSearch 
                     => return self.start_search(instance.settings().invidious_index()),
 
                 SearchResultsMessage::SearchComplete(result) 
-                    => return self.on_search_complete(result, instance.cache()),
+                    => return self.on_search_complete(result, instance),
 
                 SearchResultsMessage::NewPage(page_number) 
                     => return self.on_new_page(page_number),
 
-                SearchResultsMessage::ToVideo(id) 
-                    => return go_to_video(id),
+                SearchResultsMessage::ToVideo(video) 
+                    => return self.select_video(video, instance),
 
                 SearchResultsMessage::ToChannelVideos(id)
                     => return go_to_channel_videos(&id),
 
                 SearchResultsMessage::ToPlaylistVideos(id)
-                    => return go_to_playlist_videos(id)
+                    => return go_to_playlist_videos(id),
+
+                SearchResultsMessage::SetChannelTab(tab)
+                    => return self.set_channel_tab(tab, instance.settings().invidious_index()),
+
+                SearchResultsMessage::ChannelInfoLoaded(result)
+                    => return self.on_channel_info_loaded(*result, instance),
+
+                SearchResultsMessage::TogglePin => {
+                    let pin = self.pin();
+                    let task = super::load_pin_thumbnail(&pin, instance);
+
+                    instance.settings_mut().toggle_pin(pin);
+                    return (task, Navigation::None);
+                },
+
+                SearchResultsMessage::ToggleSubscription(channel) => {
+                    let archive = instance.archive_mut();
+
+                    if archive.is_subscribed(&channel.id) {
+                        archive.unsubscribe(&channel.id);
+                    } else {
+                        archive.subscribe(channel);
+                    }
+                },
+
+                SearchResultsMessage::ToggleAllowed(channel) => {
+                    let archive = instance.archive_mut();
+
+                    if archive.is_allowed(&channel.id) {
+                        archive.disallow_channel(&channel.id);
+                    } else {
+                        archive.allow_channel(&channel.id, &channel.name);
+                    }
+                },
+
+                SearchResultsMessage::ToggleFavorite(favorite)
+                    => instance.archive_mut().toggle_favorite(favorite),
+
+                SearchResultsMessage::PlayUploads(order)
+                    => return self.play_uploads(order),
+
+                SearchResultsMessage::AddToQueue(id, title) => {
+                    info!("Adding video to queue: {}", id);
+                    instance.queue_mut().push(id, title);
+                },
+
+                SearchResultsMessage::SetPreference(preference)
+                    => instance.archive_mut().set_channel_preference(&self.query, preference),
+
+                SearchResultsMessage::CloseDetail
+                    => self.detail = None
             }
         }
 
@@ -118,123 +259,292 @@ impl super::PomeloPage for SearchResultsPage {
     }
 
     fn view(&self, instance: &PomeloInstance) -> Element<Msg> {
+        use iced::widget::Container;
+
+        let results = self.get_results_view(instance);
+
+        match &self.detail {
+            Some(detail) if self.use_two_panes(instance) => row![
+                Container::new(results).width(Length::FillPortion(1)),
+
+                column![
+                    Button::new(Text::new("Close").center())
+                        .width(100)
+                        .on_press(SearchResultsMessage::CloseDetail.into()),
+
+                    detail.view(instance)
+                ].spacing(10).align_x(iced::Alignment::Center).width(Length::FillPortion(1))
+            ].spacing(10).into(),
+
+            _ => results
+        }
+    }
+
+    fn subscription(&self, _instance: &PomeloInstance) -> iced::Subscription<Msg> {
+        iced::Subscription::none()
+    }
+}
+
+impl SearchResultsPage {
+    // The search results, with buttons for changing pages.
+    fn get_results_view(&self, instance: &PomeloInstance) -> Element<Msg> {
         use super::ConditionalMessage;
 
-        if let Some(result) = &self.search_results {
-            let result_element = self.get_search_results_element(result, instance);
+        if self.search_results.is_none() && self.error.is_none() {
+            return super::loading_skeleton(5);
+        }
 
-            let buttons = row![
-                Button::new(Text::new("Prev").center())
-                    .width(100)
-                    .on_press_maybe(
-                        SearchResultsMessage::NewPage(self.page_number-1)
-                            .on_condition(self.page_number > 1)
-                    ),
-            
-                Button::new(Text::new("Back").center())
-                    .width(100)
-                    .on_press(Msg::Back),
-            
-                Button::new(Text::new("Next").center())
+        let mut column = Column::new()
+            .align_x(iced::Alignment::Center)
+            .spacing(25);
+
+        column = column.push_maybe(self.error.as_ref().map(|banner| banner.view()));
+
+        if let SearchType::ChannelUploads = self.search_type {
+            column = column.push(self.get_channel_tabs_element());
+
+            if self.channel_tab == ChannelTab::About {
+                return column.extend([
+                    self.get_channel_about_element(instance),
+                    self.get_preference_element(instance),
+                    row![
+                        Button::new(Text::new("Back").center())
+                            .width(100)
+                            .on_press(Msg::Back),
+
+                        Button::new(Text::new("Home").center())
+                            .width(100)
+                            .on_press(Msg::Home)
+                    ].spacing(25).into()
+                ]).into();
+            }
+
+            column = column.push_maybe(
+                self.search_results.as_ref().map(|_| row![
+                    Button::new(Text::new("Play all").center())
+                        .width(100)
+                        .on_press(SearchResultsMessage::PlayUploads(VideoOrder::Sequential(0)).into()),
+
+                    Button::new(Text::new("Shuffle all").center())
+                        .width(100)
+                        .on_press(SearchResultsMessage::PlayUploads(VideoOrder::Shuffled).into()),
+
+                    subscribe_button(self.channel(), instance.archive()),
+                    favorite_button(self.channel(), instance.archive())
+                ]
+                .push_maybe((!instance.settings().is_restricted()).then(|| allow_button(self.channel(), instance.archive())))
+                .spacing(10))
+            );
+        }
+
+        column = column.push_maybe(
+            self.search_results.as_ref()
+                .map(|search| self.get_search_results_element(search, instance))
+        );
+
+        let buttons = row![
+            Button::new(Text::new("Prev").center())
+                .width(100)
+                .on_press_maybe(
+                    SearchResultsMessage::NewPage(self.page_number-1)
+                        .on_condition(self.page_number > 1)
+                ),
+
+            Button::new(Text::new("Back").center())
+                .width(100)
+                .on_press(Msg::Back),
+
+            Button::new(Text::new("Next").center())
+                .width(100)
+                .on_press(SearchResultsMessage::NewPage(self.page_number+1).into())
+
+        ].spacing(25);
+
+        let pin_text = if instance.settings().is_pinned(&self.pin()) {
+            "Unpin"
+        } else {
+            "Pin to menu"
+        };
+
+        column.extend([
+            buttons.into(),
+            row![
+                Button::new(Text::new(pin_text).center())
                     .width(100)
-                    .on_press(SearchResultsMessage::NewPage(self.page_number+1).into())
-            
-            ].spacing(25);
-    
-            column![
-                result_element,
-                buttons,
+                    .on_press(SearchResultsMessage::TogglePin.into()),
+
                 Button::new(Text::new("Home").center())
                     .width(100)
                     .on_press(Msg::Home)
-            ].align_x(iced::Alignment::Center).spacing(25).into()
-        }
-        else {
-            "Loading...".fill()
+            ].spacing(25).into()
+        ]).into()
+    }
+
+    fn use_two_panes(&self, instance: &PomeloInstance) -> bool {
+        instance.settings().two_pane_layout() && instance.settings().window_size().0 >= TWO_PANE_MIN_WIDTH
+    }
+
+    // Show the selected video in the detail pane, or on its own page if there's no room for two panes.
+    fn select_video(&mut self, video: CommonVideo, instance: &PomeloInstance) -> (Task<Msg>, Navigation) {
+        use super::video_info_page::VideoInfoMessage;
+
+        if !self.use_two_panes(instance) {
+            return go_to_video(video);
         }
+
+        let id = video.id.clone();
+        self.detail = Some(VideoInfoPage::new_with_video(video));
+
+        (Task::done(VideoInfoMessage::LoadVideo(id).into()), Navigation::None)
     }
 
-    fn subscription(&self, _instance: &PomeloInstance) -> iced::Subscription<Msg> {
-        iced::Subscription::none()
+    // This search, or the channel being viewed, as a main menu pin.
+    fn pin(&self) -> Pin {
+        if self.search_type != SearchType::ChannelUploads {
+            return Pin::Search { query: self.query.clone(), search_type: self.search_type };
+        }
+
+        let uploader = match &self.search_results {
+            Some(SearchResults::ChannelUploads(uploads)) => uploads.videos.first().map(|video| video.author.clone()),
+            _ => None
+        };
+
+        match &self.channel_info {
+            Some(info) => Pin::Channel {
+                id: self.query.clone(),
+                name: info.name.clone(),
+                thumbnail: info.thumbnail.clone()
+            },
+            None => Pin::Channel {
+                id: self.query.clone(),
+                name: uploader.unwrap_or_else(|| self.query.clone()),
+                thumbnail: None
+            }
+        }
     }
-}
 
-impl SearchResultsPage {
+    // The channel being viewed, for subscribing to it. Uses the same name and avatar as its pin.
+    fn channel(&self) -> SubscribedChannel {
+        match self.pin() {
+            Pin::Channel { id, name, thumbnail } => SubscribedChannel { id, name, thumbnail },
+            Pin::Search { .. } | Pin::Playlist { .. } => SubscribedChannel {
+                id: self.query.clone(),
+                name: self.query.clone(),
+                thumbnail: None
+            }
+        }
+    }
 
     pub (crate) fn new(query: String, search_type: SearchType) -> Self {
         Self {
             query,
             search_type,
             search_results: None,
+            error: None,
             page_number: 1,
-            continuation: HashMap::new()
+            continuation: HashMap::new(),
+            page_ids: HashMap::new(),
+            recover_to: None,
+            load_handle: None,
+            channel_tab: ChannelTab::Uploads,
+            channel_info: None,
+            detail: None
         }
     }
 
     // Use Invidious to search for items from Youtube.
-    fn start_search(&self, instance_index: usize) -> (Task<Msg>, Navigation) {
+    fn start_search(&mut self, instance_index: usize) -> (Task<Msg>, Navigation) {
         let query = self.query.clone();
         let search_type = self.search_type;
         let page_number = self.page_number;
         let continuation = self.continuation.get(&self.page_number).cloned();
-        let instance = String::from(INVID_INSTANCES[instance_index].0);
+        let instance = instances::url(instance_index);
 
         info!("Starting Youtube search. Type: {}, Page: {}, Query: {}", search_type, page_number, query);
-        
-        (
-            Task::perform(
-                async move {
-                    let downloader = VideoFetcher::new(instance);
 
-                    if let SearchType::ChannelUploads = search_type {
-                        println!("{:?}", continuation);
-                        downloader.get_channel_videos(&query, continuation.as_deref()).await
-                            .map(SearchResults::ChannelUploads)
-                            .map_err(PomeloError::new)
-                    }
+        self.error = None;
 
-                    else {
-                        match downloader.search(&query, search_type, page_number).await {
-                            Ok(search) => match search_type {
-                                SearchType::Video => Ok(SearchResults::Videos(search)),
-                                SearchType::Channel => Ok(SearchResults::Channels(search)),
-                                SearchType::Playlist => Ok(SearchResults::Playlists(search)),
-                                _ => unreachable!()
-                            },
-                            Err(e) => Err(PomeloError::new(e))
-                        }
-                    }
-                },
-                |result| SearchResultsMessage::SearchComplete(result).into()
-            ),
+        let (task, handle) = Task::perform(
+            async move {
+                fetch_results(&Fetcher::new(instance), &query, search_type, page_number, continuation.as_deref()).await
+            },
+            |result| SearchResultsMessage::SearchComplete(result).into()
+        ).abortable();
 
-            Navigation::None
-        )
+        self.load_handle = Some(handle);
+
+        (task, Navigation::None)
     }
 
     // Handle result of search query. Start downloading thumbnails if search was successful.
-    fn on_search_complete(&mut self, result: Result<SearchResults, PomeloError>, cache: &PomeloCache) -> (Task<Msg>, Navigation) {
-        let command = match &result {
-            Ok(search) => {
+    fn on_search_complete(&mut self, result: Result<SearchResults, PomeloError>, instance: &PomeloInstance) -> (Task<Msg>, Navigation) {
+        self.load_handle = None;
+
+        let command = match result {
+            Ok(mut search) => {
 
                 info!("Search complete.");
 
+                let earlier_ids: HashSet<String> = self.page_ids.iter()
+                    .filter(|(page, _)| **page < self.page_number)
+                    .flat_map(|(_, ids)| ids.iter().cloned())
+                    .collect();
+
+                let ids = search.dedupe(&earlier_ids);
+                self.page_ids.insert(self.page_number, ids);
+
+                if instance.settings().is_restricted() {
+                    let archive = instance.archive();
+                    search.retain_channels(|id| archive.is_allowed(id));
+                }
+
                 if let SearchResults::ChannelUploads(videos) = &search {
                     if let Some(cont) = &videos.continuation {
                         self.continuation.insert(self.page_number + 1, cont.clone());
                     }
                 }
 
-                super::batch_thumbnail_commands(search, cache)
+                // Keep going until the page the user was on, or the last page if the channel has fewer pages now.
+                if let Some(target) = self.recover_to {
+                    if self.page_number < target && self.continuation.contains_key(&(self.page_number + 1)) {
+                        self.page_number += 1;
+                        return (Task::done(SearchResultsMessage::StartSearch.into()), Navigation::None);
+                    }
+
+                    info!("Recovered channel uploads at page {}.", self.page_number);
+                    self.recover_to = None;
+                }
+
+                let command = super::batch_thumbnail_commands(&search, instance);
+                self.search_results = Some(search);
+                command
             },
+            // Continuation tokens expire after a while, so later pages of a channel's uploads can stop loading.
+            // New tokens are found by going through the pages again from the start.
+            // Other errors, like timeouts or a dropped connection, are shown instead, since starting over wouldn't help.
+            Err(e) if self.search_type == SearchType::ChannelUploads
+                && self.page_number > 1
+                && self.recover_to.is_none()
+                && is_expired_continuation(&e) =>
+            {
+                warn!("Failed to load page {} of channel uploads, refetching from the first page: {}", self.page_number, e.error);
+
+                self.recover_to = Some(self.page_number);
+                self.page_number = 1;
+                self.continuation.clear();
+                self.page_ids.clear();
+
+                return (Task::done(SearchResultsMessage::StartSearch.into()), Navigation::None);
+            },
+
             Err(e) => {
                 error!("Search failed: {}", e.error);
+                self.recover_to = None;
+                self.error = Some(ErrorBanner::new(e, Some(SearchResultsMessage::StartSearch.into())));
                 Task::none()
             }
         };
 
-        self.search_results = Some(result);
-
         (command, Navigation::None)
     }
 
@@ -243,6 +553,7 @@ impl SearchResultsPage {
 
         self.page_number = page_number;
         self.search_results = None;
+        self.error = None;
 
         (
             Task::done(SearchResultsMessage::StartSearch.into()),
@@ -250,45 +561,274 @@ impl SearchResultsPage {
         )
     }
 
+    // Switch between a channel's uploads and its About tab. Channel details are loaded the first time they're needed.
+    fn set_channel_tab(&mut self, tab: ChannelTab, instance_index: usize) -> (Task<Msg>, Navigation) {
+        self.channel_tab = tab;
+
+        if tab != ChannelTab::About || self.channel_info.is_some() {
+            return (Task::none(), Navigation::None);
+        }
+
+        info!("Loading channel info with id: {}", self.query);
+
+        self.error = None;
+
+        let id = self.query.clone();
+        let instance = instances::url(instance_index);
+
+        (
+            Task::perform(
+                async move {
+                    Fetcher::new(instance)
+                        .get_channel_info(&id).await
+                        .map_err(PomeloError::new)
+                },
+                |result| SearchResultsMessage::ChannelInfoLoaded(Box::new(result)).into()
+            ),
+            Navigation::None
+        )
+    }
+
+    // Channel details finished loading. Start downloading the channel's banner if it has one.
+    fn on_channel_info_loaded(&mut self, result: Result<ChannelInfo, PomeloError>, instance: &PomeloInstance) -> (Task<Msg>, Navigation) {
+        use crate::yt_fetch::download_image;
+
+        let command = match result {
+            Ok(info) => {
+                let command = match info.banner.clone() {
+                    Some(url) => {
+                        let id = banner_id(&info.id);
+                        let host = super::image_host(instance);
+
+                        Task::perform(
+                            async move {
+                                download_image(&url, &host).await
+                                    .map(|handle| (id, handle))
+                                    .map_err(PomeloError::new)
+                            },
+                            Msg::ThumbnailLoaded
+                        )
+                    },
+                    None => Task::none()
+                };
+
+                self.channel_info = Some(info);
+                command
+            },
+
+            Err(e) => {
+                error!("Failed to load channel info: {}", e.error);
+                self.error = Some(
+                    ErrorBanner::new(e, Some(SearchResultsMessage::SetChannelTab(ChannelTab::About).into()))
+                );
+                Task::none()
+            }
+        };
+
+        (command, Navigation::None)
+    }
+
+    // Play the loaded uploads from this page onwards. Later pages are added to the queue as it plays.
+    fn play_uploads(&self, order: VideoOrder) -> (Task<Msg>, Navigation) {
+        use super::video_player_page::{ChannelQueue, VideoPlayerMessage, VideoPlayerPage};
+
+        let Some(SearchResults::ChannelUploads(uploads)) = &self.search_results else {
+            return (Task::none(), Navigation::None);
+        };
+
+        let videos = uploads.videos.iter()
+            .map(|video| (video.id.clone(), false))
+            .collect();
+
+        let shuffle = matches!(order, VideoOrder::Shuffled);
+        let titles = uploads.videos.iter()
+            .map(|video| (video.id.clone(), video.title.clone()));
+
+        let mut page = VideoPlayerPage::new(videos, order).with_titles(titles);
+
+        if let Some(continuation) = self.continuation.get(&(self.page_number + 1)) {
+            page = page.with_channel_queue(
+                ChannelQueue::new(self.query.clone(), continuation.clone(), shuffle)
+            );
+        }
+
+        (
+            Task::done(VideoPlayerMessage::LoadVideo(0).into()),
+            Navigation::GoTo(Box::new(page))
+        )
+    }
+
+    fn get_channel_tabs_element(&self) -> Element<Msg> {
+        use super::ConditionalMessage;
+
+        let tab_button = |text: &'static str, tab: ChannelTab| Button::new(Text::new(text).center())
+            .width(100)
+            .on_press_maybe(
+                SearchResultsMessage::SetChannelTab(tab).on_condition(self.channel_tab != tab)
+            );
+
+        row![
+            tab_button("Uploads", ChannelTab::Uploads),
+            tab_button("About", ChannelTab::About)
+        ].spacing(10).into()
+    }
+
+    // Generate the channel's About tab, with its banner, stats, description, and links.
+    // Speed, quality, and volume offset used when one of the channel's videos starts playing.
+    fn get_preference_element(&self, instance: &PomeloInstance) -> Element<Msg> {
+        use iced::widget::{PickList, Slider};
+
+        let preference = instance.archive().channel_preference(&self.query).copied().unwrap_or_default();
+
+        let speeds: Vec<PreferenceOption<PlaybackSpeed>> = std::iter::once(PreferenceOption::Default)
+            .chain(PlaybackSpeed::ALL.map(PreferenceOption::Set))
+            .collect();
+
+        let qualities: Vec<PreferenceOption<StreamQuality>> = std::iter::once(PreferenceOption::Default)
+            .chain(
+                [StreamQuality::Highest, StreamQuality::Lowest].into_iter()
+                    .chain([360, 480, 720, 1080].map(StreamQuality::Height))
+                    .map(PreferenceOption::Set)
+            )
+            .collect();
+
+        row![
+            Text::new("Playback for this channel:"),
+
+            PickList::new(
+                speeds,
+                Some(PreferenceOption::from_option(preference.speed)),
+                move |speed| SearchResultsMessage::SetPreference(
+                    ChannelPreference { speed: speed.into_option(), ..preference }
+                ).into()
+            ).width(100),
+
+            PickList::new(
+                qualities,
+                Some(PreferenceOption::from_option(preference.quality)),
+                move |quality| SearchResultsMessage::SetPreference(
+                    ChannelPreference { quality: quality.into_option(), ..preference }
+                ).into()
+            ).width(100),
+
+            Text::new(format!("Volume {:+.0}%", preference.volume_offset * 100.0)).width(100),
+
+            // Rounded to the slider's steps, so sliding back to the middle clears the offset.
+            Slider::new(-0.5..=0.5, preference.volume_offset, move |offset| SearchResultsMessage::SetPreference(
+                ChannelPreference { volume_offset: (offset * 20.0).round() / 20.0, ..preference }
+            ).into()).step(0.05).width(150)
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center)
+        .into()
+    }
+
+    fn get_channel_about_element(&self, instance: &PomeloInstance) -> Element<Msg> {
+        use iced::widget::Scrollable;
+
+        let Some(info) = &self.channel_info else {
+            return Text::new(if self.error.is_some() { "" } else { "Loading..." }).into();
+        };
+
+        let locale = instance.settings().locale();
+
+        let mut column = Column::new()
+            .spacing(10)
+            .align_x(iced::Alignment::Center);
+
+        column = column.push_maybe(
+            instance.cache().get_thumbnail(&banner_id(&info.id)).map(Image::new)
+        );
+
+        column = column.extend([
+            Text::new(info.name.as_str()).size(24).into(),
+            Text::new(format!("{} Subscribers", format_number(info.subscribers as u64, locale))).into(),
+            Text::new(format!("{} Views", format_number(info.total_views, locale))).into()
+        ]);
+
+        column = column.push_maybe(
+            info.joined.map(|date| Text::new(format!("Joined {}", format_date(date.date_naive(), locale))))
+        );
+
+        column = column.push(Text::new(info.description.as_str()));
+
+        let feed = crate::utils::channel_feed_url(&info.id);
+
+        column = column.push(
+            row![
+                Text::new(format!("RSS: {}", feed)),
+                Button::new(Text::new("Copy").center())
+                    .width(75)
+                    .on_press(Msg::CopyToClipboard(feed))
+            ].spacing(10).align_y(iced::Alignment::Center)
+        );
+
+        for link in info.links.iter() {
+            column = column.push(
+                row![
+                    Text::new(link.as_str()),
+                    Button::new(Text::new("Copy").center())
+                        .width(75)
+                        .on_press(Msg::CopyToClipboard(link.clone()))
+                ].spacing(10).align_y(iced::Alignment::Center)
+            );
+        }
+
+        Scrollable::new(column.width(Length::Fill))
+            .height(instance.settings().window_size().1 * 3.0 / 4.0)
+            .into()
+    }
+
     // Generate a scrollable list of search items.
-    fn get_search_results_element(&self, search_results: &Result<SearchResults, PomeloError>, instance: &PomeloInstance) -> Element<Msg> {
+    fn get_search_results_element(&self, search: &SearchResults, instance: &PomeloInstance) -> Element<Msg> {
         use iced::widget::Scrollable;
 
-        let mut column = Column::new().spacing(10);
+        // Watched videos are only hidden from channel uploads, since searches are looked through on purpose.
+        let hide_watched = self.search_type == SearchType::ChannelUploads && instance.settings().hide_watched();
 
-        match search_results {
-            Ok(search) => {
-                let mut results = Column::<Msg>::new().spacing(10);
-                for item in search.get_results().iter() {
-                    let thumbnails = instance.cache().thumbnails();
-                    results = results.push(self.get_search_item_element(item, thumbnails));
-                }
-                column = column.push(
-                    Scrollable::new(results)
-                        .width(Length::Fill)
-                        .height(instance.settings().window_size().1 * 3.0 / 4.0)
-                )
-            },
-            Err(e) => column = column.push(Text::new(e.error.clone()).fill())
+        let mut results = Column::<Msg>::new().spacing(10);
+        for item in search.get_results().iter() {
+            if hide_watched && instance.archive().is_watched(&item.id()) {
+                continue;
+            }
+
+            results = results.push(self.get_search_item_element(item, instance));
         }
 
-        column.into()
+        Scrollable::new(results)
+            .width(Length::Fill)
+            .height(instance.settings().window_size().1 * 3.0 / 4.0)
+            .into()
     }
 
     // Generate a button that contains the item's thumbnail and info.
-    fn get_search_item_element(&self, item: &SearchResult, thumbnails: &HashMap<String, Handle>) -> Element<Msg> {
-        let mut row: Row<Msg> = Row::new();
+    // Channels get a subscribe button next to them. Videos get buttons for adding them to the play queue
+    // and marking them watched, and downloaded videos get a button for playing the local copy.
+    fn get_search_item_element(&self, item: &SearchResult, instance: &PomeloInstance) -> Element<Msg> {
+        let cache = instance.cache();
+        let locale = instance.settings().locale();
 
-        if let Some(handle) = thumbnails.get(&item.id()) {
-            row = row.push(Image::new(handle.clone()));
-        }
+        let name = match item {
+            SearchResult::Video(video) => &video.author,
+            SearchResult::Channel(channel) => &channel.name,
+            SearchResult::Playlist(playlist) => &playlist.author,
+            SearchResult::PlaylistVideo(video) => &video.author
+        };
+
+        let mut row: Row<Msg> = Row::new()
+            .push_maybe(super::thumbnail(cache, &item.id(), name, 320.0, 180.0));
+
+        let downloaded = match item {
+            SearchResult::Video(video) => super::downloaded_badge(instance, &video.id),
+            _ => None
+        };
 
         row = row.push(
             Column::from_vec(
-                item.info().into_iter()
+                item.info(locale).into_iter()
                     .map(|s| Text::new(s).into())
                     .collect()
-            )
+            ).push_maybe(downloaded)
         );
 
         let msg = match item {
@@ -298,19 +838,123 @@ impl SearchResultsPage {
             _ => unreachable!()
         };
 
-        Button::new(row)
+        let button = Button::new(row)
             .width(Length::Fill)
-            .on_press(msg.into())
-            .into()
+            .on_press(msg.into());
+
+        match item {
+            SearchResult::Video(video) => {
+                use super::ConditionalMessage;
+
+                let queued = instance.queue().contains(&video.id);
+
+                row![
+                    button,
+                    Button::new(Text::new(if queued { "Queued" } else { "Add to Queue" }).center())
+                        .width(120)
+                        .on_press_maybe(
+                            SearchResultsMessage::AddToQueue(video.id.clone(), video.title.clone())
+                                .on_condition(!queued)
+                        )
+                ]
+                .push_maybe(super::add_to_playlist_picker(instance, &video.id, &video.title))
+                .push_maybe(super::play_local_button(instance, &video.id))
+                .push(super::watched_button(instance, &video.id))
+                .spacing(10)
+                .align_y(iced::Alignment::Center)
+                .into()
+            },
+
+            SearchResult::Channel(ch) => {
+                // Avatars are listed from smallest to largest, prefer the medium size used elsewhere.
+                let channel = SubscribedChannel {
+                    id: ch.id.clone(),
+                    name: ch.name.clone(),
+                    thumbnail: ch.thumbnails.get(4).or(ch.thumbnails.last()).map(|thumbnail| thumbnail.url.clone())
+                };
+
+                row![button, subscribe_button(channel, instance.archive())]
+                    .spacing(10)
+                    .align_y(iced::Alignment::Center)
+                    .into()
+            },
+            _ => button.into()
+        }
     }
 }
 
-// Move to video info page with the given video.
+fn subscribe_button<'a>(channel: SubscribedChannel, archive: &Archive) -> Button<'a, Msg> {
+    let text = if archive.is_subscribed(&channel.id) {
+        "Unsubscribe"
+    } else {
+        "Subscribe"
+    };
+
+    Button::new(Text::new(text).center())
+        .width(100)
+        .on_press(SearchResultsMessage::ToggleSubscription(channel).into())
+}
+
+fn favorite_button<'a>(channel: SubscribedChannel, archive: &Archive) -> Button<'a, Msg> {
+    let text = if archive.is_favorite(&channel.id) {
+        "Unfavorite"
+    } else {
+        "Favorite"
+    };
+
+    let favorite = Favorite {
+        id: channel.id,
+        kind: FavoriteKind::Channel,
+        title: channel.name,
+        thumbnail: channel.thumbnail
+    };
+
+    Button::new(Text::new(text).center())
+        .width(100)
+        .on_press(SearchResultsMessage::ToggleFavorite(favorite).into())
+}
+
+// Lets the channel be played in restricted mode, or takes that away.
+fn allow_button<'a>(channel: SubscribedChannel, archive: &Archive) -> Button<'a, Msg> {
+    let text = if archive.is_allowed(&channel.id) {
+        "Disallow in Restricted Mode"
+    } else {
+        "Allow in Restricted Mode"
+    };
+
+    Button::new(Text::new(text).center())
+        .on_press(SearchResultsMessage::ToggleAllowed(channel).into())
+}
+
+// Seconds since the given unix timestamp.
+fn seconds_since(timestamp: u64) -> u64 {
+    (chrono::Utc::now().timestamp() as u64).saturating_sub(timestamp)
+}
+
+// Messages that belong to the video info page in the detail pane.
+fn is_detail_message(message: &Msg) -> bool {
+    matches!(
+        message,
+        Msg::VideoInfo(_) |
+        Msg::SetDownloadFormat(_) |
+        Msg::SetDownloadQuality(_) |
+        Msg::SetDownloadSubtitles(_) |
+        Msg::StartVideoDownload |
+        Msg::CopyDownloadCommand
+    )
+}
+
+// Banners are cached alongside thumbnails, so they get their own key.
+fn banner_id(channel_id: &str) -> String {
+    format!("{}_banner", channel_id)
+}
+
+// Move to video info page with the given video. The search result is shown while the full details load.
 fn go_to_video(video: CommonVideo) -> (Task<Msg>, Navigation) {
-    use super::video_info_page::VideoInfoPage;
+    use super::video_info_page::{VideoInfoMessage, VideoInfoPage};
 
     (
-        Task::none(),
+        Task::done(VideoInfoMessage::LoadVideo(video.id.clone()).into()),
         Navigation::GoTo(Box::new(VideoInfoPage::new_with_video(video)))
     )
 }
@@ -335,4 +979,119 @@ fn go_to_playlist_videos(id: String) -> (Task<Msg>, Navigation) {
             Box::new(PlaylistInfoPage::new())
         )
     )
-}
\ No newline at end of file
+}
+
+// Get one page of results, from the channel's uploads or a search.
+async fn fetch_results(
+    fetcher: &impl VideoFetcher,
+    query: &str,
+    search_type: SearchType,
+    page_number: usize,
+    continuation: Option<&str>
+) -> Result<SearchResults, PomeloError> {
+    if let SearchType::ChannelUploads = search_type {
+        return fetcher.get_channel_videos(query, continuation).await
+            .map(SearchResults::ChannelUploads)
+            .map_err(PomeloError::new);
+    }
+
+    match fetcher.search(query, search_type, page_number).await {
+        Ok(search) => match search_type {
+            SearchType::Video => Ok(SearchResults::Videos(search)),
+            SearchType::Channel => Ok(SearchResults::Channels(search)),
+            SearchType::Playlist => Ok(SearchResults::Playlists(search)),
+            _ => unreachable!()
+        },
+        Err(e) => Err(PomeloError::new(e))
+    }
+}
+
+// Whether an error looks like the instance rejecting a continuation token, rather than a network problem.
+// Errors only come as text, so this goes by what's in the message.
+fn is_expired_continuation(e: &PomeloError) -> bool {
+    let message = e.error.to_lowercase();
+    message.contains("400") || message.contains("bad request") || message.contains("continuation")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::instance::archive::Archive;
+    use crate::app::instance::settings::PomeloSettings;
+    use crate::yt_fetch::fixtures::{FixtureFetcher, DEMO_FOLDER};
+
+    use super::{fetch_results, PomeloInstance, PomeloPage, SearchResultsMessage, SearchResultsPage, SearchType};
+
+    // Load the page's current page of results from the demo fixtures.
+    fn load(page: &mut SearchResultsPage, instance: &mut PomeloInstance, runtime: &tokio::runtime::Runtime) {
+        let continuation = page.continuation.get(&page.page_number).cloned();
+
+        let result = runtime.block_on(fetch_results(
+            &FixtureFetcher::new(DEMO_FOLDER),
+            &page.query,
+            page.search_type,
+            page.page_number,
+            continuation.as_deref()
+        ));
+
+        let _ = page.update(instance, SearchResultsMessage::SearchComplete(result).into());
+    }
+
+    fn result_count(page: &SearchResultsPage) -> usize {
+        page.search_results.as_ref().map_or(0, |results| results.get_results().len())
+    }
+
+    #[test]
+    fn test_search_pages() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let mut instance = PomeloInstance::with_archive(PomeloSettings::default(), Archive::in_memory());
+        let mut page = SearchResultsPage::new(String::from("pomelo"), SearchType::Video);
+
+        load(&mut page, &mut instance, &runtime);
+        assert!(page.error.is_none());
+        assert_eq!(result_count(&page), 5);
+
+        // The fixtures only have one page.
+        page.page_number = 2;
+        load(&mut page, &mut instance, &runtime);
+        assert!(page.error.is_none());
+        assert_eq!(result_count(&page), 0);
+    }
+
+    #[test]
+    fn test_channel_upload_pages() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let mut instance = PomeloInstance::with_archive(PomeloSettings::default(), Archive::in_memory());
+        let mut page = SearchResultsPage::new(String::from("UCpomeloDemoChannel0000001"), SearchType::ChannelUploads);
+
+        load(&mut page, &mut instance, &runtime);
+        assert_eq!(result_count(&page), 3);
+        assert!(page.continuation.contains_key(&2));
+
+        // The second page ends the uploads instead of handing out the same continuation again.
+        page.page_number = 2;
+        load(&mut page, &mut instance, &runtime);
+        assert!(page.error.is_none());
+        assert_eq!(result_count(&page), 0);
+        assert!(!page.continuation.contains_key(&3));
+    }
+
+    #[test]
+    fn test_channel_upload_errors() {
+        let mut instance = PomeloInstance::with_archive(PomeloSettings::default(), Archive::in_memory());
+        let mut page = SearchResultsPage::new(String::from("UCpomeloDemoChannel0000001"), SearchType::ChannelUploads);
+
+        // Network errors are shown, since going back to the first page wouldn't help.
+        page.page_number = 3;
+        let _ = page.update(&mut instance, SearchResultsMessage::SearchComplete(Err("error sending request: connection refused".into())).into());
+        assert!(page.error.is_some());
+        assert_eq!(page.page_number, 3);
+        assert!(page.recover_to.is_none());
+
+        // A rejected continuation starts over from the first page, to get new tokens.
+        page.error = None;
+        let _ = page.update(&mut instance, SearchResultsMessage::SearchComplete(Err("HTTP status client error (400 Bad Request)".into())).into());
+        assert!(page.error.is_none());
+        assert_eq!(page.page_number, 1);
+        assert_eq!(page.recover_to, Some(3));
+    }
+}
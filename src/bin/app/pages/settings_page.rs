@@ -3,10 +3,29 @@ use std::fmt::Display;
 use iced::Task;
 use iced::widget::Text;
 
-use crate::INVID_INSTANCES;
-use crate::app::PomeloInstance;
+use crate::yt_fetch::instances;
+use crate::app::{PomeloError, PomeloInstance};
+use crate::app::instance::downloads::QueuePolicy;
+use crate::app::instance::settings::{PomeloSettings, Pin, Shortcut, SponsorCategory, StreamQuality, KEY_NAMES};
+use crate::utils::Locale;
 
-use super::{PomeloPage, Navigation, Msg};
+use super::{ErrorBanner, PomeloPage, Navigation, Msg};
+
+const PARALLEL_DOWNLOAD_OPTIONS: [usize; 4] = [1, 2, 3, 4];
+
+// Thumbnail ages to pick from, in minutes.
+const THUMBNAIL_AGE_OPTIONS: [u64; 4] = [15, 60, 240, 1440];
+
+// Choices for how long Invidious responses are reused, in minutes. Zero turns the cache off.
+const RESPONSE_CACHE_OPTIONS: [u64; 5] = [0, 1, 5, 15, 60];
+
+// Choices for the most space saved thumbnails can take up, in megabytes.
+const THUMBNAIL_CACHE_OPTIONS: [u64; 5] = [50, 100, 200, 500, 1000];
+
+// Choices for log limits: size of each file and of all of them together in megabytes, and how many days they're kept.
+const LOG_FILE_SIZE_OPTIONS: [u64; 4] = [1, 5, 10, 50];
+const LOG_TOTAL_SIZE_OPTIONS: [u64; 4] = [10, 50, 200, 1000];
+const LOG_AGE_OPTIONS: [u64; 4] = [7, 30, 90, 365];
 
 // Wrapper for usize, used as an index to the list of Invidious instances.
 #[derive(PartialEq, Eq, Clone)]
@@ -22,8 +41,10 @@ impl InstanceIndex {
 
 impl Display for InstanceIndex {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let instance = INVID_INSTANCES[self.n];
-        write!(f, "{} ({})", instance.0, instance.1)
+        match instances::all().get(self.n) {
+            Some(instance) => write!(f, "{}", instance),
+            None => write!(f, "{}", instances::url(self.n))
+        }
     }
 }
 
@@ -33,21 +54,87 @@ pub (crate) enum SettingsMessage {
     YtUseNightly(bool),
     SetDownloadFolder(String),
     VideoSkipOnError(bool),
-    OpenFolderPicker
+    PitchCorrection(bool),
+    AutoplayRelated(bool),
+    RecordWatchHistory(bool),
+    RecordSearchHistory(bool),
+    NowPlayingPopup(bool),
+    Heatmap(bool),
+    OpenFolderPicker,
+    SetTheme(String),
+    OpenDiagnostics,
+    OpenRestrictedMode,
+    SetYtConfigFile(Option<String>),
+    OpenConfigPicker,
+    YtIgnoreConfig(bool),
+    AskDownloadName(bool),
+    DownloadSound(bool),
+    SetDownloadSoundFile(Option<String>),
+    OpenSoundPicker,
+    SetParallelDownloads(usize),
+    SetQueuePolicy(QueuePolicy),
+    ExportFeeds,
+    SetStreamQuality(StreamQuality),
+    SponsorBlock(bool),
+    SponsorBlockCategory(SponsorCategory, bool),
+    TwoPaneLayout(bool),
+    InvidiousRace(bool),
+    ProxyImages(bool),
+    SetResponseCacheTtl(u64),
+    ResponseCacheDisk(bool),
+    ClearResponseCache,
+    OfflineDemo(bool),
+    CustomInstanceInput(String),
+    TestCustomInstance,
+    CustomInstanceTested(String, Result<String, PomeloError>),
+    AddCustomInstance,
+    RemoveCustomInstance(String),
+    SetLogFileSize(u64),
+    SetLogTotalSize(u64),
+    SetLogMaxAge(u64),
+    SetThumbnailMaxAge(u64),
+    SetThumbnailCacheSize(u64),
+    ClearThumbnailCache,
+    DataSaver(bool),
+    ReducedMotion(bool),
+    HighContrast(bool),
+    SetLocale(Locale),
+    SetShortcutKey(Shortcut, &'static str)
 }
 
+impl super::ConditionalMessage for SettingsMessage {}
+
 impl From<SettingsMessage> for Msg {
     fn from(value: SettingsMessage) -> Self {
         Msg::Settings(value)
     }
 }
 
+// Name shown in the theme picker for Iced's built-in theme.
+const DEFAULT_THEME: &str = "Default";
+
 // Page that allows users to modify Pomelo settings.
-pub (crate) struct SettingsPage;
+pub (crate) struct SettingsPage {
+    themes: Vec<String>,
+
+    // Result of the last feed export.
+    export_status: Option<String>,
+
+    // Url typed in for a custom instance, and the result of testing it.
+    custom_instance: String,
+    instance_test: Option<Result<String, PomeloError>>,
+
+    error: Option<ErrorBanner>
+}
 
 impl SettingsPage {
     pub (crate) fn new() -> Self {
-        Self {}
+        use crate::app::instance::theme::available_themes;
+
+        let mut themes = vec![String::from(DEFAULT_THEME)];
+        themes.extend(available_themes());
+
+        Self { themes, export_status: None, custom_instance: String::new(), instance_test: None, error: None }
     }
 }
 
@@ -60,6 +147,11 @@ impl PomeloPage for SettingsPage {
             (Task::none(), Navigation::Back)
         }
 
+        else if let Msg::DismissError = message {
+            self.error = None;
+            (Task::none(), Navigation::None)
+        }
+
         else if let Msg::Settings(msg) = message {
             match msg {
                 SettingsMessage::InvidiousSetInstance(index) 
@@ -74,9 +166,192 @@ impl PomeloPage for SettingsPage {
                 SettingsMessage::VideoSkipOnError(checked) 
                     => settings.set_video_skip_on_error(checked),
 
+                SettingsMessage::PitchCorrection(checked)
+                    => settings.set_pitch_correction(checked),
+
+                SettingsMessage::AutoplayRelated(checked)
+                    => settings.set_autoplay_related(checked),
+
+                SettingsMessage::RecordWatchHistory(checked)
+                    => settings.set_record_watch_history(checked),
+
+                SettingsMessage::RecordSearchHistory(checked)
+                    => settings.set_record_search_history(checked),
+
+                SettingsMessage::NowPlayingPopup(checked)
+                    => settings.set_now_playing_popup(checked),
+
+                SettingsMessage::Heatmap(checked)
+                    => settings.set_heatmap(checked),
+
+                SettingsMessage::SetStreamQuality(quality)
+                    => settings.set_stream_quality(quality),
+
+                SettingsMessage::SponsorBlock(checked)
+                    => settings.set_sponsorblock(checked),
+
+                SettingsMessage::SponsorBlockCategory(category, checked)
+                    => settings.set_sponsorblock_category(category, checked),
+
+                SettingsMessage::TwoPaneLayout(checked)
+                    => settings.set_two_pane_layout(checked),
+
+                SettingsMessage::InvidiousRace(checked)
+                    => settings.set_invidious_race(checked),
+
+                SettingsMessage::ProxyImages(checked)
+                    => settings.set_proxy_images(checked),
+
+                SettingsMessage::SetResponseCacheTtl(minutes) => {
+                    let persist = settings.response_cache_disk();
+                    instance.set_response_cache(minutes, persist);
+                },
+
+                SettingsMessage::ResponseCacheDisk(checked) => {
+                    let minutes = settings.response_cache_ttl_minutes();
+                    instance.set_response_cache(minutes, checked);
+                },
+
+                SettingsMessage::ClearResponseCache
+                    => crate::yt_fetch::response_cache::clear(),
+
+                SettingsMessage::OfflineDemo(checked)
+                    => instance.set_offline_demo(checked),
+
+                SettingsMessage::CustomInstanceInput(text) => {
+                    self.custom_instance = text;
+                    self.instance_test = None;
+                },
+
+                SettingsMessage::TestCustomInstance => {
+                    let url = crate::yt_fetch::instances::normalize_url(&self.custom_instance);
+
+                    self.instance_test = None;
+
+                    return (
+                        Task::perform(
+                            async move {
+                                let result = crate::yt_fetch::instances::test_instance(&url).await.map_err(PomeloError::new);
+                                (url, result)
+                            },
+                            |(url, result)| SettingsMessage::CustomInstanceTested(url, result).into()
+                        ),
+                        Navigation::None
+                    );
+                },
+
+                // Results for a url that has since been changed are ignored.
+                SettingsMessage::CustomInstanceTested(url, result) => {
+                    if url == crate::yt_fetch::instances::normalize_url(&self.custom_instance) {
+                        self.instance_test = Some(result);
+                    }
+                },
+
+                SettingsMessage::AddCustomInstance => {
+                    let url = crate::yt_fetch::instances::normalize_url(&self.custom_instance);
+
+                    instance.add_custom_instance(url);
+                    self.custom_instance.clear();
+                    self.instance_test = None;
+                },
+
+                SettingsMessage::RemoveCustomInstance(url)
+                    => instance.remove_custom_instance(&url),
+
+                SettingsMessage::SetLogFileSize(megabytes) => {
+                    let (_, total, age) = settings.log_limit_values();
+                    instance.set_log_limits(megabytes, total, age);
+                },
+
+                SettingsMessage::SetLogTotalSize(megabytes) => {
+                    let (file, _, age) = settings.log_limit_values();
+                    instance.set_log_limits(file, megabytes, age);
+                },
+
+                SettingsMessage::SetLogMaxAge(days) => {
+                    let (file, total, _) = settings.log_limit_values();
+                    instance.set_log_limits(file, total, days);
+                },
+
+                SettingsMessage::SetThumbnailMaxAge(minutes)
+                    => settings.set_thumbnail_max_age(minutes),
+
+                SettingsMessage::SetThumbnailCacheSize(megabytes)
+                    => instance.set_thumbnail_cache_size(megabytes),
+
+                SettingsMessage::ClearThumbnailCache
+                    => instance.cache_mut().clear(),
+
+                SettingsMessage::DataSaver(checked)
+                    => settings.set_data_saver(checked),
+
+                SettingsMessage::ReducedMotion(checked)
+                    => settings.set_reduced_motion(checked),
+
+                SettingsMessage::HighContrast(checked)
+                    => settings.set_high_contrast(checked),
+
+                SettingsMessage::SetLocale(locale)
+                    => settings.set_locale(locale),
+
+                SettingsMessage::SetShortcutKey(shortcut, key)
+                    => settings.set_shortcut_key(shortcut, key),
+
+                SettingsMessage::SetYtConfigFile(path)
+                    => settings.set_yt_dlp_config_file(path),
+
+                SettingsMessage::YtIgnoreConfig(checked)
+                    => settings.set_yt_dlp_ignore_config(checked),
+
+                SettingsMessage::AskDownloadName(checked)
+                    => settings.set_ask_download_name(checked),
+
+                SettingsMessage::DownloadSound(checked)
+                    => settings.set_download_sound(checked),
+
+                SettingsMessage::SetDownloadSoundFile(path)
+                    => settings.set_download_sound_file(path),
+
+                SettingsMessage::SetParallelDownloads(count)
+                    => settings.set_parallel_downloads(count),
+
+                SettingsMessage::SetQueuePolicy(policy)
+                    => settings.set_queue_policy(policy),
+
+                SettingsMessage::OpenSoundPicker => return (
+                    open_sound_picker(),
+                    Navigation::None
+                ),
+
+                SettingsMessage::ExportFeeds
+                    => self.export_status = export_feeds(settings),
+
+                SettingsMessage::OpenConfigPicker => return (
+                    open_config_picker(),
+                    Navigation::None
+                ),
+
                 SettingsMessage::OpenFolderPicker => return (
                     open_folder_picker(instance.settings().download_folder()),
                     Navigation::None
+                ),
+
+                SettingsMessage::SetTheme(name) => {
+                    let retry: Msg = SettingsMessage::SetTheme(name.clone()).into();
+
+                    self.error = instance.set_theme(Some(name).filter(|name| name != DEFAULT_THEME))
+                        .err()
+                        .map(|e| ErrorBanner::new(e, Some(retry)));
+                },
+
+                SettingsMessage::OpenDiagnostics => return (
+                    Task::none(),
+                    Navigation::GoTo(Box::new(super::diagnostics_page::DiagnosticsPage::new()))
+                ),
+
+                SettingsMessage::OpenRestrictedMode => return (
+                    Task::none(),
+                    Navigation::GoTo(Box::new(super::restricted_mode_page::RestrictedModePage::new()))
                 )
             }
 
@@ -89,10 +364,25 @@ impl PomeloPage for SettingsPage {
     }
 
     fn view(&self, instance: &PomeloInstance) -> iced::Element<Msg> {
-        use iced::widget::{column, row, PickList, Button, Checkbox, TextInput};
+        use iced::Length;
+        use iced::widget::{column, row, PickList, Button, Checkbox, Column, Scrollable, TextInput};
+        use crate::utils::format_bytes;
         use super::FillElement;
 
-        column![
+        let tip_style = TooltipStyle {
+            radius: instance.theme().map_or(10.0, |theme| theme.radius()),
+            high_contrast: instance.settings().high_contrast()
+        };
+
+        let current_theme = instance.theme()
+            .map_or(String::from(DEFAULT_THEME), |theme| String::from(theme.name()));
+
+        let (log_file_size, log_total_size, log_max_age) = instance.settings().log_limit_values();
+
+        let selected_instance = instances::url(instance.settings().invidious_index());
+        let has_custom_url = !self.custom_instance.trim().is_empty();
+
+        let content = column![
 
             // Invidious options
             column![
@@ -100,19 +390,157 @@ impl PomeloPage for SettingsPage {
 
                 row![
                     tooltip_with_background(
+                        tip_style,
                         "Instance",
                         "3rd-party Youtube API used for searching.\n\
                         Try changing this if searching doesn't work."
                     ),
 
                     PickList::new(
-                        (0..INVID_INSTANCES.len())
+                        (0..instances::count())
                             .map(InstanceIndex::new)
                             .collect::<Vec<_>>(),
                         Some(InstanceIndex::new(instance.settings().invidious_index())),
                         |index| SettingsMessage::InvidiousSetInstance(index.n).into()
                     )
-                ].spacing(10)
+                ]
+                .push_maybe(
+                    instance.settings().custom_instances().contains(&selected_instance).then(||
+                        Button::new(Text::new("Remove").center())
+                            .on_press(SettingsMessage::RemoveCustomInstance(selected_instance.clone()).into())
+                    )
+                )
+                .spacing(10).align_y(iced::Alignment::Center),
+
+                // The result of the last connection test is shown under the url.
+                column![
+                    row![
+                        tooltip_with_background(
+                            tip_style,
+                            "Custom instance",
+                            "Add a self-hosted or unlisted instance, e.g. https://invidious.example.com.\n\
+                            It's listed after the official instances, and selected once added."
+                        ),
+
+                        TextInput::new("Instance url", &self.custom_instance)
+                            .on_input(|text| SettingsMessage::CustomInstanceInput(text).into())
+                            .width(250),
+
+                        Button::new(Text::new("Test connection").center())
+                            .on_press_maybe(SettingsMessage::TestCustomInstance.on_condition(has_custom_url)),
+
+                        Button::new(Text::new("Add").center())
+                            .on_press_maybe(SettingsMessage::AddCustomInstance.on_condition(has_custom_url))
+                    ].spacing(10).align_y(iced::Alignment::Center)
+                ]
+                .push_maybe(self.instance_test.as_ref().map(|result| match result {
+                    Ok(version) => Text::new(format!("Connected, running {}", version)).size(12),
+                    Err(e) => Text::new(format!("Connection failed: {}", e.error)).size(12).style(iced::widget::text::danger)
+                }))
+                .spacing(5).align_x(iced::Alignment::Center),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Race instances",
+                        "Ask the selected instance and the next ones in the list for video info at the same time,\n\
+                        and use whichever answers first. Helps when instances are slow or unreliable."
+                    ),
+
+                    Checkbox::new("", instance.settings().invidious_race())
+                        .on_toggle(|checked| SettingsMessage::InvidiousRace(checked).into())
+                ].spacing(10),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Reuse responses for (minutes)",
+                        "Searches, channels, and playlists opened again within this time are shown right away,\n\
+                        without asking the instance again. Set to 0 to always ask."
+                    ),
+
+                    PickList::new(
+                        RESPONSE_CACHE_OPTIONS,
+                        Some(instance.settings().response_cache_ttl_minutes()),
+                        |minutes| SettingsMessage::SetResponseCacheTtl(minutes).into()
+                    ),
+
+                    Button::new(Text::new("Clear").center())
+                        .on_press(SettingsMessage::ClearResponseCache.into())
+                ].spacing(10).align_y(iced::Alignment::Center),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Keep responses between runs",
+                        "Save reused responses to disk, so they're still there after Pomelo is restarted."
+                    ),
+
+                    Checkbox::new("", instance.settings().response_cache_disk())
+                        .on_toggle_maybe(
+                            (instance.settings().response_cache_ttl_minutes() > 0)
+                                .then_some(|checked| Msg::from(SettingsMessage::ResponseCacheDisk(checked)))
+                        )
+                ].spacing(10),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Offline demo mode",
+                        "Show saved responses from the fixtures folder instead of asking an instance.\n\
+                        Thumbnails and playback still need a connection."
+                    ),
+
+                    Checkbox::new("", instance.settings().offline_demo())
+                        .on_toggle(|checked| SettingsMessage::OfflineDemo(checked).into())
+                ].spacing(10),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Load images through instance",
+                        "Load thumbnails and channel pictures through the selected instance,\n\
+                        so Youtube doesn't see the requests. Images may load slower."
+                    ),
+
+                    Checkbox::new("", instance.settings().proxy_images())
+                        .on_toggle(|checked| SettingsMessage::ProxyImages(checked).into())
+                ].spacing(10),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Refresh thumbnails after (minutes)",
+                        "Thumbnails and channel pictures older than this are downloaded again\n\
+                        the next time they're shown. The old image is kept until the new one arrives."
+                    ),
+
+                    PickList::new(
+                        THUMBNAIL_AGE_OPTIONS,
+                        Some(instance.settings().thumbnail_max_age_minutes()),
+                        |minutes| SettingsMessage::SetThumbnailMaxAge(minutes).into()
+                    )
+                ].spacing(10),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Thumbnail cache size (MB)",
+                        "Thumbnails are saved to disk so they don't need to be downloaded again next time.\n\
+                        Once the cache is full, the ones that haven't been shown for the longest are deleted."
+                    ),
+
+                    PickList::new(
+                        THUMBNAIL_CACHE_OPTIONS,
+                        Some(instance.settings().thumbnail_cache_size_mb()),
+                        |megabytes| SettingsMessage::SetThumbnailCacheSize(megabytes).into()
+                    ),
+
+                    Button::new(Text::new("Clear cache").center())
+                        .on_press(SettingsMessage::ClearThumbnailCache.into())
+                ].spacing(10).align_y(iced::Alignment::Center),
+
+                Text::new(format!("Using {}", format_bytes(instance.cache().disk_size()))).size(12)
             ].spacing(10).align_x(iced::Alignment::Center),
 
             // Yt-dlp options
@@ -121,6 +549,7 @@ impl PomeloPage for SettingsPage {
 
                 row![
                     tooltip_with_background(
+                        tip_style,
                         "Use nightly build",
                         "Use the latest nightly release of yt-dlp, instead of the stable one.\n\
                         Try changing this if downloads don't work or stop working."
@@ -130,6 +559,112 @@ impl PomeloPage for SettingsPage {
                         .on_toggle(|checked| SettingsMessage::YtUseNightly(checked).into())
                 ].spacing(10),
 
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Config file",
+                        "Yt-dlp config file to use for downloads.\n\
+                        If none is set, yt-dlp uses your own config file, if you have one."
+                    ),
+                    TextInput::new("None", instance.settings().yt_dlp_config_file().unwrap_or_default()).width(350),
+                    Button::new(Text::new("Change").center())
+                        .width(100)
+                        .on_press(SettingsMessage::OpenConfigPicker.into()),
+                    Button::new(Text::new("Clear").center())
+                        .width(100)
+                        .on_press_maybe(
+                            instance.settings().yt_dlp_config_file()
+                                .map(|_| SettingsMessage::SetYtConfigFile(None).into())
+                        )
+                ].spacing(10),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Ignore user config",
+                        "Don't let yt-dlp read your own config file when no config file is set above."
+                    ),
+
+                    Checkbox::new("", instance.settings().yt_dlp_ignore_config())
+                        .on_toggle_maybe(
+                            instance.settings().yt_dlp_config_file().is_none()
+                                .then_some(|checked| Msg::from(SettingsMessage::YtIgnoreConfig(checked)))
+                        )
+                ].spacing(10),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Ask for file name",
+                        "Edit the file name and folder before downloading a single video.\n\
+                        Playlists still use the usual names."
+                    ),
+
+                    Checkbox::new("", instance.settings().ask_download_name())
+                        .on_toggle(|checked| SettingsMessage::AskDownloadName(checked).into())
+                ].spacing(10),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Sound when finished",
+                        "Play a sound when a download finishes or fails."
+                    ),
+
+                    Checkbox::new("", instance.settings().download_sound())
+                        .on_toggle(|checked| SettingsMessage::DownloadSound(checked).into())
+                ].spacing(10),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Sound file",
+                        "Sound to play instead of the built-in tones (WAV, MP3, OGG, or FLAC)."
+                    ),
+                    TextInput::new("Built-in", instance.settings().download_sound_file().unwrap_or_default()).width(350),
+                    Button::new(Text::new("Change").center())
+                        .width(100)
+                        .on_press_maybe(
+                            instance.settings().download_sound()
+                                .then(|| SettingsMessage::OpenSoundPicker.into())
+                        ),
+                    Button::new(Text::new("Clear").center())
+                        .width(100)
+                        .on_press_maybe(
+                            instance.settings().download_sound_file()
+                                .map(|_| SettingsMessage::SetDownloadSoundFile(None).into())
+                        )
+                ].spacing(10),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Parallel downloads",
+                        "How many downloads can run at once. Other downloads wait in the queue."
+                    ),
+
+                    PickList::new(
+                        PARALLEL_DOWNLOAD_OPTIONS,
+                        Some(instance.settings().parallel_downloads()),
+                        |count| SettingsMessage::SetParallelDownloads(count).into()
+                    )
+                ].spacing(10),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Download order",
+                        "Which queued download starts next. Shortest first lets quick downloads skip ahead of long ones,\n\
+                        and manual lets you reorder the queue on the Downloads page."
+                    ),
+
+                    PickList::new(
+                        QueuePolicy::ALL,
+                        Some(instance.settings().queue_policy()),
+                        |policy| SettingsMessage::SetQueuePolicy(policy).into()
+                    )
+                ].spacing(10),
+
                 row![
                     Text::new("Download Folder"),
                     TextInput::new("", instance.settings().download_folder()).width(350),
@@ -149,14 +684,350 @@ impl PomeloPage for SettingsPage {
                     Checkbox::new("", instance.settings().video_skip_on_error())
                         .on_toggle(|checked| SettingsMessage::VideoSkipOnError(checked).into()),
 
+                ].spacing(10),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Pitch correction",
+                        "Keep voices sounding normal when the playback speed is changed.\n\
+                        Turn off to speed up or slow down the sound along with its pitch, like a record.\n\
+                        Applies from the next video."
+                    ),
+
+                    Checkbox::new("", instance.settings().pitch_correction())
+                        .on_toggle(|checked| SettingsMessage::PitchCorrection(checked).into())
+                ].spacing(10),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Autoplay recommendations",
+                        "When the last video in the queue ends, keep going with the video Youtube recommends next."
+                    ),
+
+                    Checkbox::new("", instance.settings().autoplay_related())
+                        .on_toggle(|checked| SettingsMessage::AutoplayRelated(checked).into())
+                ].spacing(10),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Watch history",
+                        "Remember played videos and where they were stopped, so they can be resumed later."
+                    ),
+
+                    Checkbox::new("", instance.settings().record_watch_history())
+                        .on_toggle(|checked| SettingsMessage::RecordWatchHistory(checked).into())
+                ].spacing(10),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Search history",
+                        "Remember submitted searches, so they can be picked again from the search page."
+                    ),
+
+                    Checkbox::new("", instance.settings().record_search_history())
+                        .on_toggle(|checked| SettingsMessage::RecordSearchHistory(checked).into())
+                ].spacing(10),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Now playing popup",
+                        "Show the title, channel, and thumbnail of the next video\n\
+                        over the player for a few seconds when it starts."
+                    ),
+
+                    Checkbox::new("", instance.settings().now_playing_popup())
+                        .on_toggle(|checked| SettingsMessage::NowPlayingPopup(checked).into())
+                ].spacing(10),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Most replayed",
+                        "Show how often each part of a Youtube video is replayed above the seek bar.\n\
+                        Click a bar to jump there. Not loaded with the data saver on."
+                    ),
+
+                    Checkbox::new("", instance.settings().heatmap())
+                        .on_toggle(|checked| SettingsMessage::Heatmap(checked).into())
+                ].spacing(10),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Data saver",
+                        "For metered connections. Streams play at 360p, thumbnails are only\n\
+                        loaded for the first few results of a list, and at a smaller size."
+                    ),
+
+                    Checkbox::new("", instance.settings().data_saver())
+                        .on_toggle(|checked| SettingsMessage::DataSaver(checked).into())
+                ].spacing(10),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Stream quality",
+                        "Auto picks the stream that best fits the window,\n\
+                        and switches streams when the window is resized.\n\
+                        A resolution picked in the player is kept for later videos."
+                    )
+                ]
+                .push(
+                    if instance.settings().data_saver() {
+                        iced::Element::from(Text::new(format!("{} (data saver)", instance.settings().stream_quality())))
+                    }
+                    else {
+                        PickList::new(
+                            StreamQuality::ALL,
+                            Some(instance.settings().stream_quality()),
+                            |quality| SettingsMessage::SetStreamQuality(quality).into()
+                        ).into()
+                    }
+                )
+                .spacing(10).align_y(iced::Alignment::Center)
+            ].spacing(10).align_x(iced::Alignment::Center),
+
+            // SponsorBlock options
+            column![
+                header("SponsorBlock"),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Skip segments",
+                        "Skip parts of Youtube videos that SponsorBlock users have marked,\n\
+                        like sponsor reads and intros. Downloaded videos aren't affected."
+                    ),
+
+                    Checkbox::new("", instance.settings().sponsorblock())
+                        .on_toggle(|checked| SettingsMessage::SponsorBlock(checked).into())
+                ].spacing(10)
+            ]
+            .extend(
+                SponsorCategory::ALL.into_iter().map(|category| {
+                    Checkbox::new(
+                        category.to_string(),
+                        instance.settings().sponsorblock_categories().contains(&category)
+                    )
+                    .on_toggle_maybe(
+                        instance.settings().sponsorblock()
+                            .then_some(move |checked| Msg::from(SettingsMessage::SponsorBlockCategory(category, checked)))
+                    )
+                    .into()
+                })
+            )
+            .spacing(10).align_x(iced::Alignment::Center),
+
+            // Appearance options
+            column![
+                header("Appearance"),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Theme",
+                        "Themes are loaded from JSON files in the \"themes\" folder.\n\
+                        Changes to the selected theme's file are applied automatically."
+                    ),
+
+                    PickList::new(
+                        self.themes.as_slice(),
+                        Some(current_theme),
+                        |name| SettingsMessage::SetTheme(name).into()
+                    )
+                ].spacing(10),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Two-pane layout",
+                        "In wide windows, show the selected video next to the search results\n\
+                        instead of opening it on its own page."
+                    ),
+
+                    Checkbox::new("", instance.settings().two_pane_layout())
+                        .on_toggle(|checked| SettingsMessage::TwoPaneLayout(checked).into())
+                ].spacing(10).align_y(iced::Alignment::Center),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Reduce motion",
+                        "Keep the player controls on screen in fullscreen\n\
+                        instead of hiding them while the mouse is still."
+                    ),
+
+                    Checkbox::new("", instance.settings().reduced_motion())
+                        .on_toggle(|checked| SettingsMessage::ReducedMotion(checked).into())
+                ].spacing(10).align_y(iced::Alignment::Center),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "High contrast",
+                        "White on black colors with bigger buttons, sliders, and tooltips.\n\
+                        Used instead of the selected theme."
+                    ),
+
+                    Checkbox::new("", instance.settings().high_contrast())
+                        .on_toggle(|checked| SettingsMessage::HighContrast(checked).into())
+                ].spacing(10).align_y(iced::Alignment::Center),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Number and date format",
+                        "Used for view counts, subscriber counts, and dates."
+                    ),
+
+                    PickList::new(
+                        Locale::ALL,
+                        Some(instance.settings().locale()),
+                        |locale| SettingsMessage::SetLocale(locale).into()
+                    )
+                ].spacing(10).align_y(iced::Alignment::Center)
+            ].spacing(10).align_x(iced::Alignment::Center),
+
+            // Keyboard shortcuts
+            column![
+                header("Keyboard"),
+
+                tooltip_with_background(
+                    tip_style,
+                    "Shortcuts",
+                    "Shortcuts don't work while typing in a text box.\n\
+                    Player shortcuts only work while a video is playing."
+                )
+            ]
+            .extend(
+                Shortcut::ALL.into_iter().map(|shortcut| {
+                    let key = instance.settings().shortcut_key(shortcut);
+
+                    row![
+                        Text::new(shortcut.to_string()).width(150),
+
+                        PickList::new(
+                            KEY_NAMES,
+                            KEY_NAMES.iter().copied().find(|name| *name == key),
+                            move |key| SettingsMessage::SetShortcutKey(shortcut, key).into()
+                        ).width(120)
+                    ].spacing(10).align_y(iced::Alignment::Center).into()
+                })
+            )
+            .spacing(10).align_x(iced::Alignment::Center),
+
+            // Feeds
+            column![
+                header("Feeds"),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Pinned channels",
+                        "Save the RSS feeds of the channels pinned to the main menu as an OPML file,\n\
+                        which most feed readers can import."
+                    ),
+
+                    Button::new(Text::new("Export OPML").center())
+                        .width(150)
+                        .on_press_maybe(
+                            instance.settings().pins().iter()
+                                .any(|pin| matches!(pin, Pin::Channel { .. }))
+                                .then(|| SettingsMessage::ExportFeeds.into())
+                        )
+                ].spacing(10).align_y(iced::Alignment::Center)
+            ]
+            .push_maybe(self.export_status.as_deref().map(Text::new))
+            .spacing(10).align_x(iced::Alignment::Center),
+
+            // Data usage
+            column![
+                header("Data Usage"),
+
+                Text::new(
+                    format!(
+                        "Today: {}    This month: {}    Last month: {}",
+                        format_bytes(instance.usage().today()),
+                        format_bytes(instance.usage().this_month()),
+                        format_bytes(instance.usage().last_month())
+                    )
+                ),
+
+                super::bar_chart(&instance.usage().last_days(30), 60.0, 8.0),
+
+                Text::new("Downloads and streaming over the last 30 days").size(12)
+            ].spacing(10).align_x(iced::Alignment::Center),
+
+            // Logs
+            column![
+                header("Logs"),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Log file size (MB)",
+                        "A new log file is started once the current one reaches this size."
+                    ),
+
+                    PickList::new(
+                        LOG_FILE_SIZE_OPTIONS,
+                        Some(log_file_size),
+                        |megabytes| SettingsMessage::SetLogFileSize(megabytes).into()
+                    )
+                ].spacing(10),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Total log size (MB)",
+                        "The oldest logs are deleted once all of them together take up more than this."
+                    ),
+
+                    PickList::new(
+                        LOG_TOTAL_SIZE_OPTIONS,
+                        Some(log_total_size),
+                        |megabytes| SettingsMessage::SetLogTotalSize(megabytes).into()
+                    )
+                ].spacing(10),
+
+                row![
+                    tooltip_with_background(
+                        tip_style,
+                        "Keep logs for (days)",
+                        "Logs older than this are deleted."
+                    ),
+
+                    PickList::new(
+                        LOG_AGE_OPTIONS,
+                        Some(log_max_age),
+                        |days| SettingsMessage::SetLogMaxAge(days).into()
+                    )
                 ].spacing(10)
             ].spacing(10).align_x(iced::Alignment::Center),
 
+            Button::new(Text::new("Diagnostics").center())
+                .width(150)
+                .on_press(SettingsMessage::OpenDiagnostics.into()),
+
+            Button::new(Text::new("Restricted Mode").center())
+                .width(150)
+                .on_press(SettingsMessage::OpenRestrictedMode.into()),
+
             Button::new(Text::new("Back").center())
                 .width(100)
                 .on_press(Msg::Back)
 
-        ].spacing(25).align_x(iced::Alignment::Center).fill()
+        ].spacing(25).align_x(iced::Alignment::Center).padding(25);
+
+        Column::new()
+            .push_maybe(self.error.as_ref().map(|banner| banner.view()))
+            .push(Scrollable::new(content.width(Length::Fill)).height(Length::Fill))
+            .fill()
     }
 
     fn subscription(&self, _instance: &PomeloInstance) -> iced::Subscription<Msg> {
@@ -175,28 +1046,75 @@ fn header(text: &str) -> iced::Element<Msg> {
     ).size(24).into()
 }
 
-fn tooltip_with_background <'a> (text: &'a str, tip: &'a str) -> iced::Element<'a, Msg> {
+// Corner radius from the theme. High-contrast tooltips get a white border and bigger text.
+#[derive(Clone, Copy)]
+struct TooltipStyle {
+    radius: f32,
+    high_contrast: bool
+}
+
+fn tooltip_with_background <'a> (style: TooltipStyle, text: &'a str, tip: &'a str) -> iced::Element<'a, Msg> {
     use iced::widget::{Container, Tooltip};
     use iced::widget::container;
     use iced::widget::tooltip::Position;
 
+    let (border_color, text_size, padding) = match style.high_contrast {
+        true => (iced::Color::WHITE, 18, 15),
+        false => (iced::Color::BLACK, 16, 10)
+    };
+
     Tooltip::new(
-        Text::new(text),
-        Container::new(Text::new(tip)).style(
-            |e: &iced::Theme| container::Style {
+        Text::new(text).size(text_size),
+        Container::new(Text::new(tip).size(text_size)).style(
+            move |e: &iced::Theme| container::Style {
                 background: Some(iced::Background::Color(e.palette().primary)),
                 border: iced::Border {
-                    color: iced::Color::BLACK,
+                    color: border_color,
                     width: 2.5,
-                    radius: iced::border::Radius::new(10)
+                    radius: iced::border::Radius::new(style.radius)
                 },
                 ..Default::default()
             }
-        ).padding(10),
+        ).padding(padding),
         Position::default()
     ).into()
 }
 
+fn open_config_picker() -> Task<Msg> {
+    use rfd::FileDialog;
+
+    let maybe_file = FileDialog::new()
+        .set_directory(".")
+        .pick_file();
+
+    if let Some(file) = maybe_file {
+        Task::done(
+            SettingsMessage::SetYtConfigFile(Some(file.to_string_lossy().replace('\\', "/"))).into()
+        )
+    }
+    else {
+        Task::none()
+    }
+}
+
+fn open_sound_picker() -> Task<Msg> {
+    use rfd::FileDialog;
+
+    let maybe_file = FileDialog::new()
+        .add_filter("Audio", &["wav", "mp3", "ogg", "flac"])
+        .set_directory(".")
+        .pick_file();
+
+    if let Some(file) = maybe_file {
+        Task::done(
+            SettingsMessage::SetDownloadSoundFile(Some(file.to_string_lossy().replace('\\', "/"))).into()
+        )
+    }
+    else {
+        Task::none()
+    }
+}
+
 fn open_folder_picker(path: &str) -> Task<Msg> {
     use rfd::FileDialog;
 
@@ -212,4 +1130,34 @@ fn open_folder_picker(path: &str) -> Task<Msg> {
     else {
         Task::none()
     }
-}
\ No newline at end of file
+}
+
+// Save the pinned channels' feeds to an OPML file chosen by the user. Returns a status message, unless the user cancelled.
+fn export_feeds(settings: &PomeloSettings) -> Option<String> {
+    use rfd::FileDialog;
+    use log::{info, error};
+    use crate::utils::{channel_feed_url, feeds_to_opml};
+
+    let feeds: Vec<(String, String)> = settings.pins().iter()
+        .filter_map(|pin| match pin {
+            Pin::Channel { id, name, .. } => Some((name.clone(), channel_feed_url(id))),
+            _ => None
+        })
+        .collect();
+
+    let path = FileDialog::new()
+        .add_filter("OPML", &["opml", "xml"])
+        .set_file_name("pomelo-channels.opml")
+        .save_file()?;
+
+    match std::fs::write(&path, feeds_to_opml("Pomelo channels", &feeds)) {
+        Ok(_) => {
+            info!("Exported {} channel feeds to: {:?}", feeds.len(), path);
+            Some(format!("Exported {} channels to {}", feeds.len(), path.display()))
+        },
+        Err(e) => {
+            error!("Failed to export channel feeds: {}", e);
+            Some(format!("Export failed: {}", e))
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use iced::{Element, Task};
+use iced::widget::{column, row, Button, Text};
+
+use crate::app::PomeloError;
+use crate::app::instance::settings::PomeloSettings;
+
+use super::{ConditionalMessage, FillElement, PomeloInstance, PomeloPage, Navigation, Msg};
+
+#[derive(Debug, Clone)]
+pub (crate) enum SettingsRecoveryMessage {
+    RestoreBackup,
+    UseDefaults
+}
+
+impl ConditionalMessage for SettingsRecoveryMessage {}
+
+impl From<SettingsRecoveryMessage> for Msg {
+    fn from(value: SettingsRecoveryMessage) -> Self {
+        Self::SettingsRecovery(value)
+    }
+}
+
+// Shown on startup when the settings file couldn't be read.
+// Lets the user restore the backup from the last save, or continue with the default settings.
+pub (crate) struct SettingsRecoveryPage {
+    error: PomeloError,
+    backup: Option<PomeloSettings>
+}
+
+impl SettingsRecoveryPage {
+    pub (crate) fn new(error: PomeloError, backup: Option<PomeloSettings>) -> Self {
+        Self { error, backup }
+    }
+}
+
+impl PomeloPage for SettingsRecoveryPage {
+    fn update(&mut self, instance: &mut PomeloInstance, message: Msg) -> (Task<Msg>, Navigation) {
+        let Msg::SettingsRecovery(msg) = message else {
+            return (Task::none(), Navigation::None);
+        };
+
+        match msg {
+            SettingsRecoveryMessage::RestoreBackup => if let Some(backup) = self.backup.take() {
+                instance.restore_settings(backup);
+
+                let tasks: Vec<Task<Msg>> = instance.settings().pins().iter()
+                    .map(|pin| super::load_pin_thumbnail(pin, instance))
+                    .collect();
+
+                return (Task::batch(tasks), Navigation::Back);
+            },
+
+            SettingsRecoveryMessage::UseDefaults => {
+                instance.settings().save();
+                return (Task::none(), Navigation::Back);
+            }
+        }
+
+        (Task::none(), Navigation::None)
+    }
+
+    fn view(&self, _instance: &PomeloInstance) -> Element<Msg> {
+        let backup_text = if self.backup.is_some() {
+            "A backup from the last time settings were saved is available."
+        } else {
+            "No usable backup was found."
+        };
+
+        column![
+            Text::new("Your settings couldn't be loaded").size(20),
+            Text::new(format!("Error: {}", self.error.error)),
+            Text::new("The unreadable file was kept as settings.json.corrupt."),
+            Text::new(backup_text),
+
+            row![
+                Button::new(Text::new("Restore Backup").center())
+                    .width(150)
+                    .on_press_maybe(
+                        SettingsRecoveryMessage::RestoreBackup.on_condition(self.backup.is_some())
+                    ),
+
+                Button::new(Text::new("Use Defaults").center())
+                    .width(150)
+                    .on_press(SettingsRecoveryMessage::UseDefaults.into())
+            ].spacing(25)
+        ]
+        .spacing(15)
+        .align_x(iced::Alignment::Center)
+        .fill()
+    }
+
+    fn subscription(&self, _instance: &PomeloInstance) -> iced::Subscription<Msg> {
+        iced::Subscription::none()
+    }
+}
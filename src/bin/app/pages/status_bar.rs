@@ -0,0 +1,77 @@
+use iced::{Element, Length};
+use iced::widget::{button, container, horizontal_space, row, Button, Container, Text};
+
+use crate::yt_fetch::instances;
+
+use super::{ConditionalMessage, PomeloInstance, Msg};
+
+#[derive(Debug, Clone)]
+pub (crate) enum StatusBarMessage {
+    ToDownloads,
+    ToSettings,
+    ToNowPlaying,
+    ToggleHideWatched
+}
+
+impl From<StatusBarMessage> for Msg {
+    fn from(value: StatusBarMessage) -> Self {
+        Self::StatusBar(value)
+    }
+}
+
+impl ConditionalMessage for StatusBarMessage {}
+
+// Slim bar drawn at the bottom of every page.
+// Shows the number of active downloads, the current Invidious instance, whether watched videos are hidden,
+// and the title of the video being played.
+pub (crate) fn status_bar(instance: &PomeloInstance) -> Element<'static, Msg> {
+    let downloads = instance.active_downloads();
+
+    let download_text = match downloads {
+        1 => String::from("1 download"),
+        n => format!("{} downloads", n)
+    };
+
+    let invidious_text = format!(
+        "Invidious: {}",
+        instances::url(instance.settings().invidious_index())
+    );
+
+    let now_playing_text = match instance.now_playing() {
+        Some(title) => format!("Now playing: {}", title),
+        None => String::from("Nothing playing")
+    };
+
+    let bar = row![
+        status_button(download_text)
+            .on_press_maybe(StatusBarMessage::ToDownloads.on_condition(downloads > 0)),
+
+        status_button(invidious_text)
+            .on_press_maybe(StatusBarMessage::ToSettings.on_condition(!instance.settings().is_restricted())),
+
+        status_button(String::from(if instance.settings().hide_watched() { "Watched: hidden" } else { "Watched: shown" }))
+            .on_press(StatusBarMessage::ToggleHideWatched.into()),
+
+        horizontal_space(),
+
+        status_button(now_playing_text)
+            .on_press_maybe(
+                StatusBarMessage::ToNowPlaying.on_condition(instance.now_playing().is_some())
+            )
+    ].spacing(10).align_y(iced::Alignment::Center);
+
+    Container::new(bar)
+        .width(Length::Fill)
+        .padding([2, 10])
+        .style(|theme: &iced::Theme| container::Style {
+            background: Some(theme.extended_palette().background.weak.color.into()),
+            ..Default::default()
+        })
+        .into()
+}
+
+fn status_button(text: String) -> Button<'static, Msg> {
+    Button::new(Text::new(text).size(12))
+        .padding(2)
+        .style(button::text)
+}
@@ -1,23 +1,34 @@
 use std::collections::VecDeque;
-use std::io::BufRead;
 
 use iced::Task;
 
 use invidious::CommonVideo;
+use invidious::video::Video as VideoDetails;
 
 use log::{info, error};
 
-use crate::INVID_INSTANCES;
-use crate::app::{DownloadFormat, DownloadQuality, PomeloError};
-use crate::yt_fetch::VideoFetcher;
+use crate::app::{DownloadFormat, DownloadQuality, DownloadSubtitles, PomeloError};
+use crate::yt_fetch::{Fetcher, VideoFetcher};
+use crate::utils::Locale;
 
-use super::{DownloadInfo, PomeloInstance, Navigation, Msg};
+use crate::app::instance::cache::PomeloCache;
+use crate::app::instance::absolute_path;
+use crate::app::instance::downloads::DownloadId;
+
+use super::{ErrorBanner, PomeloInstance, Navigation, Msg};
 
 #[derive(Debug, Clone)]
 pub (crate) enum VideoInfoMessage {
     LoadVideo(String),
-    VideoLoaded(Box<Result<CommonVideo, PomeloError>>),
-    PlayVideo
+    VideoLoaded(Box<Result<VideoDetails, PomeloError>>),
+    PlayVideo,
+    AddToQueue,
+    ToggleDescription,
+    SetFileName(String),
+    SetSubfolder(String),
+    ConfirmDownloadName,
+    CancelDownloadName,
+    OpenRelated(String)
 }
 
 impl From<VideoInfoMessage> for Msg {
@@ -28,15 +39,31 @@ impl From<VideoInfoMessage> for Msg {
 
 impl super::ConditionalMessage for VideoInfoMessage {}
 
+// File name and folder for a download, edited before it starts.
+// The folder is relative to the download folder.
+#[derive(Debug, Clone)]
+struct DownloadName {
+    file_name: String,
+    subfolder: String
+}
+
 // Displays info for a given video, with options for playback and downloading.
 #[derive(Default)]
 pub (crate) struct VideoInfoPage {
     video: Option<CommonVideo>,
-    downloading: bool,
+    details: Option<VideoDetails>,
+    show_description: bool,
     selected_format: DownloadFormat,
     selected_quality: DownloadQuality,
-    download_info: Option<DownloadInfo>,
-    download_error: Option<PomeloError>
+    selected_subtitles: DownloadSubtitles,
+    download: Option<DownloadId>,
+    video_id: String,
+    error: Option<ErrorBanner>,
+    load_handle: Option<iced::task::Handle>,
+    name_prompt: Option<DownloadName>,
+
+    // Where to start playing from, e.g. the timestamp of a pasted link.
+    start_position: Option<f64>
 }
 
 impl VideoInfoPage {
@@ -46,10 +73,16 @@ impl VideoInfoPage {
 
     pub (crate) fn new_with_video(video: CommonVideo) -> Self {
         Self {
+            video_id: video.id.clone(),
             video: Some(video),
             ..Default::default()
         }
     }
+
+    pub (crate) fn with_start_position(mut self, position: f64) -> Self {
+        self.start_position = Some(position);
+        self
+    }
 }
 
 impl super::PomeloPage for VideoInfoPage {
@@ -58,22 +91,52 @@ impl super::PomeloPage for VideoInfoPage {
         match message {
             Msg::Back => return (Task::none(), Navigation::Back),
             Msg::Home => return (Task::none(), Navigation::Home),
+            Msg::DismissError => self.error = None,
+            Msg::CancelLoad => return self.cancel_load(),
             Msg::SetDownloadFormat(format) => self.selected_format = format,
             Msg::SetDownloadQuality(quality) => self.selected_quality = quality,
-            Msg::StartVideoDownload => return self.download_video(instance),
-            Msg::NextVideoChunk(line, result) => return self.on_next_chunk(line, result),
-            Msg::VideoDownloadCancelled => return on_download_cancelled(instance),
-            Msg::VideoDownloadComplete(result) => self.on_download_complete(result),
+            Msg::SetDownloadSubtitles(subtitles) => self.selected_subtitles = subtitles,
+            Msg::StartVideoDownload => return self.start_download(instance),
+            Msg::CopyDownloadCommand => return self.copy_download_command(instance),
 
             Msg::VideoInfo(msg) => match msg {
-                VideoInfoMessage::LoadVideo(id) 
-                    => return load_video(id, instance.settings().invidious_index()),
+                VideoInfoMessage::LoadVideo(id) => {
+                    self.video_id = id.clone();
+                    self.error = None;
+                    return self.load_video(id, instance);
+                },
 
                 VideoInfoMessage::VideoLoaded(result)
-                    => return self.on_video_loaded(*result),
+                    => return self.on_video_loaded(*result, instance),
 
                 VideoInfoMessage::PlayVideo
-                    => return self.play_video()
+                    => return self.play_video(),
+
+                VideoInfoMessage::AddToQueue
+                    => self.add_to_queue(instance),
+
+                VideoInfoMessage::ToggleDescription
+                    => self.show_description = !self.show_description,
+
+                VideoInfoMessage::SetFileName(name) => if let Some(prompt) = self.name_prompt.as_mut() {
+                    prompt.file_name = name;
+                },
+
+                VideoInfoMessage::SetSubfolder(folder) => if let Some(prompt) = self.name_prompt.as_mut() {
+                    prompt.subfolder = folder;
+                },
+
+                VideoInfoMessage::ConfirmDownloadName => if let Some(name) = self.name_prompt.take() {
+                    return self.download_video(instance, Some(name));
+                },
+
+                VideoInfoMessage::CancelDownloadName
+                    => self.name_prompt = None,
+
+                VideoInfoMessage::OpenRelated(id) => return (
+                    Task::done(VideoInfoMessage::LoadVideo(id).into()),
+                    Navigation::GoTo(Box::new(VideoInfoPage::new()))
+                )
             }
 
             _ => ()
@@ -84,8 +147,9 @@ impl super::PomeloPage for VideoInfoPage {
 
     fn view(&self, instance: &PomeloInstance) -> iced::Element<Msg> {
         use iced::{Alignment, Length};
-        use iced::widget::{column, Column, Image, ProgressBar, Button, Text, Scrollable};
-        use super::{download_element, FillElement};
+        use iced::widget::{column, row, Column, Button, Text, Scrollable};
+        use crate::utils::format_number;
+        use super::{download_element, thumbnail, ConditionalMessage, FillElement};
 
         match &self.video {
             Some(video) => {
@@ -93,66 +157,91 @@ impl super::PomeloPage for VideoInfoPage {
                 .spacing(25)
                 .align_x(iced::Alignment::Center);
     
-                if let Some(handle) = instance.cache().get_thumbnail(&video.id) {
-                    column = column.push(Image::new(handle.clone()));
-                }
+                column = column.push_maybe(
+                    thumbnail(instance.cache(), &thumbnail_key(&video.id, instance.cache()), &video.author, 480.0, 270.0)
+                );
         
                 column = column.push(
                     column![
                         Text::new(video.title.clone()),
                         Text::new(format!("{}\n", video.author)),
-                        Text::new(format!("{} Views", video.views))
+                        Text::new(format!("{} Views", format_number(video.views, instance.settings().locale())))
                     ]
                 );
-        
-                if let Some(e) = &self.download_error {
-                    column = column.push(Text::new(&e.error));
-                }
+
+                column = column.push_maybe(
+                    self.details.as_ref().map(|details| self.get_details_element(details, instance.settings().locale()))
+                );
+
+                column = column.push_maybe(self.error.as_ref().map(|banner| banner.view()));
+
+                column = column.push_maybe(
+                    self.details.as_ref().and_then(|details| related_element(details, instance))
+                );
 
                 // Draw download progress.
-                if self.downloading {
-                    let info = self.download_info.as_ref().unwrap();
-                    column = column.extend(
-                        vec![
-                            ProgressBar::new(0.0..=info.length as f32, info.progress as f32)
-                                .width(instance.settings().window_size().0 / 2.0)
-                                .into(),
-        
-                            Button::new(Text::new("Cancel").center())
-                                .width(100)
-                                .on_press(Msg::VideoDownloadCancelled)
-                                .into()
-                        ]
-                    );
-                }
+                column = column.push_maybe(
+                    self.download.and_then(|id| super::download_progress_element(instance, id))
+                );
 
                 // Draw playback, download, and navigation buttons.
-                else {
-                    column = column.push(
-                        column![
+                column = column.push(
+                    column![
+                        row![
                             Button::new(Text::new("Play").center())
                                 .width(100)
                                 .on_press(VideoInfoMessage::PlayVideo.into()),
 
-                            download_element(&self.selected_format, &self.selected_quality),
+                            Button::new(
+                                Text::new(
+                                    if instance.queue().contains(&video.id) { "Queued" } else { "Add to Queue" }
+                                ).center()
+                            )
+                                .width(125)
+                                .on_press_maybe(
+                                    VideoInfoMessage::AddToQueue.on_condition(!instance.queue().contains(&video.id))
+                                )
+                        ]
+                        .push_maybe(super::add_to_playlist_picker(instance, &video.id, &video.title))
+                        .spacing(10),
 
-                            column![
-                                Button::new(Text::new("Back").center())
-                                    .width(100)
-                                    .on_press(Msg::Back),
+                        download_element(&self.selected_format, &self.selected_quality, &self.selected_subtitles),
 
-                                Button::new(Text::new("Home").center())
-                                    .width(100)
-                                    .on_press(Msg::Home)
-                            ].spacing(25)
+                        column![
+                            Button::new(Text::new("Back").center())
+                                .width(100)
+                                .on_press(Msg::Back),
 
-                        ].spacing(50).align_x(Alignment::Center)
-                    );
-                }
+                            Button::new(Text::new("Home").center())
+                                .width(100)
+                                .on_press(Msg::Home)
+                        ].spacing(25)
 
-                Scrollable::new(column.width(Length::Fill)).fill()
+                    ].spacing(50).align_x(Alignment::Center)
+                );
+
+                let page = Scrollable::new(column.width(Length::Fill)).fill();
+
+                match &self.name_prompt {
+                    Some(prompt) => iced::widget::stack![page, name_prompt_element(prompt)].into(),
+                    None => page
+                }
             },
-            None => Text::new("Loading...").fill()
+
+            // Video info failed to load.
+            None if self.error.is_some() => column![
+                self.error.as_ref().unwrap().view(),
+
+                Button::new(Text::new("Back").center())
+                    .width(100)
+                    .on_press(Msg::Back),
+
+                Button::new(Text::new("Home").center())
+                    .width(100)
+                    .on_press(Msg::Home)
+            ].spacing(25).align_x(Alignment::Center).fill(),
+
+            None => super::loading_skeleton(1)
         }
     }
 
@@ -162,27 +251,84 @@ impl super::PomeloPage for VideoInfoPage {
 }
 
 impl VideoInfoPage {
+    // Use Invidious to load video info from Youtube.
+    // In race mode, several instances are asked at once and the first answer is used.
+    fn load_video(&mut self, id: String, instance: &PomeloInstance) -> (Task<Msg>, Navigation) {
+        use crate::yt_fetch::{race_video_details, RACE_INSTANCES};
+
+        info!("Loading video info with id: {}", id);
+
+        let race = instance.settings().invidious_race();
+        let instances = super::preferred_instances(instance, if race { RACE_INSTANCES } else { 1 });
+
+        let (task, handle) = Task::perform(
+            async move {
+                let result = if race {
+                    race_video_details(&instances, &id).await
+                }
+                else {
+                    Fetcher::new(instances[0].clone()).get_video_details(&id).await
+                };
+
+                result.map_err(PomeloError::new)
+            },
+            |result| VideoInfoMessage::VideoLoaded(Box::new(result)).into()
+        ).abortable();
+
+        self.load_handle = Some(handle);
+
+        (task, Navigation::None)
+    }
+
+    // Stop loading video info and go back to the previous page.
+    fn cancel_load(&mut self) -> (Task<Msg>, Navigation) {
+        if let Some(handle) = self.load_handle.take() {
+            handle.abort();
+        }
+
+        (Task::none(), Navigation::Back)
+    }
+
     // Video finished loading, or an error occured.
-    fn on_video_loaded(&mut self, result: Result<CommonVideo, PomeloError>) -> (Task<Msg>, Navigation) {
+    fn on_video_loaded(&mut self, result: Result<VideoDetails, PomeloError>, instance: &PomeloInstance) -> (Task<Msg>, Navigation) {
         use crate::yt_fetch::{SearchResult, download_thumbnail};
 
+        self.load_handle = None;
+
         let command = match result {
-            Ok(video) => {
+            Ok(details) => {
                 info!("Info load complete.");
+
+                let video: CommonVideo = details.clone().into();
                 self.video = Some(video.clone());
+                self.details = Some(details);
+
+                let id = large_thumbnail_id(&video.id);
+                let data_saver = instance.settings().data_saver();
+
+                // The data saver makes do with the list row's thumbnail, if it has been loaded.
+                if !super::needs_thumbnail(&id, instance) || (data_saver && instance.cache().has_thumbnail(&video.id)) {
+                    return (Task::none(), Navigation::None);
+                }
+
+                let host = super::image_host(instance);
+                let width = if data_saver { super::ROW_THUMBNAIL_WIDTH } else { super::LARGE_THUMBNAIL_WIDTH };
+
                 Task::perform(
-                    async {
-                        let id = video.id.clone();
-                        download_thumbnail(&SearchResult::Video(video), 4).await
-                            .map(|handle| (id, handle))
-                            .map_err(PomeloError::new)
+                    async move {
+                        download_thumbnail(&SearchResult::Video(video), width, &host).await
                     },
-                    Msg::ThumbnailLoaded
+                    move |result| match result {
+                        Ok(handle) => Msg::ThumbnailLoaded(Ok((id, handle))),
+                        Err(e) => Msg::ThumbnailFailed(id, PomeloError::new(e))
+                    }
                 )
             },
             Err(e) => {
                 error!("Failed to load video info: {}", e.error);
-                self.download_error = Some(e);
+                self.error = Some(
+                    ErrorBanner::new(e, Some(VideoInfoMessage::LoadVideo(self.video_id.clone()).into()))
+                );
                 Task::none()
             }
         };
@@ -190,205 +336,313 @@ impl VideoInfoPage {
         (command, Navigation::None)
     }
 
+    // Generate the extra details section, with a collapsible description and buttons for copying the video's id and url.
+    fn get_details_element(&self, details: &VideoDetails, locale: Locale) -> iced::Element<Msg> {
+        use iced::widget::{column, row, Button, Text};
+        use crate::utils::{format_date, format_number, format_relative_time, secs_to_timestamp};
+
+        let length = u64::from(details.length);
+
+        let published = chrono::DateTime::from_timestamp(details.published as i64, 0)
+            .map(|date| format!(
+                "{} ({})",
+                format_date(date.date_naive(), locale),
+                format_relative_time((chrono::Utc::now() - date).num_seconds().max(0) as u64, locale)
+            ))
+            .unwrap_or_else(|| details.published_text.clone());
+
+        let mut column = column![
+            Text::new(format!("Published: {}", published)),
+            Text::new(format!("Likes: {}", format_number(details.likes as u64, locale))),
+            Text::new(format!("Length: {}", secs_to_timestamp(length, length >= 3600))),
+            Text::new(format!("Genre: {}", details.genre)),
+
+            row![
+                Button::new(Text::new("Copy ID").center())
+                    .width(100)
+                    .on_press(Msg::CopyToClipboard(details.id.clone())),
+
+                Button::new(Text::new("Copy URL").center())
+                    .width(100)
+                    .on_press(
+                        Msg::CopyToClipboard(format!("https://www.youtube.com/watch?v={}", details.id))
+                    ),
+
+                Button::new(
+                    Text::new(
+                        if self.show_description { "Hide Description" } else { "Show Description" }
+                    ).center()
+                )
+                    .width(175)
+                    .on_press(VideoInfoMessage::ToggleDescription.into())
+            ].spacing(10)
+        ].spacing(5).align_x(iced::Alignment::Center);
+
+        column = column.push_maybe(
+            self.show_description.then(|| Text::new(details.description.as_str()))
+        );
+
+        column.into()
+    }
+
     // Move to video player page.
     fn play_video(&self) -> (Task<Msg>, Navigation) {
         use super::VideoOrder;
         use super::video_player_page::{VideoPlayerMessage, VideoPlayerPage};
 
         let id = self.video.as_ref().unwrap().id.clone();
+        let mut page = VideoPlayerPage::new(VecDeque::from([(id, false)]), VideoOrder::Sequential(0));
+
+        if let Some(position) = self.start_position {
+            page = page.with_start_position(position);
+        }
+
         (
             Task::done(VideoPlayerMessage::LoadVideo(0).into()),
-            Navigation::GoTo(
-                Box::new(
-                    VideoPlayerPage::new(VecDeque::from([(id, false)]), VideoOrder::Sequential(0))
-                )
-            )
+            Navigation::GoTo(Box::new(page))
         )
     }
 
+    // Add the video to the play queue, it'll be played after whatever is currently playing.
+    fn add_to_queue(&self, instance: &mut PomeloInstance) {
+        if let Some(video) = &self.video {
+            info!("Adding video to queue: {}", video.id);
+            instance.queue_mut().push(video.id.clone(), video.title.clone());
+        }
+    }
+
     // Setup yt-dlp to download the video.
-    fn download_video(&mut self, instance: &mut PomeloInstance) -> (Task<Msg>, Navigation) {
-        use std::path::Path;
+    // Ask for the file name first if the user wants to, otherwise start downloading right away.
+    fn start_download(&mut self, instance: &mut PomeloInstance) -> (Task<Msg>, Navigation) {
+        use filenamify::filenamify;
+
+        let Some(video) = self.video.as_ref() else {
+            return (Task::none(), Navigation::None);
+        };
+
+        if !instance.settings().ask_download_name() {
+            return self.download_video(instance, None);
+        }
+
+        self.name_prompt = Some(DownloadName {
+            file_name: filenamify(format!("{} [{}]", video.title, video.id)),
+            subfolder: filenamify(&video.author)
+        });
+
+        (Task::none(), Navigation::None)
+    }
+
+    // Where the video would be downloaded to, and the arguments yt-dlp would be run with.
+    // Without a name, the file goes in the uploader's folder with yt-dlp's default name.
+    fn download_args(&self, instance: &PomeloInstance, name: Option<DownloadName>) -> Option<(String, Vec<String>)> {
+        use filenamify::filenamify;
+
+        let video = self.video.as_ref()?;
+
+        // Each part of the folder is cleaned up separately, so nested folders still work.
+        let subfolder = match &name {
+            Some(name) => name.subfolder
+                .split(['/', '\\'])
+                .filter(|part| !part.trim().is_empty())
+                .map(|part| filenamify(part.trim()))
+                .filter(|part| part != "." && part != "..")
+                .collect::<Vec<_>>()
+                .join("/"),
+            None => video.author.clone()
+        };
 
-        let video = self.video.as_ref().unwrap();
         let out_path = format!(
             "{}/{}/{}",
             instance.settings().download_folder(),
             if self.selected_format.is_audio() {"audio"} else {"videos"},
-            video.author
+            subfolder
         );
 
-        info!("Downloading video: \"{}\"", video.title);
+        let out_template = name
+            .map(|name| filenamify(name.file_name.trim()))
+            .filter(|file_name| !file_name.is_empty())
+            .map(|file_name| format!("{}.%(ext)s", file_name.replace('%', "%%")));
 
-        if !Path::exists(Path::new(&out_path)) {
-            let _ = std::fs::create_dir(&out_path);
-        }
+        // yt-dlp runs in its own working directory, so it needs the full path.
+        let out_dir = absolute_path(&out_path);
 
-        let mut args = vec![
-            &video.id,
+        let mut args: Vec<String> = [
+            video.id.as_str(),
             "-P",
-            &out_path,
-            "-q",
+            out_dir.as_str(),
             "-w",
-            "--no-warnings",
-            "--progress",
-            "--newline",
-            "--progress-template",
-            "download:%(progress.downloaded_bytes)s|%(progress.total_bytes)s|%(progress.fragment_index)s|%(progress.fragment_count)s",
             //"--ffmpeg-location",
             //"./ffmpeg/bin"
-        ];
-
-        let ext = self.selected_format.as_ext();
-        let quality: String;
-        let v_filter: String;
-
-        if self.selected_format.is_audio() {
-            args.extend([
-                "-x",
-                "--audio-format",
-                ext
-            ]);
-        }
-        else {
-            let q = self.selected_quality.num().to_string();
-            v_filter = format!("b[height={}]/bv[height={}]+ba", ext, q);
-            quality = format!("res:{}", self.selected_quality.num());
-
-            args.extend([
-                "-S",
-                &quality,
-                "-f",
-                &v_filter,
-                "--remux-video",
-                ext
-            ]);
-        }
+        ].map(String::from).to_vec();
 
-        let command = match instance.create_download_process(&args) {
-            Ok((mut stdout, stderr)) => {
-                let mut output = String::new();
-                let result = stdout.read_line(&mut output);
+        if let Some(template) = out_template {
+            args.extend([String::from("-o"), template]);
+        }
 
-                self.downloading = true;
-                self.download_info = Some(DownloadInfo::new(out_path, stdout, stderr));
+        args.extend(super::format_args(&self.selected_format, &self.selected_quality, &self.selected_subtitles));
 
-                Task::done(
-                    Msg::NextVideoChunk(output, result.map_err(PomeloError::new))
-                )
-            },
+        Some((out_path, args))
+    }
 
-            Err(e) => Task::done(Msg::VideoDownloadComplete(Err(e)))
+    // Copy the command for downloading the video with the current settings, using yt-dlp's default file name.
+    fn copy_download_command(&self, instance: &PomeloInstance) -> (Task<Msg>, Navigation) {
+        let task = match self.download_args(instance, None) {
+            Some((_, args)) => Task::done(Msg::CopyToClipboard(instance.download_command_line(&args))),
+            None => Task::none()
         };
 
-        (command, Navigation::None)
+        (task, Navigation::None)
     }
 
-    // Load the next chunk of bytes and append it to the video file
-    fn on_next_chunk(&mut self, line: String, result: Result<usize, PomeloError>) -> (Task<Msg>, Navigation) {
+    // Queue the video for downloading with yt-dlp.
+    fn download_video(&mut self, instance: &mut PomeloInstance, name: Option<DownloadName>) -> (Task<Msg>, Navigation) {
+        use std::path::Path;
 
-        if line.to_lowercase().contains("error") {
-            return (
-                Task::done(
-                    Msg::VideoDownloadComplete(
-                        Err(PomeloError::from(String::from("Failed to retrieve next video chunk.")))
-                    )
-                ),
+        self.error = None;
 
-                Navigation::None
-            );
-        }
+        let Some((out_path, args)) = self.download_args(instance, name) else {
+            return (Task::none(), Navigation::None);
+        };
 
-        let command = match result {
-            Ok(index) => match index {
-                0 => Task::done(Msg::VideoDownloadComplete(Ok(()))),
-                _ => {
-
-                    let nums: Vec<usize> = line
-                        .trim()
-                        .split('|')
-                        .map(|s| s.parse().unwrap_or_default())
-                        .collect();
-
-                    let info = self.download_info.as_mut().unwrap();
-
-                    // Update progress bar, fallback to fragments if total_bytes is 0.
-                    if nums[1] != 0 {
-                        info.progress = nums[0];
-                        info.length = nums[1];
-                    }
-                    else {
-                        info.progress = nums[2];
-                        info.length = nums[3];
-                    }
+        let title = self.video.as_ref().map(|video| video.title.clone()).unwrap_or_default();
+        let channel = self.video.as_ref().map(|video| video.author.clone());
+        let duration = self.video.as_ref().map(|video| u64::from(video.length));
 
-                    let mut output = String::new();
-                    let result = info.stdout
-                        .read_line(&mut output)
-                        .map_err(PomeloError::new);
+        info!("Downloading video: \"{}\"", title);
 
-                    Task::done(Msg::NextVideoChunk(output, result))
-                }
-            },
+        if !Path::exists(Path::new(&out_path)) {
+            let _ = std::fs::create_dir_all(&out_path);
+        }
 
-            Err(e) => Task::done(Msg::VideoDownloadComplete(Err(e)))
-        };
+        self.download = Some(instance.enqueue_download(title, channel, out_path, args, duration));
 
-        (command, Navigation::None)
+        (Task::none(), Navigation::None)
     }
+}
 
-    // Video finished downloading, or an error occured.
-    fn on_download_complete(&mut self, result: Result<(), PomeloError>) {
-        use std::path::Path;
+// The large thumbnail is cached separately from the one used in list rows, so it gets its own key.
+pub (crate) fn large_thumbnail_id(video_id: &str) -> String {
+    format!("{}_large", video_id)
+}
 
-        if let Err(e) = result {
-            error!("Download failed: {}", e.error);
-            self.download_error = Some(e);
-        }
+// Show the list row's thumbnail until the large one has loaded.
+fn thumbnail_key(video_id: &str, cache: &PomeloCache) -> String {
+    let large_id = large_thumbnail_id(video_id);
 
-        else {
-            let info = self.download_info.take().unwrap();
+    if !cache.has_thumbnail(&large_id) && cache.has_thumbnail(video_id) {
+        String::from(video_id)
+    }
+    else {
+        large_id
+    }
+}
 
-            if let Some(Ok(line)) = info.stderr.lines().last() {
-                error!("Download failed: {}", line);
-                self.download_error = Some(PomeloError::from(line));
-            }
+// Modal for editing a download's file name and folder.
+fn name_prompt_element(prompt: &DownloadName) -> iced::Element<Msg> {
+    use iced::widget::{center, column, container, opaque, row, Button, Text, TextInput};
+
+    let dialog = container(
+        column![
+            Text::new("Save As").size(20),
+
+            Text::new("File name"),
+            TextInput::new("File name", &prompt.file_name)
+                .on_input(|name| VideoInfoMessage::SetFileName(name).into())
+                .on_submit(VideoInfoMessage::ConfirmDownloadName.into())
+                .width(400),
+
+            Text::new("Folder"),
+            TextInput::new("Folder", &prompt.subfolder)
+                .on_input(|folder| VideoInfoMessage::SetSubfolder(folder).into())
+                .on_submit(VideoInfoMessage::ConfirmDownloadName.into())
+                .width(400),
+
+            row![
+                Button::new(Text::new("Download").center())
+                    .width(100)
+                    .on_press(VideoInfoMessage::ConfirmDownloadName.into()),
+
+                Button::new(Text::new("Cancel").center())
+                    .width(100)
+                    .style(iced::widget::button::secondary)
+                    .on_press(VideoInfoMessage::CancelDownloadName.into())
+            ].spacing(10)
+        ].spacing(10)
+    )
+    .padding(20)
+    .style(container::rounded_box);
+
+    // Dim the page behind the dialog, and block clicks to it.
+    opaque(
+        center(opaque(dialog))
+            .style(|_theme| container::Style {
+                background: Some(iced::Color { a: 0.6, ..iced::Color::BLACK }.into()),
+                ..Default::default()
+            })
+    )
+}
 
-            else {
-                info!("Video downloaded to file: {:?}", Path::new(&info.path));
-            }
-        }
+// Videos Youtube recommends alongside this one. In restricted mode, only ones from allowed channels are listed.
+fn related_element<'a>(details: &VideoDetails, instance: &PomeloInstance) -> Option<iced::Element<'a, Msg>> {
+    use iced::widget::{column, Button, Column, Text};
 
-        self.downloading = false;
-    }
-}
+    let restricted = instance.settings().is_restricted();
 
-// Use Invidious to load video info from Youtube.
-fn load_video(id: String, instance_index: usize) -> (Task<Msg>, Navigation) {
-    info!("Loading video info with id: {}", id);
+    let videos: Vec<iced::Element<Msg>> = details.recommended_videos.iter()
+        .filter(|video| !restricted || instance.archive().is_allowed(&video.author_id))
+        .map(|video|
+            Button::new(
+                column![
+                    Text::new(video.title.clone()),
+                    Text::new(video.author.clone()).size(12)
+                ]
+            )
+            .width(iced::Length::Fill)
+            .style(iced::widget::button::text)
+            .on_press(VideoInfoMessage::OpenRelated(video.id.clone()).into())
+            .into()
+        )
+        .collect();
 
-    let instance = String::from(INVID_INSTANCES[instance_index].0);
-    (
-        Task::perform(
-            async move {
-                let downloader = VideoFetcher::new(instance);
+    if videos.is_empty() {
+        return None;
+    }
 
-                downloader.get_video_details(&id)
-                    .await
-                    .map(|video| video.into())
-                    .map_err(PomeloError::new)
-            },
-            |result| VideoInfoMessage::VideoLoaded(Box::new(result)).into()
-        ),
-        Navigation::None
+    Some(
+        column![
+            Text::new("Related").size(20),
+            Column::with_children(videos).spacing(5)
+        ]
+        .spacing(10)
+        .max_width(600)
+        .into()
     )
 }
 
-// Download was cancelled by the user.
-fn on_download_cancelled(instance: &mut PomeloInstance) -> (Task<Msg>, Navigation) {
-    instance.cancel_download();
-    (
-        Task::done(Msg::VideoDownloadComplete(Err(PomeloError::from("Cancelled by user.")))),
-        Navigation::None
-    )
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use crate::app::instance::archive::Archive;
+    use crate::app::instance::settings::PomeloSettings;
+    use crate::app::pages::PomeloPage;
+    use crate::app::PomeloError;
+    use crate::yt_fetch::VideoFetcher;
+    use crate::yt_fetch::fixtures::{FixtureFetcher, DEMO_FOLDER};
+
+    use super::{PomeloInstance, VideoInfoMessage, VideoInfoPage};
+
+    #[test]
+    fn test_video_info() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let mut instance = PomeloInstance::with_archive(PomeloSettings::default(), Archive::in_memory());
+        let mut page = VideoInfoPage::new();
+
+        let details = runtime.block_on(FixtureFetcher::new(DEMO_FOLDER).get_video_details("dQw4w9WgXcQ"))
+            .map_err(PomeloError::new);
+
+        let _ = page.update(&mut instance, VideoInfoMessage::VideoLoaded(Box::new(details)).into());
+
+        assert!(page.error.is_none());
+        assert_eq!(page.video.as_ref().map(|video| video.title.as_str()), Some("Growing pomelos at home"));
+        assert_eq!(page.details.as_ref().map_or(0, |details| details.recommended_videos.len()), 2);
+    }
+}
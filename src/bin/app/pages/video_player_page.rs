@@ -1,4 +1,4 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::time::Duration;
 use std::num::Wrapping;
 
@@ -6,13 +6,16 @@ use rand::seq::SliceRandom;
 
 use url::Url;
 
-use log::{info, error};
+use invidious::video::Video as VideoDetails;
+
+use log::{info, warn, error};
 
 use iced::Task;
 
 use crate::app::pages::ConditionalElement;
-use crate::INVID_INSTANCES;
+use crate::yt_fetch::instances;
 use crate::app::PomeloError;
+use crate::app::instance::settings::{PlaybackSpeed, SponsorCategory, StreamQuality};
 use iced_video_player::Video;
 
 use super::{FillElement, PomeloInstance, Navigation, Msg};
@@ -20,14 +23,43 @@ use super::{FillElement, PomeloInstance, Navigation, Msg};
 #[derive(Debug, Clone)]
 pub (crate) enum VideoPlayerMessage {
     LoadVideo(usize),
-    LoadComplete(usize, Result<(Url, bool), PomeloError>),
+    LoadComplete(usize, Result<LoadedVideo, PomeloError>),
     NextVideo(usize),
     PlayToggle,
     VolumeUpdate(f64),
     NextFrame,
     Seek(f64),
     SeekRelease,
-    SkipTimer(u8, usize)
+    SkipTimer(u8, usize),
+    MoreVideos(Result<(Vec<String>, Option<String>), PomeloError>),
+    CheckStreamQuality,
+    UpdateUrl(String),
+    PlayUrl,
+    Replay(super::VideoOrder),
+    PlaySuggestion(String),
+    ToggleChapters,
+    ToggleTranscript,
+    SearchTranscript(String),
+    CycleRepeat,
+    ToggleQueue,
+    JumpTo(usize),
+    RemoveFromList(usize),
+    MoveInList(usize, bool),
+    EndOfStream,
+    SeekTo(f64),
+    SeekBy(f64),
+    ChangeVolume(f64),
+    PlayNext,
+    PlayPrevious,
+    MouseMoved,
+    CheckStall,
+    StartOver,
+    SelectSubtitles(String),
+    SubtitlesLoaded(String, Result<String, PomeloError>),
+    SelectQuality(StreamQuality),
+    SelectSpeed(PlaybackSpeed),
+    SegmentsLoaded(Result<Vec<(f64, f64, SponsorCategory)>, PomeloError>),
+    HeatmapLoaded(Result<Vec<(f64, f64, f64)>, PomeloError>)
 }
 
 impl From<VideoPlayerMessage> for Msg {
@@ -38,6 +70,145 @@ impl From<VideoPlayerMessage> for Msg {
 
 impl super::ConditionalMessage for VideoPlayerMessage {}
 
+// Stream info for a video that's ready to be played.
+// Streams are sorted by height, lowest first. Local files and yt-dlp streams only have one stream, with a height of 0.
+#[derive(Debug, Clone)]
+pub (crate) struct LoadedVideo {
+    streams: Vec<(u32, Url)>,
+    selected: usize,
+    live: bool,
+    title: String,
+
+    // Youtube channel the video is from. Local files don't have one.
+    channel_id: Option<String>,
+    channel: Option<String>,
+    related: Vec<RelatedVideo>,
+    chapters: Vec<(u64, String)>,
+    subtitles: Vec<SubtitleTrack>
+}
+
+impl LoadedVideo {
+    fn single(url: Url, live: bool, title: String) -> Self {
+        Self {
+            streams: vec![(0, url)],
+            selected: 0,
+            live,
+            title,
+            channel_id: None,
+            channel: None,
+            related: Vec::new(),
+            chapters: Vec::new(),
+            subtitles: Vec::new()
+        }
+    }
+}
+
+// Subtitles that can be shown over the video.
+// Tracks from Invidious are urls, and ones found next to local files are paths.
+#[derive(Debug, Clone)]
+pub (crate) struct SubtitleTrack {
+    label: String,
+    location: String
+}
+
+// Video recommended by Youtube, suggested when the queue runs out.
+#[derive(Debug, Clone)]
+pub (crate) struct RelatedVideo {
+    id: String,
+    title: String,
+    author: String,
+    author_id: String
+}
+
+// Channel uploads that haven't been added to the queue yet.
+// The next page is fetched when the queue is close to running out.
+pub (crate) struct ChannelQueue {
+    channel_id: String,
+    continuation: String,
+    shuffle: bool,
+    loading: bool
+}
+
+impl ChannelQueue {
+    pub (crate) fn new(channel_id: String, continuation: String, shuffle: bool) -> Self {
+        Self { channel_id, continuation, shuffle, loading: false }
+    }
+}
+
+// How long the video can go without a new frame before it counts as stalled.
+const STALL_THRESHOLD: Duration = Duration::from_millis(1500);
+
+// A jump in the playback clock bigger than this between two stall checks is a seek, in seconds.
+const SEEK_JUMP: f64 = 2.0;
+
+// How often the playback position and watch time are saved to the archive while playing, in seconds.
+const POSITION_SAVE_INTERVAL: f64 = 10.0;
+
+// Gaps between frames longer than this are pauses or stalls, and don't count as watch time.
+const WATCH_TIME_GAP: Duration = Duration::from_secs(1);
+
+// Label for turning subtitles off in the player's language picker.
+const SUBTITLES_OFF: &str = "Subtitles off";
+
+// Videos stopped this close to the end count as finished, and start from the beginning next time.
+const FINISHED_MARGIN: f64 = 10.0;
+
+// How long the mouse has to stay still before the controls are hidden in fullscreen.
+const CONTROLS_TIMEOUT: Duration = Duration::from_secs(3);
+
+// How long the now playing popup stays up after the queue moves on.
+const NOW_PLAYING_DURATION: Duration = Duration::from_secs(4);
+
+// Height of the tallest bar of the most replayed heatmap, in pixels.
+const HEATMAP_HEIGHT: f32 = 24.0;
+
+// What happens when a video ends. Repeat all goes back to the first video after the last one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RepeatMode {
+    Off,
+    One,
+    All
+}
+
+impl RepeatMode {
+    fn next(self) -> Self {
+        match self {
+            Self::Off => Self::All,
+            Self::All => Self::One,
+            Self::One => Self::Off
+        }
+    }
+}
+
+impl std::fmt::Display for RepeatMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Off => "Repeat: Off",
+            Self::One => "Repeat: One",
+            Self::All => "Repeat: All"
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+// Why playback stopped moving. If the clock keeps going without new frames, the decoder is falling behind,
+// otherwise the player is waiting for more data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stall {
+    Network,
+    Decoder
+}
+
+// Shown over the video for a moment when the next video in the queue starts.
+struct NowPlaying {
+    video: String,
+    from_computer: bool,
+    title: String,
+    channel: Option<String>,
+    shown_at: std::time::Instant
+}
+
 // Plays a list of videos, either from the computer or from Youtube.
 pub (crate) struct VideoPlayerPage {
     videos: VecDeque<(String, bool)>,
@@ -46,41 +217,188 @@ pub (crate) struct VideoPlayerPage {
     video_paused: bool,
     video_position: f64,
     video_volume: f64,
+
+    // Speed picked in the player, and the speed, quality, and volume offset of the current video's channel, if it has any.
+    // Speeds picked while a channel's speed is in use only last for that video.
+    speed: PlaybackSpeed,
+    channel_speed: Option<PlaybackSpeed>,
+    channel_quality: Option<StreamQuality>,
+    volume_offset: f64,
+
+    // Whether new streams get the pitch-correcting audio filter. Read from the settings when a video loads.
+    pitch_correction: bool,
     seeking: bool,
     skip_timer: Option<iced::task::Handle>,
     auto_skipping: bool,
-    skip_time: u8
+    skip_time: u8,
+    channel_queue: Option<ChannelQueue>,
+    streams: Vec<(u32, Url)>,
+    stream_index: usize,
+    live: bool,
+    started: bool,
+    finished: bool,
+    related: Vec<RelatedVideo>,
+    chapters: Vec<(u64, String)>,
+    show_chapters: bool,
+    repeat: RepeatMode,
+
+    // Titles of videos in the list, where they're known. Shown in the queue panel.
+    titles: HashMap<String, String>,
+    show_queue: bool,
+
+    url_input: String,
+    url_error: Option<String>,
+
+    // Subtitles for the current video, and the language picked for them.
+    // The language is kept between videos, and used again if the next video has it.
+    subtitles: Vec<SubtitleTrack>,
+    subtitle_label: Option<String>,
+    cues: Vec<(f64, f64, String)>,
+
+    // Transcript panel, listing every cue of the loaded subtitles. Only cues containing the query are listed.
+    show_transcript: bool,
+    transcript_query: String,
+
+    // SponsorBlock segments of the current video, and which of them have been skipped.
+    // Each segment is only skipped once, so the user can still seek back into it.
+    segments: Vec<(f64, f64, SponsorCategory)>,
+    skipped_segments: Vec<usize>,
+    last_skip: Option<SponsorCategory>,
+    segments_handle: Option<iced::task::Handle>,
+
+    // How often each part of the current video is replayed, as (start, end, value) with values from 0 to 1.
+    heatmap: Vec<(f64, f64, f64)>,
+    heatmap_handle: Option<iced::task::Handle>,
+
+    // Position to seek to once the first video loads, in seconds.
+    start_position: Option<f64>,
+
+    // Saved position the current video was resumed from, shown with a button to start over.
+    resumed_from: Option<f64>,
+
+    // Position last saved to the watch history.
+    saved_position: f64,
+
+    // Last time the mouse moved over the window. Used to hide the controls in fullscreen.
+    last_mouse_move: std::time::Instant,
+
+    // Popup for the video that just started, if it was queued after another one.
+    now_playing: Option<NowPlaying>,
+    played_before: bool,
+
+    // Youtube channel of the current video as (id, name), and time spent watching it that isn't in the archive yet, in seconds.
+    channel: Option<(String, String)>,
+    unsaved_watch_time: f64,
+
+    // Used to notice when playback stalls.
+    last_frame: Option<std::time::Instant>,
+    last_clock: f64,
+    stall: Option<Stall>,
+    stall_ticks: usize,
+
+    // How far into the video the stream has been downloaded, in seconds. Shown under the seek slider.
+    buffered_until: Option<f64>,
+
+    // Bytes the stream had read at the last stall check, to count streaming towards the data usage.
+    stream_bytes: Option<u64>
 }
 
 impl super::PomeloPage for VideoPlayerPage {
 
     fn update(&mut self, instance: &mut PomeloInstance, message: Msg) -> (Task<Msg>, Navigation) {
 
+        // Videos queued from other pages are played after the current list.
+        if self.started {
+            self.take_queued(instance);
+        }
+
         if let Msg::Back = message {
             if let Some(timer) = self.skip_timer.take() {
                 timer.abort();
             }
 
+            self.save_position(instance);
+
+            instance.set_now_playing(None);
+
             return (Task::none(), Navigation::Back);
         }
 
         else if let Msg::VideoPlayer(msg) = message {
             match msg {
-                VideoPlayerMessage::LoadVideo(index) => return (
-                    self.load_video(index, instance),
-                    Navigation::None
-                ),
+                VideoPlayerMessage::UpdateUrl(text) => self.url_input = text,
+                VideoPlayerMessage::PlayUrl => return (self.play_url(), Navigation::None),
+
+                VideoPlayerMessage::LoadVideo(index) => {
+                    self.take_queued(instance);
+
+                    return (
+                        Task::batch([
+                            self.load_video(index, instance),
+                            self.fetch_more_videos(index, instance)
+                        ]),
+                        Navigation::None
+                    );
+                },
 
-                VideoPlayerMessage::LoadComplete(index, result) => return (
-                    self.on_load_complete(index, result, instance.settings().video_skip_on_error()),
-                    Navigation::None
-                ),
+                VideoPlayerMessage::MoreVideos(result) => self.on_more_videos(result),
+                VideoPlayerMessage::CheckStreamQuality => self.check_stream_quality(instance),
+
+                VideoPlayerMessage::SelectQuality(quality) => {
+                    instance.settings_mut().set_stream_quality(quality);
+                    self.channel_quality = None;
+
+                    let target = pick_stream(&self.streams, quality, instance.settings().window_size().1);
+                    self.switch_stream(target);
+                },
+
+                VideoPlayerMessage::SelectSpeed(speed) => self.set_speed(speed),
+
+                VideoPlayerMessage::LoadComplete(index, result) => {
+                    let result = self.check_restricted(index, result, instance)
+                        .map(|loaded| self.apply_channel_preference(loaded, instance));
+
+                    instance.set_now_playing(result.as_ref().ok().map(|loaded| loaded.title.clone()));
+                    self.pitch_correction = instance.settings().pitch_correction();
+
+                    self.channel = result.as_ref().ok()
+                        .and_then(|loaded| loaded.channel_id.clone().zip(loaded.channel.clone()));
+
+                    if let Ok(loaded) = &result {
+                        if let Some((video, _)) = self.videos.get(index) {
+                            self.titles.insert(video.clone(), loaded.title.clone());
+                        }
+
+                        self.record_watch(index, loaded, instance);
+                        self.find_resume_position(index, loaded, instance);
+                        self.show_now_playing(index, loaded, instance);
+                    }
+
+                    return (
+                        Task::batch([
+                            self.on_load_complete(index, result, instance.settings().video_skip_on_error()),
+                            self.load_subtitles(self.subtitle_label.clone())
+                        ]),
+                        Navigation::None
+                    );
+                },
 
                 // Video control messages
-                VideoPlayerMessage::NextVideo(index) => return (
-                    self.next_video(index),
-                    Navigation::None
-                ),
+                VideoPlayerMessage::NextVideo(index) => {
+                    self.save_position(instance);
+                    self.resumed_from = None;
+
+                    let task = self.next_video(index);
+
+                    if self.finished {
+                        instance.set_now_playing(None);
+                    }
+
+                    return (task, Navigation::None);
+                },
+
+                VideoPlayerMessage::Replay(order) => return (self.replay(order), Navigation::None),
+                VideoPlayerMessage::PlaySuggestion(id) => return (self.play_suggestion(id), Navigation::None),
 
                 
                 VideoPlayerMessage::SkipTimer(time, index) => return (
@@ -88,23 +406,73 @@ impl super::PomeloPage for VideoPlayerPage {
                     Navigation::None
                 ),
 
+                VideoPlayerMessage::ToggleChapters => self.show_chapters = !self.show_chapters,
+                VideoPlayerMessage::ToggleTranscript => self.show_transcript = !self.show_transcript,
+                VideoPlayerMessage::SearchTranscript(query) => self.transcript_query = query,
+                VideoPlayerMessage::CycleRepeat => self.repeat = self.repeat.next(),
+                VideoPlayerMessage::ToggleQueue => self.show_queue = !self.show_queue,
+                VideoPlayerMessage::RemoveFromList(index) => self.remove_from_list(index),
+                VideoPlayerMessage::MoveInList(index, up) => self.move_in_list(index, up),
+
+                VideoPlayerMessage::JumpTo(index) => {
+                    self.save_position(instance);
+                    self.resumed_from = None;
+
+                    return (self.jump_to(index), Navigation::None);
+                },
+                VideoPlayerMessage::EndOfStream => return (self.on_end_of_stream(instance), Navigation::None),
+                VideoPlayerMessage::SeekTo(position) => self.seek_to(position),
+                VideoPlayerMessage::SeekBy(offset) => self.seek_by(offset),
+                VideoPlayerMessage::ChangeVolume(change) => self.set_volume((self.video_volume + change).clamp(0.0, 1.0)),
+
+                VideoPlayerMessage::PlayNext if self.video_index.0 + 1 < self.videos.len() => return (
+                    Task::done(VideoPlayerMessage::JumpTo(self.video_index.0 + 1).into()),
+                    Navigation::None
+                ),
+
+                VideoPlayerMessage::PlayPrevious if self.video_index.0 > 0 => return (
+                    Task::done(VideoPlayerMessage::JumpTo(self.video_index.0 - 1).into()),
+                    Navigation::None
+                ),
+
+                VideoPlayerMessage::PlayNext | VideoPlayerMessage::PlayPrevious => (),
+                VideoPlayerMessage::MouseMoved => self.last_mouse_move = std::time::Instant::now(),
                 VideoPlayerMessage::PlayToggle => self.toggle_playback(),
                 VideoPlayerMessage::VolumeUpdate(f) => self.set_volume(f),
                 VideoPlayerMessage::Seek(f) => self.seek(f),
                 VideoPlayerMessage::SeekRelease => self.on_seek_release(),
-                VideoPlayerMessage::NextFrame => self.on_next_frame()
+                VideoPlayerMessage::NextFrame => self.on_next_frame(instance),
+                VideoPlayerMessage::CheckStall => self.check_stall(instance),
+
+                VideoPlayerMessage::StartOver => {
+                    self.seek_to(0.0);
+                    self.resumed_from = None;
+                },
+
+                VideoPlayerMessage::SelectSubtitles(label) => {
+                    let label = Some(label).filter(|label| label != SUBTITLES_OFF);
+                    return (self.load_subtitles(label), Navigation::None);
+                },
+
+                VideoPlayerMessage::SubtitlesLoaded(label, result) => self.on_subtitles_loaded(label, result),
+                VideoPlayerMessage::SegmentsLoaded(result) => self.on_segments_loaded(result),
+                VideoPlayerMessage::HeatmapLoaded(result) => self.on_heatmap_loaded(result)
             }
         }
 
         (Task::none(), Navigation::None)
     }
 
-    fn view(&self, _instance: &PomeloInstance) -> iced::Element<Msg> {
+    fn view(&self, instance: &PomeloInstance) -> iced::Element<Msg> {
         use crate::utils;
-        use iced::widget::{row, Row, Column, Text, Slider, Button};
+        use iced::widget::{row, Row, Column, Text, Slider, Button, ProgressBar};
         use iced_video_player::VideoPlayer;
         use super::ConditionalMessage;
 
+        if self.finished {
+            return self.get_finished_element(instance);
+        }
+
         if let Some(result) = &self.current_video {
 
             let mut column: Column<Msg> = Column::new()
@@ -122,15 +490,37 @@ impl super::PomeloPage for VideoPlayerPage {
                         "Play"
                     };
 
+                    let slider_style = super::slider_style(instance);
+                    let padding = super::button_padding(instance);
+
                     let video_player = VideoPlayer::new(video)
                         .on_new_frame(VideoPlayerMessage::NextFrame.into())
-                        .on_end_of_stream(
-                            VideoPlayerMessage::NextVideo((self.video_index + Wrapping(1)).0).into()
-                        );
+                        .on_end_of_stream(VideoPlayerMessage::EndOfStream.into());
+
+                    // Show subtitles and what's holding playback up on top of the video.
+                    // The video can only be drawn in one window, so it's left out while the mini player is open.
+                    let display: iced::Element<Msg> = if instance.mini_player_open() {
+                        "Playing in the mini player".fill()
+                    }
+                    else {
+                        iced::widget::stack![video_player.fill()]
+                            .extend(self.get_subtitle_element())
+                            .extend(self.stall.map(|stall| self.get_stall_element(stall)))
+                            .extend(self.get_now_playing_element(instance))
+                            .into()
+                    };
+
+                    if self.controls_hidden(instance) {
+                        return display;
+                    }
 
-                    // Add the video display
+                    // Add the video display, with the chapter list, transcript, and queue next to it if they're open.
                     column = column.push(
-                        video_player.fill()
+                        row![display]
+                            .push_maybe((self.show_chapters && !self.chapters.is_empty()).then(|| self.get_chapters_element()))
+                            .push_maybe((self.show_transcript && !self.cues.is_empty()).then(|| self.get_transcript_element()))
+                            .push_maybe(self.show_queue.then(|| self.get_queue_element(instance)))
+                            .spacing(10)
                     );
 
                     // Add video controls
@@ -140,6 +530,7 @@ impl super::PomeloPage for VideoPlayerPage {
                             // Play/Pause button
                             Button::new(Text::new(play_button_text).center())
                                 .width(100)
+                                .padding(padding)
                                 .on_press(VideoPlayerMessage::PlayToggle.into()),
 
                             // Label for elapsed time
@@ -150,12 +541,25 @@ impl super::PomeloPage for VideoPlayerPage {
                                 )
                             ),
 
-                            // Playback slider
-                            Slider::new(
-                                0.0..=video.duration().as_secs_f64(),
-                                self.video_position,
-                                |f| VideoPlayerMessage::Seek(f).into()
-                            ).step(0.1).on_release(VideoPlayerMessage::SeekRelease.into()),
+                            // Playback slider, under the most replayed heatmap if there is one
+                            Column::new()
+                                .width(iced::Length::Fill)
+                                .push_maybe(self.get_heatmap_element())
+                                .push(
+                                    Slider::new(
+                                        0.0..=video.duration().as_secs_f64(),
+                                        self.video_position,
+                                        |f| VideoPlayerMessage::Seek(f).into()
+                                    )
+                                    .step(0.1)
+                                    .on_release(VideoPlayerMessage::SeekRelease.into())
+                                    .style(move |theme, status| slider_style.style(theme, status))
+                                )
+                                .push_maybe(self.buffered_until.map(|until|
+                                    ProgressBar::new(0.0..=video.duration().as_secs_f32(), until as f32)
+                                        .height(3)
+                                        .style(iced::widget::progress_bar::secondary)
+                                )),
 
                             // Label for total video length
                             Text::new(
@@ -168,10 +572,77 @@ impl super::PomeloPage for VideoPlayerPage {
                                 0.0..=1.0,
                                 self.video_volume,
                                 |f| VideoPlayerMessage::VolumeUpdate(f).into()
-                            ).width(100).step(0.01)
-
-                        ].spacing(10)
+                            )
+                            .width(100)
+                            .step(0.01)
+                            .style(move |theme, status| slider_style.style(theme, status))
+
+                        ]
+                        .push_maybe(
+                            (!self.chapters.is_empty()).then(||
+                                Button::new(Text::new("Chapters").center())
+                                    .width(100)
+                                    .padding(padding)
+                                    .on_press(VideoPlayerMessage::ToggleChapters.into())
+                            )
+                        )
+                        .push_maybe(
+                            (!self.cues.is_empty()).then(||
+                                Button::new(Text::new("Transcript").center())
+                                    .width(100)
+                                    .padding(padding)
+                                    .on_press(VideoPlayerMessage::ToggleTranscript.into())
+                            )
+                        )
+                        .push(
+                            Button::new(Text::new("Queue").center())
+                                .width(100)
+                                .padding(padding)
+                                .on_press(VideoPlayerMessage::ToggleQueue.into())
+                        )
+                        .push(
+                            Button::new(Text::new(self.repeat.to_string()).center())
+                                .width(130)
+                                .padding(padding)
+                                .on_press(VideoPlayerMessage::CycleRepeat.into())
+                        )
+                        .push(
+                            Button::new(Text::new(if instance.is_fullscreen() { "Windowed" } else { "Fullscreen" }).center())
+                                .width(100)
+                                .padding(padding)
+                                .on_press(Msg::ToggleFullscreen)
+                        )
+                        .push(
+                            Button::new(Text::new(if instance.mini_player_open() { "Close Mini" } else { "Mini Player" }).center())
+                                .width(100)
+                                .padding(padding)
+                                .on_press(Msg::ToggleMiniPlayer)
+                        )
+                        .push(
+                            iced::widget::PickList::new(
+                                PlaybackSpeed::ALL,
+                                Some(self.current_speed()),
+                                |speed| VideoPlayerMessage::SelectSpeed(speed).into()
+                            ).width(80)
+                        )
+                        .push_maybe(self.get_quality_picker(instance))
+                        .push_maybe(self.get_subtitle_picker())
+                        .spacing(10)
                     );
+
+                    // Mention the last skipped segment, so the jump doesn't look like a glitch.
+                    column = column.push_maybe(self.last_skip.map(|category|
+                        Text::new(format!("Skipped {} segment", category.to_string().to_lowercase())).size(12)
+                    ));
+
+                    // Let the user start from the beginning instead, if the video picked up where it was left off.
+                    column = column.push_maybe(self.resumed_from.map(|position|
+                        row![
+                            Text::new(format!("Resumed from {}", utils::secs_to_timestamp(position as u64, use_hour_timestamp))).size(12),
+                            Button::new(Text::new("Start Over").size(12).center())
+                                .on_press(VideoPlayerMessage::StartOver.into())
+                        ].spacing(10).align_y(iced::Alignment::Center)
+                    ));
                 },
                 Err(e) => {
                     let error_msg = e.error.to_string();
@@ -214,33 +685,128 @@ impl super::PomeloPage for VideoPlayerPage {
             return column.fill();
         }
 
+        else if !self.started {
+            self.get_empty_element(instance)
+        }
+
         else {
             "Loading...".fill()
         }
     }
 
-    fn subscription(&self, _instance: &PomeloInstance) -> iced::Subscription<Msg> {
-        iced::Subscription::none()
+    fn subscription(&self, instance: &PomeloInstance) -> iced::Subscription<Msg> {
+        // Resize events come in bursts, so the window size is checked on a timer instead.
+        let quality_check = if instance.settings().stream_quality() == StreamQuality::Auto
+            && self.channel_quality.is_none()
+            && self.streams.len() > 1
+        {
+            iced::time::every(Duration::from_secs(2)).map(|_| VideoPlayerMessage::CheckStreamQuality.into())
+        }
+        else {
+            iced::Subscription::none()
+        };
+
+        let stall_check = if self.is_playing() {
+            iced::time::every(Duration::from_millis(500)).map(|_| VideoPlayerMessage::CheckStall.into())
+        }
+        else {
+            iced::Subscription::none()
+        };
+
+        // Only needed for showing the controls again in fullscreen.
+        let mouse_moves = if instance.is_fullscreen() {
+            iced::event::listen_with(|event, _status, _id| match event {
+                iced::Event::Mouse(iced::mouse::Event::CursorMoved { .. }) => Some(VideoPlayerMessage::MouseMoved.into()),
+                _ => None
+            })
+        }
+        else {
+            iced::Subscription::none()
+        };
+
+        iced::Subscription::batch([quality_check, stall_check, mouse_moves])
+    }
+
+    fn is_playing(&self) -> bool {
+        matches!(self.current_video, Some(Ok(_)))
+    }
+
+    // Just the video, with buttons for pausing and skipping to the next video.
+    fn mini_view(&self, _instance: &PomeloInstance) -> Option<iced::Element<Msg>> {
+        use iced::widget::{column, row, Button, Text};
+        use iced_video_player::VideoPlayer;
+        use super::ConditionalMessage;
+
+        let Some(Ok(video)) = &self.current_video else {
+            return None;
+        };
+
+        let play_button_text = if self.is_video_playing() { "Pause" } else { "Play" };
+
+        let video_player = VideoPlayer::new(video)
+            .on_new_frame(VideoPlayerMessage::NextFrame.into())
+            .on_end_of_stream(VideoPlayerMessage::EndOfStream.into());
+
+        Some(
+            column![
+                iced::widget::stack![video_player.fill()].extend(self.get_subtitle_element()),
+
+                row![
+                    Button::new(Text::new(play_button_text).center())
+                        .width(80)
+                        .on_press(VideoPlayerMessage::PlayToggle.into()),
+
+                    Button::new(Text::new("Next").center())
+                        .width(80)
+                        .on_press_maybe(
+                            VideoPlayerMessage::PlayNext.on_condition(self.video_index.0 + 1 < self.videos.len())
+                        )
+                ].spacing(10)
+            ]
+            .spacing(5)
+            .padding(5)
+            .align_x(iced::Alignment::Center)
+            .into()
+        )
+    }
+
+    fn on_close(&mut self, instance: &mut PomeloInstance) {
+        self.save_position(instance);
+        self.save_session(instance);
     }
 }
 
 impl VideoPlayerPage {
 
     // Start loading the current video for playback.
-    fn load_video(&self, video_index: usize, instance: &PomeloInstance) -> Task<Msg> {
-        use crate::yt_fetch::VideoFetcher;
+    fn load_video(&mut self, video_index: usize, instance: &PomeloInstance) -> Task<Msg> {
+        self.started = true;
 
         let (video, from_computer) = self.videos[video_index].clone();
 
         info!("Loading video for playback: {}", video);
 
-        let invid_index = String::from(INVID_INSTANCES[instance.settings().invidious_index()].0);
+        // Try the selected instance first, then fall back to the next few in the list.
+        let instances = super::preferred_instances(instance, STREAM_RETRY_INSTANCES);
+        let race = instance.settings().invidious_race();
 
-        Task::perform(
+        let config_args = instance.settings().yt_dlp_config_args();
+        let quality = instance.settings().stream_quality();
+        let window_height = instance.settings().window_size().1;
+
+        let segments = self.load_segments(&video, from_computer, instance);
+        let heatmap = self.load_heatmap(&video, from_computer, instance);
+
+        let stream = Task::perform(
             async move {
                 if from_computer {
+                    let title = String::from(video.split('/').last().unwrap_or_default());
+
                     Url::parse(&video)
-                        .map(|url| (url, false))
+                        .map(|url| {
+                            let subtitles = local_subtitles(&url);
+                            LoadedVideo { subtitles, ..LoadedVideo::single(url, false, title) }
+                        })
                         .map_err(|e| {
                                 eprintln!("{}", e);
                                 PomeloError::new(e)
@@ -248,122 +814,1024 @@ impl VideoPlayerPage {
                         )
                 } 
                 else {
-                    let downloader = VideoFetcher::new(invid_index);
-                    
-                    match downloader.get_video_details(&video).await {
-                        Ok(r) => Url::parse(&r.format_streams[0].url)
-                            .map(|url| (url, r.live))
-                            .map_err(PomeloError::new),
-
-                        Err(e) => Err(PomeloError::new(e))
-                    }
+                    resolve_stream(&video, &instances, race, &config_args, quality, window_height).await
                 }
             },
             move |result| VideoPlayerMessage::LoadComplete(video_index, result).into()
-        )
+        );
+
+        Task::batch([stream, segments, heatmap])
     }
 
-    // Video finished loading, start playing if there were no errors.
-    fn on_load_complete(&mut self, video_index: usize, result: Result<(Url, bool), PomeloError>, skip_on_error: bool) -> Task<Msg> {
-        let mut maybe_video = match result {
-            Ok((url, live)) => Video::new(&url, live).map_err(PomeloError::new),
-            Err(e) => {
-                Err(e)
-            }
-        };
+    // In restricted mode, only videos from allowed channels can be played. Files from the computer are always allowed.
+    fn check_restricted(&self, index: usize, result: Result<LoadedVideo, PomeloError>, instance: &PomeloInstance) -> Result<LoadedVideo, PomeloError> {
+        let from_computer = self.videos.get(index).is_some_and(|(_, local)| *local);
 
-        let task = match &mut maybe_video {
-            Ok(video) => {
-                self.video_index = Wrapping(video_index);
-                let _ = video.seek(0);  // For some reason autoplay doesn't work properly without this line
-                video.set_volume(self.video_volume);
-                Task::none()
+        match result {
+            Ok(loaded) if instance.settings().is_restricted() && !from_computer => {
+                let allowed = loaded.channel_id.as_deref()
+                    .is_some_and(|id| instance.archive().is_allowed(id));
+
+                if allowed {
+                    Ok(loaded)
+                }
+                else {
+                    warn!("Blocked video from a channel that isn't allowed in restricted mode: {}", loaded.title);
+                    Err(PomeloError::from("This channel isn't allowed in restricted mode."))
+                }
             },
 
-            Err(e) => {
-                error!("Failed to load video: {}", e.error);
+            result => result
+        }
+    }
 
-                if skip_on_error && !(video_index == 0 || video_index == self.videos.len()-1) {
+    // Ask SponsorBlock for the current video's segments in the categories the user wants skipped.
+    // Local files and videos played with SponsorBlock turned off have none.
+    fn load_segments(&mut self, video: &str, from_computer: bool, instance: &PomeloInstance) -> Task<Msg> {
+        use crate::yt_fetch::get_sponsor_segments;
 
-                    let next_index = if self.video_index.0 <= video_index {
-                        video_index + 1
-                    } else if video_index > 0 {
-                        video_index - 1
-                    } else {
-                        0
-                    };
-                    
-                    self.video_index = Wrapping(video_index);
-                    self.auto_skipping = true;
+        if let Some(handle) = self.segments_handle.take() {
+            handle.abort();
+        }
 
-                    let (timer, handle) = Task::done(
-                        VideoPlayerMessage::SkipTimer(5, next_index).into()
-                    ).abortable();
+        self.segments.clear();
+        self.skipped_segments.clear();
+        self.last_skip = None;
 
-                    self.skip_timer = Some(handle);
+        let categories = instance.settings().sponsorblock_categories();
 
-                    timer
-                }
-                else {
-                    self.video_index = Wrapping(video_index);
-                    Task::none()
-                }
-            }
-        };
+        if from_computer || !instance.settings().sponsorblock() || categories.is_empty() {
+            return Task::none();
+        }
 
-        self.current_video = Some(maybe_video);
+        let id = String::from(video);
+        let categories: Vec<&'static str> = categories.iter().map(SponsorCategory::api_name).collect();
+
+        let (task, handle) = Task::perform(
+            async move {
+                get_sponsor_segments(&id, &categories).await
+                    .map(|segments| segments.into_iter()
+                        .filter_map(|segment| {
+                            SponsorCategory::from_api_name(&segment.category)
+                                .map(|category| (segment.segment.0, segment.segment.1, category))
+                        })
+                        .collect()
+                    )
+                    .map_err(PomeloError::new)
+            },
+            |result| VideoPlayerMessage::SegmentsLoaded(result).into()
+        ).abortable();
+
+        self.segments_handle = Some(handle);
 
         task
     }
 
-    // Start loading the next video in the list.
-    fn next_video(&mut self, index: usize) -> Task<Msg> {
+    fn on_segments_loaded(&mut self, result: Result<Vec<(f64, f64, SponsorCategory)>, PomeloError>) {
+        self.segments_handle = None;
 
-        if let Some(handle) = self.skip_timer.take() {
+        match result {
+            Ok(segments) => {
+                info!("Found {} SponsorBlock segments.", segments.len());
+                self.segments = segments;
+            },
+
+            Err(e) => warn!("Failed to get SponsorBlock segments: {}", e.error)
+        }
+    }
+
+    // Ask yt-dlp for the current video's most replayed heatmap. Invidious doesn't have it.
+    // Skipped for local files, and with the data saver on.
+    fn load_heatmap(&mut self, video: &str, from_computer: bool, instance: &PomeloInstance) -> Task<Msg> {
+        if let Some(handle) = self.heatmap_handle.take() {
             handle.abort();
         }
 
-        if index > self.video_index.0 && index < self.videos.len() ||
-            index < self.video_index.0 && index > 0 
-        {
-            self.current_video = None;
-            //self.video_index = Wrapping(index);
+        self.heatmap.clear();
 
-            Task::done(VideoPlayerMessage::LoadVideo(index).into())
-        }
-        else {
-            Task::none()
+        if from_computer || !instance.settings().heatmap() || instance.settings().data_saver() {
+            return Task::none();
         }
+
+        let id = String::from(video);
+        let config_args = instance.settings().yt_dlp_config_args();
+
+        let (task, handle) = Task::perform(
+            async move { get_heatmap(&id, &config_args).await },
+            |result| VideoPlayerMessage::HeatmapLoaded(result).into()
+        ).abortable();
+
+        self.heatmap_handle = Some(handle);
+
+        task
     }
 
-    // Pause/Play the video.
-    fn toggle_playback(&mut self) {
-        if let Some(Ok(video)) = self.current_video.as_mut() {
-            video.set_paused(!video.paused());
-            self.video_paused = video.paused();
+    fn on_heatmap_loaded(&mut self, result: Result<Vec<(f64, f64, f64)>, PomeloError>) {
+        self.heatmap_handle = None;
+
+        match result {
+            Ok(heatmap) => self.heatmap = heatmap,
+            Err(e) => warn!("Failed to get the most replayed heatmap: {}", e.error)
         }
     }
 
-    // Set the video's volume, 0.0 for mute, 1.0 for full volume.
-    fn set_volume(&mut self, volume: f64) {
-        if let Some(Ok(video)) = self.current_video.as_mut() {
-            self.video_volume = volume;
-            video.set_volume(volume);
+    // Bars above the seek slider showing how often each part of the video is replayed. Clicking a bar seeks to it.
+    fn get_heatmap_element(&self) -> Option<iced::Element<Msg>> {
+        use iced::widget::{container, mouse_area, Container, Row};
+
+        if self.heatmap.is_empty() {
+            return None;
         }
+
+        let bars = self.heatmap.iter()
+            .map(|(start, _, value)| {
+                let bar = Container::new(iced::widget::Space::new(iced::Length::Fill, iced::Length::Fill))
+                    .width(iced::Length::Fill)
+                    .height((*value as f32 * HEATMAP_HEIGHT).max(1.0))
+                    .style(|theme: &iced::Theme| container::Style {
+                        background: Some(theme.palette().primary.scale_alpha(0.4).into()),
+                        ..Default::default()
+                    });
+
+                mouse_area(bar)
+                    .on_press(VideoPlayerMessage::SeekTo(*start).into())
+                    .into()
+            });
+
+        Some(
+            Row::with_children(bars)
+                .height(HEATMAP_HEIGHT)
+                .align_y(iced::Alignment::End)
+                .into()
+        )
     }
 
-    // Track the new position, and keep the video paused while seeking.
-    fn seek(&mut self, position: f64) {
-        if let Some(Ok(video)) = self.current_video.as_mut() {
-            if !self.seeking {
-                self.seeking = true;
-                video.set_paused(true);
-            }
-            self.video_position = position;
+    // Jump past the segment the video is in, if it hasn't been skipped yet.
+    fn skip_segment(&mut self) {
+        let position = self.video_position;
+
+        let found = self.segments.iter().enumerate()
+            .find(|(i, (start, end, _))| (*start..*end).contains(&position) && !self.skipped_segments.contains(i))
+            .map(|(i, (_, end, category))| (i, *end, *category));
+
+        if let Some((index, end, category)) = found {
+            info!("Skipping {} segment at {:.1}s", category, position);
+
+            self.skipped_segments.push(index);
+            self.last_skip = Some(category);
+            self.seek_to(end);
         }
     }
 
-    // Seek the video to the new position
+    // Add a video that finished loading to the watch history, unless the user turned it off.
+    // Live streams can't be resumed, so they're left out.
+    fn record_watch(&self, video_index: usize, loaded: &LoadedVideo, instance: &mut PomeloInstance) {
+        if !instance.settings().record_watch_history() || loaded.live {
+            return;
+        }
+
+        if let Some((video, from_computer)) = self.videos.get(video_index) {
+            instance.archive_mut().add_watch(video, &loaded.title, *from_computer);
+        }
+    }
+
+    // Save how far into the current video playback got, so it can be resumed from the watch history.
+    // Videos that were watched to the end start over next time.
+    // Watch time is saved along with it, live streams included.
+    fn save_position(&mut self, instance: &mut PomeloInstance) {
+        if !instance.settings().record_watch_history() {
+            return;
+        }
+
+        self.save_watch_time(instance);
+
+        if self.live {
+            return;
+        }
+
+        let Some(Ok(video)) = &self.current_video else {
+            return;
+        };
+
+        let duration = video.duration().as_secs_f64();
+
+        let position = if self.video_position >= duration - FINISHED_MARGIN {
+            0.0
+        } else {
+            self.video_position
+        };
+
+        if let Some((id, _)) = self.videos.get(self.video_index.0) {
+            instance.archive_mut().set_watch_position(id, position, duration);
+            self.saved_position = self.video_position;
+        }
+    }
+
+    fn save_watch_time(&mut self, instance: &PomeloInstance) {
+        let seconds = std::mem::take(&mut self.unsaved_watch_time);
+
+        if seconds <= 0.0 {
+            return;
+        }
+
+        if let Some((id, _)) = self.videos.get(self.video_index.0) {
+            let channel = self.channel.as_ref().map(|(id, name)| (id.as_str(), name.as_str()));
+            instance.archive().add_watch_time(id, channel, seconds);
+        }
+    }
+
+    // Save the list of videos and where playback is, so it can be resumed from the main menu next time.
+    fn save_session(&self, instance: &PomeloInstance) {
+        use crate::app::instance::archive::{PlayerSession, PlaylistEntry};
+
+        if !instance.settings().record_watch_history() || self.live {
+            return;
+        }
+
+        let Some(Ok(video)) = &self.current_video else {
+            return;
+        };
+
+        let index = self.video_index.0;
+
+        if index >= self.videos.len() {
+            return;
+        }
+
+        let position = if self.video_position >= video.duration().as_secs_f64() - FINISHED_MARGIN {
+            0.0
+        } else {
+            self.video_position
+        };
+
+        let videos = self.videos.iter()
+            .map(|(id, local)| PlaylistEntry {
+                video: id.clone(),
+                title: self.titles.get(id).cloned().unwrap_or_else(|| id.clone()),
+                local: *local
+            })
+            .collect();
+
+        instance.archive().save_player_session(&PlayerSession { videos, index, position });
+    }
+
+    // Continue from where the video was last stopped, unless a start position was already given.
+    fn find_resume_position(&mut self, video_index: usize, loaded: &LoadedVideo, instance: &PomeloInstance) {
+        self.resumed_from = None;
+
+        if self.start_position.is_some() || !instance.settings().record_watch_history() || loaded.live {
+            return;
+        }
+
+        let position = self.videos.get(video_index)
+            .and_then(|(id, _)| instance.archive().watch_position(id));
+
+        if let Some(position) = position {
+            info!("Resuming playback from {:.0}s", position);
+            self.start_position = Some(position);
+            self.resumed_from = Some(position);
+        }
+    }
+
+    // Move videos from the play queue to the end of this player's list.
+    fn take_queued(&mut self, instance: &mut PomeloInstance) {
+        for video in instance.queue_mut().take_all() {
+            self.titles.insert(video.id.clone(), video.title);
+            self.videos.push_back((video.id, false));
+        }
+    }
+
+    // Start playing the video from the pasted link, after anything that's already queued.
+    fn play_url(&mut self) -> Task<Msg> {
+        use crate::utils::video_id_from_url;
+
+        match video_id_from_url(&self.url_input) {
+            Some(id) => {
+                self.url_input.clear();
+                self.url_error = None;
+                self.videos.push_back((id, false));
+
+                Task::done(VideoPlayerMessage::LoadVideo(0).into())
+            },
+
+            None => {
+                self.url_error = Some(String::from("Not a valid Youtube link."));
+                Task::none()
+            }
+        }
+    }
+
+    // Shown before anything is played, lists the queue and lets the user paste a link to play.
+    fn get_empty_element(&self, instance: &PomeloInstance) -> iced::Element<Msg> {
+        use iced::widget::{column, row, Button, Column, Scrollable, Text, TextInput};
+        use super::ConditionalMessage;
+
+        let queue_list = Column::with_children(
+            instance.queue().iter()
+                .enumerate()
+                .map(|(i, video)| Text::new(format!("{}. {}", i + 1, video.title)).into())
+        ).spacing(5);
+
+        let queue_empty = instance.queue().is_empty();
+
+        let mut column = Column::new()
+            .spacing(25)
+            .align_x(iced::Alignment::Center)
+            .push(Text::new("Nothing playing").size(24))
+            .push_maybe(
+                (!instance.settings().is_restricted()).then(|| row![
+                    TextInput::new("Paste a Youtube link", &self.url_input)
+                        .on_input(|text| VideoPlayerMessage::UpdateUrl(text).into())
+                        .on_submit(VideoPlayerMessage::PlayUrl.into())
+                        .width(400),
+
+                    Button::new(Text::new("Play").center())
+                        .width(100)
+                        .on_press(VideoPlayerMessage::PlayUrl.into())
+                ].spacing(10))
+            );
+
+        column = column.push_maybe(self.url_error.as_deref().map(Text::new));
+
+        column = column.push(
+            if queue_empty {
+                column![Text::new("The queue is empty.")]
+            }
+            else {
+                column![
+                    Text::new("Queue").size(20),
+                    Scrollable::new(queue_list).height(instance.settings().window_size().1 / 3.0)
+                ].spacing(10).align_x(iced::Alignment::Center)
+            }
+        );
+
+        column.push(
+            row![
+                Button::new(Text::new("Play Queue").center())
+                    .width(125)
+                    .on_press_maybe(VideoPlayerMessage::LoadVideo(0).on_condition(!queue_empty)),
+
+                Button::new(Text::new("Back").center())
+                    .width(100)
+                    .on_press(Msg::Back)
+            ].spacing(25)
+        ).fill()
+    }
+
+    // Fetch the next page of channel uploads if the queue is about to run out.
+    fn fetch_more_videos(&mut self, video_index: usize, instance: &PomeloInstance) -> Task<Msg> {
+        use crate::yt_fetch::{Fetcher, VideoFetcher};
+
+        let Some(queue) = self.channel_queue.as_mut() else {
+            return Task::none();
+        };
+
+        if queue.loading || video_index + 2 < self.videos.len() {
+            return Task::none();
+        }
+
+        info!("Fetching more uploads for channel: {}", queue.channel_id);
+
+        queue.loading = true;
+
+        let fetcher = Fetcher::new(instances::url(instance.settings().invidious_index()));
+        let channel_id = queue.channel_id.clone();
+        let continuation = queue.continuation.clone();
+
+        Task::perform(
+            async move {
+                fetcher.get_channel_videos(&channel_id, Some(&continuation)).await
+                    .map(|uploads| (
+                        uploads.videos.into_iter().map(|video| video.id).collect(),
+                        uploads.continuation
+                    ))
+                    .map_err(PomeloError::new)
+            },
+            |result| VideoPlayerMessage::MoreVideos(result).into()
+        )
+    }
+
+    // Add the next page of channel uploads to the end of the queue.
+    fn on_more_videos(&mut self, result: Result<(Vec<String>, Option<String>), PomeloError>) {
+        let Some(queue) = self.channel_queue.as_mut() else {
+            return;
+        };
+
+        queue.loading = false;
+
+        match result {
+            Ok((mut ids, continuation)) => {
+                // Pages of uploads can overlap, don't queue the same video twice.
+                ids.retain(|id| !self.videos.iter().any(|(queued, _)| queued == id));
+
+                if queue.shuffle {
+                    ids.shuffle(&mut rand::thread_rng());
+                }
+
+                self.videos.extend(ids.into_iter().map(|id| (id, false)));
+
+                match continuation {
+                    Some(continuation) => queue.continuation = continuation,
+                    None => self.channel_queue = None
+                }
+            },
+
+            // Stop fetching, the videos already in the queue can still be played.
+            Err(e) => {
+                error!("Failed to fetch more channel uploads: {}", e.error);
+                self.channel_queue = None;
+            }
+        }
+    }
+
+    // Video finished loading, start playing if there were no errors.
+    fn on_load_complete(&mut self, video_index: usize, result: Result<LoadedVideo, PomeloError>, skip_on_error: bool) -> Task<Msg> {
+        let mut maybe_video = match result {
+            Ok(loaded) => {
+                let video = Video::new(&loaded.streams[loaded.selected].1, loaded.live).map_err(PomeloError::new);
+
+                self.streams = loaded.streams;
+                self.stream_index = loaded.selected;
+                self.live = loaded.live;
+                self.related = loaded.related;
+                self.chapters = loaded.chapters;
+                self.subtitles = loaded.subtitles;
+
+                video
+            },
+            Err(e) => {
+                self.subtitles.clear();
+                Err(e)
+            }
+        };
+
+        let task = match &mut maybe_video {
+            Ok(video) => {
+                self.video_index = Wrapping(video_index);
+
+                if self.pitch_correction {
+                    if let Err(e) = add_pitch_correction(video) {
+                        warn!("Failed to turn on pitch correction: {}", e.error);
+                    }
+                }
+
+                let _ = video.seek(0);  // For some reason autoplay doesn't work properly without this line
+                video.set_volume(self.video_volume);
+
+                if let Err(e) = video.set_speed(self.current_speed().0) {
+                    warn!("Failed to set playback speed: {}", e);
+                }
+
+                // Pick up where the video was left off.
+                self.video_position = 0.0;
+
+                if let Some(position) = self.start_position.take() {
+                    match video.seek(Duration::from_secs_f64(position)) {
+                        Ok(_) => self.video_position = position,
+                        Err(e) => {
+                            warn!("Failed to resume playback: {}", e);
+                            self.resumed_from = None;
+                        }
+                    }
+                }
+
+                self.saved_position = self.video_position;
+
+                self.last_frame = Some(std::time::Instant::now());
+                self.stall = None;
+                Task::none()
+            },
+
+            Err(e) => {
+                error!("Failed to load video: {}", e.error);
+
+                if skip_on_error && !(video_index == 0 || video_index == self.videos.len()-1) {
+
+                    let next_index = if self.video_index.0 <= video_index {
+                        video_index + 1
+                    } else if video_index > 0 {
+                        video_index - 1
+                    } else {
+                        0
+                    };
+                    
+                    self.video_index = Wrapping(video_index);
+                    self.auto_skipping = true;
+
+                    let (timer, handle) = Task::done(
+                        VideoPlayerMessage::SkipTimer(5, next_index).into()
+                    ).abortable();
+
+                    self.skip_timer = Some(handle);
+
+                    timer
+                }
+                else {
+                    self.video_index = Wrapping(video_index);
+                    Task::none()
+                }
+            }
+        };
+
+        self.current_video = Some(maybe_video);
+
+        task
+    }
+
+    // Start loading the next video in the list.
+    fn next_video(&mut self, index: usize) -> Task<Msg> {
+
+        if let Some(handle) = self.skip_timer.take() {
+            handle.abort();
+        }
+
+        if index > self.video_index.0 && index < self.videos.len() ||
+            index < self.video_index.0 && index > 0 
+        {
+            self.current_video = None;
+            //self.video_index = Wrapping(index);
+
+            Task::done(VideoPlayerMessage::LoadVideo(index).into())
+        }
+
+        // Reached the end of the queue, unless more channel uploads are on the way.
+        else if index == self.videos.len() && self.channel_queue.is_none() {
+            info!("Reached the end of the queue.");
+
+            self.current_video = None;
+            self.finished = true;
+
+            Task::none()
+        }
+
+        else {
+            Task::none()
+        }
+    }
+
+    // A video finished playing. Play it again, move on to the next one,
+    // or go back to the first video if the queue is over and repeat all is on.
+    fn on_end_of_stream(&mut self, instance: &mut PomeloInstance) -> Task<Msg> {
+        let next_index = (self.video_index + Wrapping(1)).0;
+
+        match self.repeat {
+            RepeatMode::One => {
+                self.seek_to(0.0);
+
+                if let Some(Ok(video)) = self.current_video.as_mut() {
+                    video.set_paused(false);
+                    self.video_paused = false;
+                }
+
+                Task::none()
+            },
+
+            // Keep going with the top recommendation that isn't already in the queue.
+            RepeatMode::Off if next_index == self.videos.len() && self.channel_queue.is_none() && instance.settings().autoplay_related() => {
+                let next = self.suggestions(instance)
+                    .find(|video| !self.videos.iter().any(|(id, _)| *id == video.id))
+                    .map(|video| video.id.clone());
+
+                match next {
+                    Some(id) => {
+                        info!("Reached the end of the queue, playing recommended video: {}", id);
+
+                        self.save_position(instance);
+                        self.resumed_from = None;
+                        self.current_video = None;
+
+                        self.play_suggestion(id)
+                    },
+                    None => Task::done(VideoPlayerMessage::NextVideo(next_index).into())
+                }
+            },
+
+            RepeatMode::All if next_index == self.videos.len() && self.channel_queue.is_none() => {
+                info!("Reached the end of the queue, starting over.");
+
+                self.save_position(instance);
+                self.resumed_from = None;
+                self.current_video = None;
+
+                self.replay(super::VideoOrder::Sequential(0))
+            },
+
+            _ => Task::done(VideoPlayerMessage::NextVideo(next_index).into())
+        }
+    }
+
+    // Play the queue again from the start, either in the same order or shuffled.
+    fn replay(&mut self, order: super::VideoOrder) -> Task<Msg> {
+        use super::VideoOrder;
+
+        if let VideoOrder::Shuffled = order {
+            self.videos.make_contiguous().shuffle(&mut rand::thread_rng());
+        }
+
+        self.finished = false;
+        self.video_index = Wrapping(0);
+
+        Task::done(VideoPlayerMessage::LoadVideo(0).into())
+    }
+
+    // Add a suggested video to the end of the queue and play it.
+    fn play_suggestion(&mut self, id: String) -> Task<Msg> {
+        self.finished = false;
+        self.videos.push_back((id, false));
+
+        Task::done(VideoPlayerMessage::LoadVideo(self.videos.len() - 1).into())
+    }
+
+    // Recommendations for the current video. In restricted mode, only ones from allowed channels are suggested.
+    fn suggestions<'a>(&'a self, instance: &'a PomeloInstance) -> impl Iterator<Item = &'a RelatedVideo> {
+        let restricted = instance.settings().is_restricted();
+
+        self.related.iter()
+            .filter(move |video| !restricted || instance.archive().is_allowed(&video.author_id))
+    }
+
+    // Shown when the last video in the queue ends.
+    fn get_finished_element(&self, instance: &PomeloInstance) -> iced::Element<Msg> {
+        use iced::widget::{column, row, Button, Column, Scrollable, Text};
+        use super::VideoOrder;
+
+        let suggestions: Vec<&RelatedVideo> = self.suggestions(instance).collect();
+
+        let has_suggestions = !suggestions.is_empty();
+
+        let suggestions = Column::with_children(
+            suggestions.into_iter().map(|video|
+                Button::new(
+                    column![
+                        Text::new(video.title.as_str()),
+                        Text::new(video.author.as_str()).size(12)
+                    ]
+                )
+                .width(iced::Length::Fill)
+                .on_press(VideoPlayerMessage::PlaySuggestion(video.id.clone()).into())
+                .into()
+            )
+        ).spacing(5);
+
+        let mut column = Column::new()
+            .spacing(25)
+            .align_x(iced::Alignment::Center)
+            .extend([
+                Text::new("End of queue").size(24).into(),
+
+                row![
+                    Button::new(Text::new("Replay").center())
+                        .width(100)
+                        .on_press(VideoPlayerMessage::Replay(VideoOrder::Sequential(0)).into()),
+
+                    Button::new(Text::new("Shuffle").center())
+                        .width(100)
+                        .on_press(VideoPlayerMessage::Replay(VideoOrder::Shuffled).into())
+                ].spacing(10).into()
+            ]);
+
+        if has_suggestions {
+            column = column.extend([
+                Text::new("Up next").size(20).into(),
+                Scrollable::new(suggestions)
+                    .height(instance.settings().window_size().1 / 2.0)
+                    .into()
+            ]);
+        }
+
+        column.push(
+            Button::new(Text::new("Back").center())
+                .width(100)
+                .on_press(Msg::Back)
+        ).fill()
+    }
+
+    // Switch to the stream that best fits the window, if it's not already playing.
+    fn check_stream_quality(&mut self, instance: &PomeloInstance) {
+        let target = pick_stream(&self.streams, StreamQuality::Auto, instance.settings().window_size().1);
+        self.switch_stream(target);
+    }
+
+    // Swap the current stream for another one of the same video, carrying on from the same position.
+    fn switch_stream(&mut self, target: usize) {
+        if target == self.stream_index || target >= self.streams.len() || !matches!(self.current_video, Some(Ok(_))) {
+            return;
+        }
+
+        info!("Switching stream from {}p to {}p", self.streams[self.stream_index].0, self.streams[target].0);
+
+        match Video::new(&self.streams[target].1, self.live) {
+            Ok(mut video) => {
+                if self.pitch_correction {
+                    if let Err(e) = add_pitch_correction(&video) {
+                        warn!("Failed to turn on pitch correction: {}", e.error);
+                    }
+                }
+
+                if !self.live {
+                    let _ = video.seek(Duration::from_secs_f64(self.video_position));
+                }
+
+                video.set_volume(self.video_volume);
+                video.set_paused(self.video_paused);
+
+                if let Err(e) = video.set_speed(self.current_speed().0) {
+                    warn!("Failed to set playback speed: {}", e);
+                }
+
+                self.stream_index = target;
+                self.current_video = Some(Ok(video));
+            },
+
+            // Keep playing the current stream.
+            Err(e) => warn!("Failed to switch streams: {}", e)
+        }
+    }
+
+    // Chapter list for the sidebar. Chapters that have already played are dimmed, the current one is marked.
+    fn get_chapters_element(&self) -> iced::Element<Msg> {
+        use iced::widget::{button, Button, Column, Scrollable, Text};
+        use crate::utils::secs_to_timestamp;
+
+        let use_hour = self.chapters.last().is_some_and(|(start, _)| *start >= 3600);
+        let current = self.chapters.iter()
+            .rposition(|(start, _)| *start as f64 <= self.video_position)
+            .unwrap_or_default();
+
+        let chapters = Column::with_children(
+            self.chapters.iter().enumerate().map(|(i, (start, title))| {
+                let marker = if i == current { "> " } else { "" };
+
+                Button::new(
+                    Text::new(format!("{}{}  {}", marker, secs_to_timestamp(*start, use_hour), title)).size(12)
+                )
+                .width(iced::Length::Fill)
+                .style(if i < current { button::secondary } else { button::text })
+                .on_press(VideoPlayerMessage::SeekTo(*start as f64).into())
+                .into()
+            })
+        ).spacing(2);
+
+        Scrollable::new(chapters)
+            .width(250)
+            .height(iced::Length::Fill)
+            .into()
+    }
+
+    // Every line of the loaded subtitles, with a search box. Clicking a line seeks to it, and the current line is marked.
+    fn get_transcript_element(&self) -> iced::Element<Msg> {
+        use iced::widget::{button, column, Button, Column, Scrollable, Text, TextInput};
+        use crate::utils::secs_to_timestamp;
+
+        let use_hour = self.cues.last().is_some_and(|(start, _, _)| *start >= 3600.0);
+        let query = self.transcript_query.trim().to_lowercase();
+
+        let current = self.cues.iter()
+            .rposition(|(start, _, _)| *start <= self.video_position);
+
+        let matches: Vec<(usize, &(f64, f64, String))> = self.cues.iter()
+            .enumerate()
+            .filter(|(_, (_, _, text))| query.is_empty() || text.to_lowercase().contains(&query))
+            .collect();
+
+        let count = match query.is_empty() {
+            true => format!("{} lines", matches.len()),
+            false => format!("{} matching lines", matches.len())
+        };
+
+        let lines = Column::with_children(
+            matches.into_iter().map(|(i, (start, _, text))| {
+                let marker = if Some(i) == current { "> " } else { "" };
+
+                Button::new(
+                    Text::new(format!("{}{}  {}", marker, secs_to_timestamp(*start as u64, use_hour), text.replace('\n', " "))).size(12)
+                )
+                .width(iced::Length::Fill)
+                .style(button::text)
+                .on_press(VideoPlayerMessage::SeekTo(*start).into())
+                .into()
+            })
+        ).spacing(2);
+
+        column![
+            TextInput::new("Search transcript", &self.transcript_query)
+                .on_input(|query| VideoPlayerMessage::SearchTranscript(query).into()),
+            Text::new(count).size(12),
+            Scrollable::new(lines).height(iced::Length::Fill)
+        ]
+        .spacing(5)
+        .width(300)
+        .into()
+    }
+
+    // The video list, with the current video marked. Each other video can be played, moved, or removed.
+    fn get_queue_element(&self, instance: &PomeloInstance) -> iced::Element<Msg> {
+        use iced::widget::{button, column, row, Button, Column, Container, Scrollable, Text};
+
+        let current = self.video_index.0;
+        let last = self.videos.len().saturating_sub(1);
+
+        let videos = Column::with_children(
+            self.videos.iter().enumerate().map(|(i, (video, from_computer))| {
+                let title = self.video_title(video, *from_computer);
+                let marker = if i == current { "> " } else { "" };
+
+                let small_button = |label: &'static str, message: Option<VideoPlayerMessage>| {
+                    Button::new(Text::new(label).size(10).center())
+                        .width(22)
+                        .padding(2)
+                        .on_press_maybe(message.map(Msg::from))
+                };
+
+                let item = row![]
+                    .push_maybe(
+                        super::thumbnail(instance.cache(), video, &title, 80.0, 45.0)
+                            .map(|thumbnail| Container::new(thumbnail).width(80).height(45))
+                    )
+                    .push(Text::new(format!("{}{}. {}", marker, i + 1, title)).size(12).width(iced::Length::Fill))
+                    .spacing(5)
+                    .align_y(iced::Alignment::Center);
+
+                row![
+                    Button::new(item)
+                        .width(iced::Length::Fill)
+                        .style(if i < current { button::secondary } else { button::text })
+                        .on_press_maybe((i != current).then(|| VideoPlayerMessage::JumpTo(i).into())),
+
+                    column![
+                        small_button("^", (i > 0).then_some(VideoPlayerMessage::MoveInList(i, true))),
+                        small_button("v", (i < last).then_some(VideoPlayerMessage::MoveInList(i, false)))
+                    ].spacing(2),
+
+                    small_button("x", (i != current).then_some(VideoPlayerMessage::RemoveFromList(i)))
+                ]
+                .spacing(2)
+                .align_y(iced::Alignment::Center)
+                .into()
+            })
+        ).spacing(2);
+
+        Scrollable::new(videos)
+            .width(300)
+            .height(iced::Length::Fill)
+            .into()
+    }
+
+    // Title to show for a video in the list. Local files use their file name,
+    // and videos that haven't been loaded yet fall back to their id.
+    fn video_title(&self, video: &str, from_computer: bool) -> String {
+        if let Some(title) = self.titles.get(video) {
+            return title.clone();
+        }
+
+        if from_computer {
+            let name = video.split('/').last().unwrap_or(video);
+            return urlencoding::decode(name).map_or_else(|_| String::from(name), |name| name.into_owned());
+        }
+
+        String::from(video)
+    }
+
+    // Stop the current video and play another one from the list.
+    fn jump_to(&mut self, index: usize) -> Task<Msg> {
+        if index >= self.videos.len() {
+            return Task::none();
+        }
+
+        if let Some(handle) = self.skip_timer.take() {
+            handle.abort();
+        }
+
+        self.current_video = None;
+
+        Task::done(VideoPlayerMessage::LoadVideo(index).into())
+    }
+
+    // Take a video out of the list. The current video can't be removed.
+    fn remove_from_list(&mut self, index: usize) {
+        let current = self.video_index.0;
+
+        if index == current || index >= self.videos.len() {
+            return;
+        }
+
+        self.videos.remove(index);
+
+        if index < current {
+            self.video_index -= Wrapping(1);
+        }
+    }
+
+    // Swap a video with the one above or below it, keeping track of where the current video ends up.
+    fn move_in_list(&mut self, index: usize, up: bool) {
+        let other = if up { index.checked_sub(1) } else { Some(index + 1) };
+
+        let Some(other) = other.filter(|other| *other < self.videos.len()) else {
+            return;
+        };
+
+        self.videos.swap(index, other);
+
+        if self.video_index.0 == index {
+            self.video_index = Wrapping(other);
+        }
+        else if self.video_index.0 == other {
+            self.video_index = Wrapping(index);
+        }
+    }
+
+    // Jump straight to a position, e.g. when a chapter is picked or a seek shortcut is pressed.
+    fn seek_to(&mut self, position: f64) {
+        if let Some(Ok(video)) = self.current_video.as_mut() {
+            match video.seek(Duration::from_secs_f64(position)) {
+                Ok(_) => self.video_position = position,
+                Err(e) => warn!("Failed to seek to {:.1}s: {}", position, e)
+            }
+        }
+    }
+
+    // Move the position by the given number of seconds, staying inside the video. Live streams can't be seeked.
+    fn seek_by(&mut self, offset: f64) {
+        let Some(Ok(video)) = self.current_video.as_ref() else {
+            return;
+        };
+
+        if self.live {
+            return;
+        }
+
+        let duration = video.duration().as_secs_f64();
+        self.seek_to((self.video_position + offset).clamp(0.0, duration));
+    }
+
+    // Pause/Play the video.
+    fn toggle_playback(&mut self) {
+        if let Some(Ok(video)) = self.current_video.as_mut() {
+            video.set_paused(!video.paused());
+            self.video_paused = video.paused();
+        }
+    }
+
+    // The channel's saved speed, if there is one, takes over from the global speed.
+    fn current_speed(&self) -> PlaybackSpeed {
+        self.channel_speed.unwrap_or(self.speed)
+    }
+
+    fn set_speed(&mut self, speed: PlaybackSpeed) {
+        match self.channel_speed {
+            Some(_) => self.channel_speed = Some(speed),
+            None => self.speed = speed
+        }
+
+        if let Some(Ok(video)) = &mut self.current_video {
+            if let Err(e) = video.set_speed(speed.0) {
+                warn!("Failed to set playback speed: {}", e);
+            }
+        }
+    }
+
+    // Use the speed, quality, and volume offset saved for the video's channel.
+    // Volume offsets are taken back off when the next video starts, so they don't add up.
+    // The data saver still decides the quality while it's on.
+    fn apply_channel_preference(&mut self, mut loaded: LoadedVideo, instance: &PomeloInstance) -> LoadedVideo {
+        let preference = loaded.channel_id.as_deref()
+            .and_then(|id| instance.archive().channel_preference(id))
+            .copied()
+            .unwrap_or_default();
+
+        self.channel_speed = preference.speed;
+        self.channel_quality = preference.quality;
+
+        self.video_volume = (self.video_volume - self.volume_offset + preference.volume_offset).clamp(0.0, 1.0);
+        self.volume_offset = preference.volume_offset;
+
+        if let Some(quality) = preference.quality.filter(|_| !instance.settings().data_saver()) {
+            loaded.selected = pick_stream(&loaded.streams, quality, instance.settings().window_size().1);
+        }
+
+        loaded
+    }
+
+    // Set the video's volume, 0.0 for mute, 1.0 for full volume.
+    fn set_volume(&mut self, volume: f64) {
+        if let Some(Ok(video)) = self.current_video.as_mut() {
+            self.video_volume = volume;
+            video.set_volume(volume);
+        }
+    }
+
+    // Track the new position, and keep the video paused while seeking.
+    fn seek(&mut self, position: f64) {
+        if let Some(Ok(video)) = self.current_video.as_mut() {
+            if !self.seeking {
+                self.seeking = true;
+                video.set_paused(true);
+            }
+            self.video_position = position;
+        }
+    }
+
+    // Seek the video to the new position
     fn on_seek_release(&mut self) {
         if let Some(Ok(video)) = self.current_video.as_mut() {
             self.seeking = false;
@@ -374,14 +1842,293 @@ impl VideoPlayerPage {
         }
     }
 
-    // Track the video's current position while it's playing.
-    fn on_next_frame(&mut self) {
+    // Track the video's current position while it's playing, and save it to the watch history every so often.
+    fn on_next_frame(&mut self, instance: &mut PomeloInstance) {
+        let now = std::time::Instant::now();
+
+        if let Some(elapsed) = self.last_frame.map(|last| now - last).filter(|elapsed| *elapsed < WATCH_TIME_GAP) {
+            self.unsaved_watch_time += elapsed.as_secs_f64();
+        }
+
+        self.last_frame = Some(now);
+        self.stall = None;
+
         if let Some(Ok(video)) = self.current_video.as_mut() {
             //println!("{}, {}", video.paused(), video.position().as_secs_f64());
             if !self.seeking {
                 self.video_position = video.position().as_secs_f64();
             }
         }
+
+        if !self.seeking {
+            self.skip_segment();
+        }
+
+        // Live streams have no position to save, only watch time.
+        let position_moved = !self.live && (self.video_position - self.saved_position).abs() >= POSITION_SAVE_INTERVAL;
+
+        if position_moved || self.unsaved_watch_time >= POSITION_SAVE_INTERVAL {
+            self.save_position(instance);
+        }
+    }
+
+    // Check whether frames have stopped coming in while the video should be playing.
+    fn check_stall(&mut self, instance: &mut PomeloInstance) {
+        let Some(Ok(video)) = self.current_video.as_ref() else {
+            self.stall = None;
+            self.buffered_until = None;
+            self.stream_bytes = None;
+            return;
+        };
+
+        self.buffered_until = buffered_until(video);
+
+        let clock = video.position().as_secs_f64();
+        let clock_moved = (clock - self.last_clock).abs() > f64::EPSILON;
+        let seeked = (clock - self.last_clock).abs() > SEEK_JUMP;
+        self.last_clock = clock;
+
+        // Count what the stream downloaded since the last check towards the data usage.
+        // A seek makes the stream skip ahead, and a new stream starts from zero, so both start the count over.
+        let from_computer = self.videos.get(self.video_index.0).is_some_and(|(_, local)| *local);
+        let read = (!from_computer).then(|| stream_bytes_read(video)).flatten();
+
+        if let (Some(read), Some(last)) = (read, self.stream_bytes) {
+            if read > last && !seeked {
+                instance.usage_mut().add_bytes(read - last);
+                instance.usage_mut().save_if_due();
+            }
+        }
+
+        self.stream_bytes = read;
+
+        let waiting = self.last_frame.is_some_and(|time| time.elapsed() >= STALL_THRESHOLD);
+
+        if video.paused() || self.seeking || video.eos() || !waiting {
+            self.stall = None;
+            return;
+        }
+
+        if self.stall.is_none() {
+            warn!("Playback stalled at {:.1}s", clock);
+        }
+
+        self.stall = Some(if clock_moved { Stall::Decoder } else { Stall::Network });
+        self.stall_ticks = self.stall_ticks.wrapping_add(1);
+    }
+
+    // Load the subtitles with the given label, or turn subtitles off.
+    // The label is remembered even if this video doesn't have it, so later videos can use it.
+    fn load_subtitles(&mut self, label: Option<String>) -> Task<Msg> {
+        self.cues.clear();
+        self.subtitle_label = label.clone();
+
+        let Some(label) = label else {
+            return Task::none();
+        };
+
+        let Some(track) = self.subtitles.iter().find(|track| track.label == label) else {
+            return Task::none();
+        };
+
+        info!("Loading subtitles: {}", label);
+
+        let location = track.location.clone();
+
+        Task::perform(
+            async move { read_subtitles(&location).await },
+            move |result| VideoPlayerMessage::SubtitlesLoaded(label, result).into()
+        )
+    }
+
+    fn on_subtitles_loaded(&mut self, label: String, result: Result<String, PomeloError>) {
+        // Another language was picked while these were loading.
+        if self.subtitle_label.as_ref() != Some(&label) {
+            return;
+        }
+
+        match result {
+            Ok(text) => {
+                use crate::utils::parse_subtitles;
+
+                self.cues = parse_subtitles(&text);
+
+                if self.cues.is_empty() {
+                    warn!("No subtitles found in \"{}\" track.", label);
+                }
+            },
+
+            Err(e) => error!("Failed to load subtitles: {}", e.error)
+        }
+    }
+
+    // Quality picker for the player controls, if the video has more than one stream.
+    // The choice is saved as the stream quality setting, so later videos use it too.
+    fn get_quality_picker(&self, instance: &PomeloInstance) -> Option<iced::Element<Msg>> {
+        use iced::widget::PickList;
+
+        // The data saver decides the quality while it's on.
+        if self.streams.len() < 2 || instance.settings().data_saver() {
+            return None;
+        }
+
+        let mut options = vec![StreamQuality::Auto];
+
+        options.extend(
+            self.streams.iter()
+                .filter(|(height, _)| *height > 0)
+                .map(|(height, _)| StreamQuality::Height(*height))
+        );
+
+        options.dedup();
+
+        let selected = match instance.settings().stream_quality() {
+            StreamQuality::Auto => StreamQuality::Auto,
+            _ => StreamQuality::Height(self.streams[self.stream_index].0)
+        };
+
+        Some(
+            PickList::new(options, Some(selected), |quality| VideoPlayerMessage::SelectQuality(quality).into())
+                .width(100)
+                .into()
+        )
+    }
+
+    // Language picker for the player controls, if the video has any subtitles.
+    fn get_subtitle_picker(&self) -> Option<iced::Element<Msg>> {
+        use iced::widget::PickList;
+
+        if self.subtitles.is_empty() {
+            return None;
+        }
+
+        let options: Vec<String> = std::iter::once(String::from(SUBTITLES_OFF))
+            .chain(self.subtitles.iter().map(|track| track.label.clone()))
+            .collect();
+
+        let selected = self.subtitle_label.clone()
+            .filter(|label| self.subtitles.iter().any(|track| &track.label == label))
+            .unwrap_or_else(|| String::from(SUBTITLES_OFF));
+
+        Some(
+            PickList::new(options, Some(selected), |label| VideoPlayerMessage::SelectSubtitles(label).into())
+                .width(150)
+                .into()
+        )
+    }
+
+    // Subtitle lines for the current position, along the bottom of the video.
+    fn get_subtitle_element(&self) -> Option<iced::Element<Msg>> {
+        use iced::widget::{container, Container, Text};
+
+        let lines: Vec<&str> = self.cues.iter()
+            .filter(|(start, end, _)| (*start..*end).contains(&self.video_position))
+            .map(|(_, _, text)| text.as_str())
+            .collect();
+
+        if lines.is_empty() {
+            return None;
+        }
+
+        let caption = container(Text::new(lines.join("\n")).size(22).center())
+            .padding([4, 10])
+            .style(|_theme| container::Style {
+                background: Some(iced::Color::from_rgba(0.0, 0.0, 0.0, 0.7).into()),
+                text_color: Some(iced::Color::WHITE),
+                ..Default::default()
+            });
+
+        Some(
+            Container::new(caption)
+                .width(iced::Length::Fill)
+                .height(iced::Length::Fill)
+                .align_x(iced::Alignment::Center)
+                .align_y(iced::Alignment::End)
+                .padding(30)
+                .into()
+        )
+    }
+
+    // Overlay shown while playback is stalled, with dots that move so it doesn't look frozen.
+    fn get_stall_element(&self, stall: Stall) -> iced::Element<Msg> {
+        use iced::widget::{center, container, Text};
+
+        let reason = match stall {
+            Stall::Network => "Buffering, waiting for the network",
+            Stall::Decoder => "Playback is behind, the video can't be decoded fast enough"
+        };
+
+        let dots = ".".repeat(self.stall_ticks % 3 + 1);
+
+        center(
+            container(Text::new(format!("{}{:<3}", reason, dots)))
+                .padding(10)
+                .style(container::rounded_box)
+        ).into()
+    }
+
+    // Title, channel, and thumbnail of the video that just started, in the top left corner of the video.
+    fn get_now_playing_element(&self, instance: &PomeloInstance) -> Option<iced::Element<Msg>> {
+        use iced::widget::{column, container, row, Container, Text};
+
+        let now_playing = self.now_playing.as_ref()
+            .filter(|now_playing| now_playing.shown_at.elapsed() < NOW_PLAYING_DURATION)?;
+
+        let thumbnail = (!now_playing.from_computer)
+            .then(|| super::thumbnail(instance.cache(), &now_playing.video, &now_playing.title, 120.0, 68.0))
+            .flatten()
+            .map(|thumbnail| Container::new(thumbnail).width(120).height(68));
+
+        let popup = row![]
+            .push_maybe(thumbnail)
+            .push(
+                column![
+                    Text::new("Now playing").size(12),
+                    Text::new(now_playing.title.clone()),
+                ]
+                .push_maybe(now_playing.channel.clone().map(|channel| Text::new(channel).size(12)))
+                .spacing(2)
+            )
+            .spacing(10)
+            .align_y(iced::Alignment::Center);
+
+        Some(
+            Container::new(
+                container(popup)
+                    .padding(10)
+                    .max_width(500)
+                    .style(container::rounded_box)
+            )
+            .padding(20)
+            .width(iced::Length::Fill)
+            .height(iced::Length::Fill)
+            .align_x(iced::alignment::Horizontal::Left)
+            .align_y(iced::alignment::Vertical::Top)
+            .into()
+        )
+    }
+
+    // Pop up the video's details if it followed another video, and the popup is turned on.
+    fn show_now_playing(&mut self, index: usize, loaded: &LoadedVideo, instance: &PomeloInstance) {
+        let played_before = std::mem::replace(&mut self.played_before, true);
+
+        self.now_playing = None;
+
+        if !played_before || !instance.settings().now_playing_popup() {
+            return;
+        }
+
+        if let Some((video, from_computer)) = self.videos.get(index) {
+            self.now_playing = Some(
+                NowPlaying {
+                    video: video.clone(),
+                    from_computer: *from_computer,
+                    title: loaded.title.clone(),
+                    channel: loaded.channel.clone(),
+                    shown_at: std::time::Instant::now()
+                }
+            );
+        }
     }
 
     fn skip_timer_update(&mut self, time: u8, index: usize) -> Task<Msg> {
@@ -403,6 +2150,302 @@ impl VideoPlayerPage {
     }
 }
 
+// Number of Invidious instances to try before falling back to yt-dlp.
+const STREAM_RETRY_INSTANCES: usize = 3;
+
+// Find a working stream for a Youtube video.
+// Some instances hand out stream urls that don't work, so each url is checked before it's used.
+// In race mode, all instances are asked at once first, and the rest are only tried one by one if that fails.
+// If none of the instances work, yt-dlp is used instead.
+async fn resolve_stream(id: &str, instances: &[String], race: bool, config_args: &[String], quality: StreamQuality, window_height: f32) -> Result<LoadedVideo, PomeloError> {
+    use crate::yt_fetch::{race_video_details, Fetcher, VideoFetcher};
+
+    if race {
+        // Caption urls work on any instance, so the first one is used no matter which instance answered.
+        let result = match race_video_details(instances, id).await {
+            Ok(details) => stream_from_details(details, &instances[0], quality, window_height).await,
+            Err(e) => Err(PomeloError::new(e))
+        };
+
+        match result {
+            Ok(loaded) => return Ok(loaded),
+            Err(e) => warn!("Failed to get stream from racing instances: {}", e.error)
+        }
+    }
+
+    for instance in instances {
+        let result = match Fetcher::new(instance.clone()).get_video_details(id).await {
+            Ok(details) => stream_from_details(details, instance, quality, window_height).await,
+            Err(e) => Err(PomeloError::new(e))
+        };
+
+        match result {
+            Ok(loaded) => return Ok(loaded),
+            Err(e) => warn!("Failed to get stream from {}: {}", instance, e.error)
+        }
+    }
+
+    info!("Falling back to yt-dlp for stream url.");
+
+    resolve_stream_yt_dlp(id, config_args).await
+}
+
+// Put GStreamer's scaletempo filter in front of the audio sink, so changing the speed doesn't change the pitch.
+// Without it, sped up audio is just resampled. Playbin only links a new audio filter when it's set up again,
+// so the pipeline goes back to Ready for a moment. Only meant for streams that were just opened.
+fn add_pitch_correction(video: &Video) -> Result<(), PomeloError> {
+    use gstreamer::prelude::{ElementExt, ObjectExt, ToValue};
+
+    let filter = gstreamer::ElementFactory::make("scaletempo").build().map_err(PomeloError::new)?;
+
+    let pipeline = video.pipeline();
+    let state = pipeline.current_state();
+
+    pipeline.set_state(gstreamer::State::Ready).map_err(PomeloError::new)?;
+    pipeline.set_property_from_value("audio-filter", &filter.to_value());
+    pipeline.set_state(state).map_err(PomeloError::new)?;
+
+    Ok(())
+}
+
+// Bytes read so far by the pipeline's source, if it's playbin reading over the network or a file.
+fn stream_bytes_read(video: &Video) -> Option<u64> {
+    use gstreamer::prelude::{ElementExtManual, ObjectExt};
+
+    let pipeline = video.pipeline();
+    pipeline.find_property("source")?;
+
+    pipeline.property::<Option<gstreamer::Element>>("source")?
+        .query_position::<gstreamer::format::Bytes>()
+        .map(|bytes| *bytes)
+}
+
+// How far into the video the stream has been downloaded, from GStreamer's buffering query. Local files don't answer it.
+fn buffered_until(video: &Video) -> Option<f64> {
+    use gstreamer::prelude::ElementExtManual;
+
+    // Percent values in GStreamer queries go up to a million, not a hundred.
+    const PERCENT_MAX: f64 = 1_000_000.0;
+
+    let mut query = gstreamer::query::Buffering::new(gstreamer::Format::Percent);
+
+    if !video.pipeline().query(&mut query) {
+        return None;
+    }
+
+    let (_, stop, _) = query.range();
+
+    (stop.value() > 0).then(|| video.duration().as_secs_f64() * (stop.value() as f64 / PERCENT_MAX).min(1.0))
+}
+
+// Pick a stream from the video's info, and make sure it can actually be played.
+// Caption urls are relative, so they're joined onto the instance the details came from.
+async fn stream_from_details(details: VideoDetails, instance: &str, quality: StreamQuality, window_height: f32) -> Result<LoadedVideo, PomeloError> {
+    use crate::utils::parse_chapters;
+
+    let mut streams: Vec<(u32, Url)> = details.format_streams.iter()
+        .filter_map(|stream| Url::parse(&stream.url).ok().map(|url| (stream_height(&stream.resolution), url)))
+        .collect();
+
+    streams.sort_by_key(|(height, _)| *height);
+
+    if streams.is_empty() {
+        return Err(PomeloError::from("No streams available."));
+    }
+
+    let selected = pick_stream(&streams, quality, window_height);
+
+    let related = details.recommended_videos.iter()
+        .map(|video| RelatedVideo {
+            id: video.id.clone(),
+            title: video.title.clone(),
+            author: video.author.clone(),
+            author_id: video.author_id.clone()
+        })
+        .collect();
+
+    let chapters = parse_chapters(&details.description);
+
+    let subtitles = details.captions.iter()
+        .map(|caption| SubtitleTrack {
+            label: caption.label.clone(),
+            location: format!("{}{}", instance, caption.url)
+        })
+        .collect();
+
+    check_stream(&streams[selected].1).await.map(|_|
+        LoadedVideo {
+            streams,
+            selected,
+            live: details.live,
+            title: details.title,
+            channel_id: Some(details.author_id),
+            channel: Some(details.author),
+            related,
+            chapters,
+            subtitles
+        }
+    )
+}
+
+// Subtitle files next to a local video, named like yt-dlp names them: "<video name>.<language>.vtt".
+// A file with just the video's name and a subtitle extension is picked up too.
+fn local_subtitles(url: &Url) -> Vec<SubtitleTrack> {
+    let Ok(path) = url.to_file_path() else {
+        return Vec::new();
+    };
+
+    let (Some(dir), Some(stem)) = (path.parent(), path.file_stem().and_then(|stem| stem.to_str())) else {
+        return Vec::new();
+    };
+
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut tracks: Vec<SubtitleTrack> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let rest = name.strip_prefix(stem)?.strip_prefix('.')?;
+
+            let language = rest.strip_suffix(".vtt").or(rest.strip_suffix(".srt"));
+
+            let label = match language {
+                Some(language) => String::from(language),
+                None if rest == "vtt" || rest == "srt" => String::from("Default"),
+                None => return None
+            };
+
+            Some(SubtitleTrack { label, location: entry.path().to_string_lossy().into_owned() })
+        })
+        .collect();
+
+    tracks.sort_by(|a, b| a.label.cmp(&b.label));
+    tracks
+}
+
+// Get a subtitle file's text, from Invidious or from the computer.
+async fn read_subtitles(location: &str) -> Result<String, PomeloError> {
+    if location.starts_with("http") {
+        let response = reqwest::get(location).await
+            .and_then(|response| response.error_for_status())
+            .map_err(PomeloError::new)?;
+
+        response.text().await.map_err(PomeloError::new)
+    }
+    else {
+        std::fs::read_to_string(location).map_err(PomeloError::new)
+    }
+}
+
+// Height of a stream from its resolution label, e.g. "720p". Unknown resolutions are 0.
+fn stream_height(resolution: &str) -> u32 {
+    resolution.trim_end_matches('p').parse().unwrap_or_default()
+}
+
+// Pick a stream from a list sorted by height.
+// Auto picks the smallest stream that's at least as tall as the window, or the tallest stream if none are.
+fn pick_stream(streams: &[(u32, Url)], quality: StreamQuality, window_height: f32) -> usize {
+    let last = streams.len().saturating_sub(1);
+
+    match quality {
+        StreamQuality::Highest => last,
+        StreamQuality::Lowest => 0,
+        StreamQuality::Height(max) => streams.iter()
+            .rposition(|(height, _)| *height <= max)
+            .unwrap_or(0),
+        StreamQuality::Auto => streams.iter()
+            .position(|(height, _)| *height as f32 >= window_height)
+            .unwrap_or(last)
+    }
+}
+
+// Make sure the stream url actually responds before handing it to the player.
+async fn check_stream(url: &Url) -> Result<(), PomeloError> {
+    let response = reqwest::Client::new()
+        .get(url.as_str())
+        .header(reqwest::header::RANGE, "bytes=0-0")
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(PomeloError::new)?;
+
+    if response.status().is_success() {
+        Ok(())
+    }
+    else {
+        Err(PomeloError::from(format!("Stream responded with {}", response.status())))
+    }
+}
+
+// Ask yt-dlp for the video's most replayed heatmap, as (start, end, value).
+// Videos without enough views don't have one, and yt-dlp prints "NA" for them.
+async fn get_heatmap(id: &str, config_args: &[String]) -> Result<Vec<(f64, f64, f64)>, PomeloError> {
+    use crate::app::instance::{yt_dlp_command, yt_dlp_path};
+
+    #[derive(serde::Deserialize)]
+    struct HeatmapPoint {
+        start_time: f64,
+        end_time: f64,
+        value: f64
+    }
+
+    let output = tokio::process::Command::from(yt_dlp_command(&yt_dlp_path()))
+        .args(config_args)
+        .args(["--no-warnings", "--skip-download", "--print", "%(heatmap)j", "--", id])
+        .output()
+        .await
+        .map_err(PomeloError::new)?;
+
+    if !output.status.success() {
+        return Err(PomeloError::from(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    match stdout.trim() {
+        "NA" | "null" | "" => Ok(Vec::new()),
+        json => serde_json::from_str::<Vec<HeatmapPoint>>(json)
+            .map(|points| points.into_iter()
+                .map(|point| (point.start_time, point.end_time, point.value.clamp(0.0, 1.0)))
+                .collect()
+            )
+            .map_err(PomeloError::new)
+    }
+}
+
+// Ask yt-dlp for the video's title, live status, channel, and stream url.
+async fn resolve_stream_yt_dlp(id: &str, config_args: &[String]) -> Result<LoadedVideo, PomeloError> {
+    use crate::app::instance::{yt_dlp_command, yt_dlp_path};
+
+    let output = tokio::process::Command::from(yt_dlp_command(&yt_dlp_path()))
+        .args(config_args)
+        .args(["--no-warnings", "-f", "b", "--print", "title", "--print", "is_live", "--print", "channel_id", "--print", "channel", "--print", "urls", "--", id])
+        .output()
+        .await
+        .map_err(PomeloError::new)?;
+
+    if !output.status.success() {
+        return Err(PomeloError::from(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines();
+
+    match (lines.next(), lines.next(), lines.next(), lines.next(), lines.next()) {
+        (Some(title), Some(live), Some(channel_id), Some(channel), Some(url)) => Url::parse(url)
+            .map(|url| LoadedVideo {
+                channel_id: Some(String::from(channel_id)),
+                channel: Some(String::from(channel)),
+                ..LoadedVideo::single(url, live == "True", String::from(title))
+            })
+            .map_err(PomeloError::new),
+
+        _ => Err(PomeloError::from("Yt-dlp did not return a stream url."))
+    }
+}
+
 impl VideoPlayerPage {
     pub (crate) fn new(mut videos: VecDeque<(String, bool)>, order: super::VideoOrder) -> Self {
         use super::VideoOrder;
@@ -426,17 +2469,93 @@ impl VideoPlayerPage {
             video_paused: false,
             video_position: 0.0,
             video_volume: 0.5,
+            speed: PlaybackSpeed::default(),
+            channel_speed: None,
+            channel_quality: None,
+            volume_offset: 0.0,
+            pitch_correction: true,
             seeking: false,
             skip_timer: None,
             auto_skipping: false,
-            skip_time: 0
+            skip_time: 0,
+            channel_queue: None,
+            streams: Vec::new(),
+            stream_index: 0,
+            live: false,
+            started: false,
+            finished: false,
+            related: Vec::new(),
+            chapters: Vec::new(),
+            show_chapters: false,
+            repeat: RepeatMode::Off,
+            titles: HashMap::new(),
+            show_queue: false,
+            url_input: String::new(),
+            url_error: None,
+            subtitles: Vec::new(),
+            subtitle_label: None,
+            cues: Vec::new(),
+            show_transcript: false,
+            transcript_query: String::new(),
+            segments: Vec::new(),
+            skipped_segments: Vec::new(),
+            last_skip: None,
+            segments_handle: None,
+            heatmap: Vec::new(),
+            heatmap_handle: None,
+            start_position: None,
+            resumed_from: None,
+            saved_position: 0.0,
+            channel: None,
+            unsaved_watch_time: 0.0,
+            last_frame: None,
+            last_clock: 0.0,
+            stall: None,
+            stall_ticks: 0,
+            buffered_until: None,
+            stream_bytes: None,
+            last_mouse_move: std::time::Instant::now(),
+            now_playing: None,
+            played_before: false
         }
     }
 
+    // Player with nothing loaded, used for the Now Playing page.
+    pub (crate) fn empty() -> Self {
+        Self::new(VecDeque::new(), super::VideoOrder::Sequential(0))
+    }
+
+    // Keep adding uploads from a channel as the queue advances.
+    pub (crate) fn with_channel_queue(mut self, queue: ChannelQueue) -> Self {
+        self.channel_queue = Some(queue);
+        self
+    }
+
+    // Titles for the videos in the list, as (id, title), so the queue panel can show them before they're loaded.
+    pub (crate) fn with_titles(mut self, titles: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.titles.extend(titles);
+        self
+    }
+
+    // Start the first video from the given position, in seconds.
+    pub (crate) fn with_start_position(mut self, position: f64) -> Self {
+        self.start_position = Some(position);
+        self
+    }
+
     fn is_video_playing(&self) -> bool {
         if let Some(Ok(video)) = &self.current_video {
             return !video.paused();
         }
         false
     }
+
+    // In fullscreen, only the video is shown while it's playing and the mouse is still.
+    // The controls stay put with reduced motion on.
+    fn controls_hidden(&self, instance: &PomeloInstance) -> bool {
+        !instance.settings().reduced_motion()
+            && instance.is_fullscreen()
+            && self.is_video_playing()
+            && self.last_mouse_move.elapsed() > CONTROLS_TIMEOUT
+    }
 }
\ No newline at end of file
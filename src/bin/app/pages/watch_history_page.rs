@@ -0,0 +1,254 @@
+use iced::{Element, Length, Task};
+use iced::widget::{column, row, Button, Column, Scrollable, Text, TextInput};
+
+use log::{error, info};
+
+use crate::app::PomeloError;
+use crate::app::instance::archive::{ExportedWatch, WatchEntry, MIN_RESUME_POSITION};
+use crate::utils::{csv_row, format_relative_time, secs_to_timestamp};
+
+use super::{ConditionalMessage, ErrorBanner, FillElement, PomeloInstance, PomeloPage, Navigation, Msg};
+
+#[derive(Debug, Clone)]
+pub (crate) enum WatchHistoryMessage {
+    Search(String),
+    Play(usize),
+    Resume(usize),
+    ClearHistory,
+    ToStats,
+    Export
+}
+
+impl ConditionalMessage for WatchHistoryMessage {}
+
+impl From<WatchHistoryMessage> for Msg {
+    fn from(value: WatchHistoryMessage) -> Self {
+        Self::WatchHistory(value)
+    }
+}
+
+// List of played videos, newest first. Videos can be played again from the start, or from where they were left off.
+pub (crate) struct WatchHistoryPage {
+    search: String,
+    entries: Vec<WatchEntry>,
+    status: Option<String>,
+    error: Option<ErrorBanner>
+}
+
+impl WatchHistoryPage {
+    pub (crate) fn new(instance: &PomeloInstance) -> Self {
+        Self {
+            search: String::new(),
+            entries: instance.archive().watch_history(""),
+            status: None,
+            error: None
+        }
+    }
+
+    // Save the whole watch history to a CSV or JSON file chosen by the user, depending on its extension.
+    // Only allowed while the watch history is recorded, so turning it off also keeps it from being exported.
+    fn export(&mut self, instance: &PomeloInstance) {
+        use rfd::FileDialog;
+
+        if !instance.settings().record_watch_history() {
+            return;
+        }
+
+        let maybe_path = FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .add_filter("JSON", &["json"])
+            .set_file_name("pomelo-watch-history.csv")
+            .save_file();
+
+        let Some(path) = maybe_path else {
+            return;
+        };
+
+        let json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+
+        let result = instance.archive().export_watch_history()
+            .and_then(|entries| if json {
+                serde_json::to_string_pretty(&entries).map_err(PomeloError::new)
+            } else {
+                Ok(history_to_csv(&entries))
+            })
+            .and_then(|text| std::fs::write(&path, text).map_err(PomeloError::new));
+
+        match result {
+            Ok(_) => {
+                info!("Watch history exported to: {:?}", path);
+                self.status = Some(format!("Watch history saved to {}", path.display()));
+            },
+
+            Err(e) => {
+                error!("Failed to export the watch history: {}", e.error);
+                self.error = Some(ErrorBanner::new(e, Some(WatchHistoryMessage::Export.into())));
+            }
+        }
+    }
+
+    // Play the entry's video, starting from the given position.
+    fn play(&self, index: usize, position: f64) -> (Task<Msg>, Navigation) {
+        use std::collections::VecDeque;
+        use super::VideoOrder;
+        use super::video_player_page::{VideoPlayerMessage, VideoPlayerPage};
+
+        let Some(entry) = self.entries.get(index) else {
+            return (Task::none(), Navigation::None);
+        };
+
+        let page = VideoPlayerPage::new(
+            VecDeque::from([(entry.video.clone(), entry.local)]),
+            VideoOrder::Sequential(0)
+        ).with_start_position(position);
+
+        (
+            Task::done(VideoPlayerMessage::LoadVideo(0).into()),
+            Navigation::GoTo(Box::new(page))
+        )
+    }
+}
+
+impl PomeloPage for WatchHistoryPage {
+    fn update(&mut self, instance: &mut PomeloInstance, message: Msg) -> (Task<Msg>, Navigation) {
+        match message {
+            Msg::Back => return (Task::none(), Navigation::Back),
+            Msg::DismissError => self.error = None,
+
+            Msg::WatchHistory(msg) => match msg {
+                WatchHistoryMessage::Search(text) => {
+                    self.entries = instance.archive().watch_history(&text);
+                    self.search = text;
+                },
+
+                WatchHistoryMessage::Play(index) => return self.play(index, 0.0),
+
+                WatchHistoryMessage::Resume(index) => {
+                    let position = self.entries.get(index).map_or(0.0, |entry| entry.position);
+                    return self.play(index, position);
+                },
+
+                WatchHistoryMessage::ClearHistory => {
+                    instance.archive_mut().clear_watch_history();
+                    self.entries.clear();
+                },
+
+                WatchHistoryMessage::Export => self.export(instance),
+
+                WatchHistoryMessage::ToStats => return (
+                    Task::none(),
+                    Navigation::GoTo(Box::new(super::watch_stats_page::WatchStatsPage::new(instance)))
+                )
+            },
+
+            _ => ()
+        }
+
+        (Task::none(), Navigation::None)
+    }
+
+    fn view(&self, instance: &PomeloInstance) -> Element<Msg> {
+        let locale = instance.settings().locale();
+        let now = chrono::Local::now().timestamp();
+
+        let entries = Column::with_children(
+            self.entries.iter().enumerate().map(|(i, entry)| {
+                let watched = format_relative_time((now - entry.watched_at).max(0) as u64, locale);
+                let can_resume = entry.position >= MIN_RESUME_POSITION;
+
+                row![
+                    column![
+                        Text::new(entry.title.clone()),
+                        Text::new(
+                            if can_resume {
+                                format!("{} | Stopped at {}", watched, secs_to_timestamp(entry.position as u64, entry.position >= 3600.0))
+                            }
+                            else {
+                                watched
+                            }
+                        ).size(12)
+                    ].width(Length::Fill),
+
+                    Button::new(Text::new("Play").size(12).center())
+                        .width(70)
+                        .on_press(WatchHistoryMessage::Play(i).into()),
+
+                    Button::new(Text::new("Resume").size(12).center())
+                        .width(70)
+                        .on_press_maybe(WatchHistoryMessage::Resume(i).on_condition(can_resume))
+                ].spacing(5).align_y(iced::Alignment::Center).into()
+            })
+        ).spacing(10);
+
+        let list: Element<Msg> = if self.entries.is_empty() {
+            Text::new(if self.search.is_empty() { "Nothing has been watched yet." } else { "No matches." }).into()
+        }
+        else {
+            Scrollable::new(entries)
+                .width(Length::Fill)
+                .height(instance.settings().window_size().1 / 2.0)
+                .into()
+        };
+
+        Column::new()
+            .push_maybe(self.error.as_ref().map(|banner| banner.view()))
+            .push(Text::new("Watch History").size(20))
+            .push(
+                TextInput::new("Search", &self.search)
+                    .on_input(|text| WatchHistoryMessage::Search(text).into())
+                    .width(400)
+            )
+            .push(list)
+            .push(
+                row![
+                    Button::new(Text::new("Clear History").center())
+                        .width(150)
+                        .on_press_maybe(WatchHistoryMessage::ClearHistory.on_condition(!self.entries.is_empty())),
+
+                    Button::new(Text::new("Watch Time").center())
+                        .width(120)
+                        .on_press(WatchHistoryMessage::ToStats.into()),
+
+                    Button::new(Text::new("Export").center())
+                        .width(100)
+                        .on_press_maybe(WatchHistoryMessage::Export.on_condition(instance.settings().record_watch_history())),
+
+                    Button::new(Text::new("Back").center())
+                        .width(100)
+                        .on_press(Msg::Back)
+                ].spacing(10)
+            )
+            .push_maybe(self.status.as_deref().map(Text::new))
+            .push_maybe(
+                (!instance.settings().record_watch_history())
+                    .then(|| Text::new("Watch history is turned off in Settings, and can't be exported.").size(12))
+            )
+            .spacing(10)
+            .padding(25)
+            .align_x(iced::Alignment::Center)
+            .fill()
+    }
+
+    fn subscription(&self, _instance: &PomeloInstance) -> iced::Subscription<Msg> {
+        iced::Subscription::none()
+    }
+}
+
+// Header line followed by one line per entry. Times are Unix timestamps, and watch time is in whole seconds.
+fn history_to_csv(entries: &[ExportedWatch]) -> String {
+    let mut csv = csv_row(&["video_id", "title", "channel", "watched_at", "seconds_watched"]);
+    csv.push('\n');
+
+    for entry in entries {
+        csv.push_str(&csv_row(&[
+            &entry.video,
+            &entry.title,
+            entry.channel.as_deref().unwrap_or_default(),
+            &entry.watched_at.to_string(),
+            &(entry.seconds_watched as u64).to_string()
+        ]));
+        csv.push('\n');
+    }
+
+    csv
+}
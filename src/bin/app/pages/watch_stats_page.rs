@@ -0,0 +1,111 @@
+use iced::{Element, Length, Task};
+use iced::widget::{column, row, Button, Column, ProgressBar, Scrollable, Text};
+
+use super::{FillElement, PomeloInstance, PomeloPage, Navigation, Msg};
+
+// Days of watch time shown on the stats page, counting today.
+const STATS_DAYS: u32 = 30;
+
+// Channels listed under the daily watch time.
+const TOP_CHANNELS: usize = 10;
+
+// Watch time for each of the last days, and the channels watched the longest. Everything is read from the archive.
+pub (crate) struct WatchStatsPage {
+    days: Vec<u64>,
+    channels: Vec<(String, f64)>
+}
+
+impl WatchStatsPage {
+    pub (crate) fn new(instance: &PomeloInstance) -> Self {
+        let watched = instance.archive().daily_watch_time(STATS_DAYS);
+        let today = chrono::Local::now().date_naive();
+
+        // Oldest day first, with days nothing was watched on left at zero.
+        let days = (0..STATS_DAYS as i64).rev()
+            .map(|ago| (today - chrono::Duration::days(ago)).format("%Y-%m-%d").to_string())
+            .map(|day| watched.iter().find(|(d, _)| *d == day).map_or(0, |(_, seconds)| *seconds as u64))
+            .collect();
+
+        Self {
+            days,
+            channels: instance.archive().top_channels(TOP_CHANNELS)
+        }
+    }
+}
+
+impl PomeloPage for WatchStatsPage {
+    fn update(&mut self, _instance: &mut PomeloInstance, message: Msg) -> (Task<Msg>, Navigation) {
+        if let Msg::Back = message {
+            return (Task::none(), Navigation::Back);
+        }
+
+        (Task::none(), Navigation::None)
+    }
+
+    fn view(&self, instance: &PomeloInstance) -> Element<Msg> {
+        let total: u64 = self.days.iter().sum();
+
+        let stats: Element<Msg> = if total == 0 && self.channels.is_empty() {
+            Text::new("Nothing has been watched yet.").into()
+        }
+        else {
+            Scrollable::new(
+                column![
+                    Text::new(format!("Last {} days: {}", STATS_DAYS, format_watch_time(total as f64))),
+                    super::bar_chart(&self.days, 60.0, 8.0),
+                    Text::new("Top channels"),
+                    channel_bars(&self.channels)
+                ].spacing(10)
+            )
+            .width(Length::Fill)
+            .height(instance.settings().window_size().1 / 2.0)
+            .into()
+        };
+
+        column![
+            Text::new("Watch Time").size(20),
+            stats,
+            Button::new(Text::new("Back").center())
+                .width(100)
+                .on_press(Msg::Back)
+        ]
+        .push_maybe(
+            (!instance.settings().record_watch_history())
+                .then(|| Text::new("Watch history is turned off in Settings, so nothing new is recorded.").size(12))
+        )
+        .spacing(10)
+        .padding(25)
+        .align_x(iced::Alignment::Center)
+        .fill()
+    }
+
+    fn subscription(&self, _instance: &PomeloInstance) -> iced::Subscription<Msg> {
+        iced::Subscription::none()
+    }
+}
+
+// A labelled bar for each (channel, seconds) pair, scaled to the longest one.
+fn channel_bars<'a>(bars: &[(String, f64)]) -> Element<'a, Msg> {
+    let longest = bars.iter().map(|(_, seconds)| *seconds).fold(1.0, f64::max) as f32;
+
+    Column::with_children(
+        bars.iter().map(|(label, seconds)| {
+            row![
+                Text::new(label.clone()).size(12).width(150),
+                ProgressBar::new(0.0..=longest, *seconds as f32).height(12).width(Length::Fill),
+                Text::new(format_watch_time(*seconds)).size(12).width(70)
+            ].spacing(10).align_y(iced::Alignment::Center).into()
+        })
+    ).spacing(5).into()
+}
+
+// Watch time in hours and minutes, or seconds if it's under a minute.
+fn format_watch_time(seconds: f64) -> String {
+    let seconds = seconds as u64;
+
+    match (seconds / 3600, seconds / 60 % 60) {
+        (0, 0) => format!("{}s", seconds),
+        (0, minutes) => format!("{}m", minutes),
+        (hours, minutes) => format!("{}h {:02}m", hours, minutes)
+    }
+}
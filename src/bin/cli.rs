@@ -0,0 +1,141 @@
+// Headless commands, so scripts can use Pomelo's backend without opening a window.
+// e.g. "pomelo search "query" --type video --json"
+
+use log::{info, warn};
+
+use crate::yt_fetch::instances;
+use crate::yt_fetch::{FetchError, Fetcher, SearchType, VideoFetcher};
+
+// Number of instances to try before giving up on a search.
+const SEARCH_INSTANCES: usize = 3;
+
+const SEARCH_USAGE: &str = "Usage: pomelo search <query> [--type video|channel|playlist] [--page <n>] [--json]";
+
+// Search Youtube and print the results, one per line as "<id>\t<title>", or as a JSON array with "--json".
+// Returns false if the arguments were wrong or the search failed.
+pub (crate) fn search(args: &[String]) -> bool {
+    let mut query = None;
+    let mut search_type = SearchType::Video;
+    let mut page = 1;
+    let mut json = false;
+
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+
+            "--type" => match args.next().map(String::as_str) {
+                Some("video") => search_type = SearchType::Video,
+                Some("channel") => search_type = SearchType::Channel,
+                Some("playlist") => search_type = SearchType::Playlist,
+                _ => {
+                    eprintln!("{}", SEARCH_USAGE);
+                    return false;
+                }
+            },
+
+            "--page" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(n) if n > 0 => page = n,
+                _ => {
+                    eprintln!("{}", SEARCH_USAGE);
+                    return false;
+                }
+            },
+
+            _ if query.is_none() => query = Some(arg.clone()),
+
+            _ => {
+                eprintln!("{}", SEARCH_USAGE);
+                return false;
+            }
+        }
+    }
+
+    let Some(query) = query else {
+        eprintln!("{}", SEARCH_USAGE);
+        return false;
+    };
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Failed to start: {}", e);
+            return false;
+        }
+    };
+
+    info!("Searching from the command line: {}", query);
+
+    match runtime.block_on(search_any_instance(&query, search_type, page)) {
+        Ok(results) => {
+            if json {
+                println!("{}", serde_json::Value::Array(results));
+            }
+            else {
+                for result in results {
+                    let id = result["id"].as_str().unwrap_or_default();
+                    let title = result["title"].as_str().unwrap_or_default();
+                    println!("{}\t{}", id, title);
+                }
+            }
+
+            true
+        },
+
+        Err(e) => {
+            eprintln!("Search failed: {}", e);
+            false
+        }
+    }
+}
+
+// Try the first few instances in order until one of them answers.
+async fn search_any_instance(query: &str, search_type: SearchType, page: usize) -> Result<Vec<serde_json::Value>, FetchError> {
+    use invidious::hidden::SearchItem;
+    use serde_json::json;
+
+    let mut error = FetchError::from("No instances to query.");
+
+    for instance in instances::all().into_iter().take(SEARCH_INSTANCES).map(|instance| instance.url) {
+        let search = match Fetcher::new(instance.clone()).search(query, search_type, page).await {
+            Ok(search) => search,
+            Err(e) => {
+                warn!("Search on {} failed: {}", instance, e);
+                error = e;
+                continue;
+            }
+        };
+
+        // Channels use "title" for their name, so every kind of result can be printed the same way.
+        return Ok(
+            search.items.into_iter()
+                .map(|item| match item {
+                    SearchItem::Video(video) => json!({
+                        "type": "video",
+                        "id": video.id,
+                        "title": video.title,
+                        "author": video.author,
+                        "length": video.length
+                    }),
+
+                    SearchItem::Channel(channel) => json!({
+                        "type": "channel",
+                        "id": channel.id,
+                        "title": channel.name
+                    }),
+
+                    SearchItem::Playlist(playlist) => json!({
+                        "type": "playlist",
+                        "id": playlist.id,
+                        "title": playlist.title,
+                        "author": playlist.author,
+                        "video_count": playlist.video_count
+                    })
+                })
+                .collect()
+        );
+    }
+
+    Err(error)
+}
@@ -0,0 +1,216 @@
+// Logger that writes to ./logs/<date>/log-<date>-<time>.txt, starting a new file once the current one is too big.
+// Old logs are deleted once they're older than the max age, or once all of them together take up more than the max total size.
+// The per-download yt-dlp logs in ./logs/downloads count towards the limits too.
+// The limits come from the settings, which are loaded after logging starts, so the defaults are used until then.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
+
+use chrono::Local;
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::LOG_DIR;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub (crate) struct LogLimits {
+    // Size a log file can reach before a new one is started, and the most space all logs can take up, in bytes.
+    pub (crate) max_file_size: u64,
+    pub (crate) max_total_size: u64,
+    pub (crate) max_age: Duration
+}
+
+impl Default for LogLimits {
+    fn default() -> Self {
+        Self {
+            max_file_size: 5 * 1024 * 1024,
+            max_total_size: 50 * 1024 * 1024,
+            max_age: Duration::from_secs(30 * 24 * 60 * 60)
+        }
+    }
+}
+
+struct LogFile {
+    file: File,
+    path: PathBuf,
+    size: u64
+}
+
+struct State {
+    current: Option<LogFile>,
+    limits: LogLimits
+}
+
+struct RotatingLogger {
+    level: LevelFilter,
+    state: Mutex<State>
+}
+
+impl Log for RotatingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        // The target is the module the line came from, so the diagnostics page can filter by it.
+        let line = format!(
+            "[{}] ({:?}) {:>5} {}: {}\n",
+            Local::now().format("%H:%M:%S%.3f"),
+            std::thread::current().id(),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+
+        let mut state = self.state.lock().unwrap();
+
+        let full = state.current.as_ref()
+            .is_some_and(|log| log.size > 0 && log.size + line.len() as u64 > state.limits.max_file_size);
+
+        if full {
+            state.rotate();
+        }
+
+        // Nowhere else to report it if the log can't be written to, so that's left to stderr.
+        match state.current.as_mut() {
+            Some(log) => match log.file.write_all(line.as_bytes()) {
+                Ok(_) => log.size += line.len() as u64,
+                Err(e) => eprintln!("Failed to write to log file: {}", e)
+            },
+            None => eprint!("{}", line)
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(log) = self.state.lock().unwrap().current.as_mut() {
+            let _ = log.file.flush();
+        }
+    }
+}
+
+impl State {
+    // Start a new log file, then delete old logs if they're over the limits.
+    fn rotate(&mut self) {
+        self.current = open_log_file()
+            .inspect_err(|e| eprintln!("Failed to start a new log file, logging to stderr instead: {}", e))
+            .ok();
+
+        self.prune();
+    }
+
+    // Delete logs older than the max age, then the oldest ones until the rest fit in the max total size.
+    // The current log is never deleted.
+    fn prune(&self) {
+        let current = self.current.as_ref().map(|log| log.path.as_path());
+        let now = SystemTime::now();
+
+        let mut logs: Vec<(PathBuf, SystemTime, u64)> = find_logs().into_iter()
+            .filter(|(path, _, _)| Some(path.as_path()) != current)
+            .collect();
+
+        // Newest first, so the total is counted from the logs that are kept longest.
+        logs.sort_by(|a, b| b.1.cmp(&a.1));
+
+        let mut total = self.current.as_ref().map_or(0, |log| log.size);
+
+        for (path, modified, size) in logs {
+            total += size;
+
+            let expired = now.duration_since(modified).unwrap_or_default() > self.limits.max_age;
+
+            if expired || total > self.limits.max_total_size {
+                total -= size;
+
+                if let Err(e) = std::fs::remove_file(&path) {
+                    eprintln!("Failed to delete old log {}: {}", path.display(), e);
+                    continue;
+                }
+
+                // Each day has its own folder, which is removed once it's empty. So is the downloads folder, which is made again when needed.
+                if let Some(dir) = path.parent() {
+                    let _ = std::fs::remove_dir(dir);
+                }
+            }
+        }
+    }
+}
+
+fn logger() -> &'static RotatingLogger {
+    static LOGGER: OnceLock<RotatingLogger> = OnceLock::new();
+
+    LOGGER.get_or_init(|| RotatingLogger {
+        level: LevelFilter::Info,
+        state: Mutex::new(State { current: None, limits: LogLimits::default() })
+    })
+}
+
+// Start logging to a new file. Lines go to stderr if the file can't be opened.
+pub (crate) fn init() {
+    let logger = logger();
+
+    logger.state.lock().unwrap().rotate();
+
+    if log::set_logger(logger).is_ok() {
+        log::set_max_level(logger.level);
+    }
+}
+
+// Use new limits, e.g. after the settings are loaded or changed. Logs that are over them are deleted right away.
+pub (crate) fn configure(limits: LogLimits) {
+    let mut state = logger().state.lock().unwrap();
+
+    if state.limits == limits {
+        return;
+    }
+
+    state.limits = limits;
+
+    match state.current.as_ref().is_some_and(|log| log.size > limits.max_file_size) {
+        true => state.rotate(),
+        false => state.prune()
+    }
+}
+
+fn open_log_file() -> Result<LogFile, std::io::Error> {
+    let now = Local::now();
+    let dir = format!("{}/{}", LOG_DIR, now.format("%F"));
+    // Milliseconds are included so a file that fills up within a second isn't opened again.
+    let path = PathBuf::from(format!("{}/log-{}.txt", dir, now.format("%F-%H-%M-%S-%3f")));
+
+    std::fs::create_dir_all(&dir)?;
+
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+    let size = file.metadata().map_or(0, |metadata| metadata.len());
+
+    Ok(LogFile { file, path, size })
+}
+
+// Every log file in the day folders and the downloads folder, with when it was last written to and its size.
+fn find_logs() -> Vec<(PathBuf, SystemTime, u64)> {
+    std::fs::read_dir(LOG_DIR)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .flat_map(|dir| std::fs::read_dir(dir.path()).into_iter().flatten())
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| is_log_name(&entry.path()))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok().filter(|metadata| metadata.is_file())?;
+            Some((entry.path(), metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH), metadata.len()))
+        })
+        .collect()
+}
+
+fn is_log_name(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| {
+        let name = name.to_string_lossy();
+        name.starts_with("log-") || name.starts_with("download-")
+    })
+}
@@ -3,72 +3,57 @@
  */
 
 mod app;
+mod cli;
+mod logger;
+mod protocol;
+mod taskbar;
 mod utils;
 mod yt_fetch;
 
 //mod iced_video_player;
 
-// List of instances to use for Invidious.
-// Only instances from the official Invidious docs are used.
-const INVID_INSTANCES: &[(&str, &str)] = &[
-    ("https://invidious.darkness.services", "USA"),
-    ("https://invidious.incogniweb.net", "USA"),
-    ("https://inv.in.projectsegfau.lt", "India"),
-    ("https://invidious.materialio.us", "New Zealand"),
-    ("https://invidious.reallyaweso.me", "Germany"),
-    ("https://invidious.privacyredirect.com", "Finland"),
-    ("https://invidious.jing.rocks", "Japan"),
-    ("https://inv.us.projectsegfau.lt", "USA"),
-    ("https://invidious.drgns.space", "USA"),
-    ("https://invidious.fdn.fr", "France"),
-    ("https://iv.datura.network", "Finland"),
-    ("https://yt.drgnz.club", "Czech Republic"),
-    ("https://invidious.private.coffee", "Austria"),
-    ("https://invidious.protokolla.fi", "Finland"),
-    ("https://inv.tux.pizza", "USA"),
-    ("https://inv.nadeko.net", "Chile"),
-    ("https://iv.melmac.space", "Germany"),
-    ("https://invidious.privacydev.net", "France"),
-    ("https://invidious.flokinet.to", "Romania"),
-    ("https://yt.artemislena.eu", "Germany"),
-    ("https://yewtu.be", "Germany")
-];
+// Folder that log files are written to, one subfolder per day.
+const LOG_DIR: &str = "./logs";
 
 fn main() {
-    use std::path::Path;
-    use std::time::SystemTime;
-    use log::{warn, LevelFilter};
-    use chrono::{DateTime, Utc};
     use app::PomeloApp;
 
-    // Get current timestamp and use it as the log's filename.
-    let datetime: DateTime<Utc> = SystemTime::now().into();
-    let date_str = datetime.format("%F");
-    let time_str = datetime.format("%H-%M-%S");
-    let log_dir = format!("./logs/{}", date_str);
-    let log_file = format!(
-        "{}/log-{}-{}.txt",
-        log_dir,
-        date_str,
-        time_str
-    );
+    // "--register-protocol" sets Pomelo as the handler for pomelo:// links, then exits.
+    // "search" is handled by the cli module. Any other argument is treated as a link to open.
+    let arg = std::env::args().nth(1);
 
-    // Check for log directory, create it if it doesn't exist.
-    if !Path::exists(Path::new(&log_dir)) {
-        if let Err(e) = std::fs::create_dir_all(log_dir) {
-            warn!("Log directory could not be found or created: {}", e)
+    // Opened from a pomelo:// link, so the working directory is whatever the browser or OS used.
+    if arg.as_deref().is_some_and(protocol::is_link) {
+        protocol::use_exe_dir();
+    }
+
+    logger::init();
+
+    if arg.as_deref() == Some("--register-protocol") {
+        match protocol::register() {
+            Ok(_) => println!("Registered pomelo:// links."),
+            Err(e) => eprintln!("Failed to register pomelo:// links: {}", e)
+        }
+        return;
+    }
+
+    // "search" runs a search without opening a window, and prints the results.
+    if arg.as_deref() == Some("search") {
+        let args: Vec<String> = std::env::args().skip(2).collect();
+
+        if !cli::search(&args) {
+            std::process::exit(1);
         }
+        return;
     }
 
-    if let Err(e) = simple_logging::log_to_file(log_file, LevelFilter::Info) {
-        simple_logging::log_to_stderr(LevelFilter::Info);
-        warn!("Failed to setup log file. Logging to stderr instead: {}", e);
-    };
+    let video_id = arg.as_deref().and_then(protocol::video_id_from_link);
 
     // Run Pomelo
     match iced::daemon(PomeloApp::title, PomeloApp::update, PomeloApp::view)
         .subscription(PomeloApp::subscription)
-        .run_with(PomeloApp::new)
+        .theme(PomeloApp::theme)
+        .run_with(move || PomeloApp::new(video_id))
     {
         Ok(_) => println!("Goodbye!"),
         Err(e) => eprintln!("{}", e)
@@ -0,0 +1,121 @@
+// Support for opening "pomelo://" links, e.g. "pomelo://watch?v=<id>" or "pomelo://https://youtu.be/<id>".
+// The OS passes the link to Pomelo as a command line argument once the handler is registered.
+
+use log::{info, error};
+
+const SCHEME: &str = "pomelo";
+
+// Get the video id from a link passed on the command line. Plain Youtube links work too.
+pub (crate) fn video_id_from_link(link: &str) -> Option<String> {
+    use crate::utils::video_id_from_url;
+
+    let prefix = format!("{}://", SCHEME);
+
+    match link.strip_prefix(&prefix) {
+        Some(rest) if rest.starts_with("http") => video_id_from_url(rest),
+        Some(rest) => video_id_from_url(&format!("https://www.youtube.com/{}", rest))
+            .or_else(|| video_id_from_url(rest.trim_end_matches('/'))),
+        None => video_id_from_url(link)
+    }
+}
+
+// Whether a command line argument is a pomelo:// link.
+pub (crate) fn is_link(arg: &str) -> bool {
+    arg.starts_with(&format!("{}://", SCHEME))
+}
+
+// Switch to the folder Pomelo's executable is in.
+// Links are opened by the browser or OS, which start Pomelo in their own working directory,
+// but settings.json, archive.db, the logs and yt-dlp are all found relative to it.
+pub (crate) fn use_exe_dir() {
+    let result = std::env::current_exe().and_then(|exe| match exe.parent() {
+        Some(dir) => std::env::set_current_dir(dir),
+        None => Ok(())
+    });
+
+    // Logging hasn't started yet.
+    if let Err(e) = result {
+        eprintln!("Failed to switch to Pomelo's folder: {}", e);
+    }
+}
+
+// Register Pomelo as the handler for pomelo:// links for the current user.
+pub (crate) fn register() -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let exe = exe.to_string_lossy();
+
+    info!("Registering {}:// handler: {}", SCHEME, exe);
+
+    let result = register_for_os(&exe);
+
+    if let Err(e) = &result {
+        error!("Failed to register {}:// handler: {}", SCHEME, e);
+    }
+
+    result
+}
+
+#[cfg(target_os = "windows")]
+fn register_for_os(exe: &str) -> Result<(), String> {
+    let key = format!("HKCU\\Software\\Classes\\{}", SCHEME);
+    let command = format!("\"{}\" \"%1\"", exe);
+
+    reg_add(&[&key, "/ve", "/d", "URL:Pomelo Protocol", "/f"])?;
+    reg_add(&[&key, "/v", "URL Protocol", "/d", "", "/f"])?;
+    reg_add(&[&format!("{}\\shell\\open\\command", key), "/ve", "/d", &command, "/f"])
+}
+
+#[cfg(target_os = "windows")]
+fn reg_add(args: &[&str]) -> Result<(), String> {
+    let status = std::process::Command::new("reg")
+        .arg("add")
+        .args(args)
+        .status()
+        .map_err(|e| e.to_string())?;
+
+    if status.success() {
+        Ok(())
+    }
+    else {
+        Err(format!("reg add exited with {}", status))
+    }
+}
+
+// Freedesktop: install a .desktop file that handles the scheme, then make it the default handler.
+#[cfg(not(target_os = "windows"))]
+fn register_for_os(exe: &str) -> Result<(), String> {
+    let home = std::env::var("HOME").map_err(|e| e.to_string())?;
+    let dir = format!("{}/.local/share/applications", home);
+    let desktop_file = format!("{}-handler.desktop", SCHEME);
+
+    // Path sets the working directory, which Pomelo's files are found relative to.
+    let exe_dir = std::path::Path::new(exe).parent().map(|dir| dir.to_string_lossy().into_owned()).unwrap_or_default();
+
+    let contents = format!(
+        "[Desktop Entry]\n\
+        Type=Application\n\
+        Name=Pomelo\n\
+        Exec=\"{}\" %u\n\
+        Path={}\n\
+        NoDisplay=true\n\
+        MimeType=x-scheme-handler/{};\n",
+        exe,
+        exe_dir,
+        SCHEME
+    );
+
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    std::fs::write(format!("{}/{}", dir, desktop_file), contents).map_err(|e| e.to_string())?;
+
+    let status = std::process::Command::new("xdg-mime")
+        .args(["default", &desktop_file, &format!("x-scheme-handler/{}", SCHEME)])
+        .status()
+        .map_err(|e| e.to_string())?;
+
+    if status.success() {
+        Ok(())
+    }
+    else {
+        Err(format!("xdg-mime exited with {}", status))
+    }
+}
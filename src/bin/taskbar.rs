@@ -0,0 +1,99 @@
+// Download progress on Pomelo's taskbar button, so it can be seen while the window is minimized.
+// Windows shows it through ITaskbarList3. On Linux, the Unity launcher API is used, which Ubuntu's dock, KDE and Plank
+// show on the launcher icon. The launcher matches it to pomelo.desktop, so the window's application id is "pomelo".
+// Other platforms only get the progress in the window title.
+
+use iced::{window, Task};
+
+// Application id of Pomelo's window, matching the desktop entry the launcher progress is sent for.
+pub (crate) const APP_ID: &str = "pomelo";
+
+// Show progress from 0.0 to 1.0 on the window's taskbar button, or hide it with None.
+#[cfg(target_os = "windows")]
+pub (crate) fn set_progress<T: Send + 'static>(window_id: window::Id, progress: Option<f32>) -> Task<T> {
+    window::run_with_handle(window_id, move |handle| win32::set_progress(handle, progress)).discard()
+}
+
+#[cfg(target_os = "linux")]
+pub (crate) fn set_progress<T: Send + 'static>(_window_id: window::Id, progress: Option<f32>) -> Task<T> {
+    unity::set_progress(progress);
+    Task::none()
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub (crate) fn set_progress<T: Send + 'static>(_window_id: window::Id, _progress: Option<f32>) -> Task<T> {
+    Task::none()
+}
+
+#[cfg(target_os = "windows")]
+mod win32 {
+    use log::warn;
+    use raw_window_handle::{RawWindowHandle, WindowHandle};
+
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED};
+    use windows::Win32::UI::Shell::{ITaskbarList3, TaskbarList, TBPF_NOPROGRESS};
+
+    pub (super) fn set_progress(handle: WindowHandle, progress: Option<f32>) {
+        let RawWindowHandle::Win32(handle) = handle.as_raw() else {
+            return;
+        };
+
+        let hwnd = HWND(handle.hwnd.get() as *mut _);
+
+        // Runs on the window's thread. COM may already be initialized there, which is fine.
+        let result = unsafe {
+            let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+
+            CoCreateInstance::<_, ITaskbarList3>(&TaskbarList, None, CLSCTX_INPROC_SERVER).and_then(|taskbar| {
+                taskbar.HrInit()?;
+
+                match progress {
+                    Some(progress) => taskbar.SetProgressValue(hwnd, (progress * 1000.0) as u64, 1000),
+                    None => taskbar.SetProgressState(hwnd, TBPF_NOPROGRESS)
+                }
+            })
+        };
+
+        if let Err(e) = result {
+            warn!("Failed to set taskbar progress: {}", e);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod unity {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use log::warn;
+
+    const DESKTOP_ENTRY: &str = "application://pomelo.desktop";
+    const OBJECT_PATH: &str = "/com/canonical/unity/launcherentry/pomelo";
+    const SIGNAL: &str = "com.canonical.Unity.LauncherEntry.Update";
+
+    // Only warn once if the signal can't be sent, e.g. without gdbus or a session bus.
+    static WARNED: AtomicBool = AtomicBool::new(false);
+
+    pub (super) fn set_progress(progress: Option<f32>) {
+        let properties = match progress {
+            Some(progress) => format!("{{'progress': <{:.3}>, 'progress-visible': <true>}}", progress),
+            None => String::from("{'progress-visible': <false>}")
+        };
+
+        let mut command = std::process::Command::new("gdbus");
+        command.args(["emit", "--session", "--object-path", OBJECT_PATH, "--signal", SIGNAL, DESKTOP_ENTRY, &properties]);
+
+        // Waited on in a thread so the UI isn't held up.
+        std::thread::spawn(move || {
+            let error = match command.output() {
+                Ok(output) if output.status.success() => return,
+                Ok(output) => String::from_utf8_lossy(&output.stderr).trim().to_string(),
+                Err(e) => e.to_string()
+            };
+
+            if !WARNED.swap(true, Ordering::Relaxed) {
+                warn!("Failed to set launcher progress: {}", error);
+            }
+        });
+    }
+}
@@ -13,6 +13,510 @@ pub (crate) fn secs_to_timestamp(seconds: u64, include_hour: bool) -> String {
     result
 }
 
+// Format a byte count using the largest fitting unit, e.g. "1.5 MB".
+pub (crate) fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[unit])
+    }
+    else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+// Get the video id from a Youtube link, e.g. "https://youtu.be/<id>" or "https://www.youtube.com/watch?v=<id>".
+// A bare video id is returned as-is.
+pub (crate) fn video_id_from_url(text: &str) -> Option<String> {
+    let text = text.trim();
+
+    if is_video_id(text) {
+        return Some(String::from(text));
+    }
+
+    match parse_youtube_link(text)? {
+        YoutubeLink::Video { id, .. } => Some(id),
+        _ => None
+    }
+}
+
+// What a Youtube link points to.
+#[derive(Debug, Clone, PartialEq)]
+pub (crate) enum YoutubeLink {
+    // Start is the "t=" timestamp in seconds, if the link has one.
+    Video { id: String, start: Option<u64> },
+    Playlist(String),
+    Channel(String),
+
+    // Channels linked by handle or custom name ("/@name", "/c/name", "/user/name") don't include the channel id.
+    ChannelName(String)
+}
+
+// Work out what a Youtube link points to. Links to a video in a playlist ("watch?v=<id>&list=<id>") are treated as the video.
+// Links to the Invidious instances Pomelo knows about use the same paths as Youtube, so they're understood too.
+pub (crate) fn parse_youtube_link(text: &str) -> Option<YoutubeLink> {
+    let url = url::Url::parse(text.trim()).ok()?;
+    let host = url.host_str()?.trim_start_matches("www.").trim_start_matches("m.");
+
+    let query = |name: &str| url.query_pairs()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.into_owned());
+
+    let start = query("t").and_then(|t| link_time_to_secs(&t));
+
+    let mut segments = url.path_segments()?.filter(|segment| !segment.is_empty());
+    let first = segments.next();
+    let second = segments.next().map(String::from);
+
+    let link = match host {
+        "youtu.be" => first.map(|id| YoutubeLink::Video { id: String::from(id), start }),
+
+        _ if host == "youtube.com" || host == "music.youtube.com" || is_invidious_host(host) => match (first, second) {
+            (Some("watch"), _) => query("v").map(|id| YoutubeLink::Video { id, start })
+                .or_else(|| query("list").map(YoutubeLink::Playlist)),
+            (Some("shorts" | "embed" | "live"), Some(id)) => Some(YoutubeLink::Video { id, start }),
+            (Some("playlist"), _) => query("list").map(YoutubeLink::Playlist),
+            (Some("channel"), Some(id)) => Some(YoutubeLink::Channel(id)),
+            (Some("c" | "user"), Some(name)) => Some(YoutubeLink::ChannelName(name)),
+            (Some(handle), None) if handle.starts_with('@') && handle.len() > 1 => Some(YoutubeLink::ChannelName(String::from(handle))),
+            _ => None
+        },
+
+        _ => None
+    };
+
+    match link {
+        Some(YoutubeLink::Video { id, .. }) if !is_video_id(&id) => None,
+        link => link
+    }
+}
+
+fn is_invidious_host(host: &str) -> bool {
+    crate::yt_fetch::instances::is_instance_host(host)
+}
+
+fn is_video_id(text: &str) -> bool {
+    text.len() == 11 && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+// Parse the "t=" part of a link, either plain seconds ("90", "90s") or units ("1m30s", "1h2m3s").
+fn link_time_to_secs(time: &str) -> Option<u64> {
+    if let Ok(secs) = time.trim_end_matches('s').parse() {
+        return Some(secs);
+    }
+
+    let mut total = 0;
+    let mut number = String::new();
+
+    for c in time.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return None
+        };
+
+        total += number.parse::<u64>().ok()? * unit;
+        number.clear();
+    }
+
+    number.is_empty().then_some(total)
+}
+
+// Parse a timestamp like "1:23" or "01:02:03" into seconds.
+pub (crate) fn timestamp_to_secs(timestamp: &str) -> Option<u64> {
+    let parts: Vec<&str> = timestamp.split(':').collect();
+
+    // Only the first part can have more than 2 digits.
+    let valid = (2..=3).contains(&parts.len())
+        && parts.iter().enumerate().all(|(i, part)| !part.is_empty() && (i == 0 || part.len() <= 2));
+
+    if !valid {
+        return None;
+    }
+
+    parts.iter().try_fold(0, |total, part| {
+        part.parse::<u64>().ok().map(|n| total * 60 + n)
+    })
+}
+
+// Find chapters in a video description, Youtube-style: one per line, starting with a timestamp.
+// Like Youtube, the first chapter has to start at 0:00, and there have to be at least 2 chapters.
+pub (crate) fn parse_chapters(description: &str) -> Vec<(u64, String)> {
+    let chapters: Vec<(u64, String)> = description.lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let (first, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let secs = timestamp_to_secs(first.trim_start_matches(['(', '[']).trim_end_matches([')', ']']))?;
+            let title = rest.trim_start_matches(|c: char| c.is_whitespace() || "-–—:|".contains(c)).trim();
+
+            Some((secs, String::from(title)))
+        })
+        .collect();
+
+    let valid = chapters.len() >= 2
+        && chapters[0].0 == 0
+        && chapters.windows(2).all(|pair| pair[0].0 < pair[1].0);
+
+    if valid { chapters } else { Vec::new() }
+}
+
+// Read the cues from an SRT or WebVTT subtitle file, as (start, end, text) with times in seconds.
+// Styling tags like <i> are taken out, and cue numbers, headers, and notes are skipped.
+pub (crate) fn parse_subtitles(text: &str) -> Vec<(f64, f64, String)> {
+    let text = text.replace("\r\n", "\n");
+
+    text.split("\n\n")
+        .filter_map(|block| {
+            let mut lines = block.lines().skip_while(|line| !line.contains("-->"));
+            let (start, end) = lines.next()?.split_once("-->")?;
+
+            // WebVTT can put cue settings after the end time.
+            let start = cue_time(start.trim())?;
+            let end = cue_time(end.split_whitespace().next()?)?;
+
+            let text = lines
+                .map(strip_tags)
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            (!text.trim().is_empty()).then_some((start, end, text))
+        })
+        .collect()
+}
+
+// Parse a cue time like "00:01:02,500" or "01:02.500" into seconds.
+fn cue_time(time: &str) -> Option<f64> {
+    let time = time.replace(',', ".");
+    let parts: Vec<&str> = time.split(':').collect();
+
+    if !(2..=3).contains(&parts.len()) {
+        return None;
+    }
+
+    let (seconds, rest) = parts.split_last()?;
+
+    let minutes = rest.iter().try_fold(0, |total, part| part.parse::<u64>().ok().map(|n| total * 60 + n))?;
+
+    seconds.parse::<f64>().ok().map(|s| minutes as f64 * 60.0 + s)
+}
+
+fn strip_tags(line: &str) -> String {
+    let mut result = String::new();
+    let mut in_tag = false;
+
+    for c in line.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => result.push(c),
+            _ => ()
+        }
+    }
+
+    result
+}
+
+// Locale used for formatting numbers and dates. The rest of the interface is still in English.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub (crate) enum Locale {
+    #[default]
+    English,
+    German,
+    French,
+    Spanish
+}
+
+impl Locale {
+    pub (crate) const ALL: [Self; 4] = [Self::English, Self::German, Self::French, Self::Spanish];
+
+    // Thousands separator and decimal point.
+    fn separators(&self) -> (&str, &str) {
+        match self {
+            Self::English => (",", "."),
+            Self::German | Self::Spanish => (".", ","),
+            Self::French => ("\u{202F}", ",")
+        }
+    }
+
+    // Suffixes for thousands, millions, and billions.
+    fn compact_suffixes(&self) -> [&str; 3] {
+        match self {
+            Self::English => ["K", "M", "B"],
+            Self::German => ["\u{A0}Tsd.", "\u{A0}Mio.", "\u{A0}Mrd."],
+            Self::French => ["\u{A0}k", "\u{A0}M", "\u{A0}Md"],
+            Self::Spanish => ["\u{A0}mil", "\u{A0}M", "\u{A0}mil\u{A0}M"]
+        }
+    }
+
+    // Singular and plural names for seconds, minutes, hours, days, weeks, months, and years.
+    fn time_units(&self) -> [(&str, &str); 7] {
+        match self {
+            Self::English => [
+                ("second", "seconds"), ("minute", "minutes"), ("hour", "hours"), ("day", "days"),
+                ("week", "weeks"), ("month", "months"), ("year", "years")
+            ],
+            Self::German => [
+                ("Sekunde", "Sekunden"), ("Minute", "Minuten"), ("Stunde", "Stunden"), ("Tag", "Tagen"),
+                ("Woche", "Wochen"), ("Monat", "Monaten"), ("Jahr", "Jahren")
+            ],
+            Self::French => [
+                ("seconde", "secondes"), ("minute", "minutes"), ("heure", "heures"), ("jour", "jours"),
+                ("semaine", "semaines"), ("mois", "mois"), ("an", "ans")
+            ],
+            Self::Spanish => [
+                ("segundo", "segundos"), ("minuto", "minutos"), ("hora", "horas"), ("día", "días"),
+                ("semana", "semanas"), ("mes", "meses"), ("año", "años")
+            ]
+        }
+    }
+}
+
+impl std::fmt::Display for Locale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::English => "English",
+            Self::German => "Deutsch",
+            Self::French => "Français",
+            Self::Spanish => "Español"
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+// Format a number with the locale's thousands separator, e.g. "1,234,567".
+pub (crate) fn format_number(n: u64, locale: Locale) -> String {
+    let (group, _) = locale.separators();
+    let digits = n.to_string();
+
+    let mut result = String::new();
+
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            result.push_str(group);
+        }
+        result.push(c);
+    }
+
+    result
+}
+
+// Format a number in a short form, e.g. "1.2M". Numbers under 1000 are left as-is.
+pub (crate) fn format_compact(n: u64, locale: Locale) -> String {
+    let (_, decimal) = locale.separators();
+    let suffixes = locale.compact_suffixes();
+
+    let (value, suffix) = match n {
+        0..=999 => return n.to_string(),
+        1_000..=999_999 => (n as f64 / 1e3, suffixes[0]),
+        1_000_000..=999_999_999 => (n as f64 / 1e6, suffixes[1]),
+        _ => (n as f64 / 1e9, suffixes[2])
+    };
+
+    // One decimal place for small values, none once it's 10 or more.
+    let number = if value < 10.0 {
+        let rounded = (value * 10.0).floor() / 10.0;
+
+        if rounded.fract() == 0.0 {
+            format!("{}", rounded as u64)
+        }
+        else {
+            format!("{:.1}", rounded).replace('.', decimal)
+        }
+    }
+    else {
+        format!("{}", value.floor() as u64)
+    };
+
+    format!("{}{}", number, suffix)
+}
+
+// Describe how long ago something happened, e.g. "2 weeks ago".
+pub (crate) fn format_relative_time(seconds_ago: u64, locale: Locale) -> String {
+    const STEPS: [u64; 7] = [1, 60, 3600, 86400, 604_800, 2_592_000, 31_536_000];
+
+    let unit = STEPS.iter().rposition(|step| seconds_ago >= *step).unwrap_or_default();
+    let count = seconds_ago / STEPS[unit];
+    let (singular, plural) = locale.time_units()[unit];
+    let name = if count == 1 { singular } else { plural };
+
+    match locale {
+        Locale::English => format!("{} {} ago", count, name),
+        Locale::German => format!("vor {} {}", count, name),
+        Locale::French => format!("il y a {} {}", count, name),
+        Locale::Spanish => format!("hace {} {}", count, name)
+    }
+}
+
+// Format a date the way the locale usually writes it.
+pub (crate) fn format_date(date: chrono::NaiveDate, locale: Locale) -> String {
+    let pattern = match locale {
+        Locale::English => "%B %-d, %Y",
+        Locale::German => "%d.%m.%Y",
+        Locale::French | Locale::Spanish => "%d/%m/%Y"
+    };
+
+    date.format(pattern).to_string()
+}
+
+// Youtube's RSS feed of a channel's uploads.
+pub (crate) fn channel_feed_url(channel_id: &str) -> String {
+    format!("https://www.youtube.com/feeds/videos.xml?channel_id={}", channel_id)
+}
+
+// Build an OPML document from (title, feed url) pairs, for importing into feed readers.
+pub (crate) fn feeds_to_opml(title: &str, feeds: &[(String, String)]) -> String {
+    let mut opml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<opml version=\"2.0\">\n  <head>\n    <title>{}</title>\n  </head>\n  <body>\n",
+        escape_xml(title)
+    );
+
+    for (name, url) in feeds {
+        opml.push_str(&format!(
+            "    <outline type=\"rss\" text=\"{0}\" title=\"{0}\" xmlUrl=\"{1}\"/>\n",
+            escape_xml(name),
+            escape_xml(url)
+        ));
+    }
+
+    opml.push_str("  </body>\n</opml>\n");
+    opml
+}
+
+// Read video ids from a list of links or ids, one per line. Blank lines and lines starting with '#' are ignored.
+// Returns the ids in order without duplicates, and the number of lines that weren't recognized.
+pub (crate) fn parse_url_list(text: &str) -> (Vec<String>, usize) {
+    let mut ids: Vec<String> = Vec::new();
+    let mut skipped = 0;
+
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+        match video_id_from_url(line) {
+            Some(id) if !ids.contains(&id) => ids.push(id),
+            Some(_) => (),
+            None => skipped += 1
+        }
+    }
+
+    (ids, skipped)
+}
+
+// Quote an argument so it can be pasted into a shell. Arguments without special characters are left as is.
+// Uses double quotes on Windows, since cmd doesn't understand single quotes.
+pub (crate) fn quote_arg(arg: &str) -> String {
+    let plain = !arg.is_empty() && arg.chars()
+        .all(|c| c.is_alphanumeric() || "-_./:=,+@".contains(c));
+
+    if plain {
+        String::from(arg)
+    }
+    else if cfg!(target_os = "windows") {
+        format!("\"{}\"", arg.replace('"', "\"\""))
+    }
+    else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+// Join fields into a line of CSV. Fields with commas, quotes, or line breaks are quoted.
+pub (crate) fn csv_row(fields: &[&str]) -> String {
+    let fields: Vec<String> = fields.iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                String::from(*field)
+            }
+        })
+        .collect();
+
+    fields.join(",")
+}
+
+// Turn an image url from Invidious into one that can be downloaded.
+// Instances hand out protocol-relative urls ("//i.ytimg.com/...") and urls relative to themselves ("/vi/...").
+// With proxy set, Youtube image urls are rewritten to go through the instance, so Youtube doesn't see the request.
+pub (crate) fn normalize_image_url(url: &str, instance: &str, proxy: bool) -> String {
+    let instance = instance.trim_end_matches('/');
+
+    let url = if let Some(rest) = url.strip_prefix("//") {
+        format!("https://{}", rest)
+    }
+    else if url.starts_with('/') {
+        return format!("{}{}", instance, url);
+    }
+    else {
+        String::from(url)
+    };
+
+    if !proxy {
+        return url;
+    }
+
+    let Some(rest) = url.strip_prefix("https://").or_else(|| url.strip_prefix("http://")) else {
+        return url;
+    };
+
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+
+    if host.ends_with("ytimg.com") && path.starts_with("vi/") {
+        format!("{}/{}", instance, path)
+    }
+    else if host.ends_with("ggpht.com") || host.ends_with("googleusercontent.com") {
+        format!("{}/ggpht/{}", instance, path)
+    }
+    else {
+        url
+    }
+}
+
+// PBKDF2 rounds for PIN hashes. PINs are only a few digits, so each guess has to be made slow instead.
+const PIN_HASH_ROUNDS: u32 = 100_000;
+
+// Hash a restricted mode PIN, so the PIN itself isn't kept in the settings file.
+// PBKDF2-HMAC-SHA256 with a new random salt, stored together as "salt$hash".
+// This is slow on purpose, so it shouldn't be run on the UI thread.
+pub (crate) fn hash_pin(pin: &str) -> String {
+    let salt = to_hex(&rand::random::<[u8; 16]>());
+    format!("{}${}", salt, pbkdf2_hex(pin, &salt))
+}
+
+// Whether a PIN matches a hash made by hash_pin. Just as slow as making one.
+pub (crate) fn verify_pin(pin: &str, stored: &str) -> bool {
+    stored.split_once('$').is_some_and(|(salt, hash)| pbkdf2_hex(pin, salt) == hash)
+}
+
+fn pbkdf2_hex(pin: &str, salt: &str) -> String {
+    let mut hash = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<sha2::Sha256>(pin.as_bytes(), salt.as_bytes(), PIN_HASH_ROUNDS, &mut hash);
+    to_hex(&hash)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
 mod tests {
 
     #[test]
@@ -33,4 +537,249 @@ mod tests {
 
         assert_eq!(secs_to_timestamp(360_000, true), "100:00:00");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_format_bytes() {
+        use super::format_bytes;
+
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1024), "1.0 KB");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(5 * 1024 * 1024), "5.0 MB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GB");
+    }
+
+    #[test]
+    fn test_video_id_from_url() {
+        use super::video_id_from_url;
+
+        let id = Some(String::from("dQw4w9WgXcQ"));
+
+        assert_eq!(video_id_from_url("dQw4w9WgXcQ"), id);
+        assert_eq!(video_id_from_url("https://www.youtube.com/watch?v=dQw4w9WgXcQ"), id);
+        assert_eq!(video_id_from_url("https://youtube.com/watch?list=PL123&v=dQw4w9WgXcQ&t=10"), id);
+        assert_eq!(video_id_from_url("https://m.youtube.com/watch?v=dQw4w9WgXcQ"), id);
+        assert_eq!(video_id_from_url("https://youtu.be/dQw4w9WgXcQ?si=abc"), id);
+        assert_eq!(video_id_from_url("https://www.youtube.com/shorts/dQw4w9WgXcQ"), id);
+        assert_eq!(video_id_from_url("  https://www.youtube.com/embed/dQw4w9WgXcQ  "), id);
+
+        assert_eq!(video_id_from_url("https://www.youtube.com/watch"), None);
+        assert_eq!(video_id_from_url("https://example.com/watch?v=dQw4w9WgXcQ"), None);
+        assert_eq!(video_id_from_url("not a link"), None);
+    }
+
+    #[test]
+    fn test_parse_youtube_link() {
+        use super::{parse_youtube_link, YoutubeLink};
+
+        let video = |start| Some(YoutubeLink::Video { id: String::from("dQw4w9WgXcQ"), start });
+
+        assert_eq!(parse_youtube_link("https://www.youtube.com/watch?v=dQw4w9WgXcQ"), video(None));
+        assert_eq!(parse_youtube_link("https://youtu.be/dQw4w9WgXcQ?t=90"), video(Some(90)));
+        assert_eq!(parse_youtube_link("https://youtube.com/watch?v=dQw4w9WgXcQ&t=1m30s"), video(Some(90)));
+        assert_eq!(parse_youtube_link("https://youtube.com/watch?v=dQw4w9WgXcQ&list=PL123&t=1h0m5s"), video(Some(3605)));
+        assert_eq!(parse_youtube_link("https://www.youtube.com/shorts/dQw4w9WgXcQ"), video(None));
+
+        assert_eq!(
+            parse_youtube_link("https://www.youtube.com/playlist?list=PL123"),
+            Some(YoutubeLink::Playlist(String::from("PL123")))
+        );
+        assert_eq!(
+            parse_youtube_link("https://www.youtube.com/channel/UC123/videos"),
+            Some(YoutubeLink::Channel(String::from("UC123")))
+        );
+        assert_eq!(
+            parse_youtube_link("https://www.youtube.com/@someone"),
+            Some(YoutubeLink::ChannelName(String::from("@someone")))
+        );
+        assert_eq!(
+            parse_youtube_link("https://www.youtube.com/c/someone"),
+            Some(YoutubeLink::ChannelName(String::from("someone")))
+        );
+
+        assert_eq!(parse_youtube_link("https://yewtu.be/watch?v=dQw4w9WgXcQ&t=90"), video(Some(90)));
+        assert_eq!(
+            parse_youtube_link("https://inv.nadeko.net/channel/UC123"),
+            Some(YoutubeLink::Channel(String::from("UC123")))
+        );
+        assert_eq!(
+            parse_youtube_link("https://www.youtube.com/watch?list=PL123"),
+            Some(YoutubeLink::Playlist(String::from("PL123")))
+        );
+
+        assert_eq!(parse_youtube_link("https://youtu.be/dQw4w9WgXcQ?t=abc"), video(None));
+        assert_eq!(parse_youtube_link("https://www.youtube.com/feed/trending"), None);
+        assert_eq!(parse_youtube_link("https://example.com/watch?v=dQw4w9WgXcQ"), None);
+        assert_eq!(parse_youtube_link("dQw4w9WgXcQ"), None);
+    }
+
+    #[test]
+    fn test_timestamp_to_secs() {
+        use super::timestamp_to_secs;
+
+        assert_eq!(timestamp_to_secs("0:00"), Some(0));
+        assert_eq!(timestamp_to_secs("1:23"), Some(83));
+        assert_eq!(timestamp_to_secs("01:02:03"), Some(3723));
+        assert_eq!(timestamp_to_secs("123:00"), Some(7380));
+
+        assert_eq!(timestamp_to_secs("5"), None);
+        assert_eq!(timestamp_to_secs("1:2:3:4"), None);
+        assert_eq!(timestamp_to_secs("1:234"), None);
+        assert_eq!(timestamp_to_secs("a:00"), None);
+        assert_eq!(timestamp_to_secs("1:"), None);
+    }
+
+    #[test]
+    fn test_parse_chapters() {
+        use super::parse_chapters;
+
+        let description = "Episode notes\n\
+            0:00 Intro\n\
+            (1:30) - Guest introduction\n\
+            Links are below\n\
+            1:05:00 | Wrap up";
+
+        assert_eq!(
+            parse_chapters(description),
+            vec![
+                (0, String::from("Intro")),
+                (90, String::from("Guest introduction")),
+                (3900, String::from("Wrap up"))
+            ]
+        );
+
+        // First chapter has to start at 0:00.
+        assert!(parse_chapters("0:30 Intro\n1:00 Outro").is_empty());
+
+        // A single timestamp isn't a chapter list.
+        assert!(parse_chapters("Skip to 0:00 for the start").is_empty());
+    }
+
+    #[test]
+    fn test_parse_subtitles() {
+        use super::parse_subtitles;
+
+        let srt = "1\r\n00:00:01,000 --> 00:00:02,500\r\nHello\r\n\r\n2\r\n00:01:02,000 --> 00:01:04,000\r\n<i>Two</i>\r\nlines\r\n";
+
+        assert_eq!(
+            parse_subtitles(srt),
+            vec![
+                (1.0, 2.5, String::from("Hello")),
+                (62.0, 64.0, String::from("Two\nlines"))
+            ]
+        );
+
+        let vtt = "WEBVTT\n\nNOTE made by hand\n\n01:00.500 --> 01:02.000 align:start\n<c.yellow>Hi</c>\n";
+
+        assert_eq!(parse_subtitles(vtt), vec![(60.5, 62.0, String::from("Hi"))]);
+    }
+
+    #[test]
+    fn test_format_number() {
+        use super::{format_number, Locale};
+
+        assert_eq!(format_number(0, Locale::English), "0");
+        assert_eq!(format_number(999, Locale::English), "999");
+        assert_eq!(format_number(1000, Locale::English), "1,000");
+        assert_eq!(format_number(1_234_567, Locale::English), "1,234,567");
+        assert_eq!(format_number(1_234_567, Locale::German), "1.234.567");
+    }
+
+    #[test]
+    fn test_format_compact() {
+        use super::{format_compact, Locale};
+
+        assert_eq!(format_compact(999, Locale::English), "999");
+        assert_eq!(format_compact(1000, Locale::English), "1K");
+        assert_eq!(format_compact(1250, Locale::English), "1.2K");
+        assert_eq!(format_compact(45_600, Locale::English), "45K");
+        assert_eq!(format_compact(1_200_000, Locale::English), "1.2M");
+        assert_eq!(format_compact(3_000_000_000, Locale::English), "3B");
+        assert_eq!(format_compact(1_200_000, Locale::German), "1,2\u{A0}Mio.");
+    }
+
+    #[test]
+    fn test_format_relative_time() {
+        use super::{format_relative_time, Locale};
+
+        assert_eq!(format_relative_time(0, Locale::English), "0 seconds ago");
+        assert_eq!(format_relative_time(1, Locale::English), "1 second ago");
+        assert_eq!(format_relative_time(90, Locale::English), "1 minute ago");
+        assert_eq!(format_relative_time(2 * 604_800, Locale::English), "2 weeks ago");
+        assert_eq!(format_relative_time(3 * 86400, Locale::German), "vor 3 Tagen");
+        assert_eq!(format_relative_time(31_536_000, Locale::Spanish), "hace 1 año");
+    }
+
+    #[test]
+    fn test_feeds_to_opml() {
+        use super::{channel_feed_url, feeds_to_opml};
+
+        let feeds = vec![(String::from("Tom & Jerry's \"Channel\""), channel_feed_url("UC123"))];
+        let opml = feeds_to_opml("Pomelo", &feeds);
+
+        assert!(opml.contains("<title>Pomelo</title>"));
+        assert!(opml.contains("text=\"Tom &amp; Jerry&apos;s &quot;Channel&quot;\""));
+        assert!(opml.contains("xmlUrl=\"https://www.youtube.com/feeds/videos.xml?channel_id=UC123\""));
+        assert!(feeds_to_opml("Empty", &[]).ends_with("<body>\n  </body>\n</opml>\n"));
+    }
+
+    #[test]
+    fn test_parse_url_list() {
+        use super::parse_url_list;
+
+        let list = "# Exported list\n\nhttps://youtu.be/dQw4w9WgXcQ\n  jNQXAC9IVRw  \nhttps://www.youtube.com/watch?v=dQw4w9WgXcQ\nnot a link\n";
+
+        assert_eq!(parse_url_list(list), (vec![String::from("dQw4w9WgXcQ"), String::from("jNQXAC9IVRw")], 1));
+        assert_eq!(parse_url_list(""), (Vec::new(), 0));
+    }
+
+    #[test]
+    fn test_normalize_image_url() {
+        use super::normalize_image_url;
+
+        let instance = "https://yewtu.be";
+
+        assert_eq!(normalize_image_url("//yt3.ggpht.com/abc=s176", instance, false), "https://yt3.ggpht.com/abc=s176");
+        assert_eq!(normalize_image_url("/vi/dQw4w9WgXcQ/mqdefault.jpg", instance, false), "https://yewtu.be/vi/dQw4w9WgXcQ/mqdefault.jpg");
+        assert_eq!(normalize_image_url("https://i.ytimg.com/vi/dQw4w9WgXcQ/mqdefault.jpg", instance, false), "https://i.ytimg.com/vi/dQw4w9WgXcQ/mqdefault.jpg");
+
+        assert_eq!(normalize_image_url("https://i.ytimg.com/vi/dQw4w9WgXcQ/mqdefault.jpg", instance, true), "https://yewtu.be/vi/dQw4w9WgXcQ/mqdefault.jpg");
+        assert_eq!(normalize_image_url("//yt3.ggpht.com/abc=s176", "https://yewtu.be/", true), "https://yewtu.be/ggpht/abc=s176");
+        assert_eq!(normalize_image_url("https://yt3.googleusercontent.com/banner", instance, true), "https://yewtu.be/ggpht/banner");
+        assert_eq!(normalize_image_url("https://example.com/image.png", instance, true), "https://example.com/image.png");
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn test_quote_arg() {
+        use super::quote_arg;
+
+        assert_eq!(quote_arg("res:720"), "res:720");
+        assert_eq!(quote_arg("b[height=720]/bv[height=720]+ba"), "'b[height=720]/bv[height=720]+ba'");
+        assert_eq!(quote_arg("It's here"), "'It'\\''s here'");
+        assert_eq!(quote_arg(""), "''");
+    }
+
+    #[test]
+    fn test_hash_pin() {
+        use super::{hash_pin, verify_pin};
+
+        let stored = hash_pin("1234");
+        assert!(verify_pin("1234", &stored));
+        assert!(!verify_pin("1235", &stored));
+        assert!(!verify_pin("1234", "1234"));
+
+        // Each hash gets its own salt.
+        assert_ne!(stored, hash_pin("1234"));
+    }
+
+    #[test]
+    fn test_csv_row() {
+        use super::csv_row;
+
+        assert_eq!(csv_row(&["dQw4w9WgXcQ", "Growing pomelos at home", "1700000000"]), "dQw4w9WgXcQ,Growing pomelos at home,1700000000");
+        assert_eq!(csv_row(&["Pomelos, part 2", "The \"best\" pomelo"]), "\"Pomelos, part 2\",\"The \"\"best\"\" pomelo\"");
+        assert_eq!(csv_row(&["", "a"]), ",a");
+    }
+}
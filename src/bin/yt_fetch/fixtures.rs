@@ -0,0 +1,147 @@
+// Saved Invidious responses, used instead of an instance in offline demo mode and in tests.
+// Each request reads a JSON file from the fixture folder, in the same format the Invidious API sends
+// (except suggestions.json, which is just a list of strings):
+//
+//   search.json, suggestions.json, channel_videos.json
+//   videos/<id>.json, or video.json for any video
+//   channels/<id>.json, or channel.json for any channel
+//   playlists/<id>.json, or playlist.json for any playlist
+//
+// Fixtures only hold one page, so later pages come back empty and channel uploads have no continuation past the first.
+// Missing files are reported as errors, the same way a failed request would be.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use invidious::{
+    channel::{Channel, ChannelVideos},
+    hidden::SearchItem,
+    universal::{Playlist, Search},
+    video::Video as VideoDetails
+};
+
+use log::info;
+use serde::de::DeserializeOwned;
+
+use super::{ChannelInfo, FetchError, SearchType, VideoFetcher};
+
+// Folder offline demo mode reads from.
+pub (crate) const DEMO_FOLDER: &str = "./fixtures";
+
+fn offline() -> &'static Mutex<Option<PathBuf>> {
+    static OFFLINE: OnceLock<Mutex<Option<PathBuf>>> = OnceLock::new();
+    OFFLINE.get_or_init(|| Mutex::new(None))
+}
+
+// Answer every request from the fixtures in the given folder, or go back to the network with None.
+pub (crate) fn set_offline(folder: Option<&str>) {
+    if let Some(folder) = folder {
+        info!("Offline demo mode is on, using fixtures from: {}", folder);
+    }
+
+    *offline().lock().unwrap() = folder.map(PathBuf::from);
+}
+
+pub (crate) fn offline_folder() -> Option<PathBuf> {
+    offline().lock().unwrap().clone()
+}
+
+pub (crate) struct FixtureFetcher {
+    folder: PathBuf
+}
+
+impl FixtureFetcher {
+    pub (crate) fn new(folder: impl Into<PathBuf>) -> Self {
+        Self { folder: folder.into() }
+    }
+
+    // Read the first of the given files that exists.
+    fn load<T: DeserializeOwned>(&self, files: &[&str]) -> Result<T, FetchError> {
+        let path = files.iter()
+            .map(|file| self.folder.join(file))
+            .find(|path| path.is_file())
+            .ok_or_else(|| FetchError::new(format!("No fixture for {}", files.join(" or "))))?;
+
+        read_fixture(&path)
+    }
+}
+
+fn read_fixture<T: DeserializeOwned>(path: &Path) -> Result<T, FetchError> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| FetchError::new(format!("Failed to read fixture {}: {}", path.display(), e)))?;
+
+    serde_json::from_str(&text)
+        .map_err(|e| FetchError::new(format!("Failed to parse fixture {}: {}", path.display(), e)))
+}
+
+// The same fixtures are returned for every query.
+impl VideoFetcher for FixtureFetcher {
+    async fn get_video_details(&self, id: &str) -> Result<VideoDetails, FetchError> {
+        self.load(&[&format!("videos/{}.json", id), "video.json"])
+    }
+
+    async fn search(&self, _query: &str, _search_type: SearchType, page: usize) -> Result<Search, FetchError> {
+        // Invidious sends the results as a plain list.
+        let mut search = Search { items: self.load::<Vec<SearchItem>>(&["search.json"])? };
+
+        if page > 1 {
+            search.items.clear();
+        }
+
+        Ok(search)
+    }
+
+    async fn get_search_suggestions(&self, _query: &str) -> Result<Vec<String>, FetchError> {
+        self.load(&["suggestions.json"])
+    }
+
+    async fn get_channel_videos(&self, _channel_id: &str, continuation: Option<&str>) -> Result<ChannelVideos, FetchError> {
+        let mut videos: ChannelVideos = self.load(&["channel_videos.json"])?;
+
+        if continuation.is_some() {
+            videos.videos.clear();
+            videos.continuation = None;
+        }
+
+        Ok(videos)
+    }
+
+    async fn get_channel_info(&self, channel_id: &str) -> Result<ChannelInfo, FetchError> {
+        self.load::<Channel>(&[&format!("channels/{}.json", channel_id), "channel.json"])
+            .map(ChannelInfo::from)
+    }
+
+    async fn get_playlist_videos(&self, id: &str, page: usize) -> Result<Playlist, FetchError> {
+        let mut playlist: Playlist = self.load(&[&format!("playlists/{}.json", id), "playlist.json"])?;
+
+        if page > 1 {
+            playlist.videos.clear();
+        }
+
+        Ok(playlist)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FixtureFetcher, VideoFetcher};
+
+    #[test]
+    fn test_fixtures() {
+        let folder = std::env::temp_dir().join(format!("pomelo-fixtures-{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("suggestions.json"), r#"["pomelo", "pomelo fruit"]"#).unwrap();
+
+        let fetcher = FixtureFetcher::new(&folder);
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        let suggestions = runtime.block_on(fetcher.get_search_suggestions("pom")).unwrap();
+        assert_eq!(suggestions, vec![String::from("pomelo"), String::from("pomelo fruit")]);
+
+        // Missing fixtures fail like a request would.
+        assert!(runtime.block_on(fetcher.get_playlist_videos("PL123", 1)).is_err());
+        assert!(runtime.block_on(fetcher.get_video_details("abc")).is_err());
+
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+}
@@ -0,0 +1,237 @@
+// Invidious instances Pomelo can use.
+// The official list is fetched from api.invidious.io at startup and saved to disk, so next run starts with it right away.
+// The built-in list is only used until a list has been fetched once.
+// Instances the user added themselves, e.g. self-hosted ones, come after the listed ones.
+
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use log::{info, warn};
+
+use super::FetchError;
+
+const INSTANCE_LIST_URL: &str = "https://api.invidious.io/instances.json?sort_by=type,health";
+const CACHE_FILE: &str = "./cache/instances.json";
+
+// Region shown for instances the user added.
+const CUSTOM_REGION: &str = "Custom";
+
+// Only instances from the official Invidious docs are used.
+const FALLBACK_INSTANCES: &[(&str, &str)] = &[
+    ("https://invidious.darkness.services", "USA"),
+    ("https://invidious.incogniweb.net", "USA"),
+    ("https://inv.in.projectsegfau.lt", "India"),
+    ("https://invidious.materialio.us", "New Zealand"),
+    ("https://invidious.reallyaweso.me", "Germany"),
+    ("https://invidious.privacyredirect.com", "Finland"),
+    ("https://invidious.jing.rocks", "Japan"),
+    ("https://inv.us.projectsegfau.lt", "USA"),
+    ("https://invidious.drgns.space", "USA"),
+    ("https://invidious.fdn.fr", "France"),
+    ("https://iv.datura.network", "Finland"),
+    ("https://yt.drgnz.club", "Czech Republic"),
+    ("https://invidious.private.coffee", "Austria"),
+    ("https://invidious.protokolla.fi", "Finland"),
+    ("https://inv.tux.pizza", "USA"),
+    ("https://inv.nadeko.net", "Chile"),
+    ("https://iv.melmac.space", "Germany"),
+    ("https://invidious.privacydev.net", "France"),
+    ("https://invidious.flokinet.to", "Romania"),
+    ("https://yt.artemislena.eu", "Germany"),
+    ("https://yewtu.be", "Germany")
+];
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub (crate) struct InvidiousInstance {
+    pub (crate) url: String,
+    pub (crate) region: String,
+
+    // Percent of the last 30 days the instance was up, if the list says.
+    pub (crate) uptime: Option<f32>
+}
+
+impl std::fmt::Display for InvidiousInstance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.uptime {
+            Some(uptime) => write!(f, "{} ({}, {:.1}% uptime)", self.url, self.region, uptime),
+            None => write!(f, "{} ({})", self.url, self.region)
+        }
+    }
+}
+
+// An entry of the official list. Each entry is a pair of the instance's host and these details.
+#[derive(serde::Deserialize)]
+struct ListedInstance {
+    region: String,
+    #[serde(rename = "type")]
+    kind: String,
+    api: Option<bool>,
+    uri: String,
+    monitor: Option<Monitor>
+}
+
+#[derive(serde::Deserialize)]
+struct Monitor {
+    #[serde(rename = "30dRatio")]
+    ratio: Option<UptimeRatio>
+}
+
+#[derive(serde::Deserialize)]
+struct UptimeRatio {
+    ratio: String
+}
+
+// The part of /api/v1/stats used to check that an instance works.
+#[derive(serde::Deserialize)]
+struct Stats {
+    software: Software
+}
+
+#[derive(serde::Deserialize)]
+struct Software {
+    name: String,
+    version: String
+}
+
+struct Registry {
+    listed: Vec<InvidiousInstance>,
+    custom: Vec<InvidiousInstance>
+}
+
+impl Registry {
+    fn all(&self) -> impl Iterator<Item = &InvidiousInstance> {
+        self.listed.iter().chain(self.custom.iter())
+    }
+}
+
+fn registry() -> &'static Mutex<Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+
+    REGISTRY.get_or_init(|| Mutex::new(Registry {
+        listed: load_cached().unwrap_or_else(fallback_instances),
+        custom: Vec::new()
+    }))
+}
+
+fn fallback_instances() -> Vec<InvidiousInstance> {
+    FALLBACK_INSTANCES.iter()
+        .map(|(url, region)| InvidiousInstance { url: String::from(*url), region: String::from(*region), uptime: None })
+        .collect()
+}
+
+// The list saved by the last run, if there is a usable one.
+fn load_cached() -> Option<Vec<InvidiousInstance>> {
+    let text = std::fs::read_to_string(CACHE_FILE).ok()?;
+
+    match serde_json::from_str::<Vec<InvidiousInstance>>(&text) {
+        Ok(instances) if !instances.is_empty() => Some(instances),
+        Ok(_) => None,
+        Err(e) => {
+            warn!("Failed to read saved instance list, using the built-in one: {}", e);
+            None
+        }
+    }
+}
+
+pub (crate) fn all() -> Vec<InvidiousInstance> {
+    registry().lock().unwrap().all().cloned().collect()
+}
+
+pub (crate) fn count() -> usize {
+    registry().lock().unwrap().all().count()
+}
+
+// Url of the instance at the given index. Indexes past the end wrap around, in case the list got shorter.
+pub (crate) fn url(index: usize) -> String {
+    let registry = registry().lock().unwrap();
+    let count = registry.all().count();
+
+    registry.all().nth(index % count).map(|instance| instance.url.clone()).unwrap_or_default()
+}
+
+pub (crate) fn position(url: &str) -> Option<usize> {
+    registry().lock().unwrap().all().position(|instance| instance.url == url)
+}
+
+// Whether a link's host, e.g. "yewtu.be", is one of the instances.
+pub (crate) fn is_instance_host(host: &str) -> bool {
+    registry().lock().unwrap().all()
+        .any(|instance| instance.url.trim_start_matches("https://").trim_start_matches("http://") == host)
+}
+
+// Use the instances the user added, replacing the ones added before. These aren't saved here, they're kept in the settings.
+pub (crate) fn set_custom(urls: &[String]) {
+    registry().lock().unwrap().custom = urls.iter()
+        .map(|url| InvidiousInstance { url: url.clone(), region: String::from(CUSTOM_REGION), uptime: None })
+        .collect();
+}
+
+// Clean up a url typed by the user: "https://" is added if there's no scheme, and trailing slashes are removed.
+pub (crate) fn normalize_url(url: &str) -> String {
+    let url = url.trim().trim_end_matches('/');
+
+    match url.starts_with("http://") || url.starts_with("https://") {
+        true => String::from(url),
+        false => format!("https://{}", url)
+    }
+}
+
+// Check that an instance answers API requests, returning the Invidious version it runs. Times out after 10 seconds.
+pub (crate) async fn test_instance(url: &str) -> Result<String, FetchError> {
+    let stats_url = format!("{}/api/v1/stats", url);
+
+    let body = tokio::time::timeout(
+        Duration::from_secs(10),
+        async { reqwest::get(&stats_url).await?.error_for_status()?.text().await }
+    ).await??;
+
+    let stats: Stats = serde_json::from_str(&body)
+        .map_err(|e| FetchError::new(format!("Not an Invidious API: {}", e)))?;
+
+    Ok(format!("{} {}", stats.software.name, stats.software.version))
+}
+
+// Use a newly fetched list, and save it for next time. An empty list is ignored.
+pub (crate) fn set(instances: Vec<InvidiousInstance>) {
+    if instances.is_empty() {
+        return;
+    }
+
+    let result = std::fs::create_dir_all("./cache")
+        .map_err(|e| e.to_string())
+        .and_then(|_| serde_json::to_string_pretty(&instances).map_err(|e| e.to_string()))
+        .and_then(|json| std::fs::write(CACHE_FILE, json).map_err(|e| e.to_string()));
+
+    if let Err(e) = result {
+        warn!("Failed to save instance list: {}", e);
+    }
+
+    info!("Using {} Invidious instances from api.invidious.io.", instances.len());
+    registry().lock().unwrap().listed = instances;
+}
+
+// Fetch the official instance list. Only instances with the API turned on, reached over https, are kept.
+// Times out after 10 seconds.
+pub (crate) async fn fetch() -> Result<Vec<InvidiousInstance>, FetchError> {
+    let body = tokio::time::timeout(
+        Duration::from_secs(10),
+        async { reqwest::get(INSTANCE_LIST_URL).await?.error_for_status()?.text().await }
+    ).await??;
+
+    let listed: Vec<(String, ListedInstance)> = serde_json::from_str(&body)
+        .map_err(|e| FetchError::new(e.to_string()))?;
+
+    Ok(
+        listed.into_iter()
+            .map(|(_, instance)| instance)
+            .filter(|instance| instance.kind == "https" && instance.api == Some(true))
+            .map(|instance| InvidiousInstance {
+                uptime: instance.monitor
+                    .and_then(|monitor| monitor.ratio)
+                    .and_then(|ratio| ratio.ratio.parse().ok()),
+                url: instance.uri.trim_end_matches('/').to_string(),
+                region: instance.region
+            })
+            .collect()
+    )
+}
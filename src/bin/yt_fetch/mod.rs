@@ -5,12 +5,22 @@
  * For some reason, Invidious can't be used to get the actual videos themselves, so the rusty_ytdl crate serves this purpose instead.
  */
 
+mod rate_limit;
+pub (crate) mod fixtures;
+pub (crate) mod instances;
+pub (crate) mod response_cache;
+
+use std::collections::HashSet;
 use std::time::Duration;
 
 use iced::widget::image::Handle;
 
+use log::warn;
+
+use self::fixtures::FixtureFetcher;
+
 use invidious::{
-    channel::ChannelVideos,
+    channel::{Channel, ChannelVideos},
     hidden::{PlaylistItem, SearchItem},
     universal::{Playlist, Search},
     video::Video as VideoDetails,
@@ -69,7 +79,7 @@ impl std::error::Error for FetchError {}
 
 // We use our own SearchType enum instead of rusty_ytdl's
 // rusty's SearchType doesn't implement Copy or Eq, which are needed for the radio buttons
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum SearchType {
     Video,
     Channel,
@@ -115,6 +125,35 @@ impl From<PlaylistItem> for SearchResult {
     }
 }
 
+// Why a playlist entry can't be played.
+// Invidious keeps these entries in playlists, with a placeholder title and no uploader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unavailable {
+    Deleted,
+    Private,
+    Blocked
+}
+
+impl Unavailable {
+    // Anything else without an uploader is most likely blocked in the instance's region.
+    pub fn of(item: &PlaylistItem) -> Option<Self> {
+        match item.title.as_str() {
+            "[Deleted video]" => Some(Self::Deleted),
+            "[Private video]" => Some(Self::Private),
+            _ if item.author_id.is_empty() => Some(Self::Blocked),
+            _ => None
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Deleted => "Deleted",
+            Self::Private => "Private",
+            Self::Blocked => "Unavailable in this region"
+        }
+    }
+}
+
 // Wraps different search results to a single enum.
 #[derive(Debug, Clone)]
 pub enum SearchResults {
@@ -143,107 +182,480 @@ impl SearchResults {
                 .collect()
         }
     }
+
+    // Remove results that were already shown on other pages, as well as repeats within this page.
+    // Invidious sometimes returns the same items on neighbouring pages. Returns the ids that were kept.
+    pub fn dedupe(&mut self, seen: &HashSet<String>) -> HashSet<String> {
+        let mut kept = HashSet::new();
+        let mut keep = |id: &str| !seen.contains(id) && kept.insert(String::from(id));
+
+        match self {
+            SearchResults::Videos(search) |
+            SearchResults::Channels(search) |
+            SearchResults::Playlists(search) => search.items.retain(|item| match item {
+                SearchItem::Video(video) => keep(&video.id),
+                SearchItem::Channel(channel) => keep(&channel.id),
+                SearchItem::Playlist(playlist) => keep(&playlist.id)
+            }),
+
+            SearchResults::ChannelUploads(ch) => ch.videos.retain(|video| keep(&video.id)),
+
+            SearchResults::PlaylistVideos(playlist) => playlist.videos.retain(|video| keep(&video.id))
+        }
+
+        kept
+    }
+
+    // Remove results that aren't from, or aren't, one of the allowed channels.
+    pub fn retain_channels(&mut self, allowed: impl Fn(&str) -> bool) {
+        match self {
+            SearchResults::Videos(search) |
+            SearchResults::Channels(search) |
+            SearchResults::Playlists(search) => search.items.retain(|item| match item {
+                SearchItem::Video(video) => allowed(&video.author_id),
+                SearchItem::Channel(channel) => allowed(&channel.id),
+                SearchItem::Playlist(playlist) => allowed(&playlist.author_id)
+            }),
+
+            SearchResults::ChannelUploads(ch) => ch.videos.retain(|video| allowed(&video.author_id)),
+
+            SearchResults::PlaylistVideos(playlist) => playlist.videos.retain(|video| allowed(&video.author_id))
+        }
+    }
 }
 
-// Wrapper for Invidious that can perform searches and extract information from Youtube.
-pub struct VideoFetcher {
-    client: ClientAsync
+// Details shown on a channel's About tab.
+#[derive(Debug, Clone)]
+pub struct ChannelInfo {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub links: Vec<String>,
+    pub subscribers: u32,
+    pub total_views: u64,
+    pub banner: Option<String>,
+    pub thumbnail: Option<String>,
+    pub joined: Option<chrono::DateTime<chrono::Utc>>
 }
 
-impl VideoFetcher {
-    pub fn new(instance: String) -> Self {
-        let client = ClientAsync::new(instance, MethodAsync::Reqwest);
-        Self { client }    
+impl From<Channel> for ChannelInfo {
+    fn from(value: Channel) -> Self {
+        // Invidious doesn't list a channel's links separately, so pull them out of the description instead.
+        let links = value.description
+            .split_whitespace()
+            .filter(|word| word.starts_with("https://") || word.starts_with("http://"))
+            .map(String::from)
+            .collect();
+
+        // Banners are listed from largest to smallest.
+        let banner = value.banners
+            .first()
+            .map(|banner| banner.url.clone());
+
+        // Avatars are listed from smallest to largest, prefer the medium size used elsewhere.
+        let thumbnail = value.thumbnails
+            .get(4)
+            .or(value.thumbnails.last())
+            .map(|thumbnail| thumbnail.url.clone());
+
+        Self {
+            id: value.id,
+            name: value.author,
+            description: value.description,
+            links,
+            subscribers: value.sub_count,
+            total_views: value.total_views,
+            banner,
+            thumbnail,
+            joined: chrono::DateTime::from_timestamp(value.joined as i64, 0)
+        }
     }
+}
 
+// Everything Pomelo asks Youtube about, besides the videos themselves.
+pub (crate) trait VideoFetcher {
     // Get information about a Youtube video with the given id.
-    pub async fn get_video_details(&self, id: &str) -> Result<VideoDetails, FetchError> {
-        self.client.video(id, None).await.map_err(FetchError::from)
+    async fn get_video_details(&self, id: &str) -> Result<VideoDetails, FetchError>;
+
+    // Performs a Youtube search.
+    async fn search(&self, query: &str, search_type: SearchType, page: usize) -> Result<Search, FetchError>;
+
+    // Get Youtube's suggestions for a partly typed search query.
+    async fn get_search_suggestions(&self, query: &str) -> Result<Vec<String>, FetchError>;
+
+    // Get a list of videos from a channel with the given id, continuation determines which page of videos to return.
+    async fn get_channel_videos(&self, channel_id: &str, continuation: Option<&str>) -> Result<ChannelVideos, FetchError>;
+
+    // Get details about a channel with the given id.
+    async fn get_channel_info(&self, channel_id: &str) -> Result<ChannelInfo, FetchError>;
+
+    // Get a page of playlist videos from Youtube with a given id, starting from page 1.
+    async fn get_playlist_videos(&self, id: &str, page: usize) -> Result<Playlist, FetchError>;
+}
+
+// The fetcher pages should use. In offline demo mode, that's the fixtures instead of the given instance.
+pub (crate) enum Fetcher {
+    Invidious(InvidiousFetcher),
+    Fixtures(FixtureFetcher)
+}
+
+impl Fetcher {
+    pub (crate) fn new(instance: String) -> Self {
+        match fixtures::offline_folder() {
+            Some(folder) => Self::Fixtures(FixtureFetcher::new(folder)),
+            None => Self::Invidious(InvidiousFetcher::new(instance))
+        }
+    }
+}
+
+impl VideoFetcher for Fetcher {
+    async fn get_video_details(&self, id: &str) -> Result<VideoDetails, FetchError> {
+        match self {
+            Self::Invidious(fetcher) => fetcher.get_video_details(id).await,
+            Self::Fixtures(fetcher) => fetcher.get_video_details(id).await
+        }
+    }
+
+    async fn search(&self, query: &str, search_type: SearchType, page: usize) -> Result<Search, FetchError> {
+        match self {
+            Self::Invidious(fetcher) => fetcher.search(query, search_type, page).await,
+            Self::Fixtures(fetcher) => fetcher.search(query, search_type, page).await
+        }
+    }
+
+    async fn get_search_suggestions(&self, query: &str) -> Result<Vec<String>, FetchError> {
+        match self {
+            Self::Invidious(fetcher) => fetcher.get_search_suggestions(query).await,
+            Self::Fixtures(fetcher) => fetcher.get_search_suggestions(query).await
+        }
+    }
+
+    async fn get_channel_videos(&self, channel_id: &str, continuation: Option<&str>) -> Result<ChannelVideos, FetchError> {
+        match self {
+            Self::Invidious(fetcher) => fetcher.get_channel_videos(channel_id, continuation).await,
+            Self::Fixtures(fetcher) => fetcher.get_channel_videos(channel_id, continuation).await
+        }
+    }
+
+    async fn get_channel_info(&self, channel_id: &str) -> Result<ChannelInfo, FetchError> {
+        match self {
+            Self::Invidious(fetcher) => fetcher.get_channel_info(channel_id).await,
+            Self::Fixtures(fetcher) => fetcher.get_channel_info(channel_id).await
+        }
+    }
+
+    async fn get_playlist_videos(&self, id: &str, page: usize) -> Result<Playlist, FetchError> {
+        match self {
+            Self::Invidious(fetcher) => fetcher.get_playlist_videos(id, page).await,
+            Self::Fixtures(fetcher) => fetcher.get_playlist_videos(id, page).await
+        }
+    }
+}
+
+// Wrapper for Invidious that can perform searches and extract information from Youtube.
+// Requests are rate limited per instance. Searches, channels, and playlists are cached for a while.
+pub (crate) struct InvidiousFetcher {
+    client: ClientAsync,
+    instance: String
+}
+
+impl InvidiousFetcher {
+    pub (crate) fn new(instance: String) -> Self {
+        let client = ClientAsync::new(instance.clone(), MethodAsync::Reqwest);
+        Self { client, instance }    
+    }
+
+    // Wait for the rate limiter before sending a request to the instance.
+    async fn wait_turn(&self) {
+        rate_limit::acquire(&self.instance).await;
+    }
+
+    // Cache key for a request to this instance.
+    fn cache_key(&self, endpoint: &str, params: &str) -> String {
+        format!("{}|{}|{}", self.instance, endpoint, params)
+    }
+
+    fn cache_result<T: serde::Serialize>(&self, key: &str, result: &Result<T, FetchError>) {
+        if let Ok(value) = result {
+            response_cache::put(key, value);
+        }
+    }
+
+    // Invidious errors don't expose the status code, so look for it in the message.
+    fn check_rate_limited<T>(&self, result: Result<T, FetchError>) -> Result<T, FetchError> {
+        if let Err(e) = &result {
+            if e.error.contains("429") || e.error.contains("Too Many Requests") {
+                rate_limit::back_off(&self.instance);
+            }
+        }
+        result
+    }
+}
+
+impl VideoFetcher for InvidiousFetcher {
+    async fn get_video_details(&self, id: &str) -> Result<VideoDetails, FetchError> {
+        self.wait_turn().await;
+        self.check_rate_limited(self.client.video(id, None).await.map_err(FetchError::from))
     }
 
     // Performs a Youtube search. Times out after 10 seconds.
-    pub async fn search(&self, query: &str, search_type: SearchType, page: usize) -> Result<Search, FetchError> {
+    async fn search(&self, query: &str, search_type: SearchType, page: usize) -> Result<Search, FetchError> {
+        let params = format!("q={}&type={}&page={}", urlencoding::encode(query), search_type, page);
+        let key = self.cache_key("search", &params);
+
+        if let Some(search) = response_cache::get(&key) {
+            return Ok(search);
+        }
+
+        self.wait_turn().await;
+
         let result = tokio::time::timeout(
             Duration::from_secs(10),
-            self.client.search(
-                Some(&format!("q={}&type={}&page={}",
-                urlencoding::encode(query), search_type, page))
-            )
+            self.client.search(Some(&params))
         ).await;
 
-        match result {
+        let result = match result {
             Ok(out) => out.map_err(FetchError::from),
             Err(e) => Err(e.into())
+        };
+
+        self.cache_result(&key, &result);
+        self.check_rate_limited(result)
+    }
+
+    // Get Youtube's suggestions for a partly typed search query. Times out after 5 seconds.
+    async fn get_search_suggestions(&self, query: &str) -> Result<Vec<String>, FetchError> {
+        #[derive(serde::Deserialize)]
+        struct Suggestions {
+            suggestions: Vec<String>
         }
+
+        let url = format!("{}/api/v1/search/suggestions?q={}", self.instance, urlencoding::encode(query));
+
+        self.wait_turn().await;
+
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            async {
+                let body = reqwest::get(&url).await?.error_for_status()?.text().await?;
+
+                serde_json::from_str::<Suggestions>(&body)
+                    .map(|response| response.suggestions)
+                    .map_err(|e| FetchError::new(e.to_string()))
+            }
+        ).await;
+
+        let result = match result {
+            Ok(out) => out,
+            Err(e) => Err(e.into())
+        };
+
+        self.check_rate_limited(result)
     }
 
     // Get a list of videos from a channel with the given id, continuation determines which page of videos to return.
     // Times out after 10 seconds.
-    pub async fn get_channel_videos(&self, channel_id: &str, continuation: Option<&str>) -> Result<ChannelVideos, FetchError> {
+    async fn get_channel_videos(&self, channel_id: &str, continuation: Option<&str>) -> Result<ChannelVideos, FetchError> {
         let params = continuation
             .map(|c| format!("continuation={}", c));
 
+        let key = self.cache_key(&format!("channels/{}/videos", channel_id), params.as_deref().unwrap_or_default());
+
+        if let Some(videos) = response_cache::get(&key) {
+            return Ok(videos);
+        }
+
+        self.wait_turn().await;
+
         let result = tokio::time::timeout(
             Duration::from_secs(10),
             self.client.channel_videos(channel_id, params.as_deref())
         ).await;
 
-        match result {
+        let result = match result {
             Ok(out) => out.map_err(FetchError::from),
             Err(e) => Err(e.into())
+        };
+
+        self.cache_result(&key, &result);
+        self.check_rate_limited(result)
+    }
+
+    // Get details about a channel with the given id. Times out after 10 seconds.
+    async fn get_channel_info(&self, channel_id: &str) -> Result<ChannelInfo, FetchError> {
+        let key = self.cache_key(&format!("channels/{}", channel_id), "");
+
+        if let Some(channel) = response_cache::get::<Channel>(&key) {
+            return Ok(ChannelInfo::from(channel));
         }
+
+        self.wait_turn().await;
+
+        let result = tokio::time::timeout(
+            Duration::from_secs(10),
+            self.client.channel(channel_id, None)
+        ).await;
+
+        let result = match result {
+            Ok(out) => out.map_err(FetchError::from),
+            Err(e) => Err(e.into())
+        };
+
+        // The channel is cached as Invidious sent it, since the converted info can't be saved.
+        self.cache_result(&key, &result);
+        self.check_rate_limited(result.map(ChannelInfo::from))
     }
 
-    // Get a list of playlist videos from Youtube with a given id. Times out after 10 seconds.
-    pub async fn get_playlist_videos(&self, id: &str) -> Result<Playlist, FetchError> {
+    // Get a page of playlist videos from Youtube with a given id, starting from page 1. Times out after 10 seconds.
+    async fn get_playlist_videos(&self, id: &str, page: usize) -> Result<Playlist, FetchError> {
+        let params = format!("page={}", page);
+        let key = self.cache_key(&format!("playlists/{}", id), &params);
+
+        if let Some(playlist) = response_cache::get(&key) {
+            return Ok(playlist);
+        }
+
+        self.wait_turn().await;
+
         let result = tokio::time::timeout(
             Duration::from_secs(10),
-            self.client.playlist(id, None)
+            self.client.playlist(id, Some(&params))
         ).await;
 
-        match result {
+        let result = match result {
             Ok(out) => out.map_err(FetchError::from),
             Err(e) => Err(e.into())
+        };
+
+        self.cache_result(&key, &result);
+        self.check_rate_limited(result)
+    }
+}
+
+// Number of instances queried at once when racing requests.
+pub (crate) const RACE_INSTANCES: usize = 3;
+
+// Request video info from several instances at once, the first successful response wins.
+// If every instance fails, the last error is returned.
+pub (crate) async fn race_video_details(instances: &[String], id: &str) -> Result<VideoDetails, FetchError> {
+    if instances.is_empty() {
+        return Err(FetchError::from("No instances to query."));
+    }
+
+    let requests = instances.iter()
+        .map(|instance| Box::pin(async move {
+            Fetcher::new(instance.clone()).get_video_details(id).await
+                .inspect_err(|e| warn!("Race request to {} failed: {}", instance, e))
+        }));
+
+    futures::future::select_ok(requests).await
+        .map(|(details, _)| details)
+}
+
+// SponsorBlock API, used to find segments of a video that can be skipped.
+const SPONSORBLOCK_API: &str = "https://sponsor.ajay.app/api/skipSegments";
+
+// A part of a video marked by SponsorBlock users. Times are in seconds.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub (crate) struct SponsorSegment {
+    pub (crate) segment: (f64, f64),
+    pub (crate) category: String
+}
+
+// Get the SponsorBlock segments of a video in the given categories (e.g. "sponsor", "intro").
+// Videos nobody has marked yet have no segments.
+pub (crate) async fn get_sponsor_segments(id: &str, categories: &[&str]) -> Result<Vec<SponsorSegment>, FetchError> {
+    let categories = serde_json::to_string(categories).map_err(|e| FetchError::new(e.to_string()))?;
+
+    let url = format!(
+        "{}?videoID={}&categories={}",
+        SPONSORBLOCK_API,
+        urlencoding::encode(id),
+        urlencoding::encode(&categories)
+    );
+
+    rate_limit::acquire(&url).await;
+
+    let response = reqwest::get(&url).await?;
+
+    match response.status() {
+        reqwest::StatusCode::NOT_FOUND => return Ok(Vec::new()),
+        reqwest::StatusCode::TOO_MANY_REQUESTS => rate_limit::back_off(&url),
+        _ => ()
+    }
+
+    let body = response.error_for_status()?.text().await?;
+
+    serde_json::from_str(&body).map_err(|e| FetchError::new(e.to_string()))
+}
+
+// Instance that image urls are resolved against, and whether Youtube images should be loaded through it.
+#[derive(Debug, Clone)]
+pub (crate) struct ImageHost {
+    pub (crate) instance: String,
+    pub (crate) proxy: bool
+}
+
+// Grab an image from the given url, after fixing it up for the image host (see utils::normalize_image_url).
+// Image requests share the rate limit of the host they're sent to.
+pub (crate) async fn download_image(url: &str, host: &ImageHost) -> Result<Handle, FetchError> {
+    use crate::utils::normalize_image_url;
+
+    let url = normalize_image_url(url, &host.instance, host.proxy);
+
+    rate_limit::acquire(&url).await;
+
+    let response = reqwest::get(&url).await?;
+
+    if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        rate_limit::back_off(&url);
+    }
+
+    let response = response.error_for_status()?;
+    let bytes = response.bytes().await?;
+
+    Ok(Handle::from_bytes(bytes))
+}
+
+// Grab a video, channel, playlist thumbnail from Youtube, in the size closest to the given width.
+// If that size can't be downloaded, the other sizes are tried in order of how close they are.
+pub (crate) async fn download_thumbnail(item: &SearchResult, width: u32, host: &ImageHost) -> Result<Handle, FetchError> {
+    let urls = thumbnail_urls(item, width);
+
+    let mut error = FetchError::from("No thumbnails available.");
+
+    for url in urls.iter() {
+        match download_image(url, host).await {
+            Ok(handle) => return Ok(handle),
+            Err(e) => {
+                warn!("Failed to download thumbnail, trying another size: {}", e);
+                error = e;
+            }
         }
     }
+
+    Err(error)
 }
 
-// Grab a video, channel, playlist thumbnail from Youtube.
-pub (crate) async fn download_thumbnail(item: &SearchResult, index: usize) -> Result<Handle, FetchError> {
-    match item {
-        SearchResult::Video(v) => match v.thumbnails.get(index) {
-            Some(thumbnail) => match reqwest::get(&thumbnail.url).await {
-                Ok(response) => Ok(Handle::from_bytes(response.bytes().await.unwrap())),
-                Err(e) => Err(FetchError::from(e))
-            },
-            None => Err(FetchError::new(format!("Thumbnail index {} is invalid.", index)))
-        },
-
-        SearchResult::Channel(ch) => match ch.thumbnails.get(index) {
-            Some(thumbnail) => match reqwest::get(format!("https:{}", &thumbnail.url)).await {
-                Ok(response) => Ok(Handle::from_bytes(response.bytes().await.unwrap())),
-                Err(e) => Err(FetchError::from(e))
-            },
-            None => Err(FetchError::new(format!("Thumbnail index {} is invalid.", index)))
-        },
-
-        SearchResult::Playlist(playlist) => match reqwest::get(&playlist.thumbnail).await {
-            Ok(response) => Ok(Handle::from_bytes(response.bytes().await.unwrap())),
-            Err(e) => Err(FetchError::from(e))
-        },
-
-        SearchResult::PlaylistVideo(video) => match video.thumbnails.get(index) {
-            Some(thumbnail) => {
-                match reqwest::get(&thumbnail.url).await {
-                    Ok(response) => {
-                        Ok(Handle::from_bytes(response.bytes().await.unwrap()))
-                    },
-                    Err(e) => {
-                        Err(FetchError::from(e))
-                    }
-                }
-            },
-            None => Err(FetchError::new(format!("Thumbnail index {} is invalid.", index)))
-        }
-    }
-}
\ No newline at end of file
+// Thumbnail urls, best fit for the given width first.
+// The smallest size that's at least as wide comes first, then bigger sizes, then smaller sizes from largest to smallest.
+// Sizes without a known width go last.
+fn thumbnail_urls(item: &SearchResult, width: u32) -> Vec<String> {
+    let mut sizes: Vec<(u32, String)> = match item {
+        SearchResult::Video(v) => v.thumbnails.iter().map(|t| (t.width, t.url.clone())).collect(),
+        SearchResult::Channel(ch) => ch.thumbnails.iter().map(|t| (t.width, t.url.clone())).collect(),
+        SearchResult::Playlist(playlist) => vec![(0, playlist.thumbnail.clone())],
+        SearchResult::PlaylistVideo(video) => video.thumbnails.iter().map(|t| (t.width, t.url.clone())).collect()
+    };
+
+    sizes.retain(|(_, url)| !url.is_empty());
+
+    sizes.sort_by_key(|(w, _)| match *w {
+        0 => (2, 0),
+        w if w >= width => (0, w - width),
+        w => (1, width - w)
+    });
+
+    sizes.dedup_by(|a, b| a.1 == b.1);
+    sizes.into_iter().map(|(_, url)| url).collect()
+}
@@ -0,0 +1,107 @@
+// Client-side rate limiting, so bulk requests (thumbnails, playlist pages, etc.) don't get Pomelo banned from public instances.
+// Every host gets its own token bucket. Requests wait for a token, and a host that answers "too many requests"
+// is left alone for a while.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use log::warn;
+
+// Number of requests that can be sent to a host at once.
+const BURST: f64 = 8.0;
+
+// Tokens regained per second, per host.
+const REFILL_RATE: f64 = 4.0;
+
+// How long to wait before talking to a host again after it asks us to slow down.
+const BACKOFF: Duration = Duration::from_secs(15);
+
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+    paused_until: Option<Instant>
+}
+
+impl TokenBucket {
+    fn new(now: Instant) -> Self {
+        Self {
+            tokens: BURST,
+            last_refill: now,
+            paused_until: None
+        }
+    }
+
+    // Take a token if one is available, otherwise return how long to wait before trying again.
+    fn try_take(&mut self, now: Instant) -> Result<(), Duration> {
+        if let Some(until) = self.paused_until {
+            if now < until {
+                return Err(until - now);
+            }
+            self.paused_until = None;
+        }
+
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * REFILL_RATE).min(BURST);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        }
+        else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / REFILL_RATE))
+        }
+    }
+}
+
+fn buckets() -> &'static Mutex<HashMap<String, TokenBucket>> {
+    static BUCKETS: OnceLock<Mutex<HashMap<String, TokenBucket>>> = OnceLock::new();
+    BUCKETS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Buckets are shared by everything on the same host, e.g. an instance's API and its image proxy.
+fn host_of(url: &str) -> String {
+    let url = url.strip_prefix("//").unwrap_or(url);
+
+    url::Url::parse(url)
+        .or_else(|_| url::Url::parse(&format!("https://{}", url)))
+        .ok()
+        .and_then(|url| url.host_str().map(String::from))
+        .unwrap_or_else(|| String::from(url))
+}
+
+// Wait until a request can be sent to the given url's host.
+pub (crate) async fn acquire(url: &str) {
+    let host = host_of(url);
+
+    loop {
+        let result = {
+            let now = Instant::now();
+            let mut buckets = buckets().lock().unwrap();
+
+            buckets.entry(host.clone())
+                .or_insert_with(|| TokenBucket::new(now))
+                .try_take(now)
+        };
+
+        match result {
+            Ok(()) => return,
+            Err(wait) => tokio::time::sleep(wait).await
+        }
+    }
+}
+
+// Stop sending requests to the url's host for a while, used when it responds with "429 Too Many Requests".
+pub (crate) fn back_off(url: &str) {
+    let host = host_of(url);
+    let now = Instant::now();
+
+    warn!("{} is rate limiting requests, backing off for {} seconds.", host, BACKOFF.as_secs());
+
+    let mut buckets = buckets().lock().unwrap();
+    let bucket = buckets.entry(host).or_insert_with(|| TokenBucket::new(now));
+
+    bucket.tokens = 0.0;
+    bucket.paused_until = Some(now + BACKOFF);
+}
@@ -0,0 +1,184 @@
+// Cache of Invidious responses, so channels, playlists, and searches visited again within a few minutes
+// show up right away, without asking the instance again.
+// Responses are kept in memory as JSON, and can also be kept in an SQLite file so they last between runs.
+// Entries are keyed by instance, endpoint, and parameters, and are thrown away once they're older than the TTL.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use log::{error, info, warn};
+use rusqlite::{params, Connection};
+use serde::{de::DeserializeOwned, Serialize};
+
+const CACHE_FILE: &str = "./cache/responses.db";
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS response (
+        key TEXT PRIMARY KEY,
+        body TEXT NOT NULL,
+        saved_at INTEGER NOT NULL
+    );
+";
+
+// Nothing is cached until a TTL is set.
+#[derive(Default)]
+struct ResponseCache {
+    ttl: Duration,
+
+    // Response bodies by key, with the unix timestamp they were saved at.
+    memory: HashMap<String, (i64, String)>,
+    disk: Option<Connection>
+}
+
+impl ResponseCache {
+    fn is_fresh(&self, saved_at: i64) -> bool {
+        chrono::Utc::now().timestamp() - saved_at < self.ttl.as_secs() as i64
+    }
+
+    fn read_disk(&self, key: &str) -> Option<(i64, String)> {
+        let connection = self.disk.as_ref()?;
+
+        let result = connection.query_row(
+            "SELECT saved_at, body FROM response WHERE key = ?1",
+            params![key],
+            |row| Ok((row.get(0)?, row.get(1)?))
+        );
+
+        match result {
+            Ok(row) => Some(row),
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => {
+                error!("Failed to read cached response: {}", e);
+                None
+            }
+        }
+    }
+
+    // Forget responses that are too old to be used.
+    fn prune(&mut self) {
+        let oldest = chrono::Utc::now().timestamp() - self.ttl.as_secs() as i64;
+
+        self.memory.retain(|_, (saved_at, _)| *saved_at > oldest);
+
+        if let Some(connection) = &self.disk {
+            if let Err(e) = connection.execute("DELETE FROM response WHERE saved_at <= ?1", params![oldest]) {
+                error!("Failed to remove old cached responses: {}", e);
+            }
+        }
+    }
+}
+
+fn cache() -> &'static Mutex<ResponseCache> {
+    static CACHE: OnceLock<Mutex<ResponseCache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(ResponseCache::default()))
+}
+
+fn open_disk_cache() -> Option<Connection> {
+    let result = std::fs::create_dir_all("./cache")
+        .map_err(|e| e.to_string())
+        .and_then(|_| Connection::open(CACHE_FILE).map_err(|e| e.to_string()))
+        .and_then(|connection| connection.execute_batch(SCHEMA).map(|_| connection).map_err(|e| e.to_string()));
+
+    match result {
+        Ok(connection) => Some(connection),
+        Err(e) => {
+            warn!("Failed to open the response cache, responses will only be kept in memory: {}", e);
+            None
+        }
+    }
+}
+
+// Set how long responses are kept, and whether they're saved to disk. A TTL of zero turns the cache off.
+pub (crate) fn configure(ttl: Duration, persist: bool) {
+    let mut cache = cache().lock().unwrap();
+
+    cache.ttl = ttl;
+
+    match (persist && !ttl.is_zero(), cache.disk.is_some()) {
+        (true, false) => cache.disk = open_disk_cache(),
+        (false, true) => cache.disk = None,
+        _ => ()
+    }
+
+    cache.prune();
+}
+
+// A cached response for the key, if there's one that isn't too old.
+pub (crate) fn get<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let mut cache = cache().lock().unwrap();
+
+    if cache.ttl.is_zero() {
+        return None;
+    }
+
+    let (saved_at, body) = match cache.memory.get(key) {
+        Some(entry) => entry.clone(),
+        None => {
+            let entry = cache.read_disk(key)?;
+            cache.memory.insert(String::from(key), entry.clone());
+            entry
+        }
+    };
+
+    if !cache.is_fresh(saved_at) {
+        return None;
+    }
+
+    match serde_json::from_str(&body) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            warn!("Skipping unreadable cached response: {}", e);
+            cache.memory.remove(key);
+            None
+        }
+    }
+}
+
+pub (crate) fn put<T: Serialize>(key: &str, value: &T) {
+    let mut cache = cache().lock().unwrap();
+
+    if cache.ttl.is_zero() {
+        return;
+    }
+
+    let body = match serde_json::to_string(value) {
+        Ok(body) => body,
+        Err(e) => {
+            warn!("Failed to cache response: {}", e);
+            return;
+        }
+    };
+
+    let now = chrono::Utc::now().timestamp();
+
+    cache.prune();
+
+    if let Some(connection) = &cache.disk {
+        let result = connection.execute(
+            "INSERT OR REPLACE INTO response (key, body, saved_at) VALUES (?1, ?2, ?3)",
+            params![key, body, now]
+        );
+
+        if let Err(e) = result {
+            error!("Failed to save cached response: {}", e);
+        }
+    }
+
+    cache.memory.insert(String::from(key), (now, body));
+}
+
+// Forget every cached response, in memory and on disk.
+pub (crate) fn clear() {
+    let mut cache = cache().lock().unwrap();
+
+    info!("Clearing cached responses.");
+
+    cache.memory.clear();
+
+    if let Some(connection) = &cache.disk {
+        if let Err(e) = connection.execute("DELETE FROM response", []) {
+            error!("Failed to clear cached responses: {}", e);
+        }
+    }
+}